@@ -0,0 +1,282 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use chrono::{Local, NaiveDate};
+use clap::{Parser, Subcommand};
+use serde_json::Value;
+use stream_core::timeline::{self, TimelineInputs, TimelineSource};
+use stream_core::{commit_log, git, markdown, paths, plain_text, search};
+
+#[derive(Parser)]
+#[command(
+    name = "stream",
+    version,
+    about = "Read and write your stream notes from the terminal"
+)]
+struct Cli {
+    /// Notes directory (the folder opened in the app)
+    #[arg(long, global = true, env = "STREAM_NOTES_DIR")]
+    dir: Option<PathBuf>,
+
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Append text to today's note (or the note for --date)
+    Append {
+        /// Text to append; words are joined with spaces
+        #[arg(required = true)]
+        text: Vec<String>,
+
+        /// Target date (YYYY-MM-DD) instead of today
+        #[arg(long)]
+        date: Option<NaiveDate>,
+    },
+    /// Search daily notes
+    Search {
+        query: Vec<String>,
+
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+    },
+    /// List commits for a day across repositories
+    Commits {
+        /// Show today's commits (the default)
+        #[arg(long, conflicts_with = "date")]
+        today: bool,
+
+        /// Day to list (YYYY-MM-DD)
+        #[arg(long)]
+        date: Option<NaiveDate>,
+
+        /// Repository path; may be repeated. Defaults to STREAM_REPOS (path-list separated)
+        #[arg(long = "repo")]
+        repos: Vec<PathBuf>,
+    },
+    /// Print a day's note and timeline as plain text, without markdown syntax
+    Day {
+        /// Day to print (YYYY-MM-DD) instead of today
+        #[arg(long)]
+        date: Option<NaiveDate>,
+
+        /// Repository whose commits join the timeline; may be repeated. Defaults to
+        /// STREAM_REPOS (path-list separated)
+        #[arg(long = "repo")]
+        repos: Vec<PathBuf>,
+    },
+    /// Append a commit to today's note; run by the post-commit hook the app installs
+    LogCommit {
+        /// Commit to log
+        #[arg(default_value = "HEAD")]
+        rev: String,
+
+        /// Repository the commit is in
+        #[arg(long, default_value = ".")]
+        repo: PathBuf,
+    },
+}
+
+fn notes_dir(cli: &Cli) -> Result<PathBuf, String> {
+    cli.dir
+        .as_ref()
+        .map(|dir| paths::normalize_path(&dir.to_string_lossy()))
+        .ok_or_else(|| "No notes directory: pass --dir or set STREAM_NOTES_DIR".to_string())
+}
+
+/// Refuse to write into a vault marked read-only in the app, like the app's own commands do
+fn ensure_writable(dir: &Path) -> Result<(), String> {
+    let Some(settings_path) = paths::app_data_dir().map(|dir| dir.join("settings.json")) else {
+        return Ok(());
+    };
+    let settings: Value = match fs::read(&settings_path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map_err(|e| format!("Failed to read {}: {}", settings_path.display(), e))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(format!("Failed to read {}: {}", settings_path.display(), e)),
+    };
+
+    let vaults = settings.get("vaults").and_then(Value::as_array);
+    for vault in vaults.into_iter().flatten() {
+        let read_only = vault
+            .pointer("/overrides/read_only")
+            .and_then(Value::as_bool);
+        let path = vault.get("path").and_then(Value::as_str);
+        if let (Some(true), Some(path)) = (read_only, path) {
+            if dir.starts_with(paths::normalize_path(path)) {
+                let name = vault.get("name").and_then(Value::as_str).unwrap_or(path);
+                return Err(format!("{} is read-only and can't be changed", name));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `--repo` paths, or STREAM_REPOS when none were passed
+fn repo_paths(repos: &[PathBuf]) -> Vec<String> {
+    if !repos.is_empty() {
+        return repos
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+    }
+    std::env::var_os("STREAM_REPOS")
+        .map(|env_repos| {
+            std::env::split_paths(&env_repos)
+                .map(|p| p.to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn run(cli: Cli) -> Result<(), String> {
+    match &cli.command {
+        Command::Append { text, date } => {
+            let dir = notes_dir(&cli)?;
+            ensure_writable(&dir)?;
+            let date = date.unwrap_or_else(|| Local::now().date_naive());
+            let path = markdown::append_to_daily_note(&dir, date, &text.join(" "))
+                .map_err(|e| format!("Failed to append: {}", e))?;
+
+            if cli.json {
+                println!("{}", serde_json::json!({ "file_path": path }));
+            } else {
+                println!("Appended to {}", path.display());
+            }
+        }
+        Command::Search { query, limit } => {
+            let dir = notes_dir(&cli)?;
+            let results = search::search_markdown_files(
+                &dir.to_string_lossy(),
+                &query.join(" "),
+                *limit,
+                true,
+                false,
+            )
+            .map_err(|e| e.to_string())?;
+
+            if cli.json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&results).map_err(|e| e.to_string())?
+                );
+            } else {
+                for m in &results.matches {
+                    println!(
+                        "{}:{}: {}",
+                        m.file_path,
+                        m.line_number,
+                        m.context_snippet.trim()
+                    );
+                }
+                eprintln!(
+                    "{} of {} matches in {}ms",
+                    results.matches.len(),
+                    results.total_results,
+                    results.search_time_ms
+                );
+            }
+        }
+        Command::Commits {
+            today: _,
+            date,
+            repos,
+        } => {
+            let date = date.unwrap_or_else(|| Local::now().date_naive());
+            let repo_paths = repo_paths(repos);
+            if repo_paths.is_empty() {
+                return Err("No repositories: pass --repo or set STREAM_REPOS".to_string());
+            }
+
+            // Use the timezone the day's note was written in when a notes directory is known
+            let bounds = match &cli.dir {
+                Some(dir) => markdown::entry_day_bounds(dir, date),
+                None => git::local_day_bounds(date),
+            };
+            let (start_seconds, end_seconds) =
+                bounds.ok_or_else(|| format!("Invalid local date: {}", date))?;
+            let results = git::get_git_commits_for_repos(
+                &repo_paths,
+                start_seconds as u64 * 1000,
+                end_seconds as u64 * 1000,
+            );
+
+            if cli.json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&results).map_err(|e| e.to_string())?
+                );
+            } else {
+                for repo in &results {
+                    if let Some(error) = &repo.error {
+                        eprintln!("{}: {}", repo.repo_path, error);
+                        continue;
+                    }
+                    for commit in &repo.commits {
+                        println!(
+                            "{} {} {} ({})",
+                            &commit.id[..7.min(commit.id.len())],
+                            repo.repo_path,
+                            commit.message,
+                            commit.author_name
+                        );
+                    }
+                }
+            }
+        }
+        Command::Day { date, repos } => {
+            let dir = notes_dir(&cli)?;
+            let date = date.unwrap_or_else(|| Local::now().date_naive());
+            let repo_paths = repo_paths(repos);
+            // Focus sessions live in the app, so they're left out here
+            let inputs = TimelineInputs {
+                repo_paths: &repo_paths,
+                focus_sessions: &[],
+            };
+            let events = timeline::build_timeline(&dir, date, &TimelineSource::ALL, &inputs)
+                .map_err(|e| format!("Failed to build timeline: {}", e))?;
+            let text = plain_text::day_as_text(&dir, date, &events)
+                .map_err(|e| format!("Failed to render day: {}", e))?;
+
+            if cli.json {
+                println!(
+                    "{}",
+                    serde_json::json!({ "date": date.to_string(), "text": text })
+                );
+            } else {
+                print!("{}", text);
+            }
+        }
+        Command::LogCommit { rev, repo } => {
+            let dir = notes_dir(&cli)?;
+            ensure_writable(&dir)?;
+            let repo_path = paths::normalize_path(&repo.to_string_lossy());
+            let path = commit_log::log_commit(&dir, &repo_path.to_string_lossy(), rev)
+                .map_err(|e| format!("Failed to log commit: {}", e))?;
+
+            if cli.json {
+                println!("{}", serde_json::json!({ "file_path": path }));
+            } else {
+                println!("Logged {} to {}", rev, path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDate;
+use regex::Captures;
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::clock::now_ms;
+use crate::commit_log::{day_commits, render_commit_section, CommitLogFormat};
+use crate::git;
+use crate::lock::is_locked_path;
+use crate::markdown::{
+    entry_timezone, find_daily_note_or_locked, parse_date_from_filename, read_all_user_xattrs,
+    write_user_xattrs,
+};
+use crate::site::LOCAL_LINK_REGEX;
+use crate::sources::strip_source_config;
+use crate::undo::{UndoJournal, UndoKind};
+
+const ARCHIVE_FORMAT_VERSION: u32 = 1;
+const NOTES_PREFIX: &str = "notes/";
+const MANIFEST_ENTRY: &str = "manifest.json";
+const METADATA_ENTRY: &str = "metadata.json";
+const COMMITS_ENTRY: &str = "commits.json";
+const BUNDLE_ATTACHMENTS_DIR: &str = "attachments";
+const BUNDLE_COMMITS_ENTRY: &str = "commits.md";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub created_at: u64,
+    pub file_count: usize,
+    pub includes_commits: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveSummary {
+    pub output_path: String,
+    pub file_count: usize,
+    pub metadata_count: usize,
+    pub includes_commits: bool,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub files_written: usize,
+    pub files_skipped: usize,
+    pub metadata_applied: usize,
+    /// Undo journal entry holding the files' prior state, for rolling the import back.
+    /// None when imported without a journal
+    #[serde(default)]
+    pub snapshot_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DayBundleSummary {
+    pub output_path: String,
+    pub note_path: Option<String>,
+    pub attachment_count: usize,
+    pub commit_count: usize,
+    pub zipped: bool,
+}
+
+/// Collect every non-hidden file under `dir`, skipping dot-directories like `.git`
+pub(crate) fn collect_files(
+    dir: &Path,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+
+        let is_hidden = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .map(|n| n.starts_with('.'))
+            .unwrap_or(false);
+        if is_hidden {
+            continue;
+        }
+
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Archive-relative path with forward slashes, regardless of platform
+pub(crate) fn relative_key(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// Write every note and attachment, a JSON dump of their xattrs and, when repositories
+/// are given, the commit history covering the notes' date range into a zip archive
+pub fn export_archive(
+    directory_path: &Path,
+    output_path: &Path,
+    repo_paths: Option<&[String]>,
+) -> Result<ArchiveSummary, Box<dyn std::error::Error>> {
+    if !directory_path.is_dir() {
+        return Err(format!("Not a directory: {}", directory_path.display()).into());
+    }
+
+    let mut files = Vec::new();
+    collect_files(directory_path, &mut files)?;
+
+    // Don't archive the archive if it's being written inside the notes folder
+    let output_canonical = output_path
+        .parent()
+        .and_then(|p| p.canonicalize().ok())
+        .zip(output_path.file_name())
+        .map(|(parent, name)| parent.join(name));
+    files.retain(|f| f.canonicalize().ok() != output_canonical);
+
+    let mut zip = ZipWriter::new(File::create(output_path)?);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut metadata: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+    let mut earliest_note_ms: Option<u64> = None;
+    let mut file_count = 0;
+
+    for file in &files {
+        let key = match relative_key(directory_path, file) {
+            Some(key) => key,
+            None => continue,
+        };
+
+        let mut source = match File::open(file) {
+            Ok(source) => source,
+            Err(e) => {
+                eprintln!("Skipping unreadable file {}: {}", file.display(), e);
+                continue;
+            }
+        };
+
+        zip.start_file(format!("{}{}", NOTES_PREFIX, key), options)?;
+        io::copy(&mut source, &mut zip)?;
+        file_count += 1;
+
+        let attributes = read_all_user_xattrs(file);
+        if !attributes.is_empty() {
+            metadata.insert(key.clone(), attributes);
+        }
+
+        if let Some(date_ms) = file
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(parse_date_from_filename)
+        {
+            earliest_note_ms = Some(earliest_note_ms.map_or(date_ms, |e| e.min(date_ms)));
+        }
+    }
+
+    zip.start_file(METADATA_ENTRY, options)?;
+    zip.write_all(serde_json::to_string_pretty(&metadata)?.as_bytes())?;
+
+    let mut includes_commits = false;
+    if let Some(repo_paths) = repo_paths.filter(|paths| !paths.is_empty()) {
+        let commits =
+            git::get_git_commits_for_repos(repo_paths, earliest_note_ms.unwrap_or(0), now_ms());
+        zip.start_file(COMMITS_ENTRY, options)?;
+        zip.write_all(serde_json::to_string_pretty(&commits)?.as_bytes())?;
+        includes_commits = true;
+    }
+
+    let manifest = ArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        created_at: now_ms(),
+        file_count,
+        includes_commits,
+    };
+    zip.start_file(MANIFEST_ENTRY, options)?;
+    zip.write_all(serde_json::to_string_pretty(&manifest)?.as_bytes())?;
+
+    zip.finish()?;
+
+    Ok(ArchiveSummary {
+        output_path: output_path.to_string_lossy().to_string(),
+        file_count,
+        metadata_count: metadata.len(),
+        includes_commits,
+        size: fs::metadata(output_path).map(|m| m.len()).unwrap_or(0),
+    })
+}
+
+/// Restore notes, attachments and xattrs from an archive produced by `export_archive`.
+/// Existing files are left untouched unless `overwrite` is set. With a `journal`, the files
+/// about to be written are snapshotted first and the summary names the snapshot; nothing is
+/// imported if the snapshot can't be taken
+pub fn import_archive(
+    archive_path: &Path,
+    directory_path: &Path,
+    overwrite: bool,
+    journal: Option<&UndoJournal>,
+) -> Result<ImportSummary, Box<dyn std::error::Error>> {
+    let mut archive = ZipArchive::new(File::open(archive_path)?)?;
+
+    if archive.by_name(MANIFEST_ENTRY).is_err() {
+        return Err("Not a stream archive: manifest.json is missing".into());
+    }
+
+    fs::create_dir_all(directory_path)?;
+
+    let mut planned: Vec<(usize, String)> = Vec::new();
+    let mut files_skipped = 0;
+
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index)?;
+        if entry.is_dir() {
+            continue;
+        }
+
+        // enclosed_name rejects absolute paths and `..` components
+        let name = match entry.enclosed_name() {
+            Some(name) => name,
+            None => continue,
+        };
+        let key = match name.to_str().and_then(|n| n.strip_prefix(NOTES_PREFIX)) {
+            Some(key) if !key.is_empty() => key.to_string(),
+            _ => continue,
+        };
+
+        if directory_path.join(&key).exists() && !overwrite {
+            files_skipped += 1;
+            continue;
+        }
+        planned.push((index, key));
+    }
+
+    let snapshot_id = match journal {
+        Some(journal) => {
+            let targets: Vec<PathBuf> = planned
+                .iter()
+                .map(|(_, key)| directory_path.join(key))
+                .collect();
+            let description = format!("Import {}", archive_path.display());
+            let id = journal
+                .record_files(UndoKind::Import, &description, &targets)
+                .map_err(|e| format!("Couldn't save an undo copy before importing: {}", e))?;
+            Some(id)
+        }
+        None => None,
+    };
+
+    let mut written: Vec<String> = Vec::new();
+    for (index, key) in planned {
+        let mut entry = archive.by_index(index)?;
+        let target = directory_path.join(&key);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents)?;
+        fs::write(&target, contents)?;
+        written.push(key);
+    }
+
+    let mut metadata_applied = 0;
+    let metadata: BTreeMap<String, BTreeMap<String, String>> = match archive.by_name(METADATA_ENTRY)
+    {
+        Ok(mut entry) => {
+            let mut json = String::new();
+            entry.read_to_string(&mut json)?;
+            serde_json::from_str(&json)?
+        }
+        Err(_) => BTreeMap::new(),
+    };
+
+    for key in &written {
+        if let Some(attributes) = metadata.get(key) {
+            match write_user_xattrs(&directory_path.join(key), &strip_source_config(attributes)) {
+                Ok(()) => metadata_applied += 1,
+                Err(e) => eprintln!("Failed to restore metadata for {}: {}", key, e),
+            }
+        }
+    }
+
+    Ok(ImportSummary {
+        files_written: written.len(),
+        files_skipped,
+        metadata_applied,
+        snapshot_id,
+    })
+}
+
+/// Copy the files a note links to under `attachments/`, rewriting the links to match.
+/// Links that lead outside `vault_root` (absolute paths, `../`, symlinks) are left alone so
+/// a bundle never picks up files from elsewhere on the machine. Returns the new content and
+/// the attachments as (bundle path, source) pairs
+fn bundle_attachments(
+    content: &str,
+    note_dir: &Path,
+    vault_root: &Path,
+) -> (String, Vec<(String, PathBuf)>) {
+    let mut attachments: Vec<(String, PathBuf)> = Vec::new();
+
+    let rewritten = LOCAL_LINK_REGEX.replace_all(content, |caps: &Captures| {
+        let target = &caps[2];
+        if target.contains("://") || target.starts_with('#') {
+            return caps[0].to_string();
+        }
+        let source = match note_dir.join(target).canonicalize() {
+            Ok(source) if source.starts_with(vault_root) && source.is_file() => source,
+            _ => return caps[0].to_string(),
+        };
+
+        let key = match attachments.iter().find(|(_, path)| *path == source) {
+            Some((key, _)) => key.clone(),
+            None => {
+                let name = source
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                // Different files with the same name get a numbered prefix
+                let mut key = format!("{}/{}", BUNDLE_ATTACHMENTS_DIR, name);
+                if attachments.iter().any(|(existing, _)| *existing == key) {
+                    key = format!("{}/{}-{}", BUNDLE_ATTACHMENTS_DIR, attachments.len(), name);
+                }
+                attachments.push((key.clone(), source));
+                key
+            }
+        };
+
+        format!("{}({}{})", &caps[1], key, &caps[3])
+    });
+
+    (rewritten.to_string(), attachments)
+}
+
+/// Everything about one day in a single place to hand off: the daily note with its linked
+/// attachments, its metadata as JSON and the day's commits across `repo_paths` as markdown.
+/// Written as a zip when `output_path` ends in `.zip`, otherwise as a folder
+pub fn export_day_bundle(
+    directory_path: &Path,
+    date: NaiveDate,
+    repo_paths: &[String],
+    output_path: &Path,
+) -> Result<DayBundleSummary, Box<dyn std::error::Error>> {
+    let note_path = find_daily_note_or_locked(directory_path, date);
+    if note_path.as_deref().is_some_and(is_locked_path) {
+        return Err(format!("The note for {} is locked; unlock it to export", date).into());
+    }
+
+    let stem = date.format("%Y-%m-%d").to_string();
+    let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+    let mut attachments = Vec::new();
+
+    if let Some(note_path) = &note_path {
+        let content = fs::read_to_string(note_path)?;
+        let note_dir = note_path.parent().unwrap_or(directory_path);
+        let vault_root = directory_path.canonicalize()?;
+        let (content, linked) = bundle_attachments(&content, note_dir, &vault_root);
+        attachments = linked;
+
+        entries.push((format!("{}.md", stem), content.into_bytes()));
+        for (key, source) in &attachments {
+            entries.push((key.clone(), fs::read(source)?));
+        }
+
+        let metadata = serde_json::json!({
+            "date": stem,
+            "file_name": note_path.file_name().map(|n| n.to_string_lossy().to_string()),
+            "xattrs": read_all_user_xattrs(note_path),
+        });
+        entries.push((
+            METADATA_ENTRY.to_string(),
+            serde_json::to_vec_pretty(&metadata)?,
+        ));
+    }
+
+    let commits = day_commits(directory_path, date, repo_paths)?;
+    let commits_markdown = format!(
+        "# {}\n\n{}\n",
+        stem,
+        render_commit_section(
+            &commits,
+            CommitLogFormat::List,
+            entry_timezone(directory_path, date)
+        )
+    );
+    entries.push((
+        BUNDLE_COMMITS_ENTRY.to_string(),
+        commits_markdown.into_bytes(),
+    ));
+
+    let zipped = output_path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"));
+    if zipped {
+        if let Some(parent) = output_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut zip = ZipWriter::new(File::create(output_path)?);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (key, bytes) in &entries {
+            zip.start_file(format!("{}/{}", stem, key), options)?;
+            zip.write_all(bytes)?;
+        }
+        zip.finish()?;
+    } else {
+        for (key, bytes) in &entries {
+            let target = output_path.join(key);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(target, bytes)?;
+        }
+    }
+
+    Ok(DayBundleSummary {
+        output_path: output_path.to_string_lossy().to_string(),
+        note_path: note_path.map(|path| path.to_string_lossy().to_string()),
+        attachment_count: attachments.len(),
+        commit_count: commits.len(),
+        zipped,
+    })
+}
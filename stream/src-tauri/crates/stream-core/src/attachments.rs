@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::capture::ASSETS_DIR;
+use crate::cloud::is_placeholder;
+use crate::lock::is_locked_path;
+use crate::paths::clean;
+use crate::site::LOCAL_LINK_REGEX;
+use crate::walk::walk_files;
+
+/// Orphaned attachments are moved here rather than deleted; directory walks skip it
+pub const TRASH_DIR: &str = ".trash";
+
+/// `src="..."` on inline HTML such as `<img>` and `<audio>`
+static HTML_SRC_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"\bsrc\s*=\s*["']([^"']+)["']"#).expect("Failed to compile HTML src regex")
+});
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OrphanedAttachment {
+    pub file_path: String,
+    pub size: u64,
+    /// Where the file was moved when trashing
+    pub trashed_to: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct OrphanedAttachmentsReport {
+    pub attachment_count: usize,
+    pub notes_scanned: usize,
+    /// Locked and online-only notes, whose links couldn't be checked
+    pub unreadable_notes: Vec<String>,
+    /// Largest first
+    pub orphans: Vec<OrphanedAttachment>,
+    pub orphaned_bytes: u64,
+    pub trashed: usize,
+}
+
+/// Decode `%XX` escapes, as written by `capture::image_link` for spaces
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| value.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+/// Local files a note links to, resolved against the note's folder
+fn linked_paths(content: &str, note_dir: &Path) -> Vec<PathBuf> {
+    let markdown = LOCAL_LINK_REGEX
+        .captures_iter(content)
+        .map(|caps| caps[2].to_string());
+    let html = HTML_SRC_REGEX
+        .captures_iter(content)
+        .map(|caps| caps[1].to_string());
+
+    markdown
+        .chain(html)
+        .filter(|target| !target.contains("://") && !target.starts_with(['#', '?']))
+        .map(|target| {
+            let target = target.trim_matches(['<', '>']);
+            let target = target.split(['#', '?']).next().unwrap_or(target);
+            clean(&note_dir.join(percent_decode(target)))
+        })
+        .collect()
+}
+
+/// Move `path` under `<directory>/.trash`, keeping its place relative to the notes
+/// directory and numbering it if that name is taken
+fn move_to_trash(
+    directory_path: &Path,
+    path: &Path,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let relative = path.strip_prefix(directory_path)?;
+    let mut destination = directory_path.join(TRASH_DIR).join(relative);
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let stem = path
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let extension = path
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_string());
+    let mut counter = 2;
+    while destination.exists() {
+        let name = match &extension {
+            Some(extension) => format!("{}-{}.{}", stem, counter, extension),
+            None => format!("{}-{}", stem, counter),
+        };
+        destination.set_file_name(name);
+        counter += 1;
+    }
+
+    fs::rename(path, &destination)?;
+    Ok(destination)
+}
+
+/// Files in the assets folder that no note links to, with their sizes. With `trash`, they
+/// are moved to `.trash`; that's refused while any note couldn't be read, since its links
+/// are unknown
+pub fn find_orphaned_attachments(
+    directory_path: &Path,
+    trash: bool,
+) -> Result<OrphanedAttachmentsReport, Box<dyn std::error::Error>> {
+    if !directory_path.is_dir() {
+        return Err(format!("Not a directory: {}", directory_path.display()).into());
+    }
+    let directory_path = clean(directory_path);
+
+    // The main walk skips the top-level assets folder, so notes never come from it
+    let notes = walk_files(&directory_path, |path, _| {
+        let is_note = path.extension().is_some_and(|ext| ext == "md");
+        (is_note || is_locked_path(path)).then(|| path.to_path_buf())
+    });
+    let attachments = walk_files(&directory_path.join(ASSETS_DIR), |path, metadata| {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        (!hidden).then(|| (clean(path), metadata.len()))
+    });
+
+    let mut report = OrphanedAttachmentsReport {
+        attachment_count: attachments.len(),
+        ..OrphanedAttachmentsReport::default()
+    };
+    let mut linked = HashSet::new();
+    for note in notes {
+        let content = if is_locked_path(&note) || is_placeholder(&note) {
+            None
+        } else {
+            fs::read_to_string(&note).ok()
+        };
+        match content {
+            Some(content) => {
+                let note_dir = note.parent().unwrap_or(&directory_path);
+                linked.extend(linked_paths(&content, note_dir));
+                report.notes_scanned += 1;
+            }
+            None => report
+                .unreadable_notes
+                .push(note.to_string_lossy().to_string()),
+        }
+    }
+    report.unreadable_notes.sort();
+
+    let mut orphans: Vec<(PathBuf, u64)> = attachments
+        .into_iter()
+        .filter(|(path, _)| !linked.contains(path))
+        .collect();
+    orphans.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    report.orphaned_bytes = orphans.iter().map(|(_, size)| size).sum();
+
+    if trash && !report.unreadable_notes.is_empty() {
+        return Err(format!(
+            "{} notes are locked or online-only; unlock or download them before trashing",
+            report.unreadable_notes.len()
+        )
+        .into());
+    }
+
+    for (path, size) in orphans {
+        let trashed_to = if trash {
+            match move_to_trash(&directory_path, &path) {
+                Ok(destination) => {
+                    report.trashed += 1;
+                    Some(destination.to_string_lossy().to_string())
+                }
+                Err(e) => {
+                    eprintln!("Failed to trash {}: {}", path.display(), e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        report.orphans.push(OrphanedAttachment {
+            file_path: path.to_string_lossy().to_string(),
+            size,
+            trashed_to,
+        });
+    }
+
+    Ok(report)
+}
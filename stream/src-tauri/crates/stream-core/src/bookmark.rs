@@ -0,0 +1,255 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path};
+use std::sync::LazyLock;
+
+use chrono::Local;
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use crate::capture::save_asset;
+use crate::file_lock::lock_note_file;
+use crate::sources::url::{decode_entities, fetch_text};
+use crate::unfurl::parse_preview;
+
+// Elements that never hold article text
+static BOILERPLATE_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    [
+        "script", "style", "noscript", "nav", "header", "footer", "aside", "form", "svg", "iframe",
+        "button",
+    ]
+    .iter()
+    .map(|tag| {
+        Regex::new(&format!(r"(?is)<{0}\b[^>]*>.*?</{0}>", tag))
+            .expect("Failed to compile boilerplate regex")
+    })
+    .collect()
+});
+static COMMENT_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<!--.*?-->").expect("Failed to compile comment regex"));
+static CONTAINER_REGEXES: LazyLock<Vec<Regex>> = LazyLock::new(|| {
+    ["article", "main", "body"]
+        .iter()
+        .map(|tag| {
+            Regex::new(&format!(r"(?is)<{0}\b[^>]*>(.*)</{0}>", tag))
+                .expect("Failed to compile container regex")
+        })
+        .collect()
+});
+static PRE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<pre\b[^>]*>(.*?)</pre>").expect("Failed to compile pre regex")
+});
+static HEADING_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<h([1-6])\b[^>]*>(.*?)</h[1-6]>").expect("Failed to compile heading regex")
+});
+static LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<a\b[^>]*?href\s*=\s*["']([^"']*)["'][^>]*>(.*?)</a>"#)
+        .expect("Failed to compile link regex")
+});
+static IMAGE_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<img\b[^>]*>").expect("Failed to compile image regex"));
+static IMAGE_ATTR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)\b(src|alt)\s*=\s*["']([^"']*)["']"#)
+        .expect("Failed to compile image attribute regex")
+});
+static STRONG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<(?:strong|b)\b[^>]*>(.*?)</(?:strong|b)>")
+        .expect("Failed to compile strong regex")
+});
+static EMPHASIS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<(?:em|i)\b[^>]*>(.*?)</(?:em|i)>").expect("Failed to compile em regex")
+});
+static CODE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<code\b[^>]*>(.*?)</code>").expect("Failed to compile code regex")
+});
+static LIST_ITEM_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<li\b[^>]*>").expect("Failed to compile li regex"));
+static BREAK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<br\s*/?>").expect("Failed to compile br regex"));
+static BLOCK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)</?(?:p|div|section|blockquote|ul|ol|table|tr|figure)\b[^>]*>")
+        .expect("Failed to compile block regex")
+});
+static TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<[^>]+>").expect("Failed to compile tag regex"));
+static BLANK_LINES_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\n{3,}").expect("Failed to compile blank line regex"));
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchivedPage {
+    pub url: String,
+    pub title: String,
+    pub snapshot_path: String,
+    pub note_path: String,
+}
+
+/// Resolve `href` against the page URL. Only absolute and root-relative links are kept
+fn absolute_url(base: &str, href: &str) -> Option<String> {
+    if href.starts_with("http://") || href.starts_with("https://") {
+        return Some(href.to_string());
+    }
+    if let Some(rest) = href.strip_prefix("//") {
+        let scheme = base.split("://").next().unwrap_or("https");
+        return Some(format!("{}://{}", scheme, rest));
+    }
+    if href.starts_with('/') {
+        let (scheme, rest) = base.split_once("://")?;
+        let host = rest.split('/').next()?;
+        return Some(format!("{}://{}{}", scheme, host, href));
+    }
+    None
+}
+
+fn inline_text(html: &str) -> String {
+    decode_entities(&TAG_REGEX.replace_all(html, ""))
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reduce a page to its main content as markdown: the first `<article>`, `<main>` or
+/// `<body>`, with navigation, scripts and other boilerplate removed
+pub fn html_to_readable_markdown(html: &str, base_url: &str) -> String {
+    let mut html = COMMENT_REGEX.replace_all(html, "").to_string();
+    for regex in BOILERPLATE_REGEXES.iter() {
+        html = regex.replace_all(&html, "").to_string();
+    }
+    let content = CONTAINER_REGEXES
+        .iter()
+        .find_map(|regex| regex.captures(&html).map(|c| c[1].to_string()))
+        .unwrap_or(html);
+
+    // Code blocks are set aside so later passes don't reflow them
+    let mut code_blocks: Vec<String> = Vec::new();
+    let text = PRE_REGEX.replace_all(&content, |caps: &Captures| {
+        let code = decode_entities(&TAG_REGEX.replace_all(&caps[1], ""));
+        code_blocks.push(format!("\n\n```\n{}\n```\n\n", code.trim_matches('\n')));
+        format!("\u{0}{}\u{0}", code_blocks.len() - 1)
+    });
+
+    let text = HEADING_REGEX.replace_all(&text, |caps: &Captures| {
+        let level: usize = caps[1].parse().unwrap_or(2);
+        format!("\n\n{} {}\n\n", "#".repeat(level), inline_text(&caps[2]))
+    });
+    let text = IMAGE_REGEX.replace_all(&text, |caps: &Captures| {
+        let mut src = None;
+        let mut alt = String::new();
+        for attr in IMAGE_ATTR_REGEX.captures_iter(&caps[0]) {
+            match attr[1].to_lowercase().as_str() {
+                "src" => src = absolute_url(base_url, &attr[2]),
+                _ => alt = decode_entities(&attr[2]),
+            }
+        }
+        src.map(|src| format!("![{}]({})", alt, src))
+            .unwrap_or_default()
+    });
+    let text = LINK_REGEX.replace_all(&text, |caps: &Captures| {
+        let label = inline_text(&caps[2]);
+        match absolute_url(base_url, &caps[1]) {
+            Some(href) if !label.is_empty() => format!("[{}]({})", label, href),
+            _ => label,
+        }
+    });
+    let text = STRONG_REGEX.replace_all(&text, "**$1**");
+    let text = EMPHASIS_REGEX.replace_all(&text, "*$1*");
+    let text = CODE_REGEX.replace_all(&text, "`$1`");
+    let text = LIST_ITEM_REGEX.replace_all(&text, "\n- ");
+    let text = BREAK_REGEX.replace_all(&text, "\n");
+    let text = BLOCK_REGEX.replace_all(&text, "\n\n");
+    let text = decode_entities(&TAG_REGEX.replace_all(&text, ""));
+
+    let lines: Vec<String> = text
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    let mut markdown = BLANK_LINES_REGEX
+        .replace_all(lines.join("\n").trim(), "\n\n")
+        .to_string();
+
+    for (index, block) in code_blocks.iter().enumerate() {
+        if let Some((before, after)) = markdown.split_once(&format!("\u{0}{}\u{0}", index)) {
+            markdown = format!(
+                "{}\n\n{}\n\n{}",
+                before.trim_end(),
+                block.trim(),
+                after.trim_start()
+            );
+        }
+    }
+
+    format!("{}\n", markdown.trim())
+}
+
+/// Link from a note to a path relative to the notes directory
+fn link_from_note(directory_path: &Path, note_path: &Path, relative_path: &str) -> String {
+    let depth = note_path
+        .parent()
+        .and_then(|parent| parent.strip_prefix(directory_path).ok())
+        .map(|p| {
+            p.components()
+                .filter(|c| matches!(c, Component::Normal(_)))
+                .count()
+        })
+        .unwrap_or(0);
+
+    format!("{}{}", "../".repeat(depth), relative_path).replace(' ', "%20")
+}
+
+/// Save a readable markdown snapshot of `url` in the assets folder and add a link to it,
+/// next to the original URL, at the end of `note_path`
+pub fn archive_url(
+    directory_path: &Path,
+    url: &str,
+    note_path: &Path,
+) -> Result<ArchivedPage, Box<dyn std::error::Error>> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("Not a web URL: {}", url).into());
+    }
+    if !note_path.is_file() {
+        return Err(format!("Note not found: {}", note_path.display()).into());
+    }
+
+    let (html, content_type) = fetch_text(url)?;
+    let now = Local::now();
+    let title = parse_preview(url, &html)
+        .title
+        .unwrap_or_else(|| url.to_string());
+    let body = if content_type.contains("html") {
+        html_to_readable_markdown(&html, url)
+    } else {
+        html
+    };
+
+    let snapshot = format!(
+        "# {}\n\n> Archived from <{}> on {}\n\n{}",
+        title,
+        url,
+        now.format("%Y-%m-%d %H:%M"),
+        body
+    );
+    let relative_path = save_asset(directory_path, "archive", "md", snapshot.as_bytes(), now)?;
+
+    let _lock = lock_note_file(note_path)?;
+    let existing = fs::read(note_path)?;
+    let mut note = fs::OpenOptions::new().append(true).open(note_path)?;
+    if !existing.is_empty() && !existing.ends_with(b"\n") {
+        note.write_all(b"\n")?;
+    }
+    writeln!(
+        note,
+        "\n- [{}]({}) ([archived]({}))",
+        title.replace('[', "(").replace(']', ")"),
+        url,
+        link_from_note(directory_path, note_path, &relative_path)
+    )?;
+
+    Ok(ArchivedPage {
+        url: url.to_string(),
+        title,
+        snapshot_path: directory_path
+            .join(&relative_path)
+            .to_string_lossy()
+            .to_string(),
+        note_path: note_path.to_string_lossy().to_string(),
+    })
+}
@@ -0,0 +1,328 @@
+use std::time::Duration;
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// How far ahead recurring events are expanded when looking for the next one
+const RECURRENCE_LOOKAHEAD_DAYS: i64 = 14;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CalendarEvent {
+    pub title: String,
+    /// Unix milliseconds
+    pub start: u64,
+    pub end: Option<u64>,
+    pub location: Option<String>,
+    pub all_day: bool,
+}
+
+/// The parts of an `RRULE` that are expanded: daily and weekly repeats
+#[derive(Debug, Clone)]
+struct Recurrence {
+    weekly: bool,
+    interval: i64,
+    by_day: Vec<Weekday>,
+    until: Option<NaiveDate>,
+    count: Option<i64>,
+}
+
+/// The time zone an event's times are written in, which its repeats keep to across
+/// daylight saving changes
+#[derive(Debug, Clone, Copy)]
+enum Zone {
+    Utc,
+    Named(chrono_tz::Tz),
+    Local,
+}
+
+impl Zone {
+    fn to_millis(self, naive: &NaiveDateTime) -> Option<u64> {
+        let millis = match self {
+            Zone::Utc => Utc.from_utc_datetime(naive).timestamp_millis(),
+            Zone::Named(tz) => tz.from_local_datetime(naive).earliest()?.timestamp_millis(),
+            Zone::Local => Local
+                .from_local_datetime(naive)
+                .earliest()?
+                .timestamp_millis(),
+        };
+        Some(millis.max(0) as u64)
+    }
+}
+
+/// An event as read from the feed, before recurrences are expanded
+#[derive(Debug, Clone)]
+struct FeedEvent {
+    event: CalendarEvent,
+    /// When the first occurrence starts, as written
+    start: Option<(NaiveDateTime, Zone)>,
+    recurrence: Option<Recurrence>,
+}
+
+/// Join folded lines: a line starting with a space or tab continues the previous one
+fn unfold(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some(last)) => last.push_str(rest),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => out.push('\n'),
+            Some(other) => out.push(other),
+            None => {}
+        }
+    }
+    out
+}
+
+/// A `DTSTART`/`DTEND` value and its zone. Whole days are taken as starting at local
+/// midnight, and times without `Z` or a known `TZID` as local
+fn parse_time(params: &str, value: &str) -> Option<(NaiveDateTime, Zone, bool)> {
+    let value = value.trim();
+    if value.len() == 8 {
+        let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+        return Some((date.and_hms_opt(0, 0, 0)?, Zone::Local, true));
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value.trim_end_matches('Z'), "%Y%m%dT%H%M%S").ok()?;
+    let zone = if value.ends_with('Z') {
+        Zone::Utc
+    } else {
+        params
+            .split(';')
+            .find_map(|param| param.strip_prefix("TZID="))
+            .and_then(|tzid| tzid.trim_matches('"').parse::<chrono_tz::Tz>().ok())
+            .map_or(Zone::Local, Zone::Named)
+    };
+    Some((naive, zone, false))
+}
+
+fn parse_weekday(value: &str) -> Option<Weekday> {
+    // Ordinals like `1MO` only mean something for monthly rules
+    match value.trim_start_matches(|c: char| c == '-' || c == '+' || c.is_ascii_digit()) {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn parse_rrule(value: &str) -> Option<Recurrence> {
+    let mut recurrence = Recurrence {
+        weekly: false,
+        interval: 1,
+        by_day: Vec::new(),
+        until: None,
+        count: None,
+    };
+    for part in value.split(';') {
+        let (name, value) = part.split_once('=')?;
+        match name {
+            "FREQ" => match value {
+                "DAILY" => recurrence.weekly = false,
+                "WEEKLY" => recurrence.weekly = true,
+                _ => return None,
+            },
+            "INTERVAL" => recurrence.interval = value.parse::<i64>().ok()?.max(1),
+            "BYDAY" => recurrence.by_day = value.split(',').filter_map(parse_weekday).collect(),
+            "UNTIL" => recurrence.until = NaiveDate::parse_from_str(value.get(..8)?, "%Y%m%d").ok(),
+            "COUNT" => recurrence.count = value.parse().ok(),
+            _ => {}
+        }
+    }
+    Some(recurrence)
+}
+
+fn parse_feed(text: &str) -> Vec<FeedEvent> {
+    let mut events = Vec::new();
+    // The event being read, whether it has a start, and whether it was cancelled
+    let mut current: Option<(FeedEvent, bool, bool)> = None;
+
+    for line in unfold(text) {
+        match line.as_str() {
+            "BEGIN:VEVENT" => {
+                let event = CalendarEvent {
+                    title: String::new(),
+                    start: 0,
+                    end: None,
+                    location: None,
+                    all_day: false,
+                };
+                let feed = FeedEvent {
+                    event,
+                    start: None,
+                    recurrence: None,
+                };
+                current = Some((feed, false, false));
+                continue;
+            }
+            "END:VEVENT" => {
+                if let Some((feed, true, false)) = current.take() {
+                    events.push(feed);
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let Some((feed, has_start, cancelled)) = current.as_mut() else {
+            continue;
+        };
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let (name, params) = name.split_once(';').unwrap_or((name, ""));
+        let event = &mut feed.event;
+        match name {
+            "SUMMARY" => event.title = unescape(value),
+            "LOCATION" => event.location = Some(unescape(value)).filter(|l| !l.is_empty()),
+            "DTSTART" => {
+                let parsed = parse_time(params, value);
+                if let Some((naive, zone, all_day)) = parsed {
+                    if let Some(start) = zone.to_millis(&naive) {
+                        event.start = start;
+                        event.all_day = all_day;
+                        feed.start = Some((naive, zone));
+                        *has_start = true;
+                    }
+                }
+            }
+            "DTEND" => {
+                event.end =
+                    parse_time(params, value).and_then(|(naive, zone, _)| zone.to_millis(&naive));
+            }
+            "RRULE" => feed.recurrence = parse_rrule(value),
+            "STATUS" => *cancelled = value.eq_ignore_ascii_case("CANCELLED"),
+            _ => {}
+        }
+    }
+    events
+}
+
+/// Occurrences of a recurring event starting from `from` through the lookahead window
+fn occurrences(feed: &FeedEvent, recurrence: &Recurrence, from: NaiveDate) -> Vec<CalendarEvent> {
+    let Some((first, zone)) = feed.start else {
+        return Vec::new();
+    };
+    let first_day = first.date();
+    let duration = feed
+        .event
+        .end
+        .map(|end| end.saturating_sub(feed.event.start));
+    let by_day = if recurrence.by_day.is_empty() {
+        vec![first_day.weekday()]
+    } else {
+        recurrence.by_day.clone()
+    };
+    // A count only bounds the dates simply when there's one occurrence per period
+    let last_by_count = recurrence
+        .count
+        .filter(|_| !recurrence.weekly || by_day.len() == 1)
+        .map(|count| {
+            let periods = (count - 1).max(0) * recurrence.interval;
+            let days = if recurrence.weekly {
+                periods * 7
+            } else {
+                periods
+            };
+            first_day + chrono::Duration::days(days)
+        });
+
+    (0..=RECURRENCE_LOOKAHEAD_DAYS)
+        .map(|offset| from.max(first_day) + chrono::Duration::days(offset))
+        .filter(|day| {
+            let in_period = if recurrence.weekly {
+                let week_start = |d: NaiveDate| {
+                    d - chrono::Duration::days(d.weekday().num_days_from_monday() as i64)
+                };
+                let weeks = (week_start(*day) - week_start(first_day)).num_days() / 7;
+                weeks % recurrence.interval == 0 && by_day.contains(&day.weekday())
+            } else {
+                (*day - first_day).num_days() % recurrence.interval == 0
+            };
+            in_period
+                && recurrence.until.is_none_or(|until| *day <= until)
+                && last_by_count.is_none_or(|last| *day <= last)
+        })
+        .filter_map(|day| {
+            let start = zone.to_millis(&day.and_time(first.time()))?;
+            Some(CalendarEvent {
+                start,
+                end: duration.map(|duration| start + duration),
+                ..feed.event.clone()
+            })
+        })
+        .collect()
+}
+
+/// Every event in `text`, with repeats expanded from the day before `day`
+fn expanded_events(text: &str, day: NaiveDate) -> Vec<CalendarEvent> {
+    parse_feed(text)
+        .iter()
+        .flat_map(|feed| match &feed.recurrence {
+            Some(recurrence) => occurrences(feed, recurrence, day - chrono::Duration::days(1)),
+            None => vec![feed.event.clone()],
+        })
+        .collect()
+}
+
+/// The timed event in `text` that is under way or starts soonest after `now` (Unix
+/// milliseconds). All-day events are skipped; daily and weekly repeats are expanded
+pub fn next_event(text: &str, now: u64) -> Option<CalendarEvent> {
+    let today = Local
+        .timestamp_millis_opt(now as i64)
+        .single()?
+        .date_naive();
+    expanded_events(text, today)
+        .into_iter()
+        .filter(|event| !event.all_day)
+        .filter(|event| event.end.unwrap_or(event.start) > now || event.start >= now)
+        .min_by_key(|event| event.start)
+}
+
+/// Events in `text` that take place on `date` in local time, all-day ones included,
+/// ordered by start
+pub fn events_on(text: &str, date: NaiveDate) -> Vec<CalendarEvent> {
+    let midnight = |date: NaiveDate| Zone::Local.to_millis(&date.and_hms_opt(0, 0, 0)?);
+    let (Some(day_start), Some(day_end)) = (midnight(date), date.succ_opt().and_then(midnight))
+    else {
+        return Vec::new();
+    };
+
+    let mut events: Vec<CalendarEvent> = expanded_events(text, date)
+        .into_iter()
+        .filter(|event| {
+            event.start < day_end
+                && (event.end.unwrap_or(event.start) > day_start || event.start >= day_start)
+        })
+        .collect();
+    events.sort_by_key(|event| (!event.all_day, event.start));
+    events
+}
+
+/// Download an iCalendar feed; `webcal://` links are fetched over HTTPS
+pub fn fetch_ics(url: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let url = match url.trim().strip_prefix("webcal://") {
+        Some(rest) => format!("https://{}", rest),
+        None => url.trim().to_string(),
+    };
+    let agent = crate::net::agent_builder().timeout(FETCH_TIMEOUT).build();
+    Ok(agent.get(&url).call()?.into_string()?)
+}
@@ -0,0 +1,82 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+
+use crate::markdown::append_to_daily_note;
+
+/// Folder inside the notes directory where captured images and recordings are stored
+pub const ASSETS_DIR: &str = "assets";
+
+/// Write captured bytes to `assets/<prefix>-YYYYMMDD-HHMMSS.<ext>`, adding a counter if
+/// that name is taken. Returns the path relative to the notes directory
+pub fn save_asset(
+    directory_path: &Path,
+    prefix: &str,
+    extension: &str,
+    data: &[u8],
+    captured_at: DateTime<Local>,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let assets_dir = directory_path.join(ASSETS_DIR);
+    fs::create_dir_all(&assets_dir)?;
+
+    let stem = format!("{}-{}", prefix, captured_at.format("%Y%m%d-%H%M%S"));
+    let mut file_name = format!("{}.{}", stem, extension);
+    let mut counter = 2;
+    while assets_dir.join(&file_name).exists() {
+        file_name = format!("{}-{}.{}", stem, counter, extension);
+        counter += 1;
+    }
+
+    fs::write(assets_dir.join(&file_name), data)?;
+
+    Ok(format!("{}/{}", ASSETS_DIR, file_name))
+}
+
+/// Encode 8-bit RGBA pixels as PNG
+pub fn encode_png(
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let mut data = Vec::new();
+    let mut encoder = png::Encoder::new(&mut data, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(data)
+}
+
+/// Markdown image link for an asset saved by `save_asset`
+pub fn image_link(alt: &str, relative_path: &str) -> String {
+    format!("![{}]({})", alt, relative_path.replace(' ', "%20"))
+}
+
+/// Append a captured item to the daily note under a `**HH:MM** label` heading line
+pub fn append_capture(
+    directory_path: &Path,
+    captured_at: DateTime<Local>,
+    label: &str,
+    source: Option<&str>,
+    body: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let heading = match source {
+        Some(source) if !source.is_empty() => {
+            format!(
+                "**{}** {} from {}",
+                captured_at.format("%H:%M"),
+                label,
+                source
+            )
+        }
+        _ => format!("**{}** {}", captured_at.format("%H:%M"), label),
+    };
+
+    append_to_daily_note(
+        directory_path,
+        captured_at.date_naive(),
+        &format!("\n{}\n\n{}", heading, body.trim()),
+    )
+}
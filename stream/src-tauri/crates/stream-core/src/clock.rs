@@ -0,0 +1,7 @@
+/// Milliseconds since the Unix epoch, the timestamp stored throughout the app
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,97 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// Set on APFS files whose contents live only in the cloud (iCloud Drive, File Provider)
+#[cfg(target_os = "macos")]
+const SF_DATALESS: u32 = 0x4000_0000;
+
+#[cfg(windows)]
+const FILE_ATTRIBUTE_OFFLINE: u32 = 0x0000_1000;
+#[cfg(windows)]
+const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+#[cfg(windows)]
+const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+
+/// Whether a file's contents are on disk or still held by a cloud provider
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadState {
+    Local,
+    Placeholder,
+}
+
+#[cfg(target_os = "macos")]
+pub fn download_state(metadata: &fs::Metadata) -> DownloadState {
+    use std::os::macos::fs::MetadataExt;
+
+    if metadata.st_flags() & SF_DATALESS != 0 {
+        DownloadState::Placeholder
+    } else {
+        DownloadState::Local
+    }
+}
+
+#[cfg(windows)]
+pub fn download_state(metadata: &fs::Metadata) -> DownloadState {
+    use std::os::windows::fs::MetadataExt;
+
+    let placeholder_flags = FILE_ATTRIBUTE_OFFLINE
+        | FILE_ATTRIBUTE_RECALL_ON_OPEN
+        | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS;
+    if metadata.file_attributes() & placeholder_flags != 0 {
+        DownloadState::Placeholder
+    } else {
+        DownloadState::Local
+    }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+pub fn download_state(_metadata: &fs::Metadata) -> DownloadState {
+    DownloadState::Local
+}
+
+/// Checks the file without reading it, so it never triggers a download
+pub fn is_placeholder(file_path: &Path) -> bool {
+    fs::symlink_metadata(file_path)
+        .map(|m| download_state(&m) == DownloadState::Placeholder)
+        .unwrap_or(false)
+}
+
+/// Ask the cloud provider to download a placeholder file. Blocks until it's available
+pub fn materialize_file(file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if !file_path.is_file() {
+        return Err(format!("Not a file: {}", file_path.display()).into());
+    }
+    if !is_placeholder(file_path) {
+        return Ok(());
+    }
+
+    // iCloud Drive has a dedicated tool; other File Provider and Windows cloud
+    // filters hydrate the file when its data is first read
+    #[cfg(target_os = "macos")]
+    {
+        let status = std::process::Command::new("brctl")
+            .arg("download")
+            .arg(file_path)
+            .status();
+        if matches!(status, Ok(s) if s.success()) && !is_placeholder(file_path) {
+            return Ok(());
+        }
+    }
+
+    let mut buffer = [0u8; 1];
+    File::open(file_path)?
+        .read_exact(&mut buffer)
+        .or_else(|e| {
+            if e.kind() == std::io::ErrorKind::UnexpectedEof {
+                Ok(())
+            } else {
+                Err(e)
+            }
+        })?;
+
+    Ok(())
+}
@@ -0,0 +1,116 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::git::{self, CommitImpact, GitCommit};
+use crate::worklog::csv_field;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitExportFormat {
+    Csv,
+    Json,
+}
+
+impl CommitExportFormat {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Some(CommitExportFormat::Csv),
+            "json" => Some(CommitExportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A commit as exported: everything the app shows plus its line counts
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportedCommit {
+    #[serde(flatten)]
+    pub commit: GitCommit,
+    pub stats: CommitImpact,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitExportSummary {
+    pub output_path: String,
+    pub commit_count: usize,
+    /// Repositories that couldn't be read, with why
+    pub errors: Vec<String>,
+}
+
+pub fn render_commits_csv(commits: &[ExportedCommit]) -> String {
+    let mut csv = String::from(
+        "id,date,timestamp,repo_path,author_name,author_email,branches,type,scope,tickets,\
+         pull_request,projects,files_changed,insertions,deletions,message\n",
+    );
+    for ExportedCommit { commit, stats } in commits {
+        let conventional = commit.conventional.as_ref();
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+            commit.id,
+            csv_field(&commit.date),
+            commit.timestamp,
+            csv_field(&commit.repo_path),
+            csv_field(&commit.author_name),
+            csv_field(&commit.author_email),
+            csv_field(&commit.branches.join(";")),
+            csv_field(conventional.map(|c| c.kind.as_str()).unwrap_or("")),
+            csv_field(conventional.and_then(|c| c.scope.as_deref()).unwrap_or("")),
+            csv_field(&commit.tickets.join(";")),
+            commit
+                .pull_request
+                .as_ref()
+                .map(|pr| pr.number.to_string())
+                .unwrap_or_default(),
+            csv_field(&commit.projects.join(";")),
+            stats.files_changed,
+            stats.insertions,
+            stats.deletions,
+            csv_field(commit.message.trim_end()),
+        ));
+    }
+    csv
+}
+
+/// Write every commit in `repo_paths` between the timestamps (ms, inclusive), oldest first,
+/// with line counts, ticket ids and conventional-commit fields, to `output_path`
+pub fn export_commits(
+    repo_paths: &[String],
+    start_timestamp: u64,
+    end_timestamp: u64,
+    output_path: &Path,
+    format: CommitExportFormat,
+) -> Result<CommitExportSummary, Box<dyn std::error::Error>> {
+    let mut errors = Vec::new();
+    let mut commits = Vec::new();
+    for repo in git::get_git_commits_for_repos(repo_paths, start_timestamp, end_timestamp) {
+        if let Some(error) = repo.error {
+            errors.push(format!("{}: {}", repo.repo_path, error));
+        }
+        commits.extend(repo.commits);
+    }
+    commits.sort_by_key(|commit| commit.timestamp);
+
+    let impacts = git::get_commit_impacts(&commits);
+    let exported: Vec<ExportedCommit> = commits
+        .into_iter()
+        .zip(impacts)
+        .map(|(commit, stats)| ExportedCommit { commit, stats })
+        .collect();
+
+    let contents = match format {
+        CommitExportFormat::Csv => render_commits_csv(&exported),
+        CommitExportFormat::Json => serde_json::to_string_pretty(&exported)?,
+    };
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output_path, contents)?;
+
+    Ok(CommitExportSummary {
+        output_path: output_path.to_string_lossy().to_string(),
+        commit_count: exported.len(),
+        errors,
+    })
+}
@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, NaiveDate};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::file_lock::lock_note_file;
+use crate::git::{self, GitCommit};
+use crate::lock::ensure_unlocked;
+use crate::markdown::{
+    append_to_daily_note, entry_day_bounds, entry_timezone, writable_daily_note,
+};
+
+/// The commit section is kept between these markers so re-running replaces it in place
+const COMMITS_START_MARKER: &str = "<!-- commits -->";
+const COMMITS_END_MARKER: &str = "<!-- /commits -->";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitLogFormat {
+    /// `- 14:02 [abc1234](url) message (repo)`
+    #[default]
+    List,
+    /// A table with time, repository, commit and message columns
+    Table,
+}
+
+impl CommitLogFormat {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "list" => Some(CommitLogFormat::List),
+            "table" => Some(CommitLogFormat::Table),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitLogSummary {
+    pub note_path: String,
+    pub commit_count: usize,
+    /// False when the note already had a commit section that was replaced
+    pub appended: bool,
+}
+
+fn repo_name(repo_path: &str) -> String {
+    Path::new(repo_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| repo_path.to_string())
+}
+
+fn commit_ref(commit: &GitCommit) -> String {
+    let short = &commit.id[..7.min(commit.id.len())];
+    match &commit.url {
+        Some(url) => format!("[`{}`]({})", short, url),
+        None => format!("`{}`", short),
+    }
+}
+
+/// The commit's clock time in `timezone`, the day's note's, matching the bounds the commits
+/// were picked by. The system's when None
+fn commit_time(commit: &GitCommit, timezone: Option<Tz>) -> String {
+    DateTime::from_timestamp_millis(commit.timestamp as i64)
+        .map(|dt| match timezone {
+            Some(tz) => dt.with_timezone(&tz).format("%H:%M").to_string(),
+            None => dt.with_timezone(&Local).format("%H:%M").to_string(),
+        })
+        .unwrap_or_default()
+}
+
+fn first_line(message: &str) -> &str {
+    message.lines().next().unwrap_or("").trim()
+}
+
+fn commit_list_item(commit: &GitCommit, timezone: Option<Tz>) -> String {
+    format!(
+        "- {} {} {} ({})",
+        commit_time(commit, timezone),
+        commit_ref(commit),
+        first_line(&commit.message),
+        repo_name(&commit.repo_path)
+    )
+}
+
+/// Render commits as a marked markdown section, oldest first, with times in `timezone`
+pub fn render_commit_section(
+    commits: &[GitCommit],
+    format: CommitLogFormat,
+    timezone: Option<Tz>,
+) -> String {
+    let mut lines = vec![COMMITS_START_MARKER.to_string(), "## Commits".to_string()];
+    lines.push(String::new());
+
+    if commits.is_empty() {
+        lines.push("No commits.".to_string());
+    } else {
+        match format {
+            CommitLogFormat::List => {
+                for commit in commits {
+                    lines.push(commit_list_item(commit, timezone));
+                }
+            }
+            CommitLogFormat::Table => {
+                lines.push("| Time | Repository | Commit | Message |".to_string());
+                lines.push("| --- | --- | --- | --- |".to_string());
+                for commit in commits {
+                    lines.push(format!(
+                        "| {} | {} | {} | {} |",
+                        commit_time(commit, timezone),
+                        repo_name(&commit.repo_path),
+                        commit_ref(commit),
+                        first_line(&commit.message).replace('|', "\\|")
+                    ));
+                }
+            }
+        }
+    }
+
+    lines.push(COMMITS_END_MARKER.to_string());
+    lines.join("\n")
+}
+
+/// Replace the marked commit section in `content`, or append one. Returns the new content
+/// and whether the section was appended
+fn upsert_commit_section(content: &str, section: &str) -> (String, bool) {
+    if let Some(start) = content.find(COMMITS_START_MARKER) {
+        if let Some(end_offset) = content[start..].find(COMMITS_END_MARKER) {
+            let end = start + end_offset + COMMITS_END_MARKER.len();
+            let updated = format!("{}{}{}", &content[..start], section, &content[end..]);
+            return (updated, false);
+        }
+    }
+
+    let trimmed = content.trim_end();
+    let updated = if trimmed.is_empty() {
+        format!("{}\n", section)
+    } else {
+        format!("{}\n\n{}\n", trimmed, section)
+    };
+    (updated, true)
+}
+
+/// Commits across `repo_paths` made on the day of `date`'s entry, oldest first
+pub(crate) fn day_commits(
+    directory_path: &Path,
+    date: NaiveDate,
+    repo_paths: &[String],
+) -> Result<Vec<GitCommit>, Box<dyn std::error::Error>> {
+    let (start_seconds, end_seconds) = entry_day_bounds(directory_path, date)
+        .ok_or_else(|| format!("Invalid local date: {}", date))?;
+
+    let mut commits: Vec<GitCommit> = git::get_git_commits_for_repos(
+        repo_paths,
+        start_seconds as u64 * 1000,
+        end_seconds as u64 * 1000,
+    )
+    .into_iter()
+    .flat_map(|repo| repo.commits)
+    .collect();
+    // Each repository lists newest first; reverse before the stable sort so commits made
+    // in the same second keep their order
+    commits.reverse();
+    commits.sort_by_key(|commit| commit.timestamp);
+    Ok(commits)
+}
+
+/// Write the day's commits across `repo_paths` into the daily note for `date`, creating
+/// the note if needed. Re-running replaces the previously inserted section. Fails when the
+/// note is locked
+pub fn insert_commits_into_note(
+    directory_path: &Path,
+    date: NaiveDate,
+    repo_paths: &[String],
+    format: CommitLogFormat,
+) -> Result<CommitLogSummary, Box<dyn std::error::Error>> {
+    let commits = day_commits(directory_path, date, repo_paths)?;
+    let timezone = entry_timezone(directory_path, date);
+
+    let note_path: PathBuf = writable_daily_note(directory_path, date)?;
+    let _lock = lock_note_file(&note_path)?;
+    ensure_unlocked(&note_path)?;
+    let content = fs::read_to_string(&note_path).unwrap_or_default();
+    let (updated, appended) =
+        upsert_commit_section(&content, &render_commit_section(&commits, format, timezone));
+    fs::write(&note_path, updated)?;
+
+    Ok(CommitLogSummary {
+        note_path: note_path.to_string_lossy().to_string(),
+        commit_count: commits.len(),
+        appended,
+    })
+}
+
+/// Append one commit, e.g. from a post-commit hook, to today's daily note as a list item.
+/// Returns the note's path
+pub fn log_commit(
+    directory_path: &Path,
+    repo_path: &str,
+    rev: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let commit = git::get_commit(repo_path, rev)?;
+    let date = Local::now().date_naive();
+    append_to_daily_note(
+        directory_path,
+        date,
+        &commit_list_item(&commit, entry_timezone(directory_path, date)),
+    )
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::git::GitCommit;
+
+// `type(scope)!: subject`, with the scope and `!` optional
+static HEADER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([A-Za-z]+)(?:\(([^()]*)\))?(!)?:\s+(.+)$")
+        .expect("Failed to compile conventional commit regex")
+});
+
+/// Structured form of a conventional commit message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConventionalCommit {
+    /// Lowercased type, e.g. `feat` or `fix`
+    pub kind: String,
+    pub scope: Option<String>,
+    pub subject: String,
+    pub breaking: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitTypeCount {
+    pub kind: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitTypeStats {
+    pub total: usize,
+    /// Most common first
+    pub by_type: Vec<CommitTypeCount>,
+    pub breaking: usize,
+    /// Commits whose message doesn't follow the convention
+    pub unconventional: usize,
+}
+
+/// Parse a full commit message. A `BREAKING CHANGE:` footer marks the commit as breaking
+/// just like `!` in the header
+pub fn parse_conventional_commit(message: &str) -> Option<ConventionalCommit> {
+    let header = message.lines().next()?.trim();
+    let caps = HEADER_REGEX.captures(header)?;
+
+    let breaking = caps.get(3).is_some()
+        || message.lines().skip(1).any(|line| {
+            line.starts_with("BREAKING CHANGE:") || line.starts_with("BREAKING-CHANGE:")
+        });
+
+    Some(ConventionalCommit {
+        kind: caps[1].to_lowercase(),
+        scope: caps
+            .get(2)
+            .map(|scope| scope.as_str().trim().to_string())
+            .filter(|scope| !scope.is_empty()),
+        subject: caps[4].trim().to_string(),
+        breaking,
+    })
+}
+
+/// Drop commits whose conventional type is one of `kinds` (case-insensitive)
+pub fn exclude_commit_types(commits: &mut Vec<GitCommit>, kinds: &[String]) {
+    if kinds.is_empty() {
+        return;
+    }
+    commits.retain(|commit| {
+        commit.conventional.as_ref().is_none_or(|parsed| {
+            !kinds
+                .iter()
+                .any(|kind| kind.eq_ignore_ascii_case(&parsed.kind))
+        })
+    });
+}
+
+/// How many commits there are of each conventional type
+pub fn commit_type_stats<'a>(commits: impl IntoIterator<Item = &'a GitCommit>) -> CommitTypeStats {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut total = 0;
+    let mut breaking = 0;
+    let mut unconventional = 0;
+
+    for commit in commits {
+        total += 1;
+        match &commit.conventional {
+            Some(parsed) => {
+                *counts.entry(parsed.kind.as_str()).or_insert(0) += 1;
+                if parsed.breaking {
+                    breaking += 1;
+                }
+            }
+            None => unconventional += 1,
+        }
+    }
+
+    let mut by_type: Vec<CommitTypeCount> = counts
+        .into_iter()
+        .map(|(kind, count)| CommitTypeCount {
+            kind: kind.to_string(),
+            count,
+        })
+        .collect();
+    by_type.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.kind.cmp(&b.kind)));
+
+    CommitTypeStats {
+        total,
+        by_type,
+        breaking,
+        unconventional,
+    }
+}
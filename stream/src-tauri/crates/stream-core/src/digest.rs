@@ -0,0 +1,196 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{Datelike, IsoWeek, Local, NaiveDate, TimeZone, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::git::get_git_commits_for_repos;
+use crate::markdown::{daily_notes_in_range, extract_tags};
+
+const DIGESTS_DIR: &str = "structured/digests";
+const MAX_COMMITS_PER_REPO: usize = 20;
+const MAX_TAGS: usize = 10;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DigestSummary {
+    pub file_path: String,
+    pub week: String,
+    pub note_count: usize,
+    pub word_count: usize,
+    pub commit_count: usize,
+}
+
+/// Parse an ISO week like `2024-W05`
+pub fn parse_iso_week(week: &str) -> Option<IsoWeek> {
+    let (year, number) = week.split_once("-W")?;
+    let monday =
+        NaiveDate::from_isoywd_opt(year.parse().ok()?, number.parse().ok()?, Weekday::Mon)?;
+    Some(monday.iso_week())
+}
+
+pub fn format_iso_week(week: IsoWeek) -> String {
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+/// The most recent week that has fully ended
+pub fn last_complete_week(today: NaiveDate) -> IsoWeek {
+    (today - chrono::Duration::days(7)).iso_week()
+}
+
+pub fn digest_path(directory_path: &Path, week: IsoWeek) -> PathBuf {
+    directory_path
+        .join(DIGESTS_DIR)
+        .join(format!("{}.md", format_iso_week(week)))
+}
+
+fn repo_name(repo_path: &str) -> String {
+    Path::new(repo_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| repo_path.to_string())
+}
+
+/// Push note headings down so they nest under the digest's per-day headings
+fn demote_headings(content: &str) -> String {
+    let mut in_code_block = false;
+    content
+        .lines()
+        .map(|line| {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+            }
+            if !in_code_block && line.starts_with('#') {
+                format!("###{}", line)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Build a markdown digest of one ISO week: stats, top tags, commits per repository
+/// and the week's daily notes, written to `structured/digests/<week>.md`
+pub fn generate_weekly_digest(
+    directory_path: &Path,
+    week: IsoWeek,
+    repo_paths: &[String],
+) -> Result<DigestSummary, Box<dyn std::error::Error>> {
+    let monday = NaiveDate::from_isoywd_opt(week.year(), week.week(), Weekday::Mon)
+        .ok_or("Invalid ISO week")?;
+    let sunday = monday + chrono::Duration::days(6);
+
+    let mut notes: Vec<(NaiveDate, String)> = Vec::new();
+    for (date, path) in daily_notes_in_range(directory_path, monday, sunday)? {
+        match fs::read_to_string(&path) {
+            Ok(content) => notes.push((date, content)),
+            Err(e) => eprintln!("Digest: skipping unreadable note {}: {}", path.display(), e),
+        }
+    }
+
+    let mut tag_counts: HashMap<String, usize> = HashMap::new();
+    let mut word_count = 0;
+    let mut busiest: Option<(NaiveDate, usize)> = None;
+    for (date, content) in &notes {
+        for tag in extract_tags(content) {
+            *tag_counts.entry(tag).or_default() += 1;
+        }
+        let words = content.split_whitespace().count();
+        word_count += words;
+        if busiest.is_none_or(|(_, most)| words > most) {
+            busiest = Some((*date, words));
+        }
+    }
+    let mut top_tags: Vec<(String, usize)> = tag_counts.into_iter().collect();
+    top_tags.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top_tags.truncate(MAX_TAGS);
+
+    let start_ms = Local
+        .from_local_datetime(&monday.and_hms_opt(0, 0, 0).ok_or("Invalid date")?)
+        .earliest()
+        .ok_or("Invalid local date")?
+        .timestamp_millis() as u64;
+    let end_ms = start_ms + 7 * 24 * 60 * 60 * 1000 - 1;
+    let repos = get_git_commits_for_repos(repo_paths, start_ms, end_ms);
+    let commit_count: usize = repos.iter().map(|r| r.commits.len()).sum();
+    let active_repos = repos.iter().filter(|r| !r.commits.is_empty()).count();
+
+    let mut out = format!(
+        "# Weekly digest: {} ({} – {})\n\n",
+        format_iso_week(week),
+        monday.format("%b %-d"),
+        sunday.format("%b %-d, %Y")
+    );
+
+    out.push_str("## Stats\n\n");
+    out.push_str(&format!("- Notes written: {}\n", notes.len()));
+    out.push_str(&format!("- Words: {}\n", word_count));
+    out.push_str(&format!(
+        "- Commits: {} across {} {}\n",
+        commit_count,
+        active_repos,
+        if active_repos == 1 {
+            "repository"
+        } else {
+            "repositories"
+        }
+    ));
+    if let Some((date, _)) = busiest {
+        out.push_str(&format!("- Busiest day: {}\n", date.format("%A (%b %-d)")));
+    }
+
+    if !top_tags.is_empty() {
+        out.push_str("\n## Top tags\n\n");
+        for (tag, count) in &top_tags {
+            out.push_str(&format!("- #{} ({})\n", tag, count));
+        }
+    }
+
+    if commit_count > 0 {
+        out.push_str("\n## Commits\n");
+        for repo in repos.iter().filter(|r| !r.commits.is_empty()) {
+            out.push_str(&format!(
+                "\n### {} ({})\n\n",
+                repo_name(&repo.repo_path),
+                repo.commits.len()
+            ));
+            for commit in repo.commits.iter().take(MAX_COMMITS_PER_REPO) {
+                let day = chrono::DateTime::from_timestamp_millis(commit.timestamp as i64)
+                    .map(|dt| dt.with_timezone(&Local).format("%a").to_string())
+                    .unwrap_or_default();
+                let summary = commit.message.lines().next().unwrap_or("").trim();
+                out.push_str(&format!("- {} ({})\n", summary, day));
+            }
+            if repo.commits.len() > MAX_COMMITS_PER_REPO {
+                out.push_str(&format!(
+                    "- …and {} more\n",
+                    repo.commits.len() - MAX_COMMITS_PER_REPO
+                ));
+            }
+        }
+    }
+
+    if !notes.is_empty() {
+        out.push_str("\n## Notes\n");
+        for (date, content) in &notes {
+            out.push_str(&format!("\n### {}\n\n", date.format("%A, %b %-d")));
+            out.push_str(demote_headings(content.trim()).trim());
+            out.push('\n');
+        }
+    }
+
+    let path = digest_path(directory_path, week);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, out)?;
+
+    Ok(DigestSummary {
+        file_path: path.to_string_lossy().to_string(),
+        week: format_iso_week(week),
+        note_count: notes.len(),
+        word_count,
+        commit_count,
+    })
+}
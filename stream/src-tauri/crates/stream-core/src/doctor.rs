@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::archive::collect_files;
+use crate::cloud::is_placeholder;
+use crate::markdown::{
+    mood_score, parse_date_from_filename, RefreshInterval, XATTR_CITY_KEY, XATTR_COUNTRY_KEY,
+    XATTR_LAST_REFRESHED_KEY, XATTR_LAST_VIEWED_KEY, XATTR_MOOD_KEY, XATTR_ORDER_INDEX_KEY,
+    XATTR_REFRESH_INTERVAL_KEY, XATTR_TIMEZONE_KEY,
+};
+use crate::sync::stale_base_copies;
+use crate::undo::{UndoJournal, UndoKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetadataIssueKind {
+    /// An attribute the app reads whose value isn't valid UTF-8
+    UndecodableAttribute,
+    InvalidRefreshInterval,
+    InvalidTimestamp,
+    InvalidMood,
+    InvalidTimezone,
+    InvalidOrderIndex,
+    /// Location set on a file that isn't a note
+    MisplacedLocation,
+    /// Refresh settings on a file that's neither in `structured/` nor a daily note, where
+    /// they have no effect
+    MisplacedRefresh,
+    /// A note that can't be read as text
+    UnreadableFile,
+    /// A sync base copy for a file the last sync no longer tracks
+    StaleSyncBaseCopy,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MetadataIssue {
+    pub file_path: String,
+    pub kind: MetadataIssueKind,
+    pub attribute: Option<String>,
+    pub value: Option<String>,
+    /// Fixing removes the attribute or file; unreadable notes are only reported
+    pub fixable: bool,
+    pub fixed: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct MetadataDoctorReport {
+    pub files_scanned: usize,
+    pub issues: Vec<MetadataIssue>,
+    pub fixed: usize,
+    /// Undo journal entry holding the fixed files' attributes as they were
+    pub snapshot_id: Option<String>,
+}
+
+fn is_note(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md")
+}
+
+fn is_structured_note(directory_path: &Path, path: &Path) -> bool {
+    is_note(path) && path.parent() == Some(&directory_path.join("structured"))
+}
+
+fn is_daily_note(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .and_then(parse_date_from_filename)
+        .is_some()
+}
+
+fn is_timestamp(value: &str) -> bool {
+    value.parse::<u64>().is_ok()
+}
+
+/// What's wrong with one of the app's attributes on `path`, if anything
+fn check_attribute(
+    directory_path: &Path,
+    path: &Path,
+    name: &str,
+    value: Option<&str>,
+) -> Option<MetadataIssueKind> {
+    let value = match value {
+        Some(value) => value,
+        None => return Some(MetadataIssueKind::UndecodableAttribute),
+    };
+    match name {
+        XATTR_COUNTRY_KEY | XATTR_CITY_KEY if !is_note(path) => {
+            Some(MetadataIssueKind::MisplacedLocation)
+        }
+        XATTR_REFRESH_INTERVAL_KEY | XATTR_LAST_REFRESHED_KEY
+            if !is_structured_note(directory_path, path) && !is_daily_note(path) =>
+        {
+            Some(MetadataIssueKind::MisplacedRefresh)
+        }
+        XATTR_REFRESH_INTERVAL_KEY if RefreshInterval::from_string(value).is_none() => {
+            Some(MetadataIssueKind::InvalidRefreshInterval)
+        }
+        XATTR_LAST_REFRESHED_KEY | XATTR_LAST_VIEWED_KEY if !is_timestamp(value) => {
+            Some(MetadataIssueKind::InvalidTimestamp)
+        }
+        XATTR_MOOD_KEY if mood_score(value).is_none() => Some(MetadataIssueKind::InvalidMood),
+        XATTR_TIMEZONE_KEY if value.parse::<Tz>().is_err() => {
+            Some(MetadataIssueKind::InvalidTimezone)
+        }
+        XATTR_ORDER_INDEX_KEY if value.parse::<u64>().is_err() => {
+            Some(MetadataIssueKind::InvalidOrderIndex)
+        }
+        _ => None,
+    }
+}
+
+const CHECKED_ATTRIBUTES: &[&str] = &[
+    XATTR_COUNTRY_KEY,
+    XATTR_CITY_KEY,
+    XATTR_REFRESH_INTERVAL_KEY,
+    XATTR_LAST_REFRESHED_KEY,
+    XATTR_LAST_VIEWED_KEY,
+    XATTR_MOOD_KEY,
+    XATTR_TIMEZONE_KEY,
+    XATTR_ORDER_INDEX_KEY,
+];
+
+fn check_file(directory_path: &Path, path: &Path) -> Vec<MetadataIssue> {
+    let mut issues = Vec::new();
+    let file_path = path.to_string_lossy().to_string();
+
+    for &name in CHECKED_ATTRIBUTES {
+        let bytes = match xattr::get(path, name) {
+            Ok(Some(bytes)) => bytes,
+            _ => continue,
+        };
+        let value = String::from_utf8(bytes).ok();
+        let kind = match check_attribute(directory_path, path, name, value.as_deref()) {
+            Some(kind) => kind,
+            None => continue,
+        };
+        issues.push(MetadataIssue {
+            file_path: file_path.clone(),
+            kind,
+            attribute: Some(name.to_string()),
+            value,
+            fixable: true,
+            fixed: false,
+        });
+    }
+
+    // Online-only files would be downloaded by reading them
+    if is_note(path) && !is_placeholder(path) && fs::read_to_string(path).is_err() {
+        issues.push(MetadataIssue {
+            file_path,
+            kind: MetadataIssueKind::UnreadableFile,
+            attribute: None,
+            value: None,
+            fixable: false,
+            fixed: false,
+        });
+    }
+
+    issues
+}
+
+/// Scan the notes directory for attributes the app can't use, notes it can't read and,
+/// given the sync state file, stale sync base copies. With `apply_fixes`, bad attributes
+/// and stale copies are removed; the report lists what was found either way. With a
+/// `journal`, the attributes of the files being fixed are snapshotted first
+pub fn run_metadata_doctor(
+    directory_path: &Path,
+    sync_state_path: Option<&Path>,
+    apply_fixes: bool,
+    journal: Option<&UndoJournal>,
+) -> Result<MetadataDoctorReport, Box<dyn std::error::Error>> {
+    if !directory_path.is_dir() {
+        return Err(format!("Not a directory: {}", directory_path.display()).into());
+    }
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    collect_files(directory_path, &mut files)?;
+    files.sort();
+
+    let mut report = MetadataDoctorReport {
+        files_scanned: files.len(),
+        ..MetadataDoctorReport::default()
+    };
+    for path in &files {
+        report.issues.extend(check_file(directory_path, path));
+    }
+
+    if apply_fixes {
+        // Issues come grouped by file
+        let mut paths: Vec<PathBuf> = report
+            .issues
+            .iter()
+            .filter(|issue| issue.attribute.is_some())
+            .map(|issue| PathBuf::from(&issue.file_path))
+            .collect();
+        paths.dedup();
+        if let Some(journal) = journal.filter(|_| !paths.is_empty()) {
+            let id = journal
+                .record_xattrs(UndoKind::Migration, "Fix metadata", &paths)
+                .map_err(|e| format!("Failed to back up metadata before fixing it: {}", e))?;
+            report.snapshot_id = Some(id);
+        }
+        for issue in &mut report.issues {
+            if let Some(name) = &issue.attribute {
+                issue.fixed = xattr::remove(&issue.file_path, name).is_ok();
+            }
+        }
+    }
+
+    if let Some(state_path) = sync_state_path {
+        for copy in stale_base_copies(state_path)? {
+            let fixed = apply_fixes && fs::remove_file(&copy).is_ok();
+            report.issues.push(MetadataIssue {
+                file_path: copy.to_string_lossy().to_string(),
+                kind: MetadataIssueKind::StaleSyncBaseCopy,
+                attribute: None,
+                value: None,
+                fixable: true,
+                fixed,
+            });
+        }
+    }
+
+    report.fixed = report.issues.iter().filter(|issue| issue.fixed).count();
+    Ok(report)
+}
@@ -0,0 +1,95 @@
+use std::fmt;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+/// How long a writer waits for another writer to finish before giving up
+const LOCK_ATTEMPTS: u32 = 20;
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Suggested wait before retrying a write that found the note locked
+pub const LOCK_RETRY_AFTER_MS: u64 = 250;
+
+/// Another window, the sync engine or a content source is writing the same note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteLockedError {
+    pub file_path: String,
+    pub retry_after_ms: u64,
+    pub waited_ms: u64,
+}
+
+impl fmt::Display for NoteLockedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} is locked by another writer; retry in {}ms",
+            self.file_path, self.retry_after_ms
+        )
+    }
+}
+
+impl std::error::Error for NoteLockedError {}
+
+/// Exclusive advisory lock on a note, released when dropped
+pub struct NoteLock {
+    _file: File,
+}
+
+/// The hidden file beside a note that its lock is taken on, e.g. `.2024-01-15.md.lock`.
+/// Locking the note itself wouldn't hold: atomic writes rename a new file over it, and a
+/// writer waiting on the old inode would go ahead alongside one holding the new
+fn lock_path(path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| format!("Invalid note path: {}", path.display()))?;
+    Ok(path.with_file_name(format!(".{}.lock", name.to_string_lossy())))
+}
+
+/// Whether `path` is a note's lock file, which is left in place between writes and
+/// shouldn't be committed or synced
+pub fn is_lock_file(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| name.to_string_lossy())
+        .is_some_and(|name| name.starts_with('.') && name.ends_with(".lock"))
+}
+
+/// Take an exclusive advisory lock on the note at `path`, which needn't exist yet. Waits
+/// briefly for a current holder, then fails with `NoteLockedError`. The lock only guards
+/// against other writers that also take it; reads are unaffected
+pub fn lock_note_file(path: &Path) -> Result<NoteLock, Box<dyn std::error::Error>> {
+    let file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(lock_path(path)?)?;
+
+    for attempt in 0..LOCK_ATTEMPTS {
+        match file.try_lock() {
+            Ok(()) => return Ok(NoteLock { _file: file }),
+            Err(TryLockError::WouldBlock) if attempt + 1 < LOCK_ATTEMPTS => {
+                thread::sleep(LOCK_POLL_INTERVAL)
+            }
+            Err(TryLockError::WouldBlock) => break,
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+    }
+
+    Err(Box::new(NoteLockedError {
+        file_path: path.to_string_lossy().to_string(),
+        retry_after_ms: LOCK_RETRY_AFTER_MS,
+        waited_ms: LOCK_POLL_INTERVAL.as_millis() as u64 * (LOCK_ATTEMPTS - 1) as u64,
+    }))
+}
+
+/// Run `write` while holding the note's advisory lock
+pub fn with_note_lock<T>(
+    path: &Path,
+    write: impl FnOnce() -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let _lock = lock_note_file(path)?;
+    write()
+}
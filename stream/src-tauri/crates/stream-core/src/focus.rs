@@ -0,0 +1,210 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::capture::append_capture;
+
+/// A session that's running or paused. Elapsed time is `accumulated_ms` plus the
+/// time since `resumed_at` while running, so it survives restarts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActiveFocusSession {
+    pub label: String,
+    pub directory_path: String,
+    pub started_at: u64,
+    pub planned_duration_ms: Option<u64>,
+    pub accumulated_ms: u64,
+    pub resumed_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompletedFocusSession {
+    pub label: String,
+    pub started_at: u64,
+    pub ended_at: u64,
+    pub duration_ms: u64,
+    pub planned_duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FocusStore {
+    pub active: Option<ActiveFocusSession>,
+    pub completed: Vec<CompletedFocusSession>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusStatus {
+    pub active: bool,
+    pub paused: bool,
+    pub label: Option<String>,
+    pub elapsed_ms: u64,
+    pub remaining_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FocusStats {
+    pub total_ms: u64,
+    pub session_count: usize,
+    pub by_label: BTreeMap<String, u64>,
+    /// Keyed by local date, YYYY-MM-DD
+    pub by_day: BTreeMap<String, u64>,
+}
+
+fn local_time(timestamp_ms: u64) -> DateTime<Local> {
+    Local
+        .timestamp_millis_opt(timestamp_ms as i64)
+        .single()
+        .unwrap_or_else(Local::now)
+}
+
+impl ActiveFocusSession {
+    pub fn is_paused(&self) -> bool {
+        self.resumed_at.is_none()
+    }
+
+    pub fn elapsed_ms(&self, now: u64) -> u64 {
+        self.accumulated_ms + self.resumed_at.map_or(0, |r| now.saturating_sub(r))
+    }
+
+    pub fn is_finished(&self, now: u64) -> bool {
+        self.planned_duration_ms
+            .is_some_and(|planned| self.elapsed_ms(now) >= planned)
+    }
+}
+
+impl FocusStore {
+    pub fn load(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn status(&self, now: u64) -> FocusStatus {
+        match &self.active {
+            Some(session) => {
+                let elapsed_ms = session.elapsed_ms(now);
+                FocusStatus {
+                    active: true,
+                    paused: session.is_paused(),
+                    label: Some(session.label.clone()),
+                    elapsed_ms,
+                    remaining_ms: session
+                        .planned_duration_ms
+                        .map(|planned| planned.saturating_sub(elapsed_ms)),
+                }
+            }
+            None => FocusStatus {
+                active: false,
+                paused: false,
+                label: None,
+                elapsed_ms: 0,
+                remaining_ms: None,
+            },
+        }
+    }
+
+    pub fn start(
+        &mut self,
+        label: &str,
+        directory_path: &str,
+        planned_duration_ms: Option<u64>,
+        now: u64,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.active.is_some() {
+            return Err("A focus session is already running".into());
+        }
+        self.active = Some(ActiveFocusSession {
+            label: label.trim().to_string(),
+            directory_path: directory_path.to_string(),
+            started_at: now,
+            planned_duration_ms,
+            accumulated_ms: 0,
+            resumed_at: Some(now),
+        });
+        Ok(())
+    }
+
+    pub fn pause(&mut self, now: u64) -> Result<(), Box<dyn std::error::Error>> {
+        let session = self.active.as_mut().ok_or("No focus session running")?;
+        if let Some(resumed_at) = session.resumed_at.take() {
+            session.accumulated_ms += now.saturating_sub(resumed_at);
+        }
+        Ok(())
+    }
+
+    pub fn resume(&mut self, now: u64) -> Result<(), Box<dyn std::error::Error>> {
+        let session = self.active.as_mut().ok_or("No focus session running")?;
+        if session.resumed_at.is_none() {
+            session.resumed_at = Some(now);
+        }
+        Ok(())
+    }
+
+    /// End the active session, record it and log it to the daily note it started on
+    pub fn stop(&mut self, now: u64) -> Result<CompletedFocusSession, Box<dyn std::error::Error>> {
+        let session = self.active.take().ok_or("No focus session running")?;
+        let duration_ms = match session.planned_duration_ms {
+            Some(planned) => session.elapsed_ms(now).min(planned),
+            None => session.elapsed_ms(now),
+        };
+
+        let completed = CompletedFocusSession {
+            label: session.label.clone(),
+            started_at: session.started_at,
+            ended_at: now,
+            duration_ms,
+            planned_duration_ms: session.planned_duration_ms,
+        };
+        self.completed.push(completed.clone());
+
+        let minutes = (duration_ms + 30_000) / 60_000;
+        let label = if session.label.is_empty() {
+            format!("Focus session ({} min)", minutes)
+        } else {
+            format!("Focus: {} ({} min)", session.label, minutes)
+        };
+        if let Err(e) = append_capture(
+            Path::new(&session.directory_path),
+            local_time(session.started_at),
+            &label,
+            None,
+            "",
+        ) {
+            eprintln!("Failed to log focus session: {}", e);
+        }
+
+        Ok(completed)
+    }
+
+    /// Totals for sessions that started on local dates `start..=end`
+    pub fn stats(&self, start: NaiveDate, end: NaiveDate) -> FocusStats {
+        let mut stats = FocusStats::default();
+
+        for session in &self.completed {
+            let date = local_time(session.started_at).date_naive();
+            if date < start || date > end {
+                continue;
+            }
+            stats.total_ms += session.duration_ms;
+            stats.session_count += 1;
+            *stats.by_label.entry(session.label.clone()).or_default() += session.duration_ms;
+            *stats
+                .by_day
+                .entry(date.format("%Y-%m-%d").to_string())
+                .or_default() += session.duration_ms;
+        }
+
+        stats
+    }
+}
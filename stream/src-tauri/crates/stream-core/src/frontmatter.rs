@@ -0,0 +1,155 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_yaml::{Mapping, Value};
+
+use crate::markdown::write_markdown_file;
+
+/// A note's YAML frontmatter. `tags` and `aliases` may be written as a list or as one
+/// comma-separated string; tags lose any leading `#`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Frontmatter {
+    pub title: Option<String>,
+    pub tags: Vec<String>,
+    pub aliases: Vec<String>,
+    /// Every other key, with its value as JSON
+    pub custom: BTreeMap<String, serde_json::Value>,
+}
+
+/// `---`-fenced frontmatter at the start of `content`, and the content after it
+pub(crate) fn split_frontmatter(content: &str) -> Option<(&str, &str)> {
+    let rest = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))?;
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return Some((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    None
+}
+
+fn scalar_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn string_list(value: &Value) -> Vec<String> {
+    let items: Vec<String> = match value {
+        Value::Sequence(items) => items.iter().filter_map(scalar_string).collect(),
+        other => scalar_string(other)
+            .map(|s| s.split(',').map(str::to_string).collect())
+            .unwrap_or_default(),
+    };
+    items
+        .into_iter()
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+/// The block's keys, or None when it isn't a YAML mapping. An empty block is an empty
+/// mapping
+fn parse_mapping(block: &str) -> Option<Mapping> {
+    match serde_yaml::from_str::<Value>(block).ok()? {
+        Value::Mapping(mapping) => Some(mapping),
+        Value::Null => Some(Mapping::new()),
+        _ => None,
+    }
+}
+
+fn from_mapping(mapping: &Mapping) -> Frontmatter {
+    let mut frontmatter = Frontmatter::default();
+    for (key, value) in mapping {
+        let key = match scalar_string(key) {
+            Some(key) => key,
+            None => continue,
+        };
+        match key.as_str() {
+            "title" => frontmatter.title = scalar_string(value),
+            "tags" => {
+                frontmatter.tags = string_list(value)
+                    .into_iter()
+                    .map(|tag| tag.trim_start_matches('#').to_string())
+                    .filter(|tag| !tag.is_empty())
+                    .collect()
+            }
+            "aliases" => frontmatter.aliases = string_list(value),
+            _ => {
+                let value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+                frontmatter.custom.insert(key, value);
+            }
+        }
+    }
+    frontmatter
+}
+
+/// The frontmatter at the start of `content`, if it has a block that parses as YAML
+pub fn parse_frontmatter(content: &str) -> Option<Frontmatter> {
+    let (block, _) = split_frontmatter(content)?;
+    parse_mapping(block).map(|mapping| from_mapping(&mapping))
+}
+
+/// Set one frontmatter key to `value`, or remove it when `value` is None, rewriting only
+/// the frontmatter block: the body after it is kept byte for byte. A block is added when
+/// the note has none and dropped once its last key is removed. Other keys keep their
+/// order, but comments in the block aren't kept
+pub fn set_frontmatter_field(
+    file_path: &Path,
+    key: &str,
+    value: Option<serde_json::Value>,
+) -> Result<Frontmatter, Box<dyn std::error::Error>> {
+    let key = key.trim();
+    if key.is_empty() || key.contains('\n') {
+        return Err("Invalid frontmatter key".into());
+    }
+
+    let content = fs::read_to_string(file_path)?;
+    let newline = if content.starts_with("---\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let (mut mapping, body) = match split_frontmatter(&content) {
+        Some((block, body)) => (
+            parse_mapping(block)
+                .ok_or("The note's frontmatter isn't valid YAML; fix it before editing fields")?,
+            body,
+        ),
+        None => (Mapping::new(), content.as_str()),
+    };
+
+    let changed = match value {
+        Some(value) => {
+            let value = serde_yaml::to_value(value)?;
+            mapping.insert(Value::String(key.to_string()), value.clone()) != Some(value)
+        }
+        None => mapping.shift_remove(key).is_some(),
+    };
+    if !changed {
+        return Ok(from_mapping(&mapping));
+    }
+
+    let updated = if mapping.is_empty() {
+        body.to_string()
+    } else {
+        let yaml = serde_yaml::to_string(&mapping)?;
+        let yaml: Vec<&str> = yaml.lines().collect();
+        format!(
+            "---{nl}{}{nl}---{nl}{}",
+            yaml.join(newline),
+            body,
+            nl = newline
+        )
+    };
+    write_markdown_file(file_path, &updated)?;
+    Ok(from_mapping(&mapping))
+}
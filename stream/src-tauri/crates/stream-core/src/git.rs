@@ -0,0 +1,1886 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Local, NaiveDate, TimeZone, Utc};
+use git2::{self, DiffOptions, Oid, Repository, Time};
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::conventional::{parse_conventional_commit, ConventionalCommit};
+use crate::file_lock::is_lock_file;
+use crate::lock::is_being_locked;
+use crate::perf::{self, PerfCategory};
+use crate::references::extract_ticket_ids;
+
+/// Maximum number of commits to return per repository to prevent memory issues
+pub(crate) const MAX_COMMITS_PER_REPO: usize = 200;
+
+/// Maximum number of commits visited per repository walk, in or out of range. Bounds
+/// queries for old dates in very large histories
+pub(crate) const MAX_COMMITS_WALKED: usize = 50_000;
+
+/// Wall-clock budget for a single repository walk
+pub(crate) const WALK_TIME_BUDGET: Duration = Duration::from_secs(5);
+
+/// Maximum number of files changed to return per commit
+pub(crate) const MAX_FILES_PER_COMMIT: usize = 50;
+
+/// Limit the number of branch tips used for non-tip commit matching (performance guard)
+const MAX_BRANCH_TIPS_FOR_MATCH: usize = 50;
+
+/// With a commit-graph, containment checks use generation numbers and stay cheap, so more
+/// branches can be matched
+const MAX_BRANCH_TIPS_WITH_COMMIT_GRAPH: usize = 200;
+
+/// GitHub's merge commit subject and GitLab's `See merge request group/project!45` trailer
+static MERGE_REQUEST_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^Merge pull request #(\d+)|(?m)^See merge request \S*!(\d+)")
+        .expect("Failed to compile merge request regex")
+});
+
+/// Repository work runs on its own pool so a slow fetch or history walk doesn't tie up
+/// the global rayon pool that search and scanning use
+const GIT_POOL_THREADS: usize = 4;
+
+static GIT_POOL: LazyLock<Option<rayon::ThreadPool>> = LazyLock::new(|| {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(GIT_POOL_THREADS)
+        .thread_name(|index| format!("git-{}", index))
+        .build()
+        .ok()
+});
+
+fn on_git_pool<T: Send>(work: impl FnOnce() -> T + Send) -> T {
+    match GIT_POOL.as_ref() {
+        Some(pool) => pool.install(work),
+        None => work(),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitCommit {
+    pub id: String,
+    pub message: String,
+    pub author_name: String,
+    pub author_email: String,
+    pub timestamp: u64,
+    pub date: String,
+    pub repo_path: String,
+    pub files_changed: Vec<String>,
+    pub branches: Vec<String>,
+    pub url: Option<String>,
+    /// Sub-projects of a monorepo this commit touches, from the repository's settings
+    #[serde(default)]
+    pub projects: Vec<String>,
+    /// Type, scope and subject when the message follows conventional commits
+    #[serde(default)]
+    pub conventional: Option<ConventionalCommit>,
+    /// Ticket ids like `PROJ-123` or `#42` mentioned anywhere in the message
+    #[serde(default)]
+    pub tickets: Vec<String>,
+    /// Pull or merge request the commit landed through, going by the merge commit's message
+    #[serde(default)]
+    pub pull_request: Option<PullRequestLink>,
+    /// Every configured repository the commit was found in, when clones or worktrees of the
+    /// same remote were deduplicated; empty when it's only in `repo_path`
+    #[serde(default)]
+    pub repo_paths: Vec<String>,
+    /// Set when the same change is listed again under another id, e.g. branch commits later
+    /// squashed or rebased onto the main branch; the id of the copy that's kept
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PullRequestLink {
+    pub number: u64,
+    pub url: Option<String>,
+}
+
+/// How a repository is labelled in the timeline instead of by its path
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RepoSettings {
+    pub display_name: Option<String>,
+    /// e.g. "work" or "personal", for filtering
+    pub group: Option<String>,
+    pub color: Option<String>,
+    /// Emails that count as "me" in this repository, replacing the author filter's list
+    #[serde(default)]
+    pub author_emails: Vec<String>,
+    /// Path prefixes of a monorepo's sub-projects, e.g. `apps/web`
+    #[serde(default)]
+    pub projects: Vec<String>,
+}
+
+/// Limit commits to the user's own. `emails` applies to repositories without their own list
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuthorFilter {
+    pub enabled: bool,
+    pub emails: Vec<String>,
+}
+
+/// Set each commit's `projects` to the prefixes its changed files fall under. Only the files
+/// listed on the commit are considered, so very large commits may miss a project
+pub fn attribute_projects(commits: &mut [GitCommit], prefixes: &[String]) {
+    if prefixes.is_empty() {
+        return;
+    }
+    for commit in commits {
+        commit.projects = prefixes
+            .iter()
+            .filter(|prefix| {
+                commit.files_changed.iter().any(|file| {
+                    file.strip_prefix(prefix.as_str())
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+                })
+            })
+            .cloned()
+            .collect();
+    }
+}
+
+/// Drop commits not authored by one of `emails` (case-insensitive). An empty list keeps all
+pub fn retain_authored_by(commits: &mut Vec<GitCommit>, emails: &[String]) {
+    if emails.is_empty() {
+        return;
+    }
+    commits.retain(|commit| {
+        emails
+            .iter()
+            .any(|email| email.eq_ignore_ascii_case(commit.author_email.trim()))
+    });
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoCommits {
+    pub repo_path: String,
+    pub commits: Vec<GitCommit>,
+    pub error: Option<String>,
+    /// The walk hit a commit cap or its time budget before covering the whole range
+    #[serde(default)]
+    pub truncated: bool,
+    #[serde(default)]
+    pub settings: RepoSettings,
+}
+
+/// Commits found by a bounded repository walk
+#[derive(Debug, Default)]
+pub struct CommitWalk {
+    pub commits: Vec<GitCommit>,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NotesSyncResult {
+    pub committed: bool,
+    pub commit_id: Option<String>,
+    pub pulled_commits: usize,
+    pub pushed: bool,
+    pub conflicts: Vec<String>,
+    pub message: String,
+}
+
+/// Library used to read commit history. `Gix` is only available when built with the
+/// `gix` feature
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitBackend {
+    Git2,
+    Gix,
+}
+
+impl CommitBackend {
+    pub const ALL: [CommitBackend; 2] = [CommitBackend::Git2, CommitBackend::Gix];
+
+    pub fn from_string(value: &str) -> Option<Self> {
+        match value {
+            "git2" => Some(CommitBackend::Git2),
+            "gix" => Some(CommitBackend::Gix),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CommitBackend::Git2 => "git2",
+            CommitBackend::Gix => "gix",
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        match self {
+            CommitBackend::Git2 => true,
+            CommitBackend::Gix => cfg!(feature = "gix"),
+        }
+    }
+}
+
+static COMMIT_BACKEND: AtomicU8 = AtomicU8::new(0);
+
+/// Choose the library `get_repo_commits` reads history with
+pub fn set_commit_backend(backend: CommitBackend) -> Result<(), Box<dyn std::error::Error>> {
+    if !backend.is_available() {
+        return Err(format!(
+            "The {} backend is not included in this build",
+            backend.as_str()
+        )
+        .into());
+    }
+    COMMIT_BACKEND.store(backend as u8, Ordering::Relaxed);
+    Ok(())
+}
+
+pub fn commit_backend() -> CommitBackend {
+    match COMMIT_BACKEND.load(Ordering::Relaxed) {
+        1 => CommitBackend::Gix,
+        _ => CommitBackend::Git2,
+    }
+}
+
+static FIRST_PARENT: AtomicBool = AtomicBool::new(false);
+
+/// Make `get_repo_commits` follow only the first parent of each commit, so a merged
+/// branch shows up as its merge commit rather than every commit on it
+pub fn set_first_parent(first_parent: bool) {
+    FIRST_PARENT.store(first_parent, Ordering::Relaxed);
+}
+
+pub fn first_parent() -> bool {
+    FIRST_PARENT.load(Ordering::Relaxed)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackendBenchmark {
+    pub backend: CommitBackend,
+    pub duration_ms: u64,
+    pub commit_count: usize,
+    pub truncated: bool,
+    pub error: Option<String>,
+}
+
+/// Cheap-to-read state of a working copy, for spotting repositories that need attention.
+/// Counts are None when they don't apply, e.g. no upstream to compare against
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RepoHealth {
+    pub repo_path: String,
+    pub branch: Option<String>,
+    pub last_commit_at: Option<u64>,
+    pub days_since_fetch: Option<u64>,
+    pub dirty_files: Option<usize>,
+    pub stash_count: usize,
+    pub unpushed_commits: Option<usize>,
+    pub error: Option<String>,
+}
+
+/// One author's share of a repository's commits over a time range
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AuthorStats {
+    /// Name on the author's most recent commit
+    pub author_name: String,
+    pub author_email: String,
+    pub commits: usize,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub first_commit_at: u64,
+    pub last_commit_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuthorLeaderboard {
+    pub repo_path: String,
+    /// Most commits first
+    pub authors: Vec<AuthorStats>,
+    /// The walk hit its commit or time budget before covering the whole range
+    pub truncated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepoMaintenanceResult {
+    pub repo_path: String,
+    pub had_commit_graph: bool,
+    pub has_commit_graph: bool,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FetchResult {
+    pub repo_path: String,
+    pub success: bool,
+    pub message: String,
+}
+
+pub fn fetch_repos(repo_paths: &[String]) -> Vec<FetchResult> {
+    on_git_pool(|| {
+        repo_paths
+            .par_iter()
+            .map(|repo_path| match fetch_repo(repo_path) {
+                Ok(message) => FetchResult {
+                    repo_path: repo_path.clone(),
+                    success: true,
+                    message,
+                },
+                Err(e) => FetchResult {
+                    repo_path: repo_path.clone(),
+                    success: false,
+                    message: format!("Failed to fetch: {}", e),
+                },
+            })
+            .collect()
+    })
+}
+
+fn repo_health(repo_path: &str) -> Result<RepoHealth, Box<dyn std::error::Error>> {
+    let repo = Repository::open(repo_path)?;
+    let mut health = RepoHealth {
+        repo_path: repo_path.to_string(),
+        ..RepoHealth::default()
+    };
+
+    let head = repo.head().ok();
+    if let Some(head) = &head {
+        health.branch = head.shorthand().map(|name| name.to_string());
+        health.last_commit_at = head
+            .peel_to_commit()
+            .ok()
+            .map(|commit| (commit.time().seconds() as u64) * 1000);
+    }
+
+    // FETCH_HEAD is rewritten by every fetch, so its age is the time since the last one
+    health.days_since_fetch = std::fs::metadata(repo.path().join("FETCH_HEAD"))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| modified.elapsed().ok())
+        .map(|elapsed| elapsed.as_secs() / 86_400);
+
+    if !repo.is_bare() {
+        let mut options = git2::StatusOptions::new();
+        options
+            .include_untracked(true)
+            .include_ignored(false)
+            .recurse_untracked_dirs(false);
+        health.dirty_files = repo.statuses(Some(&mut options)).ok().map(|s| s.len());
+    }
+
+    health.stash_count = repo.reflog("refs/stash").map(|log| log.len()).unwrap_or(0);
+
+    health.unpushed_commits = head.as_ref().and_then(|head| {
+        let local = head.target()?;
+        let branch = git2::Branch::wrap(repo.find_reference(head.name()?).ok()?);
+        let upstream = branch.upstream().ok()?.get().target()?;
+        repo.graph_ahead_behind(local, upstream)
+            .ok()
+            .map(|(ahead, _)| ahead)
+    });
+
+    Ok(health)
+}
+
+/// Branch, last commit, fetch age, uncommitted changes, stashes and unpushed commits for
+/// each repository, without walking history
+pub fn get_repo_health(repo_paths: &[String]) -> Vec<RepoHealth> {
+    on_git_pool(|| {
+        repo_paths
+            .par_iter()
+            .map(|repo_path| {
+                repo_health(repo_path).unwrap_or_else(|e| RepoHealth {
+                    repo_path: repo_path.clone(),
+                    error: Some(format!("Error reading repository: {}", e)),
+                    ..RepoHealth::default()
+                })
+            })
+            .collect()
+    })
+}
+
+/// What identifies a repository across clones and worktrees: its remote's address, or for
+/// repositories without one, the shared git directory
+fn repo_identity(repo_path: &str) -> Option<String> {
+    let repo = Repository::open(repo_path).ok()?;
+    if let Some(url) = get_remote_url(&repo) {
+        let url = remote_web_url(&url).unwrap_or(url);
+        let url = url.trim_end_matches('/').trim_end_matches(".git");
+        let url = url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        return Some(url.to_lowercase());
+    }
+    // A worktree's git directory is `<common dir>/worktrees/<name>`
+    let git_dir = if repo.is_worktree() {
+        repo.path().parent()?.parent()?
+    } else {
+        repo.path()
+    };
+    git_dir
+        .canonicalize()
+        .ok()
+        .map(|dir| dir.to_string_lossy().to_string())
+}
+
+/// Drop commits already listed for an earlier repository sharing the same remote, so a
+/// commit in several clones or worktrees appears once, under the first configured path.
+/// The kept commit lists every path it was found in and the branches from all of them
+fn dedupe_shared_commits(results: &mut [RepoCommits]) {
+    let identities: Vec<Option<String>> = results
+        .iter()
+        .map(|repo| repo_identity(&repo.repo_path))
+        .collect();
+
+    for later in 1..results.len() {
+        let identity = match &identities[later] {
+            Some(identity) => identity,
+            None => continue,
+        };
+        let Some(first) = (0..later).find(|&i| identities[i].as_ref() == Some(identity)) else {
+            continue;
+        };
+
+        let (kept, rest) = results.split_at_mut(later);
+        let kept = &mut kept[first];
+        let duplicate = &mut rest[0];
+        let positions: HashMap<String, usize> = kept
+            .commits
+            .iter()
+            .enumerate()
+            .map(|(index, commit)| (commit.id.clone(), index))
+            .collect();
+
+        duplicate.commits.retain(|commit| {
+            let Some(&index) = positions.get(&commit.id) else {
+                return true;
+            };
+            let original = &mut kept.commits[index];
+            if original.repo_paths.is_empty() {
+                original.repo_paths.push(original.repo_path.clone());
+            }
+            if !original.repo_paths.contains(&commit.repo_path) {
+                original.repo_paths.push(commit.repo_path.clone());
+            }
+            for branch in &commit.branches {
+                if !original.branches.contains(branch) {
+                    original.branches.push(branch.clone());
+                }
+            }
+            false
+        });
+    }
+}
+
+/// Patch-id of the change from `from` (the empty tree when `None`) to `to`. It ignores
+/// line numbers and whitespace, so it survives rebases, cherry-picks and squashes
+fn patch_id(repo: &Repository, from: Option<&git2::Commit>, to: &git2::Commit) -> Option<Oid> {
+    let old_tree = from.and_then(|commit| commit.tree().ok());
+    let new_tree = to.tree().ok()?;
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.ignore_submodules(true);
+    let diff = repo
+        .diff_tree_to_tree(old_tree.as_ref(), Some(&new_tree), Some(&mut diff_opts))
+        .ok()?;
+    if diff.deltas().len() == 0 {
+        return None;
+    }
+    diff.patchid(None).ok()
+}
+
+/// Set `duplicate_of` on commits whose change is already listed under another commit of
+/// the same repository: a commit with the same patch-id, or for a feature branch's commits
+/// together, a squash commit matching their combined change. Copies on a main branch are
+/// kept over others, then the newest
+fn mark_equivalent_commits(repo_commits: &mut RepoCommits) -> Result<(), git2::Error> {
+    let commits = &mut repo_commits.commits;
+    let on_main: Vec<bool> = commits
+        .iter()
+        .map(|commit| commit.branches.iter().any(|b| is_main_branch(b)))
+        .collect();
+    // Without a commit outside the main branches there's nothing to be a duplicate of
+    if commits.len() < 2 || on_main.iter().all(|&main| main) {
+        return Ok(());
+    }
+
+    let repo = Repository::open(&repo_commits.repo_path)?;
+    let found: Vec<Option<git2::Commit>> = commits
+        .iter()
+        .map(|commit| {
+            Oid::from_str(&commit.id)
+                .and_then(|oid| repo.find_commit(oid))
+                .ok()
+                .filter(|commit| commit.parent_count() <= 1)
+        })
+        .collect();
+
+    let mut by_patch: HashMap<Oid, Vec<usize>> = HashMap::new();
+    for (index, commit) in found.iter().enumerate() {
+        let Some(commit) = commit else { continue };
+        if let Some(id) = patch_id(&repo, commit.parent(0).ok().as_ref(), commit) {
+            by_patch.entry(id).or_default().push(index);
+        }
+    }
+
+    let rank = |index: usize| (!on_main[index], std::cmp::Reverse(commits[index].timestamp));
+    let mut kept_for: Vec<Option<usize>> = vec![None; commits.len()];
+    for indices in by_patch.values().filter(|indices| indices.len() > 1) {
+        let Some(&kept) = indices.iter().min_by_key(|&&index| rank(index)) else {
+            continue;
+        };
+        for &index in indices.iter().filter(|&&index| index != kept) {
+            kept_for[index] = Some(kept);
+        }
+    }
+
+    // Squash merges land the branch's commits as one change on the main branch
+    let mut by_branch: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (index, commit) in commits.iter().enumerate() {
+        if on_main[index] || found[index].is_none() {
+            continue;
+        }
+        if let Some(branch) = commit.branches.first() {
+            by_branch.entry(branch.as_str()).or_default().push(index);
+        }
+    }
+    for indices in by_branch.values().filter(|indices| indices.len() > 1) {
+        let newest = indices
+            .iter()
+            .max_by_key(|&&index| commits[index].timestamp);
+        let oldest = indices
+            .iter()
+            .min_by_key(|&&index| commits[index].timestamp);
+        let (Some(Some(newest)), Some(Some(oldest))) =
+            (newest.map(|&i| &found[i]), oldest.map(|&i| &found[i]))
+        else {
+            continue;
+        };
+        let Some(id) = patch_id(&repo, oldest.parent(0).ok().as_ref(), newest) else {
+            continue;
+        };
+        let squash = by_patch
+            .get(&id)
+            .and_then(|matches| matches.iter().find(|&&index| on_main[index]));
+        if let Some(&squash) = squash {
+            for &index in indices {
+                kept_for[index].get_or_insert(squash);
+            }
+        }
+    }
+
+    let ids: Vec<String> = commits.iter().map(|commit| commit.id.clone()).collect();
+    for (commit, kept) in commits.iter_mut().zip(kept_for) {
+        commit.duplicate_of = kept.map(|index| ids[index].clone());
+    }
+    Ok(())
+}
+
+pub fn get_git_commits_for_repos(
+    repo_paths: &[String],
+    start_timestamp: u64,
+    end_timestamp: u64,
+) -> Vec<RepoCommits> {
+    let start_seconds = (start_timestamp / 1000) as i64;
+    let end_seconds = (end_timestamp / 1000) as i64;
+
+    // Process all repos in parallel on the git pool
+    let mut results = on_git_pool(|| {
+        repo_paths
+            .par_iter()
+            .map(
+                |repo_path| match get_repo_commits(repo_path, start_seconds, end_seconds) {
+                    Ok(mut walk) => {
+                        if let Err(e) = attach_pull_requests(repo_path, &mut walk.commits) {
+                            eprintln!("Failed to link pull requests in {}: {}", repo_path, e);
+                        }
+                        RepoCommits {
+                            repo_path: repo_path.clone(),
+                            commits: walk.commits,
+                            error: None,
+                            truncated: walk.truncated,
+                            settings: RepoSettings::default(),
+                        }
+                    }
+                    Err(e) => RepoCommits {
+                        repo_path: repo_path.clone(),
+                        commits: Vec::new(),
+                        error: Some(format!("Error reading repository: {}", e)),
+                        truncated: false,
+                        settings: RepoSettings::default(),
+                    },
+                },
+            )
+            .collect::<Vec<_>>()
+    });
+
+    dedupe_shared_commits(&mut results);
+    on_git_pool(|| {
+        results.par_iter_mut().for_each(|repo_commits| {
+            if let Err(e) = mark_equivalent_commits(repo_commits) {
+                eprintln!(
+                    "Failed to find equivalent commits in {}: {}",
+                    repo_commits.repo_path, e
+                );
+            }
+        })
+    });
+    results
+}
+
+/// Start and end (inclusive) of a calendar day in local time, as Unix seconds
+pub fn local_day_bounds(date: NaiveDate) -> Option<(i64, i64)> {
+    day_bounds_in_timezone(date, &Local)
+}
+
+/// Start and end (inclusive) of a calendar day in `tz`, as Unix seconds
+pub fn day_bounds_in_timezone<T: TimeZone>(date: NaiveDate, tz: &T) -> Option<(i64, i64)> {
+    let start = tz
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()?
+        .timestamp();
+    let next_day = date.succ_opt()?;
+    let end = tz
+        .from_local_datetime(&next_day.and_hms_opt(0, 0, 0)?)
+        .earliest()?
+        .timestamp()
+        - 1;
+
+    Some((start, end))
+}
+
+fn time_to_timestamp_ms(time: Time) -> u64 {
+    (time.seconds() as u64) * 1000
+}
+
+fn time_to_iso_date(time: Time) -> String {
+    seconds_to_iso_date(time.seconds())
+}
+
+pub(crate) fn seconds_to_iso_date(timestamp: i64) -> String {
+    let dt = DateTime::from_timestamp(timestamp, 0).unwrap_or_else(Utc::now);
+    dt.format("%Y-%m-%d").to_string()
+}
+
+/// Commit OID -> (branches, is_on_remote) for branch tips
+type BranchTipMap = HashMap<git2::Oid, (Vec<String>, bool)>;
+
+/// Build a map of commit OID -> (branches, is_on_remote) for all branch tips
+/// This is much more efficient than walking history for each commit
+fn build_branch_tip_map(repo: &Repository) -> Result<BranchTipMap, Box<dyn std::error::Error>> {
+    let mut tip_map: BranchTipMap = HashMap::new();
+
+    // Process local branches - just get the tip commits
+    let local_branches = repo.branches(Some(git2::BranchType::Local))?;
+    for branch in local_branches {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            let reference = branch.get();
+            if let Some(target) = reference.target() {
+                let entry = tip_map.entry(target).or_insert_with(|| (Vec::new(), false));
+                entry.0.push(name.to_string());
+            }
+        }
+    }
+
+    // Process remote branches - just get the tip commits
+    let remote_branches = repo.branches(Some(git2::BranchType::Remote))?;
+    for branch in remote_branches {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            let reference = branch.get();
+            if let Some(target) = reference.target() {
+                let entry = tip_map.entry(target).or_insert_with(|| (Vec::new(), false));
+                entry.1 = true; // Mark as on remote
+                let normalized = normalize_branch_name(name);
+                if !entry.0.contains(&normalized) {
+                    entry.0.push(normalized);
+                }
+            }
+        }
+    }
+
+    Ok(tip_map)
+}
+
+#[derive(Clone)]
+struct BranchTip {
+    name: String,
+    oid: git2::Oid,
+    is_remote: bool,
+    time_seconds: i64,
+}
+
+/// Build a list of all branch tips (local + remote) with normalized names.
+fn build_branch_tip_list(repo: &Repository) -> Result<Vec<BranchTip>, Box<dyn std::error::Error>> {
+    let mut tips = Vec::new();
+
+    let local_branches = repo.branches(Some(git2::BranchType::Local))?;
+    for branch in local_branches {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            let reference = branch.get();
+            if let Some(target) = reference.target() {
+                let time_seconds = repo
+                    .find_commit(target)
+                    .map(|commit| commit.time().seconds())
+                    .unwrap_or(0);
+                tips.push(BranchTip {
+                    name: normalize_branch_name(name),
+                    oid: target,
+                    is_remote: false,
+                    time_seconds,
+                });
+            }
+        }
+    }
+
+    let remote_branches = repo.branches(Some(git2::BranchType::Remote))?;
+    for branch in remote_branches {
+        let (branch, _) = branch?;
+        if let Some(name) = branch.name()? {
+            let reference = branch.get();
+            if let Some(target) = reference.target() {
+                let time_seconds = repo
+                    .find_commit(target)
+                    .map(|commit| commit.time().seconds())
+                    .unwrap_or(0);
+                tips.push(BranchTip {
+                    name: normalize_branch_name(name),
+                    oid: target,
+                    is_remote: true,
+                    time_seconds,
+                });
+            }
+        }
+    }
+
+    Ok(tips)
+}
+
+/// Get the primary branch for a commit using a simplified approach
+/// Instead of walking all branch histories, we check if commit is reachable from main branches
+fn get_branch_for_commit_fast(
+    repo: &Repository,
+    commit_oid: git2::Oid,
+    branch_tip_map: &BranchTipMap,
+    branch_tips: &[BranchTip],
+) -> (Vec<String>, bool) {
+    // First check if this commit is a branch tip (fast path)
+    if let Some((branches, is_remote)) = branch_tip_map.get(&commit_oid) {
+        let mut result = branches.clone();
+        // Prioritize main branches
+        result.sort_by(|a, b| {
+            let a_main = is_main_branch(a);
+            let b_main = is_main_branch(b);
+            b_main.cmp(&a_main)
+        });
+        result.truncate(2);
+        return (result, *is_remote);
+    }
+
+    // For non-tip commits, find all branches whose tip contains this commit.
+    let mut branches = Vec::new();
+    let mut is_on_remote = false;
+
+    for tip in branch_tips {
+        let is_ancestor = tip.oid == commit_oid
+            || repo
+                .graph_descendant_of(tip.oid, commit_oid)
+                .unwrap_or(false);
+
+        if is_ancestor {
+            if !branches.contains(&tip.name) {
+                branches.push(tip.name.clone());
+            }
+            if tip.is_remote {
+                is_on_remote = true;
+            }
+        }
+    }
+
+    if branches.is_empty() {
+        return (vec!["unknown".to_string()], false);
+    }
+
+    branches.sort_by(|a, b| {
+        let a_main = is_main_branch(a);
+        let b_main = is_main_branch(b);
+        b_main.cmp(&a_main).then_with(|| a.cmp(b))
+    });
+    branches.truncate(3);
+
+    (branches, is_on_remote)
+}
+
+pub(crate) fn normalize_branch_name(branch_name: &str) -> String {
+    branch_name
+        .replace("origin/", "")
+        .replace("refs/heads/", "")
+}
+
+pub(crate) fn is_main_branch(branch_name: &str) -> bool {
+    let main_branch_names = [
+        "main",
+        "master",
+        "origin/main",
+        "origin/master",
+        "develop",
+        "origin/develop",
+    ];
+    main_branch_names.contains(&branch_name)
+}
+
+/// Remote callbacks using the SSH agent (or default credentials) for authentication
+fn credential_callbacks<'a>() -> git2::RemoteCallbacks<'a> {
+    let mut callbacks = git2::RemoteCallbacks::new();
+    callbacks.credentials(|_url, username_from_url, _allowed_types| {
+        if let Some(username) = username_from_url {
+            git2::Cred::ssh_key_from_agent(username)
+        } else {
+            git2::Cred::default()
+        }
+    });
+    callbacks
+}
+
+pub fn fetch_repo(repo_path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let _span = perf::span(PerfCategory::Git, "fetch_repo");
+    let repo = Repository::open(repo_path)?;
+
+    let remotes = repo.remotes()?;
+    let mut fetch_results = Vec::new();
+
+    for remote_name in remotes.iter().flatten() {
+        match repo.find_remote(remote_name) {
+            Ok(mut remote) => {
+                let mut fetch_options = git2::FetchOptions::new();
+                fetch_options.remote_callbacks(credential_callbacks());
+                fetch_options.proxy_options(crate::net::git_proxy_options());
+
+                match remote.fetch(&[] as &[&str], Some(&mut fetch_options), None) {
+                    Ok(()) => {
+                        let stats = remote.stats();
+                        fetch_results.push(format!(
+                            "{}: {} objects received",
+                            remote_name,
+                            stats.received_objects()
+                        ));
+                    }
+                    Err(e) => {
+                        fetch_results.push(format!("{}: {}", remote_name, e));
+                    }
+                }
+            }
+            Err(e) => {
+                fetch_results.push(format!("{}: Failed to find remote - {}", remote_name, e));
+            }
+        }
+    }
+
+    if fetch_results.is_empty() {
+        Ok("No remotes found".to_string())
+    } else {
+        Ok(fetch_results.join("; "))
+    }
+}
+
+fn commit_graph_paths(repo: &Repository) -> [PathBuf; 2] {
+    let info = repo.path().join("objects").join("info");
+    [
+        info.join("commit-graph"),
+        info.join("commit-graphs").join("commit-graph-chain"),
+    ]
+}
+
+/// Whether git has written a commit-graph for this repository, in single-file or split form.
+/// libgit2 reads it on its own to skip parsing commit objects during walks
+fn repo_has_commit_graph(repo: &Repository) -> bool {
+    commit_graph_paths(repo).iter().any(|path| path.is_file())
+}
+
+pub fn has_commit_graph(repo_path: &str) -> bool {
+    Repository::open(repo_path)
+        .map(|repo| repo_has_commit_graph(&repo))
+        .unwrap_or(false)
+}
+
+/// Build or refresh the repository's commit-graph with the git CLI, since libgit2 can read
+/// one but not write it. Also turns on `fetch.writeCommitGraph` so fetches made with git
+/// keep it current
+pub fn run_repo_maintenance(
+    repo_path: &str,
+) -> Result<RepoMaintenanceResult, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let repo = Repository::open(repo_path)?;
+    let had_commit_graph = repo_has_commit_graph(&repo);
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo.path())
+        .args(["commit-graph", "write", "--reachable", "--changed-paths"])
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("git commit-graph write failed: {}", stderr.trim()).into());
+    }
+
+    let mut config = repo.config()?;
+    config.set_bool("core.commitGraph", true)?;
+    config.set_bool("fetch.writeCommitGraph", true)?;
+
+    Ok(RepoMaintenanceResult {
+        repo_path: repo_path.to_string(),
+        had_commit_graph,
+        has_commit_graph: repo_has_commit_graph(&repo),
+        duration_ms: started.elapsed().as_millis() as u64,
+    })
+}
+
+fn get_remote_url(repo: &Repository) -> Option<String> {
+    if let Ok(remote) = repo.find_remote("origin") {
+        if let Some(url) = remote.url() {
+            return Some(url.to_string());
+        }
+    }
+
+    if let Ok(remotes) = repo.remotes() {
+        for remote_name in remotes.iter().flatten() {
+            if let Ok(remote) = repo.find_remote(remote_name) {
+                if let Some(url) = remote.url() {
+                    return Some(url.to_string());
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Web address of the repository a remote URL points at
+fn remote_web_url(remote_url: &str) -> Option<String> {
+    if remote_url.starts_with("git@") {
+        let parts: Vec<&str> = remote_url.split(':').collect();
+        if parts.len() != 2 {
+            return None;
+        }
+        let host = parts[0].replace("git@", "");
+        let path = parts[1].trim_end_matches(".git");
+        Some(format!("https://{}/{}", host, path))
+    } else if remote_url.starts_with("https://") || remote_url.starts_with("http://") {
+        Some(remote_url.trim_end_matches(".git").to_string())
+    } else {
+        None
+    }
+}
+
+pub(crate) fn build_commit_url(remote_url: &str, commit_id: &str) -> Option<String> {
+    let url = remote_web_url(remote_url)?;
+
+    if url.contains("github.com") {
+        Some(format!("{}/commit/{}", url, commit_id))
+    } else if url.contains("gitlab.com") || url.contains("gitlab.") {
+        Some(format!("{}/-/commit/{}", url, commit_id))
+    } else if url.contains("bitbucket.org") {
+        Some(format!("{}/commits/{}", url, commit_id))
+    } else {
+        Some(format!("{}/commit/{}", url, commit_id))
+    }
+}
+
+fn build_pull_request_url(remote_url: &str, number: u64) -> Option<String> {
+    let url = remote_web_url(remote_url)?;
+
+    if url.contains("gitlab.com") || url.contains("gitlab.") {
+        Some(format!("{}/-/merge_requests/{}", url, number))
+    } else if url.contains("bitbucket.org") {
+        Some(format!("{}/pull-requests/{}", url, number))
+    } else {
+        Some(format!("{}/pull/{}", url, number))
+    }
+}
+
+/// Pull or merge request number named in a merge commit's message
+fn parse_merge_request_number(message: &str) -> Option<u64> {
+    let caps = MERGE_REQUEST_REGEX.captures(message)?;
+    caps.get(1).or_else(|| caps.get(2))?.as_str().parse().ok()
+}
+
+/// Link each merge commit among `commits` that names a pull or merge request, and the
+/// commits it merged, to that request. Commits merged more than once keep the earliest
+/// merge; commits whose merge falls outside `commits` stay unlinked
+fn attach_pull_requests(
+    repo_path: &str,
+    commits: &mut [GitCommit],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let repo = Repository::open(repo_path)?;
+    let remote_url = get_remote_url(&repo);
+    let mut links: HashMap<String, PullRequestLink> = HashMap::new();
+
+    // Oldest first, so a commit's first merge claims it
+    for commit in commits.iter().rev() {
+        if !commit.message.starts_with("Merge ") {
+            continue;
+        }
+        let merge = match Oid::from_str(&commit.id).and_then(|oid| repo.find_commit(oid)) {
+            Ok(merge) if merge.parent_count() >= 2 => merge,
+            _ => continue,
+        };
+        let number = match parse_merge_request_number(merge.message().unwrap_or("")) {
+            Some(number) => number,
+            None => continue,
+        };
+        let link = PullRequestLink {
+            number,
+            url: remote_url
+                .as_ref()
+                .and_then(|remote| build_pull_request_url(remote, number)),
+        };
+
+        // The merged commits are those reachable from the merged branch but not the target
+        let mut walk = repo.revwalk()?;
+        walk.push(merge.parent_id(1)?)?;
+        walk.hide(merge.parent_id(0)?)?;
+        for oid in walk.flatten().take(MAX_COMMITS_PER_REPO) {
+            links.entry(oid.to_string()).or_insert_with(|| link.clone());
+        }
+        links.insert(commit.id.clone(), link);
+    }
+
+    for commit in commits {
+        if let Some(link) = links.get(&commit.id) {
+            commit.pull_request = Some(link.clone());
+        }
+    }
+    Ok(())
+}
+
+/// Get files changed for a commit using optimized diff options (no content, just file names)
+fn get_files_changed_fast(repo: &Repository, commit: &git2::Commit) -> Vec<String> {
+    let mut files_changed = Vec::new();
+
+    let parent = match commit.parent(0) {
+        Ok(p) => p,
+        Err(_) => return files_changed, // Initial commit or error
+    };
+
+    let tree = match commit.tree() {
+        Ok(t) => t,
+        Err(_) => return files_changed,
+    };
+
+    let parent_tree = match parent.tree() {
+        Ok(t) => t,
+        Err(_) => return files_changed,
+    };
+
+    // Configure diff to skip content computation entirely
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.skip_binary_check(true); // Don't check if files are binary
+    diff_opts.ignore_submodules(true); // Skip submodule processing
+    diff_opts.context_lines(0); // No context lines needed
+
+    let diff = match repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), Some(&mut diff_opts)) {
+        Ok(d) => d,
+        Err(_) => return files_changed,
+    };
+
+    // Use deltas() iterator - much faster than foreach, no callbacks
+    for delta in diff.deltas().take(MAX_FILES_PER_COMMIT) {
+        if let Some(path) = delta.new_file().path() {
+            if let Some(path_str) = path.to_str() {
+                files_changed.push(path_str.to_string());
+            }
+        }
+    }
+
+    files_changed
+}
+
+/// Commits in `[start_seconds, end_seconds]` across all local and remote branches, newest
+/// first. The walk is time-sorted and stops at the first commit older than the range; it
+/// also stops, marking the result truncated, after `MAX_COMMITS_PER_REPO` matches,
+/// `MAX_COMMITS_WALKED` visited commits or `WALK_TIME_BUDGET`. With `set_first_parent` on,
+/// only first parents are followed and branches already merged into a main branch are skipped
+pub fn get_repo_commits(
+    repo_path: &str,
+    start_seconds: i64,
+    end_seconds: i64,
+) -> Result<CommitWalk, Box<dyn std::error::Error>> {
+    let _span = perf::span(PerfCategory::Git, "get_repo_commits");
+    get_repo_commits_with(commit_backend(), repo_path, start_seconds, end_seconds)
+}
+
+fn get_repo_commits_with(
+    backend: CommitBackend,
+    repo_path: &str,
+    start_seconds: i64,
+    end_seconds: i64,
+) -> Result<CommitWalk, Box<dyn std::error::Error>> {
+    match backend {
+        #[cfg(feature = "gix")]
+        CommitBackend::Gix => {
+            crate::git_gix::get_repo_commits(repo_path, start_seconds, end_seconds)
+        }
+        _ => get_repo_commits_git2(repo_path, start_seconds, end_seconds),
+    }
+}
+
+/// Time the same query against each backend built in, opening the repository fresh each
+/// time so the numbers reflect a cold query
+pub fn benchmark_commit_backends(
+    repo_path: &str,
+    start_seconds: i64,
+    end_seconds: i64,
+) -> Vec<BackendBenchmark> {
+    CommitBackend::ALL
+        .into_iter()
+        .filter(CommitBackend::is_available)
+        .map(|backend| {
+            let started = Instant::now();
+            let result = get_repo_commits_with(backend, repo_path, start_seconds, end_seconds);
+            let duration_ms = started.elapsed().as_millis() as u64;
+            match result {
+                Ok(walk) => BackendBenchmark {
+                    backend,
+                    duration_ms,
+                    commit_count: walk.commits.len(),
+                    truncated: walk.truncated,
+                    error: None,
+                },
+                Err(e) => BackendBenchmark {
+                    backend,
+                    duration_ms,
+                    commit_count: 0,
+                    truncated: false,
+                    error: Some(e.to_string()),
+                },
+            }
+        })
+        .collect()
+}
+
+/// Build a `GitCommit` from a commit read with git2
+fn git2_commit(
+    repo: &Repository,
+    repo_path: &str,
+    commit: &git2::Commit,
+    branches: Vec<String>,
+    url: Option<String>,
+) -> GitCommit {
+    let author = commit.author();
+    let message = commit.message().unwrap_or("").to_string();
+    let commit_time = commit.time();
+
+    GitCommit {
+        id: commit.id().to_string(),
+        message: message.lines().next().unwrap_or("").to_string(),
+        author_name: author.name().unwrap_or("Unknown").to_string(),
+        author_email: author.email().unwrap_or("").to_string(),
+        timestamp: time_to_timestamp_ms(commit_time),
+        date: time_to_iso_date(commit_time),
+        repo_path: repo_path.to_string(),
+        // Get files changed using optimized method (no diff content)
+        files_changed: get_files_changed_fast(repo, commit),
+        branches,
+        url,
+        projects: Vec::new(),
+        conventional: parse_conventional_commit(&message),
+        tickets: extract_ticket_ids(&message),
+        pull_request: None,
+        repo_paths: Vec::new(),
+        duplicate_of: None,
+    }
+}
+
+fn get_repo_commits_git2(
+    repo_path: &str,
+    start_seconds: i64,
+    end_seconds: i64,
+) -> Result<CommitWalk, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let repo = Repository::open(repo_path)?;
+    let mut revwalk = repo.revwalk()?;
+
+    // Build branch tip map once upfront (much faster than per-commit checks)
+    let branch_tip_map = build_branch_tip_map(&repo).unwrap_or_default();
+    let branch_tips_raw = build_branch_tip_list(&repo).unwrap_or_default();
+
+    if first_parent() {
+        // Branches merged into a main branch are left out so they show up as the merge commit
+        let main_tips: Vec<git2::Oid> = branch_tips_raw
+            .iter()
+            .filter(|tip| is_main_branch(&tip.name))
+            .map(|tip| tip.oid)
+            .collect();
+        for tip in &branch_tips_raw {
+            let merged = !is_main_branch(&tip.name)
+                && main_tips.iter().any(|&main| {
+                    main == tip.oid || repo.graph_descendant_of(main, tip.oid).unwrap_or(false)
+                });
+            if !merged {
+                revwalk.push(tip.oid)?;
+            }
+        }
+        revwalk.simplify_first_parent()?;
+    } else {
+        revwalk.push_glob("refs/heads/*")?;
+        revwalk.push_glob("refs/remotes/*")?;
+    }
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let remote_url = get_remote_url(&repo);
+
+    // Consolidate by branch name and keep the newest tip per branch
+    let mut tips_by_name: HashMap<String, BranchTip> = HashMap::new();
+    for tip in branch_tips_raw {
+        tips_by_name
+            .entry(tip.name.clone())
+            .and_modify(|existing| {
+                if tip.time_seconds > existing.time_seconds {
+                    existing.oid = tip.oid;
+                    existing.time_seconds = tip.time_seconds;
+                }
+                if tip.is_remote {
+                    existing.is_remote = true;
+                }
+            })
+            .or_insert(tip);
+    }
+
+    let mut branch_tips: Vec<BranchTip> = tips_by_name.into_values().collect();
+    branch_tips.sort_by_key(|tip| std::cmp::Reverse(tip.time_seconds));
+
+    // Always include main-like branches even if they are old
+    let mut main_like: Vec<BranchTip> = branch_tips
+        .iter()
+        .filter(|tip| is_main_branch(&tip.name))
+        .cloned()
+        .collect();
+
+    let tip_limit = if repo_has_commit_graph(&repo) {
+        MAX_BRANCH_TIPS_WITH_COMMIT_GRAPH
+    } else {
+        MAX_BRANCH_TIPS_FOR_MATCH
+    };
+    let mut limited: Vec<BranchTip> = branch_tips.into_iter().take(tip_limit).collect();
+
+    for tip in main_like.drain(..) {
+        if !limited.iter().any(|existing| existing.name == tip.name) {
+            limited.push(tip);
+        }
+    }
+
+    let mut commits = Vec::new();
+    let mut seen_commits = HashSet::new();
+    let mut truncated = false;
+
+    for oid in revwalk {
+        // Stop early if we've reached a limit
+        if commits.len() >= MAX_COMMITS_PER_REPO
+            || seen_commits.len() >= MAX_COMMITS_WALKED
+            || started.elapsed() >= WALK_TIME_BUDGET
+        {
+            truncated = true;
+            break;
+        }
+
+        let oid = match oid {
+            Ok(oid) => oid,
+            Err(_) => continue,
+        };
+
+        if seen_commits.contains(&oid) {
+            continue;
+        }
+        seen_commits.insert(oid);
+
+        let commit = match repo.find_commit(oid) {
+            Ok(c) => c,
+            Err(_) => continue,
+        };
+
+        let commit_time = commit.time();
+        let commit_timestamp = commit_time.seconds();
+
+        // Skip commits outside the date range
+        // Since we're sorted by time, we can break early if we're past the range
+        if commit_timestamp < start_seconds {
+            break;
+        }
+        if commit_timestamp > end_seconds {
+            continue;
+        }
+
+        // Use the fast branch detection
+        let (branches, is_on_remote) =
+            get_branch_for_commit_fast(&repo, oid, &branch_tip_map, &limited);
+
+        let url = if is_on_remote {
+            remote_url
+                .as_ref()
+                .and_then(|remote| build_commit_url(remote, &oid.to_string()))
+        } else {
+            None
+        };
+
+        commits.push(git2_commit(&repo, repo_path, &commit, branches, url));
+    }
+
+    commits.sort_by_key(|commit| std::cmp::Reverse(commit.timestamp));
+
+    Ok(CommitWalk { commits, truncated })
+}
+
+/// Files changed, insertions and deletions of a commit against its first parent
+fn commit_change_volume(repo: &Repository, commit: &git2::Commit) -> Option<(usize, usize, usize)> {
+    let tree = commit.tree().ok()?;
+    let parent_tree = commit.parent(0).ok().and_then(|parent| parent.tree().ok());
+    let mut diff_opts = DiffOptions::new();
+    diff_opts.ignore_submodules(true);
+    diff_opts.context_lines(0);
+    let stats = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut diff_opts))
+        .ok()?
+        .stats()
+        .ok()?;
+    Some((stats.files_changed(), stats.insertions(), stats.deletions()))
+}
+
+/// How much a commit changed, as a rough measure of how interesting it is
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CommitImpact {
+    pub score: f32,
+    pub files_changed: usize,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub touches_tests: bool,
+    pub touches_docs: bool,
+}
+
+fn is_test_path(path: &str) -> bool {
+    let path = path.to_lowercase();
+    path.split('/').any(|part| {
+        matches!(part, "test" | "tests" | "spec" | "specs" | "__tests__")
+            || part.contains("_test.")
+            || part.contains(".test.")
+            || part.contains(".spec.")
+            || part.starts_with("test_")
+    })
+}
+
+fn is_docs_path(path: &str) -> bool {
+    let path = path.to_lowercase();
+    path.starts_with("docs/")
+        || path.contains("/docs/")
+        || [".md", ".mdx", ".rst", ".adoc"]
+            .iter()
+            .any(|ext| path.ends_with(ext))
+}
+
+/// Lines and files changed count logarithmically, so a huge generated diff doesn't dwarf
+/// everything else; covering tests or docs adds a bonus. Merges score 0 since their changes
+/// belong to the merged commits
+fn commit_impact(repo: &Repository, commit: &GitCommit) -> CommitImpact {
+    let found = Oid::from_str(&commit.id)
+        .and_then(|oid| repo.find_commit(oid))
+        .ok();
+    let Some(found) = found.filter(|c| c.parent_count() <= 1) else {
+        return CommitImpact::default();
+    };
+    let (files_changed, insertions, deletions) =
+        commit_change_volume(repo, &found).unwrap_or((commit.files_changed.len(), 0, 0));
+    let touches_tests = commit.files_changed.iter().any(|f| is_test_path(f));
+    let touches_docs = commit.files_changed.iter().any(|f| is_docs_path(f));
+
+    let mut score =
+        2.0 * ((insertions + deletions) as f32).ln_1p() + (files_changed as f32).ln_1p();
+    if touches_tests {
+        score += 1.5;
+    }
+    if touches_docs {
+        score += 0.5;
+    }
+
+    CommitImpact {
+        score,
+        files_changed,
+        insertions,
+        deletions,
+        touches_tests,
+        touches_docs,
+    }
+}
+
+/// Impact of each commit, in the same order. Commits whose repository can't be opened
+/// score 0
+pub fn get_commit_impacts(commits: &[GitCommit]) -> Vec<CommitImpact> {
+    let mut repos: HashMap<&str, Option<Repository>> = HashMap::new();
+    commits
+        .iter()
+        .map(|commit| {
+            let repo = repos
+                .entry(commit.repo_path.as_str())
+                .or_insert_with(|| Repository::open(&commit.repo_path).ok());
+            repo.as_ref()
+                .map(|repo| commit_impact(repo, commit))
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn author_leaderboard(
+    repo_path: &str,
+    start_seconds: i64,
+    end_seconds: i64,
+) -> Result<AuthorLeaderboard, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let repo = Repository::open(repo_path)?;
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push_glob("refs/heads/*")?;
+    revwalk.push_glob("refs/remotes/*")?;
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    // Keyed by lowercased email so the same person under different names is counted once
+    let mut authors: HashMap<String, AuthorStats> = HashMap::new();
+    let mut truncated = false;
+    for (walked, oid) in revwalk.enumerate() {
+        if walked >= MAX_COMMITS_WALKED || started.elapsed() >= WALK_TIME_BUDGET {
+            truncated = true;
+            break;
+        }
+        let commit = match oid.and_then(|oid| repo.find_commit(oid)) {
+            Ok(commit) => commit,
+            Err(_) => continue,
+        };
+        let commit_time = commit.time();
+        if commit_time.seconds() < start_seconds {
+            break;
+        }
+        // Merges would count the merged branch's changes a second time
+        if commit_time.seconds() > end_seconds || commit.parent_count() > 1 {
+            continue;
+        }
+
+        let author = commit.author();
+        let email = author.email().unwrap_or("").to_string();
+        let timestamp = time_to_timestamp_ms(commit_time);
+        let stats = authors
+            .entry(email.to_lowercase())
+            .or_insert_with(|| AuthorStats {
+                // Walking newest first, so the first name seen is the most recent
+                author_name: author.name().unwrap_or("Unknown").to_string(),
+                author_email: email,
+                first_commit_at: timestamp,
+                last_commit_at: timestamp,
+                ..AuthorStats::default()
+            });
+        stats.commits += 1;
+        stats.first_commit_at = stats.first_commit_at.min(timestamp);
+        stats.last_commit_at = stats.last_commit_at.max(timestamp);
+        if let Some((files, insertions, deletions)) = commit_change_volume(&repo, &commit) {
+            stats.files_changed += files;
+            stats.insertions += insertions;
+            stats.deletions += deletions;
+        }
+    }
+
+    let mut authors: Vec<AuthorStats> = authors.into_values().collect();
+    authors.sort_by(|a, b| {
+        b.commits
+            .cmp(&a.commits)
+            .then_with(|| (b.insertions + b.deletions).cmp(&(a.insertions + a.deletions)))
+            .then_with(|| a.author_name.cmp(&b.author_name))
+    });
+
+    Ok(AuthorLeaderboard {
+        repo_path: repo_path.to_string(),
+        authors,
+        truncated,
+    })
+}
+
+/// Commit counts and lines changed per author in a time range, for looking at how work was
+/// shared across a team. Merge commits are left out
+pub fn get_author_stats(
+    repo_path: &str,
+    start_timestamp: u64,
+    end_timestamp: u64,
+) -> Result<AuthorLeaderboard, Box<dyn std::error::Error>> {
+    let start_seconds = (start_timestamp / 1000) as i64;
+    let end_seconds = (end_timestamp / 1000) as i64;
+    on_git_pool(|| {
+        author_leaderboard(repo_path, start_seconds, end_seconds).map_err(|e| e.to_string())
+    })
+    .map_err(Into::into)
+}
+
+/// A single commit, e.g. `HEAD` right after it was made
+pub fn get_commit(repo_path: &str, rev: &str) -> Result<GitCommit, Box<dyn std::error::Error>> {
+    let repo = Repository::open(repo_path)?;
+    let commit = repo.revparse_single(rev)?.peel_to_commit()?;
+    let branches = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(str::to_string))
+        .into_iter()
+        .collect();
+    Ok(git2_commit(&repo, repo_path, &commit, branches, None))
+}
+
+/// Commits reachable from `branch` (a local or remote branch name, e.g. `release/2.1` or
+/// `origin/main`) in a time range, following only first parents if `first_parent` is set
+fn get_branch_commits(
+    repo_path: &str,
+    branch: &str,
+    start_seconds: i64,
+    end_seconds: i64,
+    first_parent: bool,
+) -> Result<CommitWalk, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let repo = Repository::open(repo_path)?;
+    let reference = repo
+        .resolve_reference_from_short_name(branch)
+        .map_err(|_| format!("Branch not found: {}", branch))?;
+    let tip = reference.peel_to_commit()?.id();
+
+    // Commits the remote has too can link to it
+    let upstream = if reference.is_remote() {
+        Some(tip)
+    } else {
+        git2::Branch::wrap(reference)
+            .upstream()
+            .ok()
+            .and_then(|upstream| upstream.get().target())
+    };
+    let remote_url = get_remote_url(&repo);
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(tip)?;
+    if first_parent {
+        revwalk.simplify_first_parent()?;
+    }
+    revwalk.set_sorting(git2::Sort::TIME)?;
+
+    let mut commits = Vec::new();
+    let mut truncated = false;
+    for (walked, oid) in revwalk.enumerate() {
+        if commits.len() >= MAX_COMMITS_PER_REPO
+            || walked >= MAX_COMMITS_WALKED
+            || started.elapsed() >= WALK_TIME_BUDGET
+        {
+            truncated = true;
+            break;
+        }
+
+        let commit = match oid.and_then(|oid| repo.find_commit(oid)) {
+            Ok(commit) => commit,
+            Err(_) => continue,
+        };
+        let commit_timestamp = commit.time().seconds();
+        if commit_timestamp < start_seconds {
+            break;
+        }
+        if commit_timestamp > end_seconds {
+            continue;
+        }
+
+        let is_on_remote = upstream.is_some_and(|upstream| {
+            upstream == commit.id()
+                || repo
+                    .graph_descendant_of(upstream, commit.id())
+                    .unwrap_or(false)
+        });
+        let url = if is_on_remote {
+            remote_url
+                .as_ref()
+                .and_then(|remote| build_commit_url(remote, &commit.id().to_string()))
+        } else {
+            None
+        };
+        commits.push(git2_commit(
+            &repo,
+            repo_path,
+            &commit,
+            vec![normalize_branch_name(branch)],
+            url,
+        ));
+    }
+
+    commits.sort_by_key(|commit| std::cmp::Reverse(commit.timestamp));
+
+    Ok(CommitWalk { commits, truncated })
+}
+
+/// Commits that landed on one branch in a time range, as opposed to across all refs
+pub fn get_commits_for_branch(
+    repo_path: &str,
+    branch: &str,
+    start_timestamp: u64,
+    end_timestamp: u64,
+    first_parent: bool,
+) -> RepoCommits {
+    let start_seconds = (start_timestamp / 1000) as i64;
+    let end_seconds = (end_timestamp / 1000) as i64;
+
+    on_git_pool(|| {
+        match get_branch_commits(repo_path, branch, start_seconds, end_seconds, first_parent) {
+            Ok(mut walk) => {
+                if let Err(e) = attach_pull_requests(repo_path, &mut walk.commits) {
+                    eprintln!("Failed to link pull requests in {}: {}", repo_path, e);
+                }
+                RepoCommits {
+                    repo_path: repo_path.to_string(),
+                    commits: walk.commits,
+                    error: None,
+                    truncated: walk.truncated,
+                    settings: RepoSettings::default(),
+                }
+            }
+            Err(e) => RepoCommits {
+                repo_path: repo_path.to_string(),
+                commits: Vec::new(),
+                error: Some(format!("Error reading branch: {}", e)),
+                truncated: false,
+                settings: RepoSettings::default(),
+            },
+        }
+    })
+}
+
+fn sync_signature(repo: &Repository) -> Result<git2::Signature<'static>, git2::Error> {
+    repo.signature()
+        .map(|sig| sig.to_owned())
+        .or_else(|_| git2::Signature::now("stream", "stream@localhost"))
+}
+
+/// Stage every change in the working tree (including deletions) and commit it.
+/// Returns the new commit id, or None when there was nothing to commit
+fn commit_local_changes(
+    repo: &Repository,
+) -> Result<Option<git2::Oid>, Box<dyn std::error::Error>> {
+    let mut index = repo.index()?;
+    let workdir = repo.workdir().map(Path::to_path_buf);
+    // Notes' lock files stay beside them between writes, and a note being locked is left
+    // as it was so its plaintext isn't committed on the way out
+    let mut skip = |path: &Path, _: &[u8]| -> i32 {
+        let being_locked = workdir
+            .as_ref()
+            .is_some_and(|dir| is_being_locked(&dir.join(path)));
+        i32::from(is_lock_file(path) || being_locked)
+    };
+    index.add_all(["*"].iter(), git2::IndexAddOption::DEFAULT, Some(&mut skip))?;
+    index.update_all(["*"].iter(), Some(&mut skip))?;
+    index.write()?;
+
+    let tree_id = index.write_tree()?;
+    let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+
+    if let Some(parent) = &parent {
+        if parent.tree_id() == tree_id {
+            return Ok(None);
+        }
+    }
+
+    let tree = repo.find_tree(tree_id)?;
+    let signature = sync_signature(repo)?;
+    let message = format!(
+        "Sync notes {}",
+        chrono::Local::now().format("%Y-%m-%d %H:%M")
+    );
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let oid = repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &parents,
+    )?;
+
+    Ok(Some(oid))
+}
+
+/// Rebase local commits onto the fetched upstream. Returns the number of upstream
+/// commits pulled in, or the conflicted paths if the rebase could not complete
+fn rebase_onto_upstream(
+    repo: &Repository,
+    upstream_oid: git2::Oid,
+) -> Result<Result<usize, Vec<String>>, Box<dyn std::error::Error>> {
+    let head_oid = match repo.head().ok().and_then(|h| h.target()) {
+        Some(oid) => oid,
+        None => return Ok(Ok(0)),
+    };
+
+    if head_oid == upstream_oid || repo.graph_descendant_of(head_oid, upstream_oid)? {
+        return Ok(Ok(0));
+    }
+
+    let (_, pulled) = repo.graph_ahead_behind(upstream_oid, head_oid)?;
+
+    // Nothing local to replay: fast-forward
+    if repo.graph_descendant_of(upstream_oid, head_oid)? {
+        let mut head_ref = repo.head()?;
+        head_ref.set_target(upstream_oid, "stream sync: fast-forward")?;
+        repo.checkout_head(Some(git2::build::CheckoutBuilder::new().force()))?;
+        return Ok(Ok(pulled));
+    }
+
+    // Rebase from the branch reference so finishing moves the branch, not a detached HEAD
+    let local = repo.reference_to_annotated_commit(&repo.head()?)?;
+    let upstream = repo.find_annotated_commit(upstream_oid)?;
+    let mut rebase = repo.rebase(Some(&local), Some(&upstream), None, None)?;
+    let signature = sync_signature(repo)?;
+
+    while let Some(operation) = rebase.next() {
+        operation?;
+
+        let index = repo.index()?;
+        if index.has_conflicts() {
+            let mut conflicts = Vec::new();
+            for conflict in index.conflicts()? {
+                let conflict = conflict?;
+                let entry = conflict.our.or(conflict.their).or(conflict.ancestor);
+                if let Some(entry) = entry {
+                    let path = String::from_utf8_lossy(&entry.path).to_string();
+                    if !conflicts.contains(&path) {
+                        conflicts.push(path);
+                    }
+                }
+            }
+            rebase.abort()?;
+            return Ok(Err(conflicts));
+        }
+
+        match rebase.commit(None, &signature, None) {
+            Ok(_) => {}
+            // The change already exists upstream; skip it
+            Err(e) if e.code() == git2::ErrorCode::Applied => {}
+            Err(e) => {
+                rebase.abort()?;
+                return Err(e.into());
+            }
+        }
+    }
+
+    rebase.finish(Some(&signature))?;
+    Ok(Ok(pulled))
+}
+
+/// Commit local changes in a notes repository, pull with rebase and push to upstream
+pub fn sync_notes(directory_path: &str) -> Result<NotesSyncResult, Box<dyn std::error::Error>> {
+    let repo = Repository::open(directory_path)?;
+    if repo.is_bare() {
+        return Err("Notes folder is a bare repository".into());
+    }
+    if repo.state() != git2::RepositoryState::Clean {
+        return Err(format!(
+            "Repository has an operation in progress ({:?}); resolve it first",
+            repo.state()
+        )
+        .into());
+    }
+
+    let commit_id = commit_local_changes(&repo)?;
+
+    let head = repo.head()?;
+    if !head.is_branch() {
+        return Err("HEAD is detached; check out a branch to sync".into());
+    }
+    let branch_name = head
+        .shorthand()
+        .ok_or("Branch name is not valid UTF-8")?
+        .to_string();
+    drop(head);
+
+    let branch = repo.find_branch(&branch_name, git2::BranchType::Local)?;
+    let upstream_ref_name = branch
+        .upstream()
+        .map_err(|_| format!("Branch '{}' has no upstream configured", branch_name))?
+        .get()
+        .name()
+        .ok_or("Upstream name is not valid UTF-8")?
+        .to_string();
+    let remote_name = repo
+        .branch_upstream_remote(&format!("refs/heads/{}", branch_name))?
+        .as_str()
+        .ok_or("Remote name is not valid UTF-8")?
+        .to_string();
+    let upstream_merge = repo
+        .config()?
+        .get_string(&format!("branch.{}.merge", branch_name))?;
+
+    let mut remote = repo.find_remote(&remote_name)?;
+    let mut fetch_options = git2::FetchOptions::new();
+    fetch_options.remote_callbacks(credential_callbacks());
+    fetch_options.proxy_options(crate::net::git_proxy_options());
+    remote.fetch(&[upstream_merge.as_str()], Some(&mut fetch_options), None)?;
+
+    let upstream_oid = repo.refname_to_id(&upstream_ref_name)?;
+
+    let pulled_commits = match rebase_onto_upstream(&repo, upstream_oid)? {
+        Ok(pulled) => pulled,
+        Err(conflicts) => {
+            return Ok(NotesSyncResult {
+                committed: commit_id.is_some(),
+                commit_id: commit_id.map(|id| id.to_string()),
+                pulled_commits: 0,
+                pushed: false,
+                message: format!(
+                    "Sync stopped: {} file(s) changed both locally and remotely",
+                    conflicts.len()
+                ),
+                conflicts,
+            })
+        }
+    };
+
+    let head_oid = repo.head()?.target();
+    let mut pushed = false;
+    if head_oid != Some(upstream_oid) {
+        let mut rejection: Option<String> = None;
+        {
+            let mut callbacks = credential_callbacks();
+            callbacks.push_update_reference(|_refname, status| {
+                if let Some(status) = status {
+                    rejection = Some(status.to_string());
+                }
+                Ok(())
+            });
+            let mut push_options = git2::PushOptions::new();
+            push_options.remote_callbacks(callbacks);
+            push_options.proxy_options(crate::net::git_proxy_options());
+
+            let refspec = format!("refs/heads/{}:{}", branch_name, upstream_merge);
+            remote.push(&[refspec.as_str()], Some(&mut push_options))?;
+        }
+
+        if let Some(reason) = rejection {
+            return Err(format!("Push rejected: {}", reason).into());
+        }
+        pushed = true;
+    }
+
+    let message = match (commit_id.is_some(), pulled_commits, pushed) {
+        (false, 0, false) => "Already up to date".to_string(),
+        (committed, pulled, pushed) => format!(
+            "{}pulled {} commit(s){}",
+            if committed {
+                "Committed local changes, "
+            } else {
+                ""
+            },
+            pulled,
+            if pushed { ", pushed" } else { "" }
+        ),
+    };
+
+    Ok(NotesSyncResult {
+        committed: commit_id.is_some(),
+        commit_id: commit_id.map(|id| id.to_string()),
+        pulled_commits,
+        pushed,
+        conflicts: Vec::new(),
+        message,
+    })
+}
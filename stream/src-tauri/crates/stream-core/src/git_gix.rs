@@ -0,0 +1,292 @@
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::time::Instant;
+
+use gix::object::tree::diff::Action;
+use gix::revision::walk::Sorting;
+use gix::{ObjectId, Repository};
+
+use crate::conventional::parse_conventional_commit;
+use crate::git::{
+    build_commit_url, first_parent, is_main_branch, normalize_branch_name, seconds_to_iso_date,
+    CommitWalk, GitCommit, MAX_COMMITS_PER_REPO, MAX_COMMITS_WALKED, MAX_FILES_PER_COMMIT,
+    WALK_TIME_BUDGET,
+};
+use crate::references::extract_ticket_ids;
+
+/// A branch name and whether it is a remote-tracking branch
+type BranchLabel = (String, bool);
+
+fn remote_url(repo: &Repository) -> Option<String> {
+    let url_of = |name: &str| {
+        repo.find_remote(name).ok().and_then(|remote| {
+            remote
+                .url(gix::remote::Direction::Fetch)
+                .map(|url| url.to_bstring().to_string())
+        })
+    };
+
+    url_of("origin").or_else(|| {
+        repo.remote_names()
+            .iter()
+            .find_map(|name| url_of(&name.to_string()))
+    })
+}
+
+/// Every local and remote branch tip with its normalized name
+fn branch_tips(
+    repo: &Repository,
+) -> Result<Vec<(ObjectId, BranchLabel)>, Box<dyn std::error::Error>> {
+    let references = repo.references()?;
+    let mut tips = Vec::new();
+
+    for (branches, is_remote) in [
+        (references.local_branches()?, false),
+        (references.remote_branches()?, true),
+    ] {
+        for reference in branches {
+            let mut reference = match reference {
+                Ok(reference) => reference,
+                Err(_) => continue,
+            };
+            let name = normalize_branch_name(&reference.name().shorten().to_string());
+            if let Ok(id) = reference.peel_to_id_in_place() {
+                tips.push((id.detach(), (name, is_remote)));
+            }
+        }
+    }
+
+    Ok(tips)
+}
+
+/// Paths touched by a commit relative to its first parent, capped like the git2 backend
+fn files_changed(repo: &Repository, commit: &gix::Commit<'_>) -> Vec<String> {
+    let mut files = Vec::new();
+
+    let tree = match commit.tree() {
+        Ok(tree) => tree,
+        Err(_) => return files,
+    };
+    let parent_tree = match commit.parent_ids().next() {
+        Some(parent_id) => match repo
+            .find_commit(parent_id)
+            .ok()
+            .and_then(|parent| parent.tree().ok())
+        {
+            Some(tree) => tree,
+            None => return files,
+        },
+        None => repo.empty_tree(),
+    };
+
+    let mut changes = match parent_tree.changes() {
+        Ok(changes) => changes,
+        Err(_) => return files,
+    };
+    let _ = changes
+        .options(|options| {
+            options.track_rewrites(None);
+        })
+        .track_path()
+        .for_each_to_obtain_tree(&tree, |change| {
+            if !change.event.entry_mode().is_tree() {
+                files.push(change.location.to_string());
+            }
+            Ok::<_, Infallible>(if files.len() >= MAX_FILES_PER_COMMIT {
+                Action::Cancel
+            } else {
+                Action::Continue
+            })
+        });
+
+    files
+}
+
+/// Main branch tips and the tips of branches not merged into them, for a first-parent walk.
+/// Only history back to `start_seconds` is searched; a branch merged before then has nothing
+/// in the range to hide
+fn unmerged_tips(
+    repo: &Repository,
+    tips: &[(ObjectId, BranchLabel)],
+    start_seconds: i64,
+) -> Result<Vec<ObjectId>, Box<dyn std::error::Error>> {
+    let main_tips: Vec<ObjectId> = tips
+        .iter()
+        .filter(|(_, (name, _))| is_main_branch(name))
+        .map(|(id, _)| *id)
+        .collect();
+    let on_main: HashSet<ObjectId> = repo
+        .rev_walk(main_tips.iter().copied())
+        .sorting(Sorting::ByCommitTimeNewestFirstCutoffOlderThan {
+            seconds: start_seconds,
+        })
+        .all()?
+        .take(MAX_COMMITS_WALKED)
+        .filter_map(|info| info.ok().map(|info| info.id))
+        .collect();
+
+    Ok(tips
+        .iter()
+        .filter(|(id, (name, _))| is_main_branch(name) || !on_main.contains(id))
+        .map(|(id, _)| *id)
+        .collect())
+}
+
+/// Same result as the git2 backend's `get_repo_commits`. The walk is cut off natively at
+/// `start_seconds`, and branch membership is found by passing each commit's branches down to
+/// its parents as the time-ordered walk reaches them, rather than with a containment check
+/// per branch
+pub(crate) fn get_repo_commits(
+    repo_path: &str,
+    start_seconds: i64,
+    end_seconds: i64,
+) -> Result<CommitWalk, Box<dyn std::error::Error>> {
+    let started = Instant::now();
+    let repo = gix::open(repo_path)?;
+    let remote_url = remote_url(&repo);
+
+    let tips = branch_tips(&repo)?;
+    let mut tip_names: HashMap<ObjectId, Vec<BranchLabel>> = HashMap::new();
+    for (id, label) in &tips {
+        let names = tip_names.entry(*id).or_default();
+        if !names.contains(label) {
+            names.push(label.clone());
+        }
+    }
+
+    // Branches reaching each commit, propagated from children to parents during the walk
+    let mut reached_by: HashMap<ObjectId, HashSet<BranchLabel>> = HashMap::new();
+    for (id, label) in &tips {
+        reached_by.entry(*id).or_default().insert(label.clone());
+    }
+
+    let first_parent = first_parent();
+    let walk_tips = if first_parent {
+        unmerged_tips(&repo, &tips, start_seconds)?
+    } else {
+        tips.iter().map(|(id, _)| *id).collect()
+    };
+    let mut walk =
+        repo.rev_walk(walk_tips)
+            .sorting(Sorting::ByCommitTimeNewestFirstCutoffOlderThan {
+                seconds: start_seconds,
+            });
+    if first_parent {
+        walk = walk.first_parent_only();
+    }
+    let walk = walk.all()?;
+
+    let mut commits = Vec::new();
+    let mut walked = 0;
+    let mut truncated = false;
+
+    for info in walk {
+        if commits.len() >= MAX_COMMITS_PER_REPO
+            || walked >= MAX_COMMITS_WALKED
+            || started.elapsed() >= WALK_TIME_BUDGET
+        {
+            truncated = true;
+            break;
+        }
+
+        let info = match info {
+            Ok(info) => info,
+            Err(_) => continue,
+        };
+        walked += 1;
+
+        let labels = reached_by.remove(&info.id).unwrap_or_default();
+        let parents = info
+            .parent_ids()
+            .take(if first_parent { 1 } else { usize::MAX });
+        for parent_id in parents {
+            reached_by
+                .entry(parent_id.detach())
+                .or_default()
+                .extend(labels.iter().cloned());
+        }
+
+        let commit = match info.object() {
+            Ok(commit) => commit,
+            Err(_) => continue,
+        };
+        let commit_timestamp = match commit.time() {
+            Ok(time) => time.seconds,
+            Err(_) => continue,
+        };
+        if commit_timestamp < start_seconds || commit_timestamp > end_seconds {
+            continue;
+        }
+
+        let (branches, is_on_remote) = match tip_names.get(&info.id) {
+            Some(names) => branch_names(names.iter(), 2),
+            None => branch_names(labels.iter(), 3),
+        };
+
+        let commit_id = info.id.to_string();
+        let url = if is_on_remote {
+            remote_url
+                .as_ref()
+                .and_then(|remote| build_commit_url(remote, &commit_id))
+        } else {
+            None
+        };
+
+        let message = String::from_utf8_lossy(commit.message_raw_sloppy()).to_string();
+        let (author_name, author_email) = match commit.author() {
+            Ok(author) => (author.name.to_string(), author.email.to_string()),
+            Err(_) => ("Unknown".to_string(), String::new()),
+        };
+
+        commits.push(GitCommit {
+            id: commit_id,
+            message: message.lines().next().unwrap_or("").to_string(),
+            author_name,
+            author_email,
+            timestamp: (commit_timestamp as u64) * 1000,
+            date: seconds_to_iso_date(commit_timestamp),
+            repo_path: repo_path.to_string(),
+            files_changed: files_changed(&repo, &commit),
+            branches,
+            url,
+            projects: Vec::new(),
+            conventional: parse_conventional_commit(&message),
+            tickets: extract_ticket_ids(&message),
+            pull_request: None,
+            repo_paths: Vec::new(),
+            duplicate_of: None,
+        });
+    }
+
+    commits.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+    Ok(CommitWalk { commits, truncated })
+}
+
+/// Branch names main-like first, then alphabetically, and whether any is on a remote
+fn branch_names<'a>(
+    labels: impl Iterator<Item = &'a BranchLabel>,
+    limit: usize,
+) -> (Vec<String>, bool) {
+    let mut names = Vec::new();
+    let mut is_on_remote = false;
+    for (name, is_remote) in labels {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+        is_on_remote |= *is_remote;
+    }
+
+    if names.is_empty() {
+        return (vec!["unknown".to_string()], false);
+    }
+
+    names.sort_by(|a, b| {
+        is_main_branch(b)
+            .cmp(&is_main_branch(a))
+            .then_with(|| a.cmp(b))
+    });
+    names.truncate(limit);
+
+    (names, is_on_remote)
+}
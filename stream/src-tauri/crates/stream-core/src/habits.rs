@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::markdown::daily_notes_in_range;
+use crate::tasks::tasks;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HabitStatus {
+    Done,
+    Missed,
+    /// No note for the day, or the note doesn't list the habit
+    Untracked,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HabitDay {
+    pub date: String,
+    pub status: HabitStatus,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HabitStats {
+    pub habit: String,
+    pub days: Vec<HabitDay>,
+    pub completed: usize,
+    pub tracked: usize,
+    pub completion_rate: f64,
+    pub current_streak: usize,
+    pub longest_streak: usize,
+}
+
+/// Whether checkbox text belongs to a habit: a case-insensitive prefix match on whole words,
+/// so "gym" matches "Gym (legs)" but not "gymnastics"
+fn matches_habit(text: &str, habit: &str) -> bool {
+    let text = text.trim().to_lowercase();
+    match text.strip_prefix(habit) {
+        Some(rest) => rest.chars().next().is_none_or(|c| !c.is_alphanumeric()),
+        None => false,
+    }
+}
+
+/// Per-habit status for one note. A habit listed more than once counts as done if any is ticked
+fn note_statuses(content: &str, habits: &[String]) -> HashMap<usize, HabitStatus> {
+    let mut statuses = HashMap::new();
+
+    for (_, task) in tasks(content) {
+        for (index, habit) in habits.iter().enumerate() {
+            if matches_habit(task.text, habit) {
+                let status = statuses.entry(index).or_insert(HabitStatus::Missed);
+                if task.done {
+                    *status = HabitStatus::Done;
+                }
+            }
+        }
+    }
+
+    statuses
+}
+
+/// Completion series and streaks for recurring checkbox lines like `- [x] gym` across
+/// the daily notes in `start..=end`
+pub fn get_habit_stats(
+    directory_path: &Path,
+    habit_patterns: &[String],
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<HabitStats>, Box<dyn std::error::Error>> {
+    if start > end {
+        return Err("Start date is after end date".into());
+    }
+
+    let habits: Vec<String> = habit_patterns
+        .iter()
+        .map(|h| h.trim().to_lowercase())
+        .collect();
+
+    let mut by_date: HashMap<NaiveDate, HashMap<usize, HabitStatus>> = HashMap::new();
+    for (date, path) in daily_notes_in_range(directory_path, start, end)? {
+        match fs::read_to_string(&path) {
+            Ok(content) => {
+                let statuses = by_date.entry(date).or_default();
+                for (index, status) in note_statuses(&content, &habits) {
+                    if status == HabitStatus::Done || !statuses.contains_key(&index) {
+                        statuses.insert(index, status);
+                    }
+                }
+            }
+            Err(e) => eprintln!("Habits: skipping unreadable note {}: {}", path.display(), e),
+        }
+    }
+
+    let dates: Vec<NaiveDate> = start.iter_days().take_while(|d| *d <= end).collect();
+
+    let stats = habit_patterns
+        .iter()
+        .enumerate()
+        .map(|(index, habit)| {
+            let statuses: Vec<HabitStatus> = dates
+                .iter()
+                .map(|date| {
+                    by_date
+                        .get(date)
+                        .and_then(|s| s.get(&index))
+                        .copied()
+                        .unwrap_or(HabitStatus::Untracked)
+                })
+                .collect();
+
+            let completed = statuses.iter().filter(|s| **s == HabitStatus::Done).count();
+            let tracked = statuses
+                .iter()
+                .filter(|s| **s != HabitStatus::Untracked)
+                .count();
+
+            let mut longest_streak = 0;
+            let mut run = 0;
+            for status in &statuses {
+                run = if *status == HabitStatus::Done {
+                    run + 1
+                } else {
+                    0
+                };
+                longest_streak = longest_streak.max(run);
+            }
+
+            // Trailing untracked days (e.g. today's note not written yet) don't end the streak
+            let current_streak = statuses
+                .iter()
+                .rev()
+                .skip_while(|s| **s == HabitStatus::Untracked)
+                .take_while(|s| **s == HabitStatus::Done)
+                .count();
+
+            HabitStats {
+                habit: habit.trim().to_string(),
+                days: dates
+                    .iter()
+                    .zip(&statuses)
+                    .map(|(date, status)| HabitDay {
+                        date: date.format("%Y-%m-%d").to_string(),
+                        status: *status,
+                    })
+                    .collect(),
+                completed,
+                tracked,
+                completion_rate: if tracked == 0 {
+                    0.0
+                } else {
+                    completed as f64 / tracked as f64
+                },
+                current_streak,
+                longest_streak,
+            }
+        })
+        .collect();
+
+    Ok(stats)
+}
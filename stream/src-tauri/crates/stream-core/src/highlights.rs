@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use chrono::NaiveDate;
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+
+use crate::git::{self, CommitImpact, GitCommit};
+use crate::markdown::entry_day_bounds;
+use crate::walk::walk_files;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitHighlight {
+    pub commit: GitCommit,
+    pub impact: CommitImpact,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteHighlight {
+    pub file_path: String,
+    /// Commits that day touching the note, when the notes directory is a git repository
+    pub edit_count: usize,
+    pub modified_at: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DailyHighlights {
+    pub date: String,
+    /// Highest impact first
+    pub commits: Vec<CommitHighlight>,
+    /// Most edited first, then most recently modified
+    pub notes: Vec<NoteHighlight>,
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+    let modified = path.metadata().ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+/// Notes changed during the day, with how many commits to the notes repository touched each
+fn edited_notes(directory_path: &Path, start_ms: u64, end_ms: u64) -> Vec<NoteHighlight> {
+    let mut notes: HashMap<PathBuf, NoteHighlight> = walk_files(directory_path, |path, _| {
+        if path.extension()? != "md" {
+            return None;
+        }
+        let modified_at = modified_ms(path)?;
+        (start_ms..=end_ms).contains(&modified_at).then(|| {
+            let note = NoteHighlight {
+                file_path: path.to_string_lossy().to_string(),
+                edit_count: 0,
+                modified_at,
+            };
+            (path.to_path_buf(), note)
+        })
+    })
+    .into_iter()
+    .collect();
+
+    // The notes directory may be a subfolder of its repository
+    let repo = Repository::discover(directory_path).ok();
+    let workdir = repo.as_ref().and_then(|repo| repo.workdir());
+    let prefix = workdir.and_then(|workdir| {
+        let directory = directory_path.canonicalize().ok()?;
+        Some(
+            directory
+                .strip_prefix(workdir.canonicalize().ok()?)
+                .ok()?
+                .to_path_buf(),
+        )
+    });
+    if let (Some(workdir), Some(prefix)) = (workdir, prefix) {
+        let repo_path = workdir.to_string_lossy().to_string();
+        for repo in git::get_git_commits_for_repos(&[repo_path], start_ms, end_ms) {
+            for file in repo.commits.iter().flat_map(|commit| &commit.files_changed) {
+                let path = match Path::new(file).strip_prefix(&prefix) {
+                    Ok(relative) => directory_path.join(relative),
+                    Err(_) => continue,
+                };
+                if path.extension().and_then(|ext| ext.to_str()) != Some("md") || !path.is_file() {
+                    continue;
+                }
+                let modified_at = modified_ms(&path).unwrap_or(0);
+                notes
+                    .entry(path.clone())
+                    .or_insert_with(|| NoteHighlight {
+                        file_path: path.to_string_lossy().to_string(),
+                        edit_count: 0,
+                        modified_at,
+                    })
+                    .edit_count += 1;
+            }
+        }
+    }
+
+    notes.into_values().collect()
+}
+
+/// The day's highest-impact commits across `repo_paths` and its most edited notes, up to
+/// `limit` of each. The day is bucketed in the timezone its note was written in
+pub fn get_daily_highlights(
+    directory_path: &Path,
+    repo_paths: &[String],
+    date: NaiveDate,
+    limit: usize,
+) -> Result<DailyHighlights, Box<dyn std::error::Error>> {
+    let (start_seconds, end_seconds) = entry_day_bounds(directory_path, date)
+        .ok_or_else(|| format!("Invalid local date: {}", date))?;
+    let (start_ms, end_ms) = (start_seconds as u64 * 1000, end_seconds as u64 * 1000 + 999);
+
+    let commits: Vec<GitCommit> = git::get_git_commits_for_repos(repo_paths, start_ms, end_ms)
+        .into_iter()
+        .flat_map(|repo| repo.commits)
+        .collect();
+    let impacts = git::get_commit_impacts(&commits);
+    let mut highlights: Vec<CommitHighlight> = commits
+        .into_iter()
+        .zip(impacts)
+        .map(|(commit, impact)| CommitHighlight { commit, impact })
+        .collect();
+    highlights.sort_by(|a, b| {
+        b.impact
+            .score
+            .total_cmp(&a.impact.score)
+            .then_with(|| b.commit.timestamp.cmp(&a.commit.timestamp))
+    });
+    highlights.truncate(limit);
+
+    let mut notes = if directory_path.is_dir() {
+        edited_notes(directory_path, start_ms, end_ms)
+    } else {
+        Vec::new()
+    };
+    notes.sort_by(|a, b| {
+        b.edit_count
+            .cmp(&a.edit_count)
+            .then_with(|| b.modified_at.cmp(&a.modified_at))
+    });
+    notes.truncate(limit);
+
+    Ok(DailyHighlights {
+        date: date.to_string(),
+        commits: highlights,
+        notes,
+    })
+}
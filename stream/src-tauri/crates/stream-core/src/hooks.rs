@@ -0,0 +1,211 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+
+/// Bumped whenever the hook script changes, so installed hooks can be found and upgraded
+pub const DIARY_HOOK_VERSION: u32 = 2;
+
+/// The diary hook lives between these markers so it can share a hook file with others
+const HOOK_START_MARKER: &str = "# >>> stream diary hook";
+const HOOK_END_MARKER: &str = "# <<< stream diary hook";
+const HOOK_NAME: &str = "post-commit";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiaryHookStatus {
+    pub hook_path: String,
+    pub installed: bool,
+    /// Version of the installed hook script, if installed
+    pub version: Option<u32>,
+    pub up_to_date: bool,
+}
+
+/// Quote for a POSIX shell, where nothing inside single quotes is special
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Script that appends each new commit to today's note in `notes_dir` through the CLI.
+/// It runs in the background and ignores failures so committing is never slowed or blocked
+fn render_hook_block(cli_path: &str, notes_dir: &Path) -> String {
+    format!(
+        "{start} v{version}\n\
+         # Managed by Stream; reinstall or uninstall from the app rather than editing\n\
+         if command -v {cli} >/dev/null 2>&1; then\n  \
+         {cli} --dir {dir} log-commit --repo \"$(git rev-parse --show-toplevel)\" \"$(git rev-parse HEAD)\" >/dev/null 2>&1 &\n\
+         fi\n\
+         {end}",
+        start = HOOK_START_MARKER,
+        version = DIARY_HOOK_VERSION,
+        cli = shell_quote(cli_path),
+        dir = shell_quote(&notes_dir.to_string_lossy()),
+        end = HOOK_END_MARKER,
+    )
+}
+
+/// The repository's hooks directory, honouring `core.hooksPath`
+fn hooks_dir(repo: &Repository) -> PathBuf {
+    let configured = repo
+        .config()
+        .ok()
+        .and_then(|config| config.get_path("core.hooksPath").ok());
+    match configured {
+        Some(path) if path.is_absolute() => path,
+        // Relative hook paths are resolved against the working tree
+        Some(path) => repo.workdir().unwrap_or_else(|| repo.path()).join(path),
+        None => repo.path().join("hooks"),
+    }
+}
+
+fn hook_path(repo_path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let repo = Repository::open(repo_path)?;
+    Ok(hooks_dir(&repo).join(HOOK_NAME))
+}
+
+/// Byte range of the managed block in `content`, end marker included
+fn find_hook_block(content: &str) -> Option<(usize, usize)> {
+    let start = content.find(HOOK_START_MARKER)?;
+    let end = start + content[start..].find(HOOK_END_MARKER)? + HOOK_END_MARKER.len();
+    Some((start, end))
+}
+
+fn installed_version(content: &str) -> Option<u32> {
+    let (start, _) = find_hook_block(content)?;
+    content[start + HOOK_START_MARKER.len()..]
+        .lines()
+        .next()?
+        .trim()
+        .strip_prefix('v')?
+        .parse()
+        .ok()
+}
+
+#[cfg(unix)]
+fn make_executable(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mut permissions = fs::metadata(path)?.permissions();
+    permissions.set_mode(permissions.mode() | 0o755);
+    fs::set_permissions(path, permissions)
+}
+
+#[cfg(not(unix))]
+fn make_executable(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+pub fn diary_hook_status(repo_path: &str) -> Result<DiaryHookStatus, Box<dyn std::error::Error>> {
+    let path = hook_path(repo_path)?;
+    let content = fs::read_to_string(&path).unwrap_or_default();
+    let installed = find_hook_block(&content).is_some();
+    let version = installed_version(&content);
+
+    Ok(DiaryHookStatus {
+        hook_path: path.to_string_lossy().to_string(),
+        installed,
+        version,
+        up_to_date: version == Some(DIARY_HOOK_VERSION),
+    })
+}
+
+/// Shells the diary hook's block can be added to
+const SHELLS: &[&str] = &["sh", "bash", "dash", "ksh", "zsh"];
+
+/// Length of the hook's shebang line when it runs a POSIX shell, e.g. `#!/bin/sh` or
+/// `#!/usr/bin/env bash`
+fn shell_shebang_len(content: &str) -> Option<usize> {
+    let line = content.lines().next()?;
+    let mut words = line.strip_prefix("#!")?.split_whitespace();
+    let mut program = Path::new(words.next()?).file_name()?.to_str()?;
+    if program == "env" {
+        program = words.next()?;
+    }
+    SHELLS.contains(&program).then_some(line.len())
+}
+
+/// Install the post-commit hook that logs commits to the diary, or upgrade it in place.
+/// An existing post-commit hook is kept, with the diary hook added right after its
+/// shebang so an `exit` or `exec` in it can't skip the diary. Hooks that aren't shell
+/// scripts are left alone
+pub fn install_diary_hook(
+    repo_path: &str,
+    cli_path: &str,
+    notes_dir: &Path,
+) -> Result<DiaryHookStatus, Box<dyn std::error::Error>> {
+    let path = hook_path(repo_path)?;
+    let block = render_hook_block(cli_path, notes_dir);
+    let mut content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e).into()),
+    };
+
+    match find_hook_block(&content) {
+        Some((start, end)) => {
+            content = format!(
+                "{}{}",
+                &content[..start],
+                content[end..].trim_start_matches('\n')
+            )
+        }
+        None if content.contains(HOOK_START_MARKER) => {
+            return Err(format!(
+                "{} has an unfinished Stream block; fix or remove it first",
+                path.display()
+            )
+            .into());
+        }
+        None => {}
+    }
+
+    let updated = if content.trim().is_empty() {
+        format!("#!/bin/sh\n\n{}\n", block)
+    } else {
+        let shebang_len = shell_shebang_len(&content).ok_or_else(|| {
+            format!(
+                "{} isn't a shell script, so the diary hook can't be added to it",
+                path.display()
+            )
+        })?;
+        let rest = content[shebang_len..].trim_start_matches('\n');
+        format!("{}\n\n{}\n\n{}", &content[..shebang_len], block, rest)
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, updated)?;
+    make_executable(&path)?;
+
+    diary_hook_status(repo_path)
+}
+
+/// Remove the diary hook, deleting the hook file if nothing else is left in it. Returns
+/// whether a hook was removed
+pub fn uninstall_diary_hook(repo_path: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let path = hook_path(repo_path)?;
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(_) => return Ok(false),
+    };
+    let (start, end) = match find_hook_block(&content) {
+        Some(range) => range,
+        None => return Ok(false),
+    };
+
+    let remaining = format!(
+        "{}\n{}",
+        content[..start].trim_end(),
+        content[end..].trim_start_matches('\n')
+    );
+    let only_shebang = remaining
+        .lines()
+        .all(|line| line.trim().is_empty() || line.starts_with("#!"));
+    if only_shebang {
+        fs::remove_file(&path)?;
+    } else {
+        fs::write(&path, format!("{}\n", remaining.trim_end()))?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,200 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::archive::{collect_files, relative_key};
+use crate::file_lock::lock_note_file;
+use crate::frontmatter::split_frontmatter;
+use crate::markdown::daily_note_path;
+use crate::search::find_markdown_files;
+use crate::undo::{UndoJournal, UndoKind};
+
+const IMPORTABLE_EXTENSIONS: &[&str] = &["md", "markdown", "txt"];
+
+/// Finds `2024-01-05`, `2024_01_05`, `2024.01.05` or `20240105` anywhere in a file name
+const DEFAULT_FILE_NAME_PATTERN: &str =
+    r"(?P<year>\d{4})[-_.]?(?P<month>\d{2})[-_.]?(?P<day>\d{2})";
+
+/// Placed between files merged into the same day's note
+const MERGE_SEPARATOR: &str = "\n\n---\n\n";
+
+/// Where an imported file's date comes from
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DateRule {
+    /// A regex over the file name with `year`, `month` and `day` groups; by default the
+    /// first `YYYY-MM-DD`-like date in the name
+    FileName { pattern: Option<String> },
+    /// A frontmatter field such as `date` or `created`, read with a chrono `format` or, without
+    /// one, as an RFC 3339 timestamp or a value starting `YYYY-MM-DD`. The frontmatter is
+    /// dropped from the imported note
+    Frontmatter { key: String, format: Option<String> },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct TextImportSummary {
+    pub files_imported: usize,
+    pub notes_created: usize,
+    /// Existing notes the imported text was appended to
+    pub notes_appended: usize,
+    /// Files with no date under the rule or that aren't UTF-8 text, relative to the source
+    pub skipped_files: Vec<String>,
+    /// Undo journal entry holding the notes' prior state, for rolling the import back.
+    /// None when imported without a journal
+    pub snapshot_id: Option<String>,
+}
+
+fn frontmatter_value<'a>(frontmatter: &'a str, key: &str) -> Option<&'a str> {
+    frontmatter.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        (name.trim() == key).then(|| value.trim().trim_matches(|c| c == '"' || c == '\''))
+    })
+}
+
+fn parse_date_value(value: &str, format: Option<&str>) -> Option<NaiveDate> {
+    if let Some(format) = format {
+        return NaiveDate::parse_from_str(value, format).ok().or_else(|| {
+            NaiveDateTime::parse_from_str(value, format)
+                .ok()
+                .map(|dt| dt.date())
+        });
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|dt| dt.date_naive())
+        .or_else(|| NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok())
+}
+
+fn date_from_file_name(path: &Path, pattern: &Regex) -> Option<NaiveDate> {
+    let name = path.file_stem()?.to_str()?;
+    let captures = pattern.captures(name)?;
+    NaiveDate::from_ymd_opt(
+        captures.name("year")?.as_str().parse().ok()?,
+        captures.name("month")?.as_str().parse().ok()?,
+        captures.name("day")?.as_str().parse().ok()?,
+    )
+}
+
+/// The file's date under `rule` and the text to import from it
+fn dated_content(
+    path: &Path,
+    rule: &DateRule,
+    pattern: Option<&Regex>,
+) -> Option<(NaiveDate, String)> {
+    let content = fs::read_to_string(path).ok()?;
+    match rule {
+        DateRule::FileName { .. } => Some((date_from_file_name(path, pattern?)?, content)),
+        DateRule::Frontmatter { key, format } => {
+            let (frontmatter, body) = split_frontmatter(&content)?;
+            let date = parse_date_value(frontmatter_value(frontmatter, key)?, format.as_deref())?;
+            Some((date, body.to_string()))
+        }
+    }
+}
+
+/// Convert a folder of text or markdown files exported from another app into daily notes,
+/// dating each file by `rule`. Files falling on the same day are merged in path order, and
+/// appended to that day's note if it already exists. With a `journal`, the notes about to be
+/// written are snapshotted first and the summary names the snapshot; nothing is imported if
+/// the snapshot can't be taken
+pub fn import_text_files(
+    source_dir: &Path,
+    directory_path: &Path,
+    rule: &DateRule,
+    journal: Option<&UndoJournal>,
+) -> Result<TextImportSummary, Box<dyn std::error::Error>> {
+    if !source_dir.is_dir() {
+        return Err(format!("Not a directory: {}", source_dir.display()).into());
+    }
+    let pattern = match rule {
+        DateRule::FileName { pattern } => Some(
+            Regex::new(pattern.as_deref().unwrap_or(DEFAULT_FILE_NAME_PATTERN))
+                .map_err(|e| format!("Invalid file name pattern: {}", e))?,
+        ),
+        DateRule::Frontmatter { .. } => None,
+    };
+
+    let mut files: Vec<PathBuf> = Vec::new();
+    collect_files(source_dir, &mut files)?;
+    files.retain(|path| {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| IMPORTABLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+    });
+    files.sort();
+
+    let mut summary = TextImportSummary::default();
+    let mut days: BTreeMap<NaiveDate, Vec<String>> = BTreeMap::new();
+    for path in &files {
+        match dated_content(path, rule, pattern.as_ref()) {
+            Some((date, content)) => {
+                let content = content.trim();
+                if !content.is_empty() {
+                    days.entry(date).or_default().push(content.to_string());
+                }
+                summary.files_imported += 1;
+            }
+            None => summary
+                .skipped_files
+                .push(relative_key(source_dir, path).unwrap_or_else(|| path.display().to_string())),
+        }
+    }
+
+    // Notes already in subfolders are appended to where they are, as `find_daily_note` would
+    let mut nested: HashMap<String, PathBuf> = HashMap::new();
+    if directory_path.is_dir() {
+        for note in find_markdown_files(&directory_path.to_string_lossy())? {
+            let note = PathBuf::from(note);
+            if let Some(name) = note.file_name().and_then(|n| n.to_str()) {
+                nested
+                    .entry(name.to_string())
+                    .or_insert_with(|| note.clone());
+            }
+        }
+    }
+    let targets: Vec<(NaiveDate, PathBuf)> = days
+        .keys()
+        .map(|&date| {
+            let root_note = daily_note_path(directory_path, date);
+            let path = match root_note.file_name().and_then(|n| n.to_str()) {
+                Some(name) if !root_note.is_file() => {
+                    nested.get(name).cloned().unwrap_or(root_note)
+                }
+                _ => root_note,
+            };
+            (date, path)
+        })
+        .collect();
+
+    if let Some(journal) = journal {
+        let paths: Vec<PathBuf> = targets.iter().map(|(_, path)| path.clone()).collect();
+        let description = format!("Import {}", source_dir.display());
+        let id = journal
+            .record_files(UndoKind::Import, &description, &paths)
+            .map_err(|e| format!("Couldn't save an undo copy before importing: {}", e))?;
+        summary.snapshot_id = Some(id);
+    }
+
+    fs::create_dir_all(directory_path)?;
+    for (date, path) in targets {
+        let merged = days[&date].join(MERGE_SEPARATOR);
+        let _lock = lock_note_file(&path)?;
+        let existing = fs::read_to_string(&path).unwrap_or_default();
+        if existing.trim().is_empty() {
+            fs::write(&path, format!("{}\n", merged))?;
+            summary.notes_created += 1;
+        } else {
+            fs::write(
+                &path,
+                format!("{}{}{}\n", existing.trim_end(), MERGE_SEPARATOR, merged),
+            )?;
+            summary.notes_appended += 1;
+        }
+    }
+
+    Ok(summary)
+}
@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::git::GitCommit;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LanguageCount {
+    pub language: String,
+    /// File changes in this language; a file changed in two commits counts twice
+    pub files: usize,
+    /// Commits touching at least one file in this language
+    pub commits: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DayLanguages {
+    pub date: String,
+    /// Most changed first
+    pub languages: Vec<LanguageCount>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LanguageStats {
+    /// Most changed first
+    pub total: Vec<LanguageCount>,
+    /// Oldest day first
+    pub by_day: Vec<DayLanguages>,
+    /// Changed files with no recognised language, e.g. images or lockfiles
+    pub unclassified_files: usize,
+}
+
+/// Language a file is written in, going by its name or extension
+pub fn language_for_path(path: &str) -> Option<&'static str> {
+    let path = Path::new(path);
+    let file_name = path.file_name()?.to_str()?;
+    match file_name {
+        "Dockerfile" | "Containerfile" => return Some("Docker"),
+        "Makefile" | "GNUmakefile" => return Some("Makefile"),
+        "CMakeLists.txt" => return Some("CMake"),
+        _ => {}
+    }
+
+    let extension = path.extension()?.to_str()?.to_lowercase();
+    let language = match extension.as_str() {
+        "rs" => "Rust",
+        "ts" | "tsx" | "mts" | "cts" => "TypeScript",
+        "js" | "jsx" | "mjs" | "cjs" => "JavaScript",
+        "py" | "pyi" => "Python",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "swift" => "Swift",
+        "c" | "h" => "C",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => "C++",
+        "cs" => "C#",
+        "m" | "mm" => "Objective-C",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "scala" => "Scala",
+        "ex" | "exs" => "Elixir",
+        "erl" | "hrl" => "Erlang",
+        "hs" => "Haskell",
+        "ml" | "mli" => "OCaml",
+        "clj" | "cljs" | "cljc" => "Clojure",
+        "dart" => "Dart",
+        "lua" => "Lua",
+        "zig" => "Zig",
+        "r" => "R",
+        "jl" => "Julia",
+        "sql" => "SQL",
+        "sh" | "bash" | "zsh" | "fish" => "Shell",
+        "ps1" | "psm1" => "PowerShell",
+        "html" | "htm" => "HTML",
+        "css" => "CSS",
+        "scss" | "sass" | "less" => "SCSS",
+        "vue" => "Vue",
+        "svelte" => "Svelte",
+        "md" | "mdx" | "markdown" => "Markdown",
+        "json" | "jsonc" => "JSON",
+        "yaml" | "yml" => "YAML",
+        "toml" => "TOML",
+        "xml" => "XML",
+        "graphql" | "gql" => "GraphQL",
+        "proto" => "Protobuf",
+        "tf" | "hcl" => "HCL",
+        "nix" => "Nix",
+        _ => return None,
+    };
+    Some(language)
+}
+
+#[derive(Default)]
+struct Tally {
+    files: HashMap<&'static str, usize>,
+    commits: HashMap<&'static str, usize>,
+}
+
+impl Tally {
+    fn counts(self) -> Vec<LanguageCount> {
+        let mut counts: Vec<LanguageCount> = self
+            .files
+            .into_iter()
+            .map(|(language, files)| LanguageCount {
+                language: language.to_string(),
+                files,
+                commits: self.commits.get(language).copied().unwrap_or(0),
+            })
+            .collect();
+        counts.sort_by(|a, b| {
+            b.files
+                .cmp(&a.files)
+                .then_with(|| a.language.cmp(&b.language))
+        });
+        counts
+    }
+}
+
+/// Changed files by language, overall and per day, deduplicating commits that appear in
+/// more than one repository
+pub fn language_stats<'a>(commits: impl IntoIterator<Item = &'a GitCommit>) -> LanguageStats {
+    let mut total = Tally::default();
+    let mut days: BTreeMap<&str, Tally> = BTreeMap::new();
+    let mut unclassified_files = 0;
+    let mut seen = HashSet::new();
+
+    for commit in commits {
+        if !seen.insert(commit.id.as_str()) {
+            continue;
+        }
+        let day = days.entry(&commit.date).or_default();
+        let mut languages = HashSet::new();
+        for file in &commit.files_changed {
+            match language_for_path(file) {
+                Some(language) => {
+                    *total.files.entry(language).or_insert(0) += 1;
+                    *day.files.entry(language).or_insert(0) += 1;
+                    languages.insert(language);
+                }
+                None => unclassified_files += 1,
+            }
+        }
+        for language in languages {
+            *total.commits.entry(language).or_insert(0) += 1;
+            *day.commits.entry(language).or_insert(0) += 1;
+        }
+    }
+
+    LanguageStats {
+        total: total.counts(),
+        by_day: days
+            .into_iter()
+            .filter(|(_, tally)| !tally.files.is_empty())
+            .map(|(date, tally)| DayLanguages {
+                date: date.to_string(),
+                languages: tally.counts(),
+            })
+            .collect(),
+        unclassified_files,
+    }
+}
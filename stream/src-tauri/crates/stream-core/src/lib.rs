@@ -0,0 +1,52 @@
+//! Shared notes and git logic used by the Tauri app and the `stream` CLI.
+
+pub mod archive;
+pub mod attachments;
+pub mod bookmark;
+pub mod calendar;
+pub mod capture;
+pub mod clock;
+pub mod cloud;
+pub mod commit_export;
+pub mod commit_log;
+pub mod conventional;
+pub mod digest;
+pub mod doctor;
+pub mod file_lock;
+pub mod focus;
+pub mod frontmatter;
+pub mod git;
+#[cfg(feature = "gix")]
+mod git_gix;
+pub mod habits;
+pub mod highlights;
+pub mod hooks;
+pub mod import;
+pub mod languages;
+pub mod lint;
+pub mod lock;
+pub mod markdown;
+pub mod net;
+pub mod note_ids;
+pub mod paths;
+pub mod perf;
+pub mod plain_text;
+pub mod publish;
+pub mod recents;
+pub mod references;
+pub mod rollover;
+pub mod search;
+pub mod site;
+pub mod sources;
+pub mod spellcheck;
+pub mod stats;
+pub mod structure;
+pub mod sync;
+pub mod tasks;
+pub mod template;
+pub mod timeline;
+pub mod undo;
+pub mod unfurl;
+pub mod walk;
+pub mod webhooks;
+pub mod worklog;
@@ -0,0 +1,183 @@
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use chrono::{Local, NaiveDate};
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::cloud::is_placeholder;
+use crate::search::find_markdown_files;
+use crate::tasks::parse_task_line;
+
+// A TODO/FIXME marker anywhere in a line
+static TODO_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b(TODO|FIXME)\b").expect("Failed to compile TODO regex"));
+
+/// Ways a daily note's heading may spell its date
+const HEADING_DATE_FORMATS: &[&str] = &["%Y-%m-%d", "%B %-d, %Y", "%-d %B %Y", "%b %-d, %Y"];
+
+/// Which rules run. Leaving out `max_line_length` or `stale_todo_days` turns that rule off
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LintRules {
+    pub missing_date_heading: bool,
+    pub max_line_length: Option<usize>,
+    pub unclosed_code_fence: bool,
+    pub stale_todo_days: Option<u32>,
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        LintRules {
+            missing_date_heading: true,
+            max_line_length: Some(1000),
+            unclosed_code_fence: true,
+            stale_todo_days: Some(30),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LintRule {
+    /// No heading names the note's date
+    MissingDateHeading,
+    LongLine,
+    /// A ``` fence that's never closed, which swallows the rest of the note
+    UnclosedCodeFence,
+    /// An open task or TODO in a note older than the configured number of days
+    StaleTodo,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LintDiagnostic {
+    pub file_path: String,
+    pub rule: LintRule,
+    /// 1-based; None for problems with the note as a whole
+    pub line_number: Option<u64>,
+    pub message: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct LintReport {
+    pub files_scanned: usize,
+    pub diagnostics: Vec<LintDiagnostic>,
+}
+
+fn heading_names_date(heading: &str, date: NaiveDate) -> bool {
+    let heading = heading.to_lowercase();
+    HEADING_DATE_FORMATS
+        .iter()
+        .any(|format| heading.contains(&date.format(format).to_string().to_lowercase()))
+}
+
+fn lint_note(file_path: &str, rules: &LintRules, today: NaiveDate) -> Vec<LintDiagnostic> {
+    let content = match fs::read_to_string(file_path) {
+        Ok(content) => content,
+        Err(_) => return Vec::new(),
+    };
+    let date = Path::new(file_path)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok());
+    let stale = match (rules.stale_todo_days, date) {
+        (Some(days), Some(date)) => (today - date).num_days() > i64::from(days),
+        _ => false,
+    };
+
+    let mut diagnostics = Vec::new();
+    let mut diagnostic = |rule, line_number: Option<usize>, message: String| {
+        diagnostics.push(LintDiagnostic {
+            file_path: file_path.to_string(),
+            rule,
+            line_number: line_number.map(|n| n as u64 + 1),
+            message,
+        });
+    };
+
+    let mut open_fence: Option<usize> = None;
+    let mut has_date_heading = false;
+    for (index, line) in content.lines().enumerate() {
+        if line.trim_start().starts_with("```") {
+            open_fence = match open_fence {
+                Some(_) => None,
+                None => Some(index),
+            };
+            continue;
+        }
+
+        if let Some(max) = rules.max_line_length {
+            let length = line.chars().count();
+            if length > max {
+                diagnostic(
+                    LintRule::LongLine,
+                    Some(index),
+                    format!("Line is {} characters long (limit {})", length, max),
+                );
+            }
+        }
+        if open_fence.is_some() {
+            continue;
+        }
+
+        if line.starts_with('#') {
+            has_date_heading |= date.is_some_and(|date| heading_names_date(line, date));
+        }
+        let open_task = parse_task_line(line).is_some_and(|task| !task.done);
+        if stale && (open_task || TODO_REGEX.is_match(line)) {
+            diagnostic(
+                LintRule::StaleTodo,
+                Some(index),
+                format!("Open since {}: {}", date.unwrap_or(today), line.trim()),
+            );
+        }
+    }
+
+    if rules.unclosed_code_fence {
+        if let Some(index) = open_fence {
+            diagnostic(
+                LintRule::UnclosedCodeFence,
+                Some(index),
+                "Code fence is never closed".to_string(),
+            );
+        }
+    }
+    if rules.missing_date_heading && !has_date_heading {
+        if let Some(date) = date {
+            diagnostic(
+                LintRule::MissingDateHeading,
+                None,
+                format!("No heading names {}", date),
+            );
+        }
+    }
+
+    diagnostics
+}
+
+/// Check the daily notes in `directory_path` against `rules`, in path order. Online-only
+/// files are skipped rather than downloaded
+pub fn lint_notes(
+    directory_path: &Path,
+    rules: &LintRules,
+) -> Result<LintReport, Box<dyn std::error::Error>> {
+    if !directory_path.is_dir() {
+        return Err(format!("Not a directory: {}", directory_path.display()).into());
+    }
+
+    let mut files = find_markdown_files(&directory_path.to_string_lossy())?;
+    files.retain(|f| !is_placeholder(Path::new(f)));
+    let today = Local::now().date_naive();
+
+    let per_file: Vec<Vec<LintDiagnostic>> = files
+        .par_iter()
+        .map(|file_path| lint_note(file_path, rules, today))
+        .collect();
+
+    Ok(LintReport {
+        files_scanned: files.len(),
+        diagnostics: per_file.into_iter().flatten().collect(),
+    })
+}
@@ -0,0 +1,303 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Argon2, Params};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+
+use crate::file_lock::lock_note_file;
+use crate::markdown::{read_all_user_xattrs, write_user_xattrs};
+use crate::sync::crypto::{check_passphrase, derive_key, open, random_bytes, seal};
+use crate::sync::SyncProgress;
+use crate::undo::{UndoJournal, UndoKind};
+use crate::walk::walk_files;
+
+/// Appended to a note's file name while it's locked, e.g. `2024-01-05.md.locked`.
+/// Locked notes no longer end in `.md`, so search and the content readers skip them
+pub const LOCKED_EXTENSION: &str = "locked";
+const LOCKED_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LockedNote {
+    version: u32,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    data: String,
+}
+
+pub fn is_locked_path(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == LOCKED_EXTENSION)
+}
+
+/// `2024-01-05.md.locked` -> `2024-01-05.md`
+pub fn unlocked_path(path: &Path) -> PathBuf {
+    if is_locked_path(path) {
+        path.with_extension("")
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// `2024-01-05.md` -> `2024-01-05.md.locked`
+pub(crate) fn locked_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(LOCKED_EXTENSION);
+    PathBuf::from(name)
+}
+
+/// A note whose locked copy has been written but which hasn't been removed yet. Auto-commit
+/// and sync leave it out, so the note's latest plaintext isn't published on its way out
+pub(crate) fn is_being_locked(path: &Path) -> bool {
+    !is_locked_path(path) && locked_path(path).exists()
+}
+
+/// Fails for a locked note, or a note whose locked copy exists. Writers call it holding the
+/// note's file lock, which `lock_note` keeps until the plaintext is gone
+pub(crate) fn ensure_unlocked(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if is_locked_path(path) || locked_path(path).exists() {
+        return Err("Note is locked".into());
+    }
+    Ok(())
+}
+
+/// Replace `from` with `to`, whose contents `convert` makes from `from`'s, carrying the
+/// file's xattrs (location, mood, ...) over. `from` is read under both names' locks, so a
+/// write that lands before the swap is converted too rather than deleted with `from`
+fn replace_file(
+    from: &Path,
+    to: &Path,
+    convert: impl FnOnce(&[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Both names are locked, so a key rotation of the locked copy waits for the swap. Taken
+    // in a fixed order so a lock and an unlock can't each hold one
+    let (first, second) = if from < to { (from, to) } else { (to, from) };
+    let _first_lock = lock_note_file(first)?;
+    let _second_lock = lock_note_file(second)?;
+    if to.exists() {
+        return Err(format!("{} already exists", to.display()).into());
+    }
+    let contents = convert(&fs::read(from)?)?;
+    fs::write(to, contents)?;
+    if let Err(e) = write_user_xattrs(to, &read_all_user_xattrs(from)) {
+        eprintln!("Failed to carry metadata over to {}: {}", to.display(), e);
+    }
+    fs::remove_file(from)?;
+    Ok(())
+}
+
+/// Encrypt a note in place with a passphrase, renaming it to `<name>.locked`.
+/// Returns the locked file's path.
+///
+/// Only the file on disk is encrypted. Versions of the note already committed by git sync
+/// stay readable in the repository's history and its remote, and copies already uploaded
+/// by file sync stay on the remote until the next sync replaces them with the locked file.
+/// The plaintext is kept out of commits and uploads made while the lock is under way
+pub fn lock_note(
+    file_path: &Path,
+    passphrase: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if is_locked_path(file_path) {
+        return Err("Note is already locked".into());
+    }
+    check_passphrase(passphrase)?;
+
+    let target = locked_path(file_path);
+    if target.exists() {
+        return Err(format!("{} already exists", target.display()).into());
+    }
+
+    replace_file(file_path, &target, |plaintext| {
+        seal_note(passphrase, plaintext)
+    })?;
+
+    Ok(target)
+}
+
+/// Decrypt a locked note without touching the file
+pub fn read_locked_note(
+    file_path: &Path,
+    passphrase: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    open_note(passphrase, &fs::read(file_path)?)
+}
+
+/// Decrypt the contents of a locked note
+fn open_note(passphrase: &str, sealed: &[u8]) -> Result<String, Box<dyn std::error::Error>> {
+    let locked: LockedNote = serde_json::from_slice(sealed).map_err(|_| "Not a locked note")?;
+    if locked.version != LOCKED_FORMAT_VERSION {
+        return Err(format!("Unsupported locked note version {}", locked.version).into());
+    }
+
+    let key = derive_key(
+        passphrase.as_bytes(),
+        &STANDARD.decode(&locked.salt)?,
+        locked.m_cost,
+        locked.t_cost,
+        locked.p_cost,
+    )?;
+    let plaintext =
+        open(&key, &[], &STANDARD.decode(&locked.data)?).ok_or("Incorrect passphrase")?;
+
+    Ok(String::from_utf8(plaintext)?)
+}
+
+/// Decrypt a locked note back to its original name. Returns the unlocked path
+pub fn unlock_note(
+    file_path: &Path,
+    passphrase: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if !is_locked_path(file_path) {
+        return Err("Note is not locked".into());
+    }
+
+    let target = unlocked_path(file_path);
+    if target.exists() {
+        return Err(format!("{} already exists", target.display()).into());
+    }
+    replace_file(file_path, &target, |sealed| {
+        Ok(open_note(passphrase, sealed)?.into_bytes())
+    })?;
+
+    Ok(target)
+}
+
+/// Outcome of `rotate_locked_notes`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LockedNotesRotation {
+    pub rotated: usize,
+    /// Notes that already open with the new passphrase, e.g. from an interrupted rotation
+    pub already_rotated: usize,
+    /// Notes that open with neither passphrase, left as they are
+    pub skipped: Vec<String>,
+    /// Undo journal entry holding the notes as they were, sealed with the old passphrase.
+    /// None when rotated without a journal
+    pub snapshot_id: Option<String>,
+}
+
+fn seal_note(passphrase: &str, plaintext: &[u8]) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let params = Params::default();
+    let (m_cost, t_cost, p_cost) = (params.m_cost(), params.t_cost(), params.p_cost());
+    let salt = random_bytes(16);
+    let key = derive_key(passphrase.as_bytes(), &salt, m_cost, t_cost, p_cost)?;
+
+    let locked = LockedNote {
+        version: LOCKED_FORMAT_VERSION,
+        m_cost,
+        t_cost,
+        p_cost,
+        salt: STANDARD.encode(&salt),
+        data: STANDARD.encode(seal(&key, &[], plaintext)?),
+    };
+    Ok(serde_json::to_vec_pretty(&locked)?)
+}
+
+/// Where a note's re-encrypted copy is written before it replaces the note
+fn rotating_path(file_path: &Path) -> PathBuf {
+    let name = file_path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    file_path.with_file_name(format!(".{}.rotating", name))
+}
+
+/// Re-encrypt one locked note under `new_passphrase`, replacing it with a rename so the
+/// note is never half-written. The note's lock lives beside it, so it holds across the
+/// rename. Returns false when it already opens with the new passphrase
+fn rotate_locked_note(
+    file_path: &Path,
+    old_passphrase: &str,
+    new_passphrase: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let _lock = lock_note_file(file_path)?;
+    let temp_path = rotating_path(file_path);
+    // Left behind by an interrupted rotation
+    let _ = fs::remove_file(&temp_path);
+
+    if read_locked_note(file_path, new_passphrase).is_ok() {
+        return Ok(false);
+    }
+    let content = read_locked_note(file_path, old_passphrase)?;
+
+    let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(&seal_note(new_passphrase, content.as_bytes())?)?;
+        file.sync_all()?;
+        write_user_xattrs(&temp_path, &read_all_user_xattrs(file_path))?;
+        fs::rename(&temp_path, file_path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result.map(|()| true)
+}
+
+/// Re-encrypt every locked note under `directory_path` from `old_passphrase` to
+/// `new_passphrase`. Each note is replaced atomically and notes already under the new
+/// passphrase are skipped, so an interrupted rotation is finished by running it again.
+/// With a `journal`, the notes are snapshotted first and nothing is rotated if that fails
+pub fn rotate_locked_notes(
+    directory_path: &Path,
+    old_passphrase: &str,
+    new_passphrase: &str,
+    journal: Option<&UndoJournal>,
+    progress: &dyn Fn(SyncProgress),
+) -> Result<LockedNotesRotation, Box<dyn std::error::Error>> {
+    check_passphrase(new_passphrase)?;
+    let mut notes = walk_files(directory_path, |path, _| {
+        is_locked_path(path).then(|| path.to_path_buf())
+    });
+    notes.sort();
+
+    let mut rotation = LockedNotesRotation::default();
+    if let Some(journal) = journal.filter(|_| !notes.is_empty()) {
+        let id = journal
+            .record_files(UndoKind::Migration, "Rotate locked notes", &notes)
+            .map_err(|e| format!("Couldn't save an undo copy before rotating: {}", e))?;
+        rotation.snapshot_id = Some(id);
+    }
+    for (index, note) in notes.iter().enumerate() {
+        progress(SyncProgress {
+            phase: "notes".to_string(),
+            current: index + 1,
+            total: notes.len(),
+            file_path: Some(note.to_string_lossy().to_string()),
+        });
+        match rotate_locked_note(note, old_passphrase, new_passphrase) {
+            Ok(true) => rotation.rotated += 1,
+            Ok(false) => rotation.already_rotated += 1,
+            Err(e) => {
+                eprintln!("Failed to rotate {}: {}", note.display(), e);
+                rotation.skipped.push(note.to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(rotation)
+}
+
+/// Argon2id PHC string for a passphrase, safe to store
+pub fn hash_passphrase(passphrase: &str) -> Result<String, Box<dyn std::error::Error>> {
+    check_passphrase(passphrase)?;
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(passphrase.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| format!("Failed to hash passphrase: {}", e).into())
+}
+
+pub fn verify_passphrase_hash(passphrase: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| {
+            Argon2::default()
+                .verify_password(passphrase.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
@@ -0,0 +1,1569 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+
+use chrono::{Datelike, NaiveDate};
+use chrono_tz::Tz;
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::now_ms;
+use crate::cloud::{download_state, DownloadState};
+use crate::file_lock::{lock_note_file, with_note_lock};
+use crate::frontmatter::{parse_frontmatter, Frontmatter};
+use crate::git::{day_bounds_in_timezone, local_day_bounds, RepoCommits};
+use crate::lock::{ensure_unlocked, is_locked_path, locked_path, LOCKED_EXTENSION};
+use crate::perf::{self, PerfCategory};
+use crate::sources::read_source_config;
+use crate::template::{render_template, TemplateContext};
+use crate::walk::walk_files;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkdownFileMetadata {
+    pub file_path: String,
+    pub file_name: String,
+    pub created_at: u64,
+    pub modified_at: u64,
+    pub size: u64,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub date_from_filename: u64,
+    pub download_state: DownloadState,
+    pub mood: Option<String>,
+    pub timezone: Option<String>,
+    pub locked: bool,
+    pub description: Option<String>,
+    pub refresh_interval: Option<String>,
+    pub last_refreshed_at: Option<u64>,
+    /// Stable id for `stream://note/<id>` links, once one has been assigned
+    pub note_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructuredMarkdownFileMetadata {
+    pub file_path: String,
+    pub file_name: String,
+    pub created_at: u64,
+    pub modified_at: u64,
+    pub size: u64,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub download_state: DownloadState,
+    /// Position in the manually arranged list; unordered files follow, newest first
+    pub order_index: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StructuredMarkdownFile {
+    pub file_path: String,
+    pub file_name: String,
+    pub created_at: u64,
+    pub modified_at: u64,
+    pub size: u64,
+    pub country: Option<String>,
+    pub city: Option<String>,
+    pub description: Option<String>,
+    pub content: String,
+    pub refresh_interval: Option<String>,
+    pub last_refreshed_at: Option<u64>,
+    pub download_state: DownloadState,
+    /// `content` is only the first lines of a large file; load the rest with
+    /// `read_file_chunk` from the content's UTF-8 length
+    pub is_truncated: bool,
+    /// Parsed from the start of `content`; None when there's no valid YAML block
+    #[serde(default)]
+    pub frontmatter: Option<Frontmatter>,
+}
+
+/// Part of a file read by `read_file_chunk`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChunk {
+    pub content: String,
+    /// Byte offset the content starts at, moved past any partial character
+    pub offset: u64,
+    /// Where the next chunk starts; equals `size` at the end of the file
+    pub next_offset: u64,
+    pub size: u64,
+}
+
+/// Structured files larger than this are returned as a preview of their first lines
+pub const STRUCTURED_PREVIEW_BYTES: u64 = 256 * 1024;
+/// Largest chunk `read_file_chunk` returns
+pub const MAX_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+static DATE_FILENAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4})-(\d{2})-(\d{2})\.md$").expect("Failed to compile date filename regex")
+});
+
+// `#tag` preceded by start-of-line or whitespace. Requiring a letter straight after `#`
+// keeps headings (`# Title`) and issue refs (`#123`) out
+static TAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|\s)#([A-Za-z][\w/-]*)").expect("Failed to compile tag regex")
+});
+
+pub(crate) const XATTR_COUNTRY_KEY: &str = "user.location.country";
+pub(crate) const XATTR_CITY_KEY: &str = "user.location.city";
+const XATTR_DESCRIPTION_KEY: &str = "user.file.description";
+pub(crate) const XATTR_REFRESH_INTERVAL_KEY: &str = "user.refresh.interval";
+pub(crate) const XATTR_LAST_REFRESHED_KEY: &str = "user.refresh.last_refreshed";
+pub(crate) const XATTR_MOOD_KEY: &str = "user.journal.mood";
+pub(crate) const XATTR_TIMEZONE_KEY: &str = "user.journal.timezone";
+pub(crate) const XATTR_LAST_VIEWED_KEY: &str = "user.journal.last_viewed";
+pub(crate) const XATTR_ORDER_INDEX_KEY: &str = "user.structured.order_index";
+pub(crate) const XATTR_NOTE_ID_KEY: &str = "user.journal.note_id";
+
+// Never-viewed notes are this many times likelier to resurface than ones already revisited
+const UNVIEWED_WEIGHT: f64 = 4.0;
+
+// Emoji moods map onto the same 1-5 scale as numeric ones so they chart together
+const MOOD_EMOJI_SCALE: &[(&str, f64)] = &[
+    ("😞", 1.0),
+    ("😢", 1.0),
+    ("🙁", 2.0),
+    ("😕", 2.0),
+    ("😐", 3.0),
+    ("🙂", 4.0),
+    ("😊", 4.0),
+    ("😄", 5.0),
+    ("😁", 5.0),
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MoodPoint {
+    pub date: String,
+    pub file_path: String,
+    pub mood: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteForDate {
+    pub metadata: MarkdownFileMetadata,
+    pub content: FileContent,
+    /// The day's commits, when asked for
+    pub commits: Option<Vec<RepoCommits>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OnThisDayEntry {
+    pub year: i32,
+    pub years_ago: i32,
+    pub note: MarkdownFileMetadata,
+    pub excerpt: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResurfacedNote {
+    pub note: MarkdownFileMetadata,
+    pub last_viewed_at: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RefreshInterval {
+    Minutely,
+    Hourly,
+    Daily,
+    Weekly,
+    None,
+}
+
+impl std::fmt::Display for RefreshInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            RefreshInterval::Minutely => "minutely",
+            RefreshInterval::Hourly => "hourly",
+            RefreshInterval::Daily => "daily",
+            RefreshInterval::Weekly => "weekly",
+            RefreshInterval::None => "none",
+        })
+    }
+}
+
+impl RefreshInterval {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "minutely" => Some(RefreshInterval::Minutely),
+            "hourly" => Some(RefreshInterval::Hourly),
+            "daily" => Some(RefreshInterval::Daily),
+            "weekly" => Some(RefreshInterval::Weekly),
+            "none" => Some(RefreshInterval::None),
+            _ => None,
+        }
+    }
+
+    pub fn duration_ms(&self) -> Option<u64> {
+        match self {
+            RefreshInterval::Minutely => Some(60 * 1000),
+            RefreshInterval::Hourly => Some(60 * 60 * 1000),
+            RefreshInterval::Daily => Some(24 * 60 * 60 * 1000),
+            RefreshInterval::Weekly => Some(7 * 24 * 60 * 60 * 1000),
+            RefreshInterval::None => None,
+        }
+    }
+}
+
+pub fn read_location_xattrs(file_path: &Path) -> (Option<String>, Option<String>) {
+    let country = xattr::get(file_path, XATTR_COUNTRY_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok());
+
+    let city = xattr::get(file_path, XATTR_CITY_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok());
+
+    (country, city)
+}
+
+pub fn read_description_xattr(file_path: &Path) -> Option<String> {
+    xattr::get(file_path, XATTR_DESCRIPTION_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
+pub fn write_description_xattr(
+    file_path: &Path,
+    description: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if description.is_empty() {
+        let _ = xattr::remove(file_path, XATTR_DESCRIPTION_KEY);
+        Ok(())
+    } else {
+        xattr::set(file_path, XATTR_DESCRIPTION_KEY, description.as_bytes())?;
+        Ok(())
+    }
+}
+
+pub fn write_location_xattrs(
+    file_path: &Path,
+    country: &str,
+    city: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    xattr::set(file_path, XATTR_COUNTRY_KEY, country.as_bytes())?;
+    xattr::set(file_path, XATTR_CITY_KEY, city.as_bytes())?;
+    Ok(())
+}
+
+/// Score on the 1-5 scale for a numeric or emoji mood, None if it isn't recognized
+pub fn mood_score(mood: &str) -> Option<f64> {
+    let mood = mood.trim();
+    if let Ok(value) = mood.parse::<f64>() {
+        return (1.0..=5.0).contains(&value).then_some(value);
+    }
+    MOOD_EMOJI_SCALE
+        .iter()
+        .find(|(emoji, _)| mood.trim_end_matches('\u{fe0f}') == *emoji)
+        .map(|(_, score)| *score)
+}
+
+pub fn read_mood_xattr(file_path: &Path) -> Option<String> {
+    xattr::get(file_path, XATTR_MOOD_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
+/// Set a note's mood (1-5 or an emoji from the scale). An empty mood clears it
+pub fn write_mood_xattr(file_path: &Path, mood: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let mood = mood.trim();
+    if mood.is_empty() {
+        let _ = xattr::remove(file_path, XATTR_MOOD_KEY);
+        return Ok(());
+    }
+
+    if mood_score(mood).is_none() {
+        return Err(format!("Unrecognized mood '{}': use 1-5 or a mood emoji", mood).into());
+    }
+    xattr::set(file_path, XATTR_MOOD_KEY, mood.as_bytes())?;
+    Ok(())
+}
+
+/// Arrange the structured list in the order of `paths_in_order`. Files left out lose their
+/// position and go back to being sorted by modification time after the arranged ones
+pub fn set_structured_file_order(
+    directory_path: &Path,
+    paths_in_order: &[PathBuf],
+) -> Result<(), Box<dyn std::error::Error>> {
+    let structured_dir_path = directory_path.join("structured");
+    if let Some(path) = paths_in_order
+        .iter()
+        .find(|path| path.parent() != Some(structured_dir_path.as_path()))
+    {
+        return Err(format!("Not a structured file: {}", path.display()).into());
+    }
+
+    for (index, path) in paths_in_order.iter().enumerate() {
+        xattr::set(path, XATTR_ORDER_INDEX_KEY, index.to_string().as_bytes())?;
+    }
+
+    let ordered: HashSet<&PathBuf> = paths_in_order.iter().collect();
+    for entry in fs::read_dir(&structured_dir_path)?.flatten() {
+        let path = entry.path();
+        if !ordered.contains(&path) {
+            let _ = xattr::remove(&path, XATTR_ORDER_INDEX_KEY);
+        }
+    }
+    Ok(())
+}
+
+/// Moods recorded on daily notes in `start..=end`, oldest first. Notes without a mood are skipped
+pub fn get_mood_trend(
+    directory_path: &Path,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<MoodPoint>, Box<dyn std::error::Error>> {
+    Ok(daily_notes_in_range(directory_path, start, end)?
+        .into_iter()
+        .filter_map(|(date, path)| {
+            let mood = read_mood_xattr(&path)?;
+            let score = mood_score(&mood)?;
+            Some(MoodPoint {
+                date: date.format("%Y-%m-%d").to_string(),
+                file_path: path.to_string_lossy().to_string(),
+                mood,
+                score,
+            })
+        })
+        .collect())
+}
+
+/// Opening text of a note with whitespace collapsed, cut to `max_chars`
+fn note_excerpt(path: &Path, max_chars: usize) -> Option<String> {
+    let content = fs::read_to_string(path).ok()?;
+    let text = content
+        .lines()
+        .flat_map(|line| line.trim_start().trim_start_matches('#').split_whitespace())
+        .collect::<Vec<_>>()
+        .join(" ");
+    if text.is_empty() {
+        return None;
+    }
+
+    let mut excerpt: String = text.chars().take(max_chars).collect();
+    if excerpt.len() < text.len() {
+        excerpt.push('…');
+    }
+    Some(excerpt)
+}
+
+/// Daily notes written on `month`/`day` in earlier years, most recent first. With
+/// `excerpt_chars`, each entry carries the note's opening text; locked notes and cloud
+/// placeholders are left without one so nothing is decrypted or downloaded
+pub fn get_on_this_day(
+    directory_path: &Path,
+    month: u32,
+    day: u32,
+    excerpt_chars: Option<usize>,
+    cache: &MetadataCache,
+) -> Result<Vec<OnThisDayEntry>, Box<dyn std::error::Error>> {
+    // Any leap year, so Feb 29 is accepted
+    if NaiveDate::from_ymd_opt(2000, month, day).is_none() {
+        return Err(format!("Invalid month/day {}/{}", month, day).into());
+    }
+
+    let this_year = chrono::Local::now().year();
+    let notes = read_markdown_files_metadata_cached(directory_path, None, true, cache)?;
+
+    Ok(notes
+        .into_iter()
+        .filter_map(|note| {
+            let date = chrono::DateTime::from_timestamp_millis(note.date_from_filename as i64)?
+                .date_naive();
+            if date.month() != month || date.day() != day || date.year() >= this_year {
+                return None;
+            }
+
+            let excerpt = match excerpt_chars {
+                Some(max_chars) if !note.locked && note.download_state == DownloadState::Local => {
+                    note_excerpt(Path::new(&note.file_path), max_chars)
+                }
+                _ => None,
+            };
+
+            Some(OnThisDayEntry {
+                year: date.year(),
+                years_ago: this_year - date.year(),
+                note,
+                excerpt,
+            })
+        })
+        .collect())
+}
+
+pub fn read_last_viewed(file_path: &Path) -> Option<u64> {
+    xattr::get(file_path, XATTR_LAST_VIEWED_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|s| s.parse::<u64>().ok())
+}
+
+/// Record that a note was just opened, so resurfacing can favour ones never revisited
+pub fn mark_note_viewed(file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let now = now_ms();
+
+    xattr::set(file_path, XATTR_LAST_VIEWED_KEY, now.to_string().as_bytes())?;
+    Ok(())
+}
+
+/// Up to `count` daily notes at least `min_age_days` old, picked at random. With
+/// `prefer_unviewed`, notes never marked viewed are weighted more heavily
+pub fn get_random_notes(
+    directory_path: &Path,
+    count: usize,
+    min_age_days: u32,
+    prefer_unviewed: bool,
+    cache: &MetadataCache,
+) -> Result<Vec<ResurfacedNote>, Box<dyn std::error::Error>> {
+    let cutoff = chrono::Local::now().date_naive() - chrono::Duration::days(min_age_days as i64);
+    let cutoff_ms = cutoff
+        .and_hms_opt(0, 0, 0)
+        .map(|dt| dt.and_utc().timestamp_millis() as u64)
+        .unwrap_or(0);
+
+    // Weighted sampling without replacement: rank each note by u^(1/weight), keep the top
+    let mut ranked: Vec<(f64, ResurfacedNote)> =
+        read_markdown_files_metadata_cached(directory_path, None, true, cache)?
+            .into_iter()
+            .filter(|note| note.date_from_filename <= cutoff_ms)
+            .map(|note| {
+                let last_viewed_at = read_last_viewed(Path::new(&note.file_path));
+                let weight = if prefer_unviewed && last_viewed_at.is_none() {
+                    UNVIEWED_WEIGHT
+                } else {
+                    1.0
+                };
+                let key = fastrand::f64().powf(1.0 / weight);
+                (
+                    key,
+                    ResurfacedNote {
+                        note,
+                        last_viewed_at,
+                    },
+                )
+            })
+            .collect();
+
+    ranked.sort_by(|a, b| b.0.total_cmp(&a.0));
+    Ok(ranked
+        .into_iter()
+        .take(count)
+        .map(|(_, note)| note)
+        .collect())
+}
+
+/// IANA name of the system timezone, e.g. `Europe/Berlin`
+pub fn current_timezone() -> Option<String> {
+    iana_time_zone::get_timezone().ok()
+}
+
+/// Timezone the entry was written in, if it was recorded and is a known IANA zone
+pub fn read_timezone_xattr(file_path: &Path) -> Option<Tz> {
+    xattr::get(file_path, XATTR_TIMEZONE_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|name| name.parse().ok())
+}
+
+pub fn write_timezone_xattr(
+    file_path: &Path,
+    timezone: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let tz: Tz = timezone
+        .parse()
+        .map_err(|_| format!("Unknown timezone: {}", timezone))?;
+    xattr::set(file_path, XATTR_TIMEZONE_KEY, tz.name().as_bytes())?;
+    Ok(())
+}
+
+/// The timezone `date`'s daily note was written in, if it recorded one
+pub fn entry_timezone(directory_path: &Path, date: NaiveDate) -> Option<Tz> {
+    find_daily_note(directory_path, date).and_then(|path| read_timezone_xattr(&path))
+}
+
+/// Unix-second bounds of the day a daily note covers, in the timezone it was written in.
+/// Falls back to the system timezone for notes without one (or with no note at all)
+pub fn entry_day_bounds(directory_path: &Path, date: NaiveDate) -> Option<(i64, i64)> {
+    match entry_timezone(directory_path, date) {
+        Some(tz) => day_bounds_in_timezone(date, &tz),
+        None => local_day_bounds(date),
+    }
+}
+
+pub fn read_refresh_interval(file_path: &Path) -> Option<RefreshInterval> {
+    xattr::get(file_path, XATTR_REFRESH_INTERVAL_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|s| RefreshInterval::from_string(&s))
+}
+
+pub fn write_refresh_interval(
+    file_path: &Path,
+    interval: &RefreshInterval,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let interval_str = interval.to_string();
+    xattr::set(
+        file_path,
+        XATTR_REFRESH_INTERVAL_KEY,
+        interval_str.as_bytes(),
+    )?;
+    Ok(())
+}
+
+pub fn read_last_refreshed(file_path: &Path) -> Option<u64> {
+    xattr::get(file_path, XATTR_LAST_REFRESHED_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .and_then(|s| s.parse::<u64>().ok())
+}
+
+pub fn write_last_refreshed(
+    file_path: &Path,
+    timestamp_ms: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let timestamp_str = timestamp_ms.to_string();
+    xattr::set(
+        file_path,
+        XATTR_LAST_REFRESHED_KEY,
+        timestamp_str.as_bytes(),
+    )?;
+    Ok(())
+}
+
+/// Read every `user.*` extended attribute on a file as UTF-8 strings
+pub fn read_all_user_xattrs(file_path: &Path) -> BTreeMap<String, String> {
+    let mut attributes = BTreeMap::new();
+
+    let names = match xattr::list(file_path) {
+        Ok(names) => names,
+        Err(_) => return attributes,
+    };
+
+    for name in names {
+        let name = name.to_string_lossy().to_string();
+        if !name.starts_with("user.") {
+            continue;
+        }
+        if let Some(value) = xattr::get(file_path, &name)
+            .ok()
+            .flatten()
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+        {
+            attributes.insert(name, value);
+        }
+    }
+
+    attributes
+}
+
+/// Write a set of `user.*` extended attributes, e.g. from an archive or manifest
+pub fn write_user_xattrs(
+    file_path: &Path,
+    attributes: &BTreeMap<String, String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for (name, value) in attributes {
+        if name.starts_with("user.") {
+            xattr::set(file_path, name, value.as_bytes())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn parse_date_from_filename(file_name: &str) -> Option<u64> {
+    let caps = DATE_FILENAME_REGEX.captures(file_name)?;
+
+    let year: i32 = caps.get(1)?.as_str().parse().ok()?;
+    let month: u32 = caps.get(2)?.as_str().parse().ok()?;
+    let day: u32 = caps.get(3)?.as_str().parse().ok()?;
+
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+
+    let datetime = date.and_hms_opt(0, 0, 0)?.and_utc();
+    let timestamp_ms = datetime.timestamp_millis() as u64;
+
+    Some(timestamp_ms)
+}
+
+/// Lowercased `#tags` in a note, in order of appearance, skipping fenced code blocks
+pub fn extract_tags(content: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    let mut in_code_block = false;
+
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        for caps in TAG_REGEX.captures_iter(line) {
+            tags.push(caps[1].trim_end_matches(['/', '-']).to_lowercase());
+        }
+    }
+
+    tags
+}
+
+/// Path of the daily note for `date` at the root of the notes directory
+pub fn daily_note_path(directory_path: &Path, date: NaiveDate) -> PathBuf {
+    directory_path.join(format!("{}.md", date.format("%Y-%m-%d")))
+}
+
+/// Create the daily note for `date` from a template, leaving an existing note untouched,
+/// locked ones included. Placeholders like `{{date}}` or `{{open_tasks}}` are filled in by
+/// `render_template`
+pub fn create_daily_note(
+    directory_path: &Path,
+    date: NaiveDate,
+    template: Option<&str>,
+    context: &TemplateContext,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if let Some(existing) = find_daily_note_or_locked(directory_path, date) {
+        return Ok(existing);
+    }
+
+    let content = template
+        .map(|template| render_template(template, directory_path, date, context))
+        .unwrap_or_default();
+
+    let note_path = daily_note_path(directory_path, date);
+    with_note_lock(&note_path, || Ok(fs::write(&note_path, content)?))?;
+    if let Some(timezone) = current_timezone() {
+        let _ = write_timezone_xattr(&note_path, &timezone);
+    }
+
+    Ok(note_path)
+}
+
+/// The daily note for `date` to write into: the existing one, or a new one at the top level.
+/// Fails when the day's note is locked rather than starting a plaintext copy beside it
+pub fn writable_daily_note(
+    directory_path: &Path,
+    date: NaiveDate,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    match find_daily_note_or_locked(directory_path, date) {
+        Some(path) if is_locked_path(&path) => {
+            Err(format!("The note for {} is locked", date).into())
+        }
+        Some(path) => Ok(path),
+        None => Ok(daily_note_path(directory_path, date)),
+    }
+}
+
+/// Append a block of text to the daily note for `date`, creating the note if needed.
+/// Fails when the note is locked
+pub fn append_to_daily_note(
+    directory_path: &Path,
+    date: NaiveDate,
+    text: &str,
+) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let note_path = writable_daily_note(directory_path, date)?;
+    let _lock = lock_note_file(&note_path)?;
+    ensure_unlocked(&note_path)?;
+
+    let existing = fs::read(&note_path).unwrap_or_default();
+    let needs_separator = !existing.is_empty() && !existing.ends_with(b"\n");
+    // Don't start a new note with blank lines meant to separate entries
+    let text = if existing.is_empty() {
+        text.trim_start_matches('\n')
+    } else {
+        text
+    };
+
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&note_path)?;
+
+    if needs_separator {
+        file.write_all(b"\n")?;
+    }
+    file.write_all(text.trim_end().as_bytes())?;
+    file.write_all(b"\n")?;
+
+    // Stamp new notes with where they were written so their day bounds survive travel
+    if existing.is_empty() && read_timezone_xattr(&note_path).is_none() {
+        if let Some(timezone) = current_timezone() {
+            let _ = write_timezone_xattr(&note_path, &timezone);
+        }
+    }
+
+    Ok(note_path)
+}
+
+/// Replace a note's content atomically: the content is written to a temporary file beside
+/// it, which takes the note's extended attributes and permissions and is then renamed over
+/// it, so a crash never leaves a half-written note. The file's creation time becomes the
+/// save time on most platforms. Returns the note's metadata after the write; notes not
+/// named by date get their modification time as `date_from_filename`
+pub fn write_markdown_file(
+    file_path: &Path,
+    content: &str,
+) -> Result<MarkdownFileMetadata, Box<dyn std::error::Error>> {
+    ensure_unlocked(file_path)?;
+    let parent = file_path
+        .parent()
+        .ok_or_else(|| format!("Invalid note path: {}", file_path.display()))?;
+    let file_name = file_path
+        .file_name()
+        .ok_or_else(|| format!("Invalid note path: {}", file_path.display()))?
+        .to_string_lossy()
+        .to_string();
+    let temp_path = parent.join(format!(".{}.{:08x}.tmp", file_name, fastrand::u32(..)));
+
+    with_note_lock(file_path, || {
+        let existing = fs::metadata(file_path).ok().filter(|m| m.is_file());
+        let result = (|| -> Result<(), Box<dyn std::error::Error>> {
+            let mut file = fs::File::create(&temp_path)?;
+            file.write_all(content.as_bytes())?;
+            file.sync_all()?;
+            if let Some(existing) = &existing {
+                fs::set_permissions(&temp_path, existing.permissions())?;
+                write_user_xattrs(&temp_path, &read_all_user_xattrs(file_path))?;
+            }
+            fs::rename(&temp_path, file_path)?;
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        }
+        result
+    })?;
+
+    let metadata = fs::metadata(file_path)?;
+    let date_timestamp =
+        parse_date_from_filename(&file_name).unwrap_or_else(|| millis_or_now(metadata.modified()));
+    Ok(build_notes_metadata(
+        file_path,
+        &metadata,
+        date_timestamp,
+        true,
+    ))
+}
+
+/// Find the daily note for `date` anywhere under the notes directory
+pub fn find_daily_note(directory_path: &Path, date: NaiveDate) -> Option<PathBuf> {
+    let root_note = daily_note_path(directory_path, date);
+    if root_note.is_file() {
+        return Some(root_note);
+    }
+
+    let file_name = format!("{}.md", date.format("%Y-%m-%d"));
+    crate::search::find_markdown_files(&directory_path.to_string_lossy())
+        .ok()?
+        .into_iter()
+        .map(PathBuf::from)
+        .find(|path| path.file_name().and_then(|n| n.to_str()) == Some(file_name.as_str()))
+}
+
+/// Like `find_daily_note`, but a locked note at the top level counts as the day's note
+pub fn find_daily_note_or_locked(directory_path: &Path, date: NaiveDate) -> Option<PathBuf> {
+    find_daily_note(directory_path, date).or_else(|| {
+        let locked = locked_path(&daily_note_path(directory_path, date));
+        locked.is_file().then_some(locked)
+    })
+}
+
+/// The daily note for `date` with its content, or None if there isn't one. A locked note
+/// is returned with an error in place of its content
+pub fn get_note_for_date(
+    directory_path: &Path,
+    date: NaiveDate,
+) -> Result<Option<NoteForDate>, Box<dyn std::error::Error>> {
+    let Some(path) = find_daily_note_or_locked(directory_path, date) else {
+        return Ok(None);
+    };
+
+    let metadata = note_metadata(&path, &fs::metadata(&path)?, true)
+        .ok_or_else(|| format!("Not a daily note: {}", path.display()))?;
+    let content = read_file_content(&path.to_string_lossy());
+    Ok(Some(NoteForDate {
+        metadata,
+        content,
+        commits: None,
+    }))
+}
+
+/// Daily notes dated within `start..=end`, oldest first
+pub fn daily_notes_in_range(
+    directory_path: &Path,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<(NaiveDate, PathBuf)>, Box<dyn std::error::Error>> {
+    let mut notes: Vec<(NaiveDate, PathBuf)> =
+        crate::search::find_markdown_files(&directory_path.to_string_lossy())?
+            .into_iter()
+            .map(PathBuf::from)
+            .filter_map(|path| {
+                let stem = path.file_stem()?.to_str()?;
+                let date = NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok()?;
+                Some((date, path))
+            })
+            .filter(|(date, _)| *date >= start && *date <= end)
+            .collect();
+    notes.sort_by_key(|(date, _)| *date);
+    Ok(notes)
+}
+
+pub fn set_refresh_interval(
+    file_path: &Path,
+    interval: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let refresh_interval = RefreshInterval::from_string(interval)
+        .ok_or_else(|| format!("Invalid refresh interval: {}", interval))?;
+
+    write_refresh_interval(file_path, &refresh_interval)
+}
+
+pub fn mark_file_as_refreshed(file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let now = now_ms();
+
+    write_last_refreshed(file_path, now)
+}
+
+/// Whether a file's refresh interval has elapsed since it was last refreshed
+pub(crate) fn is_refresh_due(path: &Path, now: u64) -> bool {
+    let duration_ms = match read_refresh_interval(path).and_then(|i| i.duration_ms()) {
+        Some(duration_ms) => duration_ms,
+        None => return false,
+    };
+    let last_refreshed = read_last_refreshed(path).unwrap_or(0);
+
+    now.saturating_sub(last_refreshed) >= duration_ms
+}
+
+/// Markdown files directly under `structured/`
+pub(crate) fn structured_markdown_paths(directory_path: &Path) -> Vec<PathBuf> {
+    let entries = match fs::read_dir(directory_path.join("structured")) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.to_string_lossy().to_lowercase() == "md")
+        })
+        .collect()
+}
+
+/// Structured files and daily notes, the files a refresh interval can be set on
+fn refreshable_paths(directory_path: &Path) -> Vec<PathBuf> {
+    let mut paths = structured_markdown_paths(directory_path);
+    if let Ok(notes) = crate::search::find_markdown_files(&directory_path.to_string_lossy()) {
+        paths.extend(notes.into_iter().map(PathBuf::from));
+    }
+    paths
+}
+
+/// Refresh state of one structured file or daily note that has an interval set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshScheduleEntry {
+    pub file_path: String,
+    pub interval: String,
+    pub last_refreshed_at: Option<u64>,
+    pub next_due_at: u64,
+    pub is_due: bool,
+    /// Backed by a content source, so the backend refreshes it rather than the frontend
+    pub has_source: bool,
+}
+
+fn refresh_schedule_entry(path: &Path, now: u64) -> Option<RefreshScheduleEntry> {
+    let interval = read_refresh_interval(path)?;
+    let duration_ms = interval.duration_ms()?;
+    let last_refreshed_at = read_last_refreshed(path);
+    let next_due_at = last_refreshed_at.unwrap_or(0).saturating_add(duration_ms);
+
+    Some(RefreshScheduleEntry {
+        file_path: path.to_string_lossy().to_string(),
+        interval: interval.to_string(),
+        last_refreshed_at,
+        next_due_at,
+        is_due: now >= next_due_at,
+        has_source: read_source_config(path).is_some(),
+    })
+}
+
+/// Every structured file and daily note with a refresh interval, soonest due first
+pub fn get_refresh_schedule(directory_path: &Path) -> Vec<RefreshScheduleEntry> {
+    let now = now_ms();
+
+    let mut schedule: Vec<RefreshScheduleEntry> = refreshable_paths(directory_path)
+        .iter()
+        .filter_map(|path| refresh_schedule_entry(path, now))
+        .collect();
+    schedule.sort_by(|a, b| {
+        a.next_due_at
+            .cmp(&b.next_due_at)
+            .then_with(|| a.file_path.cmp(&b.file_path))
+    });
+    schedule
+}
+
+/// Structured files and daily notes the frontend should regenerate. Files backed by a content source
+/// are refreshed by the backend instead and left out
+pub fn get_files_needing_refresh(directory_path: &Path) -> Vec<RefreshScheduleEntry> {
+    get_refresh_schedule(directory_path)
+        .into_iter()
+        .filter(|entry| entry.is_due && !entry.has_source)
+        .collect()
+}
+
+/// Reads at most this many files at once, so a large batch doesn't flood a network drive
+const MAX_CONCURRENT_READS: usize = 8;
+
+static CONTENT_READ_POOL: LazyLock<Option<rayon::ThreadPool>> = LazyLock::new(|| {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(MAX_CONCURRENT_READS)
+        .thread_name(|index| format!("content-read-{}", index))
+        .build()
+        .ok()
+});
+
+/// Outcome of reading one file in `read_markdown_files_content`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FileContent {
+    Content(String),
+    Error(String),
+}
+
+fn read_file_content(file_path: &str) -> FileContent {
+    // Locked notes are ciphertext; they're read through `read_locked_note`
+    if is_locked_path(Path::new(file_path)) {
+        return FileContent::Error("Note is locked".to_string());
+    }
+    match fs::read_to_string(file_path) {
+        Ok(content) => FileContent::Content(content),
+        Err(e) => FileContent::Error(e.to_string()),
+    }
+}
+
+/// Up to `len` bytes (at least 4, so a whole character fits) of a file from `offset`,
+/// trimmed to whole UTF-8 characters so consecutive chunks join up exactly
+pub fn read_file_chunk(
+    file_path: &Path,
+    offset: u64,
+    len: u64,
+) -> Result<FileChunk, Box<dyn std::error::Error>> {
+    let mut file = fs::File::open(file_path)?;
+    let size = file.metadata()?.len();
+    let mut offset = offset.min(size);
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.take(len.clamp(4, MAX_CHUNK_BYTES))
+        .read_to_end(&mut bytes)?;
+
+    // Continuation bytes at the start belong to the previous chunk's last character
+    let skipped = bytes
+        .iter()
+        .take(3)
+        .take_while(|byte| (**byte & 0xC0) == 0x80)
+        .count();
+    bytes.drain(..skipped);
+    offset += skipped as u64;
+
+    // A character cut off at the end is left for the next chunk
+    if let Err(e) = std::str::from_utf8(&bytes) {
+        if e.error_len().is_none() {
+            bytes.truncate(e.valid_up_to());
+        }
+    }
+
+    Ok(FileChunk {
+        content: String::from_utf8_lossy(&bytes).to_string(),
+        offset,
+        next_offset: offset + bytes.len() as u64,
+        size,
+    })
+}
+
+/// The first lines of a file, up to `STRUCTURED_PREVIEW_BYTES`
+fn read_preview(file_path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let mut content = read_file_chunk(file_path, 0, STRUCTURED_PREVIEW_BYTES)?.content;
+    if let Some(end) = content.rfind('\n') {
+        content.truncate(end + 1);
+    }
+    Ok(content)
+}
+
+/// Read files on a small dedicated pool, reporting each file's content or error
+pub fn read_markdown_files_content(file_paths: Vec<String>) -> HashMap<String, FileContent> {
+    let read_all = || {
+        file_paths
+            .par_iter()
+            .map(|file_path| (file_path.clone(), read_file_content(file_path)))
+            .collect()
+    };
+
+    match CONTENT_READ_POOL.as_ref() {
+        Some(pool) => pool.install(read_all),
+        None => file_paths
+            .iter()
+            .map(|file_path| (file_path.clone(), read_file_content(file_path)))
+            .collect(),
+    }
+}
+
+/// Identifies a version of a file without reading it. The status-change time is included
+/// because writing an xattr (mood, location, ...) updates it but not the mtime
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FileStamp {
+    modified: Option<std::time::SystemTime>,
+    size: u64,
+    changed: i128,
+}
+
+impl FileStamp {
+    fn of(metadata: &fs::Metadata) -> Self {
+        #[cfg(unix)]
+        let changed = {
+            use std::os::unix::fs::MetadataExt;
+            metadata.ctime() as i128 * 1_000_000_000 + metadata.ctime_nsec() as i128
+        };
+        #[cfg(not(unix))]
+        let changed = 0;
+
+        FileStamp {
+            modified: metadata.modified().ok(),
+            size: metadata.len(),
+            changed,
+        }
+    }
+}
+
+/// A cached scan result: the stamp it was built for, whether xattrs were read, and the
+/// entry (None for files that aren't notes)
+type StampedEntries<T> = HashMap<PathBuf, (FileStamp, bool, Option<T>)>;
+
+/// Per-file results of the metadata scans, reused while a file's stamp is unchanged so
+/// repeat scans only stat the tree
+#[derive(Default)]
+pub struct MetadataCache {
+    notes: Mutex<StampedEntries<MarkdownFileMetadata>>,
+    structured: Mutex<StampedEntries<StructuredMarkdownFileMetadata>>,
+}
+
+impl MetadataCache {
+    pub fn clear(&self) {
+        if let Ok(mut notes) = self.notes.lock() {
+            notes.clear();
+        }
+        if let Ok(mut structured) = self.structured.lock() {
+            structured.clear();
+        }
+    }
+}
+
+/// Stamps of the cached entries a scan can reuse: any entry when xattrs aren't needed,
+/// otherwise only those built with them
+fn cached_stamps<T>(
+    entries: &Mutex<StampedEntries<T>>,
+    include_xattrs: bool,
+) -> HashMap<PathBuf, FileStamp> {
+    entries
+        .lock()
+        .map(|entries| {
+            entries
+                .iter()
+                .filter(|(_, (_, with_xattrs, _))| *with_xattrs || !include_xattrs)
+                .map(|(path, (stamp, _, _))| (path.clone(), *stamp))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A scanned file: its path, stamp and, when the scan already built it, the result
+type ScannedFile<T> = (PathBuf, FileStamp, Option<Option<T>>);
+
+/// Resolve scanned files against `entries`, building those whose stamp changed with `build`,
+/// and drop entries under `root` for files that no longer exist. Reuse is reported to `perf`
+/// as hits on `cache_name`
+fn refresh_entries<T: Clone>(
+    cache_name: &'static str,
+    entries: &Mutex<StampedEntries<T>>,
+    root: &Path,
+    scanned: Vec<ScannedFile<T>>,
+    include_xattrs: bool,
+    build: impl Fn(&Path) -> Option<T>,
+) -> Vec<T> {
+    let mut entries = match entries.lock() {
+        Ok(entries) => entries,
+        // A poisoned cache is only a missed optimization
+        Err(_) => {
+            return scanned
+                .into_iter()
+                .filter_map(|(path, _, fresh)| fresh.unwrap_or_else(|| build(&path)))
+                .collect()
+        }
+    };
+
+    let mut seen = HashSet::new();
+    let mut results = Vec::new();
+    let mut hits = 0;
+    for (path, stamp, fresh) in scanned {
+        let (with_xattrs, item) = match (fresh, entries.get(&path)) {
+            (Some(item), _) => (include_xattrs, item),
+            (None, Some((cached_stamp, with_xattrs, item)))
+                if *cached_stamp == stamp && (*with_xattrs || !include_xattrs) =>
+            {
+                hits += 1;
+                (*with_xattrs, item.clone())
+            }
+            (None, _) => (include_xattrs, build(&path)),
+        };
+        entries.insert(path.clone(), (stamp, with_xattrs, item.clone()));
+        results.extend(item);
+        seen.insert(path);
+    }
+    entries.retain(|path, _| !path.starts_with(root) || seen.contains(path));
+    perf::record_cache(cache_name, hits, seen.len() as u64 - hits);
+
+    results
+}
+
+/// The xattrs metadata scans report, read with one `listxattr` and a `getxattr` per
+/// attribute that's actually set rather than probing every key
+#[derive(Default)]
+struct ScanXattrs {
+    country: Option<String>,
+    city: Option<String>,
+    mood: Option<String>,
+    timezone: Option<String>,
+    order_index: Option<String>,
+    description: Option<String>,
+    refresh_interval: Option<String>,
+    last_refreshed: Option<String>,
+    note_id: Option<String>,
+}
+
+fn read_scan_xattrs(path: &Path) -> ScanXattrs {
+    let _span = perf::span(PerfCategory::Xattr, "read_scan_xattrs");
+    let mut attributes = ScanXattrs::default();
+    let names = match xattr::list(path) {
+        Ok(names) => names,
+        Err(_) => return attributes,
+    };
+
+    for name in names {
+        let slot = match name.to_str() {
+            Some(XATTR_COUNTRY_KEY) => &mut attributes.country,
+            Some(XATTR_CITY_KEY) => &mut attributes.city,
+            Some(XATTR_MOOD_KEY) => &mut attributes.mood,
+            Some(XATTR_TIMEZONE_KEY) => &mut attributes.timezone,
+            Some(XATTR_ORDER_INDEX_KEY) => &mut attributes.order_index,
+            Some(XATTR_DESCRIPTION_KEY) => &mut attributes.description,
+            Some(XATTR_REFRESH_INTERVAL_KEY) => &mut attributes.refresh_interval,
+            Some(XATTR_LAST_REFRESHED_KEY) => &mut attributes.last_refreshed,
+            Some(XATTR_NOTE_ID_KEY) => &mut attributes.note_id,
+            _ => continue,
+        };
+        *slot = xattr::get(path, &name)
+            .ok()
+            .flatten()
+            .and_then(|bytes| String::from_utf8(bytes).ok());
+    }
+
+    attributes.timezone = attributes
+        .timezone
+        .and_then(|name| name.parse::<Tz>().ok())
+        .map(|tz| tz.name().to_string());
+    attributes.refresh_interval = attributes
+        .refresh_interval
+        .and_then(|interval| RefreshInterval::from_string(&interval))
+        .map(|interval| interval.to_string());
+    attributes
+}
+
+fn millis_or_now(time: std::io::Result<std::time::SystemTime>) -> u64 {
+    time.unwrap_or_else(|_| std::time::SystemTime::now())
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// Metadata for a daily note (`YYYY-MM-DD.md` or a locked `.md.locked`); None for other
+/// files. Location, mood, timezone, description and refresh state are left empty unless
+/// `include_xattrs`
+fn note_metadata(
+    path: &Path,
+    metadata: &fs::Metadata,
+    include_xattrs: bool,
+) -> Option<MarkdownFileMetadata> {
+    let date_timestamp = note_date(path)?;
+    Some(build_notes_metadata(
+        path,
+        metadata,
+        date_timestamp,
+        include_xattrs,
+    ))
+}
+
+/// The date in a daily note's file name, as a timestamp; None for other files
+fn note_date(path: &Path) -> Option<u64> {
+    let extension = path.extension()?;
+    if extension.to_string_lossy().to_lowercase() != "md" && !is_locked_path(path) {
+        return None;
+    }
+
+    let file_name = path.file_name()?.to_string_lossy();
+    let note_name = file_name
+        .strip_suffix(&format!(".{}", LOCKED_EXTENSION))
+        .unwrap_or(&file_name);
+    parse_date_from_filename(note_name)
+}
+
+/// Whether `path` is named like a daily note, including locked ones
+pub fn is_note_path(path: &Path) -> bool {
+    note_date(path).is_some()
+}
+
+/// Metadata with xattrs for the daily note at `path`, as the directory scan would list it;
+/// None for other files or when it can't be read
+pub fn read_note_metadata(path: &Path) -> Option<MarkdownFileMetadata> {
+    let metadata = fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())?;
+    note_metadata(path, &metadata, true)
+}
+
+fn build_notes_metadata(
+    path: &Path,
+    metadata: &fs::Metadata,
+    date_timestamp: u64,
+    include_xattrs: bool,
+) -> MarkdownFileMetadata {
+    let locked = is_locked_path(path);
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let attributes = if include_xattrs {
+        read_scan_xattrs(path)
+    } else {
+        ScanXattrs::default()
+    };
+
+    MarkdownFileMetadata {
+        file_path: path.to_string_lossy().to_string(),
+        file_name,
+        created_at: millis_or_now(metadata.created().or_else(|_| metadata.modified())),
+        modified_at: millis_or_now(metadata.modified()),
+        size: metadata.len(),
+        country: attributes.country,
+        city: attributes.city,
+        date_from_filename: date_timestamp,
+        download_state: download_state(metadata),
+        mood: attributes.mood,
+        timezone: attributes.timezone,
+        locked,
+        description: attributes.description,
+        refresh_interval: attributes.refresh_interval,
+        last_refreshed_at: attributes
+            .last_refreshed
+            .and_then(|timestamp| timestamp.parse::<u64>().ok()),
+        note_id: attributes.note_id,
+    }
+}
+
+fn structured_metadata(
+    path: &Path,
+    metadata: &fs::Metadata,
+    include_xattrs: bool,
+) -> Option<StructuredMarkdownFileMetadata> {
+    if path.extension()?.to_string_lossy().to_lowercase() != "md" {
+        return None;
+    }
+
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let attributes = if include_xattrs {
+        read_scan_xattrs(path)
+    } else {
+        ScanXattrs::default()
+    };
+
+    Some(StructuredMarkdownFileMetadata {
+        file_path: path.to_string_lossy().to_string(),
+        file_name,
+        created_at: millis_or_now(metadata.created().or_else(|_| metadata.modified())),
+        modified_at: millis_or_now(metadata.modified()),
+        size: metadata.len(),
+        country: attributes.country,
+        city: attributes.city,
+        download_state: download_state(metadata),
+        order_index: attributes
+            .order_index
+            .and_then(|index| index.parse::<u64>().ok()),
+    })
+}
+
+pub fn read_markdown_files_metadata(
+    directory_path: &Path,
+    max_file_size: Option<u64>,
+) -> Result<Vec<MarkdownFileMetadata>, Box<dyn std::error::Error>> {
+    read_markdown_files_metadata_cached(
+        directory_path,
+        max_file_size,
+        true,
+        &MetadataCache::default(),
+    )
+}
+
+/// `read_markdown_files_metadata`, rebuilding only the entries for files changed since the
+/// last scan through `cache`. Without `include_xattrs`, the fields read from xattrs are only
+/// filled in where the cache already has them
+pub fn read_markdown_files_metadata_cached(
+    directory_path: &Path,
+    max_file_size: Option<u64>,
+    include_xattrs: bool,
+    cache: &MetadataCache,
+) -> Result<Vec<MarkdownFileMetadata>, Box<dyn std::error::Error>> {
+    let _span = perf::span(PerfCategory::Scan, "notes_metadata");
+    let max_size = max_file_size.unwrap_or(10 * 1024 * 1024);
+
+    // Stat and build in parallel; the walker only builds entries the cache can't vouch for
+    let cached = cached_stamps(&cache.notes, include_xattrs);
+    let scanned = walk_files(directory_path, |path, metadata| {
+        let stamp = FileStamp::of(metadata);
+        let fresh = (cached.get(path) != Some(&stamp))
+            .then(|| note_metadata(path, metadata, include_xattrs));
+        Some((path.to_path_buf(), stamp, fresh))
+    });
+
+    let mut files = refresh_entries(
+        "notes_metadata",
+        &cache.notes,
+        directory_path,
+        scanned,
+        include_xattrs,
+        |path| {
+            fs::metadata(path)
+                .ok()
+                .and_then(|metadata| note_metadata(path, &metadata, include_xattrs))
+        },
+    );
+    files.retain(|file| file.size <= max_size);
+    files.sort_by_key(|file| std::cmp::Reverse(file.date_from_filename));
+
+    Ok(files)
+}
+
+pub fn read_structured_markdown_files_metadata(
+    directory_path: &Path,
+    max_file_size: Option<u64>,
+) -> Result<Vec<StructuredMarkdownFileMetadata>, Box<dyn std::error::Error>> {
+    read_structured_markdown_files_metadata_cached(
+        directory_path,
+        max_file_size,
+        true,
+        &MetadataCache::default(),
+    )
+}
+
+pub fn read_structured_markdown_files_metadata_cached(
+    directory_path: &Path,
+    max_file_size: Option<u64>,
+    include_xattrs: bool,
+    cache: &MetadataCache,
+) -> Result<Vec<StructuredMarkdownFileMetadata>, Box<dyn std::error::Error>> {
+    let _span = perf::span(PerfCategory::Scan, "structured_metadata");
+    let max_size = max_file_size.unwrap_or(10 * 1024 * 1024);
+    let structured_dir_path = directory_path.join("structured");
+
+    if !structured_dir_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    if !structured_dir_path.is_dir() {
+        return Err(format!("Path is not a directory: {}", structured_dir_path.display()).into());
+    }
+
+    let entries = match fs::read_dir(&structured_dir_path) {
+        Ok(entries) => entries,
+        Err(e) => return Err(format!("Error reading structured directory: {}", e).into()),
+    };
+
+    let mut paths = Vec::new();
+    for entry in entries {
+        match entry {
+            Ok(entry) => paths.push(entry.path()),
+            Err(e) => eprintln!("Error reading directory entry: {}", e),
+        }
+    }
+
+    let cached = cached_stamps(&cache.structured, include_xattrs);
+    let scanned: Vec<ScannedFile<StructuredMarkdownFileMetadata>> = paths
+        .into_par_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok().filter(|m| m.is_file())?;
+            let stamp = FileStamp::of(&metadata);
+            let fresh = (cached.get(&path) != Some(&stamp))
+                .then(|| structured_metadata(&path, &metadata, include_xattrs));
+            Some((path, stamp, fresh))
+        })
+        .collect();
+
+    let mut files = refresh_entries(
+        "structured_metadata",
+        &cache.structured,
+        &structured_dir_path,
+        scanned,
+        include_xattrs,
+        |path| {
+            fs::metadata(path)
+                .ok()
+                .and_then(|metadata| structured_metadata(path, &metadata, include_xattrs))
+        },
+    );
+    files.retain(|file| file.size <= max_size);
+    files.sort_by(|a, b| match (a.order_index, b.order_index) {
+        (Some(a_index), Some(b_index)) => a_index.cmp(&b_index),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => b.modified_at.cmp(&a.modified_at),
+    });
+
+    Ok(files)
+}
+
+pub fn read_structured_markdown_files(
+    directory_path: &Path,
+    max_file_size: Option<u64>,
+) -> Result<Vec<StructuredMarkdownFile>, Box<dyn std::error::Error>> {
+    let max_size = max_file_size.unwrap_or(10 * 1024 * 1024);
+    let mut files = Vec::new();
+
+    let structured_dir_path = directory_path.join("structured");
+
+    if !structured_dir_path.exists() {
+        return Ok(files);
+    }
+
+    if !structured_dir_path.is_dir() {
+        return Err(format!("Path is not a directory: {}", structured_dir_path.display()).into());
+    }
+
+    let entries = match fs::read_dir(&structured_dir_path) {
+        Ok(entries) => entries,
+        Err(e) => return Err(format!("Error reading structured directory: {}", e).into()),
+    };
+
+    for entry in entries {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                eprintln!("Error reading directory entry: {}", e);
+                continue;
+            }
+        };
+
+        let path = entry.path();
+
+        if path.is_file() {
+            if let Some(extension) = path.extension() {
+                if extension.to_string_lossy().to_lowercase() == "md" {
+                    let file_name = path
+                        .file_name()
+                        .and_then(|n| n.to_str())
+                        .unwrap_or("unknown")
+                        .to_string();
+
+                    if let Ok(metadata) = entry.metadata() {
+                        let size = metadata.len();
+
+                        if size <= max_size {
+                            let file_path = path.to_string_lossy().to_string();
+
+                            // Reading an online-only file would download it, so
+                            // placeholders come back without content
+                            let file_download_state = download_state(&metadata);
+                            let is_truncated = file_download_state != DownloadState::Placeholder
+                                && size > STRUCTURED_PREVIEW_BYTES;
+                            let content = if file_download_state == DownloadState::Placeholder {
+                                String::new()
+                            } else {
+                                let read = if is_truncated {
+                                    read_preview(&path)
+                                } else {
+                                    fs::read_to_string(&path).map_err(|e| e.into())
+                                };
+                                match read {
+                                    Ok(content) => content,
+                                    Err(e) => {
+                                        eprintln!(
+                                            "Error reading file content for {}: {}",
+                                            file_path, e
+                                        );
+                                        continue;
+                                    }
+                                }
+                            };
+
+                            let created_at = metadata
+                                .created()
+                                .or_else(|_| metadata.modified())
+                                .unwrap_or_else(|_| std::time::SystemTime::now())
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_millis() as u64;
+
+                            let modified_at = metadata
+                                .modified()
+                                .unwrap_or_else(|_| std::time::SystemTime::now())
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_millis() as u64;
+
+                            let (country, city) = read_location_xattrs(&path);
+
+                            let description = read_description_xattr(&path);
+
+                            let refresh_interval =
+                                read_refresh_interval(&path).map(|i| i.to_string());
+                            let last_refreshed_at = read_last_refreshed(&path);
+                            let frontmatter = parse_frontmatter(&content);
+
+                            files.push(StructuredMarkdownFile {
+                                file_path,
+                                file_name,
+                                created_at,
+                                modified_at,
+                                size,
+                                country,
+                                city,
+                                description,
+                                content,
+                                refresh_interval,
+                                last_refreshed_at,
+                                download_state: file_download_state,
+                                is_truncated,
+                                frontmatter,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    files.sort_by_key(|file| std::cmp::Reverse(file.modified_at));
+
+    Ok(files)
+}
@@ -0,0 +1,191 @@
+use std::path::Path;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use rustls_pki_types::pem::PemObject;
+use rustls_pki_types::CertificateDer;
+use serde::{Deserialize, Serialize};
+
+static NETWORK: LazyLock<RwLock<Network>> = LazyLock::new(|| RwLock::new(Network::default()));
+
+/// Proxy and TLS settings shared by every outgoing request: fetches, integrations, link
+/// previews, weather, sync and webhooks
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    /// e.g. `http://proxy.corp:8080`; takes precedence over the system proxy
+    pub proxy_url: Option<String>,
+    /// Connect directly unless `proxy_url` is set
+    pub ignore_system_proxy: bool,
+    /// PEM file of CA certificates to trust alongside the built-in roots, for proxies that
+    /// intercept TLS
+    pub ca_bundle_path: Option<String>,
+}
+
+/// Settings in effect, with the proxy resolved and the CA bundle loaded
+#[derive(Default)]
+struct Network {
+    proxy_url: Option<String>,
+    ignore_system_proxy: bool,
+    proxy: Option<ureq::Proxy>,
+    tls: Option<Arc<rustls::ClientConfig>>,
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+        .map(str::to_string)
+}
+
+/// The proxy from the environment, then (on macOS) from System Settings
+pub fn system_proxy() -> Option<String> {
+    let from_env = [
+        "HTTPS_PROXY",
+        "https_proxy",
+        "ALL_PROXY",
+        "all_proxy",
+        "HTTP_PROXY",
+    ]
+    .iter()
+    .find_map(|name| non_empty(std::env::var(name).ok().as_deref()));
+    if from_env.is_some() {
+        return from_env;
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        macos_proxy()
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        None
+    }
+}
+
+/// The HTTPS (else HTTP) proxy set in System Settings, from `scutil --proxy`
+#[cfg(target_os = "macos")]
+fn macos_proxy() -> Option<String> {
+    let output = std::process::Command::new("scutil")
+        .arg("--proxy")
+        .output()
+        .ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    let value = |key: &str| {
+        output.lines().find_map(|line| {
+            let (name, value) = line.split_once(" : ")?;
+            (name.trim() == key).then(|| value.trim().to_string())
+        })
+    };
+
+    ["HTTPS", "HTTP"].iter().find_map(|scheme| {
+        if value(&format!("{}Enable", scheme))? != "1" {
+            return None;
+        }
+        let host = value(&format!("{}Proxy", scheme))?;
+        match value(&format!("{}Port", scheme)) {
+            Some(port) => Some(format!("http://{}:{}", host, port)),
+            None => Some(format!("http://{}", host)),
+        }
+    })
+}
+
+/// The built-in roots plus every certificate in the PEM file at `ca_bundle_path`
+fn tls_config(
+    ca_bundle_path: &Path,
+) -> Result<Arc<rustls::ClientConfig>, Box<dyn std::error::Error>> {
+    let mut roots = rustls::RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let mut added = 0;
+    for cert in CertificateDer::pem_file_iter(ca_bundle_path)? {
+        roots.add(cert?)?;
+        added += 1;
+    }
+    if added == 0 {
+        return Err(format!("No certificates in {}", ca_bundle_path.display()).into());
+    }
+
+    let config = rustls::ClientConfig::builder_with_provider(
+        rustls::crypto::ring::default_provider().into(),
+    )
+    .with_safe_default_protocol_versions()?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    Ok(Arc::new(config))
+}
+
+/// Apply `settings` to every request made from now on. Fails, leaving the previous
+/// settings in place, when the proxy URL or CA bundle is unusable
+pub fn configure(settings: &NetworkSettings) -> Result<(), Box<dyn std::error::Error>> {
+    let proxy_url = match non_empty(settings.proxy_url.as_deref()) {
+        Some(url) => Some(url),
+        None if settings.ignore_system_proxy => None,
+        None => system_proxy(),
+    };
+    let proxy = proxy_url
+        .as_deref()
+        .map(ureq::Proxy::new)
+        .transpose()
+        .map_err(|e| format!("Invalid proxy: {}", e))?;
+
+    let ca_bundle_path = non_empty(settings.ca_bundle_path.as_deref());
+    let tls = ca_bundle_path
+        .as_deref()
+        .map(|path| tls_config(Path::new(path)))
+        .transpose()?;
+    if let Some(path) = &ca_bundle_path {
+        // libgit2 keeps its own certificate store for HTTPS remotes. Not every TLS backend
+        // supports extra locations, so a failure here only affects git
+        if let Err(e) = unsafe { git2::opts::set_ssl_cert_file(path.as_str()) } {
+            eprintln!("Failed to add CA bundle for git: {}", e);
+        }
+    }
+
+    let mut network = NETWORK
+        .write()
+        .map_err(|_| "Network settings are unavailable")?;
+    *network = Network {
+        proxy_url,
+        ignore_system_proxy: settings.ignore_system_proxy,
+        proxy,
+        tls,
+    };
+    Ok(())
+}
+
+/// A `ureq` agent builder with the configured proxy and CA bundle applied. Every HTTP
+/// request the app makes starts here
+pub fn agent_builder() -> ureq::AgentBuilder {
+    let mut builder = ureq::AgentBuilder::new();
+    if let Ok(network) = NETWORK.read() {
+        if let Some(proxy) = &network.proxy {
+            builder = builder.proxy(proxy.clone());
+        }
+        if let Some(tls) = &network.tls {
+            builder = builder.tls_config(tls.clone());
+        }
+    }
+    builder
+}
+
+pub fn agent() -> ureq::Agent {
+    agent_builder().build()
+}
+
+/// Proxy options for git fetches: the configured proxy, else whatever git itself is set up
+/// to use unless the system proxy is ignored
+pub fn git_proxy_options() -> git2::ProxyOptions<'static> {
+    let mut options = git2::ProxyOptions::new();
+    if let Ok(network) = NETWORK.read() {
+        match &network.proxy_url {
+            Some(url) => {
+                options.url(url);
+            }
+            None if !network.ignore_system_proxy => {
+                options.auto();
+            }
+            None => {}
+        }
+    }
+    options
+}
@@ -0,0 +1,151 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::markdown::XATTR_NOTE_ID_KEY;
+use crate::walk::walk_files;
+
+/// Sidecar in the notes directory mapping note ids to paths relative to it. Extended
+/// attributes follow a file through renames but don't survive git or most sync services,
+/// so the sidecar lets ids be recovered on another machine
+pub const NOTE_IDS_FILE: &str = ".note-ids.json";
+
+pub const NOTE_LINK_PREFIX: &str = "stream://note/";
+
+/// Serializes read-modify-write of the sidecar
+static INDEX_LOCK: Mutex<()> = Mutex::new(());
+
+fn new_id() -> String {
+    let mut bytes = fastrand::u128(..).to_be_bytes();
+    // Version 4, RFC 4122 variant
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+fn is_id(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// The id in a `stream://note/<id>` link
+pub fn parse_note_link(link: &str) -> Option<String> {
+    let id = link.trim().strip_prefix(NOTE_LINK_PREFIX)?;
+    let id = id.split(['?', '#', '/']).next()?.to_lowercase();
+    is_id(&id).then_some(id)
+}
+
+pub fn note_link(id: &str) -> String {
+    format!("{}{}", NOTE_LINK_PREFIX, id)
+}
+
+pub fn read_note_id(file_path: &Path) -> Option<String> {
+    xattr::get(file_path, XATTR_NOTE_ID_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .filter(|id| is_id(id))
+}
+
+fn read_index(directory_path: &Path) -> BTreeMap<String, String> {
+    fs::read(directory_path.join(NOTE_IDS_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn relative_key(directory_path: &Path, file_path: &Path) -> Option<String> {
+    let relative = file_path.strip_prefix(directory_path).ok()?;
+    Some(relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// Record `id` at `file_path` in the sidecar, dropping any other id held by that path
+fn update_index(
+    directory_path: &Path,
+    id: &str,
+    file_path: &Path,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let key = relative_key(directory_path, file_path)
+        .ok_or_else(|| format!("{} is outside the notes directory", file_path.display()))?;
+    let _guard = INDEX_LOCK
+        .lock()
+        .map_err(|_| "Note id index is unavailable")?;
+
+    let mut index = read_index(directory_path);
+    if index.get(id) == Some(&key) {
+        return Ok(());
+    }
+    index.retain(|_, path| *path != key);
+    index.insert(id.to_string(), key);
+    fs::write(
+        directory_path.join(NOTE_IDS_FILE),
+        serde_json::to_vec_pretty(&index)?,
+    )?;
+    Ok(())
+}
+
+/// The note's stable id, assigning one if it has none. A note missing its attribute (e.g.
+/// after a sync) takes back the id the sidecar has for its path
+pub fn ensure_note_id(
+    directory_path: &Path,
+    file_path: &Path,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if !file_path.is_file() {
+        return Err(format!("Not a file: {}", file_path.display()).into());
+    }
+
+    let id = match read_note_id(file_path) {
+        Some(id) => id,
+        None => {
+            let key = relative_key(directory_path, file_path);
+            let id = read_index(directory_path)
+                .into_iter()
+                .find(|(_, path)| Some(path) == key.as_ref())
+                .map(|(id, _)| id)
+                .unwrap_or_else(new_id);
+            xattr::set(file_path, XATTR_NOTE_ID_KEY, id.as_bytes())?;
+            id
+        }
+    };
+    update_index(directory_path, &id, file_path)?;
+    Ok(id)
+}
+
+/// The note with `id`: where the sidecar last saw it, else found by scanning the notes
+/// directory, in which case the sidecar is updated when `update` is set
+pub fn resolve_note_id(
+    directory_path: &Path,
+    id: &str,
+    update: bool,
+) -> Result<Option<PathBuf>, Box<dyn std::error::Error>> {
+    let id = id.to_lowercase();
+    if let Some(relative) = read_index(directory_path).get(&id) {
+        let path = directory_path.join(relative);
+        // A note synced without attributes keeps its id through the sidecar
+        if path.is_file() && read_note_id(&path).is_none_or(|found| found == id) {
+            return Ok(Some(path));
+        }
+    }
+
+    let found = walk_files(directory_path, |path, _| {
+        (read_note_id(path).as_deref() == Some(id.as_str())).then(|| path.to_path_buf())
+    })
+    .into_iter()
+    .min();
+    if let (Some(path), true) = (&found, update) {
+        update_index(directory_path, &id, path)?;
+    }
+    Ok(found)
+}
@@ -0,0 +1,104 @@
+use std::path::{Component, Path, PathBuf};
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// The desktop app's identifier, which names its data directory
+pub const APP_IDENTIFIER: &str = "com.marcelmarais.stream";
+
+/// Where the desktop app keeps its settings and data: the directory Tauri's `app_data_dir`
+/// resolves to, for tools running outside the app
+pub fn app_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(target_os = "macos") {
+        home_dir()?.join("Library").join("Application Support")
+    } else if cfg!(windows) {
+        PathBuf::from(std::env::var_os("APPDATA")?)
+    } else {
+        match std::env::var_os("XDG_DATA_HOME").filter(|dir| !dir.is_empty()) {
+            Some(dir) => PathBuf::from(dir),
+            None => home_dir()?.join(".local").join("share"),
+        }
+    };
+    Some(base.join(APP_IDENTIFIER))
+}
+
+/// Replace a leading `~` with the user's home directory
+pub fn expand_home(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => rest,
+        _ => return PathBuf::from(path),
+    };
+
+    match home_dir() {
+        Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Drop `.` components and resolve `..` lexically, for paths that don't exist on disk
+pub(crate) fn clean(path: &Path) -> PathBuf {
+    let mut cleaned = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !cleaned.pop() {
+                    cleaned.push(component);
+                }
+            }
+            other => cleaned.push(other),
+        }
+    }
+    cleaned
+}
+
+/// Canonical form of a path so the same file compares equal however it was spelled:
+/// `~` is expanded, relative paths are made absolute, and symlinks and `..` are resolved.
+/// On case-insensitive volumes the on-disk casing is used. A path that doesn't exist yet
+/// is resolved through its nearest existing ancestor
+pub fn normalize_path(path: &str) -> PathBuf {
+    let expanded = expand_home(path.trim());
+    let absolute = if expanded.is_absolute() {
+        expanded
+    } else {
+        std::env::current_dir()
+            .map(|cwd| cwd.join(&expanded))
+            .unwrap_or(expanded)
+    };
+
+    if let Ok(canonical) = std::fs::canonicalize(&absolute) {
+        return canonical;
+    }
+
+    let absolute = clean(&absolute);
+    let mut missing = Vec::new();
+    let mut ancestor = absolute.as_path();
+    while let Some(parent) = ancestor.parent() {
+        if let Some(name) = ancestor.file_name() {
+            missing.push(name.to_os_string());
+        }
+        if let Ok(canonical) = std::fs::canonicalize(parent) {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |path, name| path.join(name));
+        }
+        ancestor = parent;
+    }
+
+    absolute
+}
+
+/// `normalize_path` as a string, the form commands pass around and store
+pub fn normalize_path_string(path: &str) -> String {
+    normalize_path(path).to_string_lossy().to_string()
+}
+
+/// Whether two paths refer to the same location once normalized
+pub fn same_path(a: &str, b: &str) -> bool {
+    a == b || normalize_path(a) == normalize_path(b)
+}
@@ -0,0 +1,206 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Durations kept per operation for percentiles; counts and totals cover every call
+const RECENT_SAMPLES: usize = 100;
+
+static PERF: LazyLock<Mutex<PerfData>> = LazyLock::new(|| Mutex::new(PerfData::default()));
+
+/// The part of the app an operation spends its time in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PerfCategory {
+    /// A whole IPC command, as timed by its caller
+    Command,
+    Scan,
+    Xattr,
+    Git,
+    Search,
+}
+
+#[derive(Default)]
+struct Timing {
+    count: u64,
+    total: Duration,
+    max: Duration,
+    recent: VecDeque<Duration>,
+}
+
+#[derive(Default)]
+struct PerfData {
+    timings: HashMap<(PerfCategory, String), Timing>,
+    caches: HashMap<&'static str, (u64, u64)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OperationTiming {
+    pub category: PerfCategory,
+    pub name: String,
+    pub count: u64,
+    pub total_ms: f64,
+    pub average_ms: f64,
+    /// Over the most recent calls only
+    pub p95_ms: f64,
+    pub max_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CategoryTiming {
+    pub category: PerfCategory,
+    pub count: u64,
+    pub total_ms: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub name: String,
+    pub hits: u64,
+    pub misses: u64,
+    pub hit_rate: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PerformanceReport {
+    /// Most total time first
+    pub operations: Vec<OperationTiming>,
+    pub categories: Vec<CategoryTiming>,
+    pub caches: Vec<CacheStats>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+pub fn record(category: PerfCategory, name: &str, duration: Duration) {
+    let mut data = match PERF.lock() {
+        Ok(data) => data,
+        Err(_) => return,
+    };
+    let timing = data
+        .timings
+        .entry((category, name.to_string()))
+        .or_default();
+    timing.count += 1;
+    timing.total += duration;
+    timing.max = timing.max.max(duration);
+    if timing.recent.len() == RECENT_SAMPLES {
+        timing.recent.pop_front();
+    }
+    timing.recent.push_back(duration);
+}
+
+pub fn record_cache(cache: &'static str, hits: u64, misses: u64) {
+    if let Ok(mut data) = PERF.lock() {
+        let counts = data.caches.entry(cache).or_default();
+        counts.0 += hits;
+        counts.1 += misses;
+    }
+}
+
+/// Times from creation until dropped
+pub struct Span {
+    category: PerfCategory,
+    name: &'static str,
+    started: Instant,
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        record(self.category, self.name, self.started.elapsed());
+    }
+}
+
+pub fn span(category: PerfCategory, name: &'static str) -> Span {
+    Span {
+        category,
+        name,
+        started: Instant::now(),
+    }
+}
+
+/// Timings and cache hit rates since startup or the last reset
+pub fn performance_report() -> PerformanceReport {
+    let data = match PERF.lock() {
+        Ok(data) => data,
+        Err(_) => {
+            return PerformanceReport {
+                operations: Vec::new(),
+                categories: Vec::new(),
+                caches: Vec::new(),
+            }
+        }
+    };
+
+    let mut operations: Vec<OperationTiming> = data
+        .timings
+        .iter()
+        .map(|((category, name), timing)| {
+            let mut recent: Vec<Duration> = timing.recent.iter().copied().collect();
+            recent.sort();
+            let p95 = recent
+                .get((recent.len() * 95 / 100).min(recent.len().saturating_sub(1)))
+                .copied()
+                .unwrap_or_default();
+            OperationTiming {
+                category: *category,
+                name: name.clone(),
+                count: timing.count,
+                total_ms: millis(timing.total),
+                average_ms: millis(timing.total) / timing.count.max(1) as f64,
+                p95_ms: millis(p95),
+                max_ms: millis(timing.max),
+            }
+        })
+        .collect();
+    operations.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+
+    let mut categories: Vec<CategoryTiming> = Vec::new();
+    for operation in &operations {
+        match categories
+            .iter_mut()
+            .find(|c| c.category == operation.category)
+        {
+            Some(category) => {
+                category.count += operation.count;
+                category.total_ms += operation.total_ms;
+            }
+            None => categories.push(CategoryTiming {
+                category: operation.category,
+                count: operation.count,
+                total_ms: operation.total_ms,
+            }),
+        }
+    }
+    categories.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+
+    let mut caches: Vec<CacheStats> = data
+        .caches
+        .iter()
+        .map(|(name, &(hits, misses))| CacheStats {
+            name: name.to_string(),
+            hits,
+            misses,
+            hit_rate: if hits + misses == 0 {
+                0.0
+            } else {
+                hits as f64 / (hits + misses) as f64
+            },
+        })
+        .collect();
+    caches.sort_by(|a, b| a.name.cmp(&b.name));
+
+    PerformanceReport {
+        operations,
+        categories,
+        caches,
+    }
+}
+
+pub fn reset() {
+    if let Ok(mut data) = PERF.lock() {
+        *data = PerfData::default();
+    }
+}
@@ -0,0 +1,316 @@
+use std::path::Path;
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Local, NaiveDate};
+use chrono_tz::Tz;
+use regex::Regex;
+
+use crate::frontmatter::split_frontmatter;
+use crate::markdown::{get_note_for_date, read_timezone_xattr, FileContent};
+use crate::structure::{
+    split_row, CALLOUT_REGEX, FOOTNOTE_DEFINITION_REGEX, FOOTNOTE_REFERENCE_REGEX, HEADING_REGEX,
+    TABLE_DELIMITER_REGEX,
+};
+use crate::tasks::parse_task_line;
+use crate::timeline::{TimelineEvent, TimelineEventKind};
+
+static RULE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?:(?:-\s*){3,}|(?:\*\s*){3,}|(?:_\s*){3,})$")
+        .expect("Failed to compile rule regex")
+});
+static BULLET_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[-*+]\s+(.*)$").expect("Failed to compile bullet regex"));
+
+/// Inline markup, applied in order, with what each match becomes
+static INLINE_RULES: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    [
+        (r"\[\[[^\]|]+\|([^\]]+)\]\]", "$1"),
+        (r"\[\[([^\]]+)\]\]", "$1"),
+        (r"<((?:https?|mailto):[^>\s]+)>", "$1"),
+        (r"</?[A-Za-z][^>]*>", ""),
+        (r"\*\*(\S(?:.*?\S)?)\*\*", "$1"),
+        (r"(^|\W)__(\S(?:.*?\S)?)__(\W|$)", "$1$2$3"),
+        (r"\*(\S(?:.*?\S)?)\*", "$1"),
+        (r"(^|\W)_(\S(?:.*?\S)?)_(\W|$)", "$1$2$3"),
+        (r"~~(\S(?:.*?\S)?)~~", "$1"),
+        (r"==(\S(?:.*?\S)?)==", "$1"),
+    ]
+    .into_iter()
+    .map(|(pattern, replacement)| {
+        (
+            Regex::new(pattern).expect("Failed to compile inline regex"),
+            replacement,
+        )
+    })
+    .collect()
+});
+static LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(!?)\[([^\]]*)\]\(\s*<?([^)\s>]*)>?(?:\s+"[^"]*")?\s*\)"#)
+        .expect("Failed to compile link regex")
+});
+
+/// Escaped punctuation is swapped for a private-use character while markup is stripped,
+/// so `\*` never reads as emphasis
+const ESCAPE_BASE: u32 = 0xE000;
+
+fn hide_escapes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match chars.peek() {
+            Some(&next) if c == '\\' && next.is_ascii_punctuation() => {
+                out.push(char::from_u32(ESCAPE_BASE + next as u32).unwrap_or(next));
+                chars.next();
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn restore_escapes(text: &str) -> String {
+    text.chars()
+        .map(|c| match (c as u32).checked_sub(ESCAPE_BASE) {
+            Some(offset) if offset < 0x80 => char::from_u32(offset).unwrap_or(c),
+            _ => c,
+        })
+        .collect()
+}
+
+/// Strip inline markup from text outside code spans. Web links keep their address after
+/// the link text; images become their alt text
+fn plain_inline(text: &str) -> String {
+    let mut text = LINK_REGEX
+        .replace_all(text, |caps: &regex::Captures| {
+            let (label, url) = (caps[2].trim(), &caps[3]);
+            if &caps[1] == "!" {
+                return match label {
+                    "" => "Image".to_string(),
+                    alt => format!("Image: {}", alt),
+                };
+            }
+            let is_web = ["http://", "https://", "mailto:"]
+                .iter()
+                .any(|scheme| url.starts_with(scheme));
+            match label {
+                "" => url.to_string(),
+                label if is_web && label != url => format!("{} ({})", label, url),
+                label => label.to_string(),
+            }
+        })
+        .to_string();
+    text = FOOTNOTE_REFERENCE_REGEX
+        .replace_all(&text, "[$1]")
+        .to_string();
+    for (regex, replacement) in INLINE_RULES.iter() {
+        text = regex.replace_all(&text, *replacement).to_string();
+    }
+    text
+}
+
+/// Inline markup stripped, with code spans kept verbatim apart from their backticks
+fn plain_spans(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (index, part) in text.split('`').enumerate() {
+        if index % 2 == 1 {
+            out.push_str(part);
+        } else {
+            out.push_str(&restore_escapes(&plain_inline(&hide_escapes(part))));
+        }
+    }
+    out
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// One line outside code blocks as plain text, or None for lines that are only markup,
+/// like a table's delimiter row
+fn plain_line(line: &str) -> Option<String> {
+    let indent_width = line
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| if c == '\t' { 4 } else { 1 })
+        .sum::<usize>();
+    let mut rest = line.trim();
+    let mut depth = indent_width / 2;
+    while let Some(quoted) = rest.strip_prefix('>') {
+        rest = quoted.trim_start();
+        depth += 1;
+    }
+    let indent = "  ".repeat(depth);
+
+    if rest.is_empty() || RULE_REGEX.is_match(rest) {
+        return Some(String::new());
+    }
+    if TABLE_DELIMITER_REGEX.is_match(rest) && rest.contains('-') && rest.contains('|') {
+        return None;
+    }
+
+    let text = if let Some(caps) = HEADING_REGEX.captures(rest) {
+        plain_spans(&caps[1])
+    } else if let Some(caps) = CALLOUT_REGEX.captures(line) {
+        let kind = capitalize(&caps[1].replace('-', " ").to_lowercase());
+        let title = plain_spans(&caps[3]);
+        if title.is_empty() {
+            kind
+        } else {
+            format!("{}: {}", kind, title)
+        }
+    } else if let Some(task) = parse_task_line(rest) {
+        let status = if task.done { "Done" } else { "To do" };
+        format!("{}: {}", status, plain_spans(task.text))
+    } else if let Some(caps) = BULLET_REGEX.captures(rest) {
+        format!("• {}", plain_spans(&caps[1]))
+    } else if let Some(caps) = FOOTNOTE_DEFINITION_REGEX.captures(rest) {
+        format!("Footnote {}: {}", &caps[1], plain_spans(&caps[2]))
+    } else if rest.starts_with('|') {
+        split_row(rest)
+            .iter()
+            .map(|cell| plain_spans(cell))
+            .collect::<Vec<_>>()
+            .join("; ")
+    } else {
+        plain_spans(rest)
+    };
+    Some(format!("{}{}", indent, text.trim_end()))
+}
+
+/// Markdown reduced to plain text for screen readers and other tools: frontmatter,
+/// heading marks, emphasis, link syntax and HTML tags are dropped, list items become `•`
+/// lines, tasks read "To do:" or "Done:", table rows become `;`-separated cells and code
+/// blocks are kept as they are, indented. Runs of blank lines collapse to one
+pub fn markdown_to_plain_text(content: &str) -> String {
+    let body = split_frontmatter(content).map_or(content, |(_, body)| body);
+
+    let mut lines: Vec<String> = Vec::new();
+    let mut in_code_block = false;
+    for line in body.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        let line = if in_code_block {
+            Some(format!("    {}", line.trim_end()))
+        } else {
+            plain_line(line)
+        };
+        match line {
+            Some(line) if !line.trim().is_empty() => lines.push(line),
+            // Runs of blank lines become one, and none lead
+            Some(_) if lines.last().is_some_and(|last| !last.is_empty()) => {
+                lines.push(String::new())
+            }
+            _ => {}
+        }
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+    lines.join("\n")
+}
+
+fn event_label(kind: TimelineEventKind) -> &'static str {
+    match kind {
+        TimelineEventKind::Commit => "Commit",
+        TimelineEventKind::NoteCreated => "Note created",
+        TimelineEventKind::NoteEdited => "Note edited",
+        TimelineEventKind::FocusSession => "Focus session",
+        TimelineEventKind::Capture => "Capture",
+    }
+}
+
+fn clock_time(millis: u64, timezone: Option<Tz>) -> String {
+    let utc = DateTime::from_timestamp_millis(millis as i64).unwrap_or_default();
+    match timezone {
+        Some(tz) => utc.with_timezone(&tz).format("%H:%M").to_string(),
+        None => utc.with_timezone(&Local).format("%H:%M").to_string(),
+    }
+}
+
+/// One timeline event as a line: its time, what happened and any detail, e.g.
+/// `09:14 Commit: Fix login (a1b2c3d, api)`
+fn event_line(event: &TimelineEvent, timezone: Option<Tz>) -> String {
+    let time = match event.end_timestamp {
+        Some(end) => format!(
+            "{} to {}",
+            clock_time(event.timestamp, timezone),
+            clock_time(end, timezone)
+        ),
+        None => clock_time(event.timestamp, timezone),
+    };
+    let mut details: Vec<String> = event.detail.iter().cloned().collect();
+    if event.kind == TimelineEventKind::Commit {
+        if let Some(repo) = event
+            .path
+            .as_deref()
+            .and_then(|path| Path::new(path).file_name())
+        {
+            details.push(repo.to_string_lossy().to_string());
+        }
+    }
+
+    let mut line = format!("{} {}", time, event_label(event.kind));
+    if !event.title.is_empty() {
+        line.push_str(&format!(": {}", event.title));
+    }
+    if !details.is_empty() {
+        line.push_str(&format!(" ({})", details.join(", ")));
+    }
+    line
+}
+
+/// A day as plain text: the date, the note without markdown syntax and the timeline one
+/// event per line, with times in `timezone` (the system's when None)
+pub fn render_day_as_text(
+    date: NaiveDate,
+    note: Option<&str>,
+    events: &[TimelineEvent],
+    timezone: Option<Tz>,
+) -> String {
+    let mut out = format!("{}\n\nNote\n\n", date.format("%A, %-d %B %Y"));
+    match note.map(markdown_to_plain_text) {
+        Some(text) if !text.is_empty() => out.push_str(&text),
+        Some(_) => out.push_str("The note is empty."),
+        None => out.push_str("No note for this day."),
+    }
+
+    out.push_str("\n\nTimeline\n\n");
+    if events.is_empty() {
+        out.push_str("Nothing recorded.");
+    }
+    let lines: Vec<String> = events
+        .iter()
+        .map(|event| event_line(event, timezone))
+        .collect();
+    out.push_str(&lines.join("\n"));
+    out.push('\n');
+    out
+}
+
+/// The day's note and `events` as plain text. Times follow the timezone the note was
+/// written in; a locked note is mentioned but not opened
+pub fn day_as_text(
+    directory_path: &Path,
+    date: NaiveDate,
+    events: &[TimelineEvent],
+) -> Result<String, Box<dyn std::error::Error>> {
+    let (note, timezone) = match get_note_for_date(directory_path, date)? {
+        Some(note) => {
+            let timezone = read_timezone_xattr(Path::new(&note.metadata.file_path));
+            let content = match note.content {
+                FileContent::Content(content) => content,
+                FileContent::Error(e) => format!("The note couldn't be read: {}.", e),
+            };
+            (Some(content), timezone)
+        }
+        None => (None, None),
+    };
+    Ok(render_day_as_text(date, note.as_deref(), events, timezone))
+}
@@ -0,0 +1,239 @@
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::site::strip_private_sections;
+
+const GITHUB_API: &str = "https://api.github.com";
+const GITLAB_API: &str = "https://gitlab.com/api/v4";
+const USER_AGENT: &str = concat!("stream/", env!("CARGO_PKG_VERSION"));
+
+const XATTR_GIST_KEY: &str = "user.publish.gist";
+const XATTR_GITLAB_SNIPPET_KEY: &str = "user.publish.gitlab_snippet";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PublishTarget {
+    Gist,
+    GitlabSnippet,
+}
+
+impl PublishTarget {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s {
+            "gist" => Some(PublishTarget::Gist),
+            "gitlab_snippet" => Some(PublishTarget::GitlabSnippet),
+            _ => None,
+        }
+    }
+
+    fn xattr_key(&self) -> &'static str {
+        match self {
+            PublishTarget::Gist => XATTR_GIST_KEY,
+            PublishTarget::GitlabSnippet => XATTR_GITLAB_SNIPPET_KEY,
+        }
+    }
+}
+
+/// Where a note was last published, stored as JSON in a per-target xattr
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PublishedNote {
+    pub target: PublishTarget,
+    pub id: String,
+    pub url: String,
+    pub updated: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PublishRecord {
+    id: String,
+    url: String,
+}
+
+pub fn read_publish_record(file_path: &Path, target: PublishTarget) -> Option<(String, String)> {
+    let bytes = xattr::get(file_path, target.xattr_key()).ok().flatten()?;
+    let record: PublishRecord = serde_json::from_slice(&bytes).ok()?;
+    Some((record.id, record.url))
+}
+
+fn write_publish_record(
+    file_path: &Path,
+    target: PublishTarget,
+    id: &str,
+    url: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let record = PublishRecord {
+        id: id.to_string(),
+        url: url.to_string(),
+    };
+    xattr::set(file_path, target.xattr_key(), &serde_json::to_vec(&record)?)?;
+    Ok(())
+}
+
+/// Send a JSON request, returning None on 404 so a deleted gist/snippet can be recreated
+fn send_json(
+    request: ureq::Request,
+    body: &Value,
+) -> Result<Option<Value>, Box<dyn std::error::Error>> {
+    match request.set("User-Agent", USER_AGENT).send_json(body) {
+        Ok(response) => Ok(Some(response.into_json()?)),
+        Err(ureq::Error::Status(404, _)) => Ok(None),
+        Err(ureq::Error::Status(code, response)) => {
+            let detail = response.into_string().unwrap_or_default();
+            Err(format!("Request failed with status {}: {}", code, detail.trim()).into())
+        }
+        Err(e) => Err(e.to_string().into()),
+    }
+}
+
+fn publish_gist(
+    file_name: &str,
+    content: &str,
+    public: bool,
+    token: &str,
+    existing_id: Option<&str>,
+) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+    let body = json!({
+        "description": file_name,
+        "public": public,
+        "files": { file_name: { "content": content } },
+    });
+    let auth = format!("Bearer {}", token);
+
+    let response = match existing_id {
+        // Visibility can't be changed after a gist is created
+        Some(id) => send_json(
+            crate::net::agent()
+                .patch(&format!("{}/gists/{}", GITHUB_API, id))
+                .set("Authorization", &auth)
+                .set("Accept", "application/vnd.github+json"),
+            &body,
+        )?,
+        None => send_json(
+            crate::net::agent()
+                .post(&format!("{}/gists", GITHUB_API))
+                .set("Authorization", &auth)
+                .set("Accept", "application/vnd.github+json"),
+            &body,
+        )?,
+    };
+
+    Ok(response.and_then(|gist| {
+        Some((
+            gist.get("id")?.as_str()?.to_string(),
+            gist.get("html_url")?.as_str()?.to_string(),
+        ))
+    }))
+}
+
+fn publish_gitlab_snippet(
+    file_name: &str,
+    content: &str,
+    visibility: &str,
+    token: &str,
+    base_url: Option<&str>,
+    existing_id: Option<&str>,
+) -> Result<Option<(String, String)>, Box<dyn std::error::Error>> {
+    let api = base_url
+        .map(|url| format!("{}/api/v4", url.trim_end_matches('/')))
+        .unwrap_or_else(|| GITLAB_API.to_string());
+
+    let response = match existing_id {
+        Some(id) => send_json(
+            crate::net::agent()
+                .put(&format!("{}/snippets/{}", api, id))
+                .set("PRIVATE-TOKEN", token),
+            &json!({
+                "title": file_name,
+                "visibility": visibility,
+                "files": [{ "action": "update", "file_path": file_name, "content": content }],
+            }),
+        )?,
+        None => send_json(
+            crate::net::agent()
+                .post(&format!("{}/snippets", api))
+                .set("PRIVATE-TOKEN", token),
+            &json!({
+                "title": file_name,
+                "visibility": visibility,
+                "files": [{ "file_path": file_name, "content": content }],
+            }),
+        )?,
+    };
+
+    Ok(response.and_then(|snippet| {
+        let id = match snippet.get("id")? {
+            Value::Number(n) => n.to_string(),
+            Value::String(s) => s.clone(),
+            _ => return None,
+        };
+        Some((id, snippet.get("web_url")?.as_str()?.to_string()))
+    }))
+}
+
+/// Publish a note as a GitHub gist or GitLab snippet, updating the one created last time
+/// if there is one. Private sections are stripped first. `visibility` is "public" or
+/// "private" ("internal" is also accepted for GitLab)
+pub fn publish_note(
+    file_path: &Path,
+    target: PublishTarget,
+    visibility: &str,
+    token: &str,
+    base_url: Option<&str>,
+) -> Result<PublishedNote, Box<dyn std::error::Error>> {
+    let visibility = match (target, visibility) {
+        (_, "public") | (_, "private") => visibility,
+        (PublishTarget::GitlabSnippet, "internal") => visibility,
+        _ => return Err(format!("Unsupported visibility: {}", visibility).into()),
+    };
+
+    let content = strip_private_sections(&fs::read_to_string(file_path)?);
+    if content.trim().is_empty() {
+        return Err("Note is empty after removing private sections".into());
+    }
+    let file_name = file_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("note.md")
+        .to_string();
+
+    let existing = read_publish_record(file_path, target).map(|(id, _)| id);
+    let publish = |existing_id: Option<&str>| match target {
+        PublishTarget::Gist => publish_gist(
+            &file_name,
+            &content,
+            visibility == "public",
+            token,
+            existing_id,
+        ),
+        PublishTarget::GitlabSnippet => publish_gitlab_snippet(
+            &file_name,
+            &content,
+            visibility,
+            token,
+            base_url,
+            existing_id,
+        ),
+    };
+
+    let (result, updated) = match existing.as_deref() {
+        Some(id) => match publish(Some(id))? {
+            Some(result) => (Some(result), true),
+            // Deleted on the remote since the last publish
+            None => (publish(None)?, false),
+        },
+        None => (publish(None)?, false),
+    };
+    let (id, url) = result.ok_or("Unexpected response from the publishing service")?;
+
+    write_publish_record(file_path, target, &id, &url)?;
+
+    Ok(PublishedNote {
+        target,
+        id,
+        url,
+        updated,
+    })
+}
@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::now_ms;
+
+/// Views kept per note for scoring; older ones only count towards `view_count`
+const MAX_VISITS_PER_NOTE: usize = 10;
+const MAX_TRACKED_NOTES: usize = 500;
+
+/// An edit counts for this many views
+const EDIT_WEIGHT: f32 = 2.0;
+
+const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+/// Weight of a visit by age, as in browser frecency: recent visits count for more
+const RECENCY_BUCKETS: &[(u64, f32)] = &[
+    (4 * DAY_MS, 100.0),
+    (14 * DAY_MS, 70.0),
+    (31 * DAY_MS, 50.0),
+    (90 * DAY_MS, 30.0),
+];
+const OLDEST_VISIT_WEIGHT: f32 = 10.0;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct NoteVisits {
+    view_count: u64,
+    /// Most recent views, oldest first
+    visits: Vec<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecentNote {
+    pub file_path: String,
+    pub score: f32,
+    pub view_count: u64,
+    pub last_viewed_at: Option<u64>,
+    /// The file's modification time, so edits made anywhere count
+    pub last_edited_at: Option<u64>,
+}
+
+fn visit_weight(age_ms: u64) -> f32 {
+    RECENCY_BUCKETS
+        .iter()
+        .find(|(max_age, _)| age_ms < *max_age)
+        .map_or(OLDEST_VISIT_WEIGHT, |(_, weight)| *weight)
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+/// Frecency of a note: each kept view weighted by its age, scaled up to the total view
+/// count, plus the last edit
+fn frecency(visits: &NoteVisits, last_edited_at: Option<u64>, now: u64) -> f32 {
+    let mut score = 0.0;
+    if !visits.visits.is_empty() {
+        let kept: f32 = visits
+            .visits
+            .iter()
+            .map(|&at| visit_weight(now.saturating_sub(at)))
+            .sum();
+        score += kept * visits.view_count as f32 / visits.visits.len() as f32;
+    }
+    if let Some(edited) = last_edited_at {
+        score += EDIT_WEIGHT * visit_weight(now.saturating_sub(edited));
+    }
+    score
+}
+
+/// Notes the user has opened, persisted to `path` so the list survives reloads and restarts
+pub struct RecentNotes {
+    path: PathBuf,
+    notes: Mutex<HashMap<String, NoteVisits>>,
+}
+
+impl RecentNotes {
+    pub fn load(path: &Path) -> Self {
+        let notes = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        RecentNotes {
+            path: path.to_path_buf(),
+            notes: Mutex::new(notes),
+        }
+    }
+
+    fn save(&self, notes: &HashMap<String, NoteVisits>) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_vec(notes)?)?;
+        Ok(())
+    }
+
+    /// Record that `file_path` was just opened. Once too many notes are tracked, the one
+    /// viewed longest ago is forgotten
+    pub fn record_view(&self, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut notes = self
+            .notes
+            .lock()
+            .map_err(|_| "Recent notes are unavailable")?;
+
+        let entry = notes
+            .entry(file_path.to_string_lossy().to_string())
+            .or_default();
+        entry.view_count += 1;
+        entry.visits.push(now_ms());
+        if entry.visits.len() > MAX_VISITS_PER_NOTE {
+            entry.visits.remove(0);
+        }
+
+        while notes.len() > MAX_TRACKED_NOTES {
+            let stalest = notes
+                .iter()
+                .min_by_key(|(_, visits)| visits.visits.last().copied().unwrap_or(0))
+                .map(|(path, _)| path.clone());
+            match stalest {
+                Some(path) => notes.remove(&path),
+                None => break,
+            };
+        }
+
+        self.save(&notes)
+    }
+
+    /// Tracked notes that still exist, highest frecency first
+    pub fn recent(&self, limit: usize) -> Vec<RecentNote> {
+        let notes = match self.notes.lock() {
+            Ok(notes) => notes.clone(),
+            Err(_) => return Vec::new(),
+        };
+        let now = now_ms();
+
+        let mut recent: Vec<RecentNote> = notes
+            .into_iter()
+            .filter_map(|(file_path, visits)| {
+                let path = Path::new(&file_path);
+                if !path.is_file() {
+                    return None;
+                }
+                let last_edited_at = modified_ms(path);
+                Some(RecentNote {
+                    score: frecency(&visits, last_edited_at, now),
+                    view_count: visits.view_count,
+                    last_viewed_at: visits.visits.last().copied(),
+                    last_edited_at,
+                    file_path,
+                })
+            })
+            .collect();
+        recent.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| b.last_viewed_at.cmp(&a.last_viewed_at))
+        });
+        recent.truncate(limit);
+        recent
+    }
+
+    /// Each tracked note's frecency relative to the highest, from 0 to 1, for ranking
+    /// search results
+    pub fn boosts(&self) -> HashMap<String, f32> {
+        let recent = self.recent(MAX_TRACKED_NOTES);
+        let max = recent.first().map_or(0.0, |note| note.score);
+        if max <= 0.0 {
+            return HashMap::new();
+        }
+        recent
+            .into_iter()
+            .map(|note| (note.file_path, note.score / max))
+            .collect()
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::{LazyLock, RwLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::git::GitCommit;
+use crate::markdown::parse_date_from_filename;
+use crate::search::find_markdown_files;
+
+/// Shortest abbreviated hash treated as a commit mention, matching git's default
+const MIN_HASH_LENGTH: usize = 7;
+
+static HASH_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\b[0-9a-fA-F]{7,40}\b").expect("Failed to compile hash regex"));
+
+/// Jira-style keys like `PROJ-123` and GitHub/GitLab-style `#123`
+static TICKET_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b[A-Z][A-Z0-9]+-\d+\b|(?:^|[^\w&/])(#\d+)\b")
+        .expect("Failed to compile ticket regex")
+});
+
+/// User-configured ticket patterns, used instead of `TICKET_REGEX` when not empty
+static TICKET_PATTERNS: LazyLock<RwLock<Vec<Regex>>> = LazyLock::new(|| RwLock::new(Vec::new()));
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum Reference {
+    CommitHash(String),
+    TicketId(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NoteReference {
+    pub file_path: String,
+    pub date_from_filename: Option<u64>,
+    pub line_number: usize,
+    pub line: String,
+}
+
+/// Everything that mentions a ticket: commits naming it in their message and note lines
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TicketActivity {
+    pub ticket: String,
+    pub commits: Vec<GitCommit>,
+    pub notes: Vec<NoteReference>,
+}
+
+/// Notes that mention a commit, either by hash or by a ticket named in its message
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitNoteLinks {
+    pub commit_id: String,
+    pub repo_path: String,
+    pub tickets: Vec<String>,
+    pub note_paths: Vec<String>,
+}
+
+fn hash_mentions(line: &str) -> impl Iterator<Item = String> + '_ {
+    HASH_REGEX
+        .find_iter(line)
+        // Plain numbers are far more likely to be counts or dates than hashes
+        .filter(|m| m.as_str().chars().any(|c| c.is_ascii_alphabetic()))
+        .map(|m| m.as_str().to_lowercase())
+}
+
+/// Use `patterns` to find ticket ids in commits and notes. A pattern's first capture group,
+/// if it has one, is the id. An empty list restores the built-in Jira and `#123` patterns
+pub fn set_ticket_patterns(patterns: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let compiled = patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern).map_err(|e| format!("Invalid ticket pattern '{}': {}", pattern, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    *TICKET_PATTERNS
+        .write()
+        .map_err(|_| "Ticket patterns lock poisoned")? = compiled;
+    Ok(())
+}
+
+pub fn ticket_patterns() -> Vec<String> {
+    TICKET_PATTERNS
+        .read()
+        .map(|patterns| patterns.iter().map(|p| p.as_str().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Ticket ids mentioned in `text`, uppercased
+pub fn extract_ticket_ids(text: &str) -> Vec<String> {
+    let configured = TICKET_PATTERNS
+        .read()
+        .map(|p| p.clone())
+        .unwrap_or_default();
+    let patterns = if configured.is_empty() {
+        std::slice::from_ref(&*TICKET_REGEX)
+    } else {
+        &configured[..]
+    };
+
+    let mut seen = HashSet::new();
+    patterns
+        .iter()
+        .flat_map(|pattern| pattern.captures_iter(text))
+        .filter_map(|caps| caps.get(1).or_else(|| caps.get(0)))
+        .map(|m| m.as_str().to_uppercase())
+        .filter(|id| seen.insert(id.clone()))
+        .collect()
+}
+
+/// Whether a hash mentioned in a note and a commit hash name the same commit
+fn hashes_match(mention: &str, hash: &str) -> bool {
+    mention.len() >= MIN_HASH_LENGTH
+        && hash.len() >= MIN_HASH_LENGTH
+        && (hash.starts_with(mention) || mention.starts_with(hash))
+}
+
+fn line_mentions(line: &str, reference: &Reference) -> bool {
+    match reference {
+        Reference::CommitHash(hash) => {
+            let hash = hash.to_lowercase();
+            hash_mentions(line).any(|mention| hashes_match(&mention, &hash))
+        }
+        Reference::TicketId(id) => {
+            let id = id.to_uppercase();
+            extract_ticket_ids(line).contains(&id)
+        }
+    }
+}
+
+/// Lines in the notes under `directory_path` that mention a commit hash (short or full)
+/// or a ticket id, newest note first
+pub fn find_references(
+    directory_path: &Path,
+    reference: &Reference,
+) -> Result<Vec<NoteReference>, Box<dyn std::error::Error>> {
+    if let Reference::CommitHash(hash) = reference {
+        if hash.len() < MIN_HASH_LENGTH || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("Not a commit hash: {}", hash).into());
+        }
+    }
+
+    let mut references = Vec::new();
+    for file_path in find_markdown_files(&directory_path.to_string_lossy())? {
+        let content = match fs::read_to_string(&file_path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        let date_from_filename = Path::new(&file_path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .and_then(parse_date_from_filename);
+
+        for (index, line) in content.lines().enumerate() {
+            if line_mentions(line, reference) {
+                references.push(NoteReference {
+                    file_path: file_path.clone(),
+                    date_from_filename,
+                    line_number: index + 1,
+                    line: line.trim().to_string(),
+                });
+            }
+        }
+    }
+
+    references.sort_by(|a, b| {
+        b.date_from_filename
+            .cmp(&a.date_from_filename)
+            .then(a.line_number.cmp(&b.line_number))
+    });
+    Ok(references)
+}
+
+/// For each commit whose hash, or a ticket id in its message, is mentioned in a note,
+/// the notes that mention it. Commits without mentions are left out
+pub fn link_commits_to_notes(
+    directory_path: &Path,
+    commits: &[GitCommit],
+) -> Result<Vec<CommitNoteLinks>, Box<dyn std::error::Error>> {
+    // Index every hash and ticket mention once, then look commits up against it
+    let mut hash_index: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut ticket_index: HashMap<String, HashSet<String>> = HashMap::new();
+
+    for file_path in find_markdown_files(&directory_path.to_string_lossy())? {
+        let content = match fs::read_to_string(&file_path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+        for mention in hash_mentions(&content) {
+            hash_index
+                .entry(mention)
+                .or_default()
+                .insert(file_path.clone());
+        }
+        for ticket in extract_ticket_ids(&content) {
+            ticket_index
+                .entry(ticket)
+                .or_default()
+                .insert(file_path.clone());
+        }
+    }
+
+    let mut links = Vec::new();
+    for commit in commits {
+        let hash = commit.id.to_lowercase();
+        let mut note_paths: HashSet<String> = hash_index
+            .iter()
+            .filter(|(mention, _)| hashes_match(mention, &hash))
+            .flat_map(|(_, paths)| paths.iter().cloned())
+            .collect();
+
+        let tickets: Vec<String> = commit
+            .tickets
+            .iter()
+            .filter(|ticket| ticket_index.contains_key(*ticket))
+            .cloned()
+            .collect();
+        for ticket in &tickets {
+            note_paths.extend(ticket_index[ticket].iter().cloned());
+        }
+
+        if note_paths.is_empty() {
+            continue;
+        }
+        let mut note_paths: Vec<String> = note_paths.into_iter().collect();
+        note_paths.sort();
+        links.push(CommitNoteLinks {
+            commit_id: commit.id.clone(),
+            repo_path: commit.repo_path.clone(),
+            tickets,
+            note_paths,
+        });
+    }
+
+    Ok(links)
+}
+
+/// The commits among `commits` that name `ticket`, newest first, and the note lines that
+/// mention it
+pub fn ticket_activity(
+    directory_path: &Path,
+    ticket: &str,
+    commits: Vec<GitCommit>,
+) -> Result<TicketActivity, Box<dyn std::error::Error>> {
+    let ticket = ticket.trim().to_uppercase();
+    if ticket.is_empty() {
+        return Err("Ticket id is empty".into());
+    }
+
+    let mut commits: Vec<GitCommit> = commits
+        .into_iter()
+        .filter(|commit| commit.tickets.contains(&ticket))
+        .collect();
+    commits.sort_by_key(|commit| std::cmp::Reverse(commit.timestamp));
+    let notes = find_references(directory_path, &Reference::TicketId(ticket.clone()))?;
+
+    Ok(TicketActivity {
+        ticket,
+        commits,
+        notes,
+    })
+}
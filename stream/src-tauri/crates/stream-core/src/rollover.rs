@@ -0,0 +1,251 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::file_lock::lock_note_file;
+use crate::lock::ensure_unlocked;
+use crate::markdown::{find_daily_note_or_locked, writable_daily_note};
+use crate::tasks::parse_task_line;
+use crate::undo::{UndoJournal, UndoKind};
+
+/// Carried-over tasks are gathered under this heading in the target note
+const CARRIED_OVER_HEADING: &str = "## Carried over";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RolloverMode {
+    /// Take the tasks out of the earlier note
+    #[default]
+    Move,
+    /// Leave the earlier note as it is
+    Copy,
+}
+
+impl RolloverMode {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "move" => Some(RolloverMode::Move),
+            "copy" => Some(RolloverMode::Copy),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RolloverSummary {
+    pub from_path: String,
+    pub to_path: String,
+    /// Tasks added to the target note
+    pub carried: usize,
+    /// Unfinished tasks the target note already had, so weren't added again
+    pub already_present: usize,
+    /// The notes' prior state was saved to the undo journal
+    pub undoable: bool,
+}
+
+fn indent_width(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// `line` without up to `width` bytes of leading whitespace, cut at a character boundary
+fn strip_indent(line: &str, width: usize) -> &str {
+    let start = line
+        .char_indices()
+        .find(|&(index, c)| index >= width || !c.is_whitespace())
+        .map_or(line.len(), |(index, _)| index);
+    &line[start..]
+}
+
+/// Text of a checkbox item and whether it's still open
+fn task(line: &str) -> Option<(&str, bool)> {
+    parse_task_line(line).map(|task| (task.text, !task.done))
+}
+
+/// Line ranges of the unfinished tasks in `lines`, each with the lines nested under it.
+/// Tasks inside fenced code are ignored
+fn open_task_blocks(lines: &[&str]) -> Vec<(usize, usize)> {
+    let mut blocks = Vec::new();
+    let mut in_fence = false;
+    let mut index = 0;
+
+    while index < lines.len() {
+        let line = lines[index];
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+        }
+        if in_fence || !task(line).is_some_and(|(_, open)| open) {
+            index += 1;
+            continue;
+        }
+
+        let indent = indent_width(line);
+        let mut end = index + 1;
+        while end < lines.len()
+            && !lines[end].trim().is_empty()
+            && indent_width(lines[end]) > indent
+        {
+            end += 1;
+        }
+        blocks.push((index, end));
+        index = end;
+    }
+
+    blocks
+}
+
+/// Insert `items` at the end of the carried-over section, adding the section if needed
+fn insert_carried_over(content: &str, items: &[String]) -> String {
+    let mut lines: Vec<&str> = content.lines().collect();
+    let heading = lines
+        .iter()
+        .position(|line| line.trim() == CARRIED_OVER_HEADING);
+
+    let joined = items.join("\n");
+    match heading {
+        Some(heading) => {
+            // The section runs to the next heading of the same or a higher level
+            let mut end = lines[heading + 1..]
+                .iter()
+                .position(|line| line.starts_with("# ") || line.starts_with("## "))
+                .map_or(lines.len(), |offset| heading + 1 + offset);
+            while end > heading + 1 && lines[end - 1].trim().is_empty() {
+                end -= 1;
+            }
+            let mut insert = vec![joined.as_str()];
+            if end == heading + 1 {
+                insert.insert(0, "");
+            }
+            if end < lines.len() {
+                insert.push("");
+            }
+            lines.splice(end..end, insert);
+            format!("{}\n", lines.join("\n"))
+        }
+        None if content.trim().is_empty() => format!("{}\n\n{}\n", CARRIED_OVER_HEADING, joined),
+        None => format!(
+            "{}\n\n{}\n\n{}\n",
+            content.trim_end(),
+            CARRIED_OVER_HEADING,
+            joined
+        ),
+    }
+}
+
+/// Carry the unfinished tasks in `from_date`'s note into `to_date`'s under a "Carried over"
+/// heading, creating that note if needed. Tasks keep the lines nested under them. Running it
+/// again adds nothing: tasks the target already has are skipped, and in `Move` mode the
+/// earlier note no longer has them. With a `journal`, both notes are recorded first so the
+/// rollover can be undone, and nothing is changed if that fails. Fails when either note is
+/// locked
+pub fn rollover_tasks(
+    directory_path: &Path,
+    from_date: NaiveDate,
+    to_date: NaiveDate,
+    mode: RolloverMode,
+    journal: Option<&UndoJournal>,
+) -> Result<RolloverSummary, Box<dyn std::error::Error>> {
+    if from_date == to_date {
+        return Err("Can't carry tasks over into the same day".into());
+    }
+    if find_daily_note_or_locked(directory_path, from_date).is_none() {
+        return Err(format!("No note for {}", from_date).into());
+    }
+    let from_path = writable_daily_note(directory_path, from_date)?;
+    let to_path = writable_daily_note(directory_path, to_date)?;
+
+    // Locks are taken beside the notes, so a target that doesn't exist yet still doesn't
+    // when it's snapshotted, and undo removes it again
+    let _to_lock = lock_note_file(&to_path)?;
+    let _from_lock = lock_note_file(&from_path)?;
+    ensure_unlocked(&to_path)?;
+    ensure_unlocked(&from_path)?;
+    let from_content = fs::read_to_string(&from_path)?;
+    let to_content = match fs::read_to_string(&to_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let from_lines: Vec<&str> = from_content.lines().collect();
+    let blocks = open_task_blocks(&from_lines);
+    let present: HashSet<&str> = to_content
+        .lines()
+        .filter_map(|line| task(line).map(|(text, _)| text))
+        .collect();
+
+    let mut items = Vec::new();
+    let mut already_present = 0;
+    for &(start, end) in &blocks {
+        let text = task(from_lines[start]).map_or("", |(text, _)| text);
+        if present.contains(text) {
+            already_present += 1;
+            continue;
+        }
+        // Nested tasks are lifted to the top level along with their children
+        let indent = indent_width(from_lines[start]);
+        let block: Vec<&str> = from_lines[start..end]
+            .iter()
+            .map(|line| strip_indent(line, indent))
+            .collect();
+        items.push(block.join("\n"));
+    }
+
+    let remove_from_source = mode == RolloverMode::Move && !blocks.is_empty();
+    if items.is_empty() && !remove_from_source {
+        return Ok(RolloverSummary {
+            from_path: from_path.to_string_lossy().to_string(),
+            to_path: to_path.to_string_lossy().to_string(),
+            carried: 0,
+            already_present,
+            undoable: false,
+        });
+    }
+
+    let undoable = match journal {
+        Some(journal) => {
+            let description = format!("Carry over tasks from {} to {}", from_date, to_date);
+            let mut paths = vec![to_path.clone()];
+            if remove_from_source {
+                paths.push(from_path.clone());
+            }
+            journal
+                .record_files(UndoKind::Rollover, &description, &paths)
+                .map_err(|e| format!("Couldn't save an undo copy before carrying over: {}", e))?;
+            true
+        }
+        None => false,
+    };
+
+    if !items.is_empty() {
+        fs::write(&to_path, insert_carried_over(&to_content, &items))?;
+    }
+    if remove_from_source {
+        let removed: HashSet<usize> = blocks.iter().flat_map(|&(start, end)| start..end).collect();
+        let kept: Vec<&str> = from_lines
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !removed.contains(index))
+            .map(|(_, line)| *line)
+            .collect();
+        let trailing_newline = if from_content.ends_with('\n') {
+            "\n"
+        } else {
+            ""
+        };
+        fs::write(
+            &from_path,
+            format!("{}{}", kept.join("\n"), trailing_newline),
+        )?;
+    }
+
+    Ok(RolloverSummary {
+        from_path: from_path.to_string_lossy().to_string(),
+        to_path: to_path.to_string_lossy().to_string(),
+        carried: items.len(),
+        already_present,
+        undoable,
+    })
+}
@@ -0,0 +1,681 @@
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::cloud::is_placeholder;
+use crate::markdown::{parse_date_from_filename, read_description_xattr, read_location_xattrs};
+use crate::perf::{self, PerfCategory};
+use crate::structure::callout_line_indices;
+use crate::walk::walk_files;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchMatch {
+    pub file_path: String,
+    pub line_number: u64,
+    pub match_ranges: Vec<(usize, usize)>, // Vec of (start, end) UTF-16 positions
+    pub context_snippet: String,
+    pub score: f32,
+    /// Metadata field the match is in (`description`, `city` or `country`), with
+    /// `line_number` 0; None for a line of the note
+    #[serde(default)]
+    pub field: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchResults {
+    pub matches: Vec<SearchMatch>,
+    pub total_results: usize,
+    pub search_time_ms: u64,
+    #[serde(default)]
+    pub skipped_placeholders: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RelatedNote {
+    pub file_path: String,
+    pub date_from_filename: Option<u64>,
+    pub score: f32,
+    pub shared_terms: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PreviewLine {
+    pub line_number: u64,
+    pub text: String,
+    pub match_ranges: Vec<(usize, usize)>, // Vec of (start, end) UTF-16 positions in `text`
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FilePreview {
+    pub file_path: String,
+    pub lines: Vec<PreviewLine>,
+    /// Whether the file has more lines than were returned
+    pub truncated: bool,
+}
+
+// Shared terms reported per related note, strongest first
+const MAX_SHARED_TERMS: usize = 5;
+
+// Too common to say anything about what two notes have in common
+const STOP_WORDS: &[&str] = &[
+    "about", "after", "again", "also", "and", "any", "are", "been", "before", "but", "can",
+    "could", "did", "does", "done", "for", "from", "get", "got", "had", "has", "have", "her",
+    "him", "his", "how", "into", "its", "just", "like", "more", "most", "much", "not", "now",
+    "off", "one", "only", "our", "out", "over", "she", "should", "some", "than", "that", "the",
+    "their", "them", "then", "there", "these", "they", "this", "today", "too", "very", "was",
+    "were", "what", "when", "which", "while", "who", "will", "with", "would", "you", "your",
+];
+
+// Compile regex once for efficient reuse
+static DATE_FILENAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\d{4})-(\d{2})-(\d{2})\.md$").expect("Failed to compile date filename regex")
+});
+
+// `field:value` or `field:"two words"`, scoping part of a query to a note's metadata
+static FIELD_FILTER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)(?:^|\s)(description|city|country):(?:"([^"]*)"|(\S+))"#)
+        .expect("Failed to compile field filter regex")
+});
+
+// A query split into free-text terms and field filters, each filter being a field name and
+// the terms its value must contain
+struct Query {
+    terms: Vec<String>,
+    filters: Vec<(String, Vec<String>)>,
+}
+
+fn parse_query(query_str: &str) -> Query {
+    let filters = FIELD_FILTER_REGEX
+        .captures_iter(query_str)
+        .filter_map(|captures| {
+            let value = captures.get(2).or_else(|| captures.get(3))?.as_str();
+            let terms = tokenize(value);
+            (!terms.is_empty()).then(|| (captures[1].to_lowercase(), terms))
+        })
+        .collect();
+    let rest = FIELD_FILTER_REGEX.replace_all(query_str, " ");
+
+    Query {
+        terms: tokenize(&rest),
+        filters,
+    }
+}
+
+// Description, city and country of a note, as (field, value) pairs
+fn metadata_fields(path: &Path) -> Vec<(&'static str, String)> {
+    let (country, city) = read_location_xattrs(path);
+    [
+        ("description", read_description_xattr(path)),
+        ("city", city),
+        ("country", country),
+    ]
+    .into_iter()
+    .filter_map(|(field, value)| Some((field, value?)))
+    .collect()
+}
+
+// Find all markdown files matching YYYY-MM-DD.md pattern. Locked notes (`.md.locked`) never match
+pub fn find_markdown_files(folder_path: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut files = walk_files(Path::new(folder_path), |path, _| {
+        // Quick extension check - case sensitive for performance
+        if path.extension()? != "md" {
+            return None;
+        }
+        let file_name = path.file_name()?.to_str()?;
+        DATE_FILENAME_REGEX
+            .is_match(file_name)
+            .then(|| path.to_string_lossy().to_string())
+    });
+    files.sort();
+    Ok(files)
+}
+
+// Tokenize query into terms (split on whitespace and punctuation)
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| c.is_whitespace() || c.is_ascii_punctuation())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+// Byte offsets of every grapheme boundary in `line`, including the end of the line.
+// Matches and snippets are cut on these so emoji ZWJ sequences and combining marks stay whole
+fn grapheme_boundaries(line: &str) -> Vec<usize> {
+    line.grapheme_indices(true)
+        .map(|(byte_idx, _)| byte_idx)
+        .chain(std::iter::once(line.len()))
+        .collect()
+}
+
+fn is_word_separator(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_whitespace() || c.is_ascii_punctuation())
+}
+
+// Where a term matched in a line, in graphemes and in bytes; ends are exclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct TermMatch {
+    grapheme_start: usize,
+    grapheme_end: usize,
+    byte_start: usize,
+    byte_end: usize,
+}
+
+// Combined matching and position finding - single pass optimization
+// Returns None if no match, or Some with every term match
+fn match_and_find_positions(line: &str, query_terms: &[String]) -> Option<Vec<TermMatch>> {
+    if query_terms.is_empty() {
+        return None;
+    }
+
+    let (match_positions, terms_found) = find_term_positions(line, query_terms);
+    // Check if all terms were found
+    if terms_found.iter().all(|&found| found) {
+        Some(match_positions)
+    } else {
+        None
+    }
+}
+
+// Positions of every term in `line`, as above, and which of the terms occur at all
+fn find_term_positions(line: &str, query_terms: &[String]) -> (Vec<TermMatch>, Vec<bool>) {
+    // Lowercase grapheme by grapheme so offsets in the lowered text map back onto the
+    // original line even when lowercasing changes a grapheme's length
+    let boundaries = grapheme_boundaries(line);
+    let graphemes: Vec<&str> = boundaries.windows(2).map(|w| &line[w[0]..w[1]]).collect();
+    let mut line_lower = String::with_capacity(line.len());
+    let mut lower_offsets = Vec::with_capacity(boundaries.len());
+    for grapheme in &graphemes {
+        lower_offsets.push(line_lower.len());
+        line_lower.push_str(&grapheme.to_lowercase());
+    }
+    lower_offsets.push(line_lower.len());
+
+    let separators: Vec<bool> = graphemes.iter().map(|g| is_word_separator(g)).collect();
+
+    // Track which terms we've found for matching check
+    let mut terms_found = vec![false; query_terms.len()];
+    let mut match_positions = Vec::new();
+
+    // Single pass through the line to find all matches
+    for (term_idx, term) in query_terms.iter().enumerate() {
+        if term.is_empty() {
+            terms_found[term_idx] = true;
+            continue;
+        }
+
+        let is_last_term = term_idx == query_terms.len() - 1;
+        let mut i = 0;
+
+        while i < graphemes.len() {
+            // Check if we're at a word boundary (start of line or after whitespace/punctuation)
+            let at_word_boundary = i == 0 || separators[i - 1];
+
+            // The term must cover whole graphemes, never ending partway through one
+            let term_end =
+                if at_word_boundary && line_lower[lower_offsets[i]..].starts_with(term.as_str()) {
+                    lower_offsets
+                        .binary_search(&(lower_offsets[i] + term.len()))
+                        .ok()
+                } else {
+                    None
+                };
+
+            let match_end = match term_end {
+                // Prefix match: extend to the end of the word
+                Some(mut end) if is_last_term => {
+                    while end < graphemes.len() && !separators[end] {
+                        end += 1;
+                    }
+                    Some(end)
+                }
+                // Exact word match: must end at a word boundary too
+                Some(end) if end >= graphemes.len() || separators[end] => Some(end),
+                _ => None,
+            };
+
+            if let Some(end) = match_end {
+                terms_found[term_idx] = true;
+                match_positions.push(TermMatch {
+                    grapheme_start: i,
+                    grapheme_end: end,
+                    byte_start: boundaries[i],
+                    byte_end: boundaries[end],
+                });
+                i = end;
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    (match_positions, terms_found)
+}
+
+// A match in one of a note's metadata fields, highlighting the whole value
+fn metadata_match(
+    file_path: &str,
+    field: &str,
+    value: &str,
+    positions: &[TermMatch],
+) -> SearchMatch {
+    let utf16_offset = |byte_idx: usize| value[..byte_idx].encode_utf16().count();
+    SearchMatch {
+        file_path: file_path.to_string(),
+        line_number: 0,
+        match_ranges: positions
+            .iter()
+            .map(|m| (utf16_offset(m.byte_start), utf16_offset(m.byte_end)))
+            .collect(),
+        context_snippet: value.to_string(),
+        score: positions.len() as f32,
+        field: Some(field.to_string()),
+    }
+}
+
+// Process a single file and return all matches, only from lines inside callouts of kind
+// `callout` when given. Files failing a field filter have no matches; outside callout
+// searches, metadata matching the free-text terms (or, without any, the filters) is included
+fn search_file(file_path: &str, query: &Query, callout: Option<&str>) -> Vec<SearchMatch> {
+    let mut file_matches = Vec::new();
+
+    if !query.filters.is_empty() || callout.is_none() {
+        let fields = metadata_fields(Path::new(file_path));
+        let passes_filters = query.filters.iter().all(|(field, terms)| {
+            fields.iter().any(|(name, value)| {
+                name == field && match_and_find_positions(value, terms).is_some()
+            })
+        });
+        if !passes_filters {
+            return Vec::new();
+        }
+
+        if callout.is_none() {
+            for (field, value) in &fields {
+                let terms = if query.terms.is_empty() {
+                    match query.filters.iter().find(|(name, _)| name == field) {
+                        Some((_, terms)) => terms,
+                        None => continue,
+                    }
+                } else {
+                    &query.terms
+                };
+                if let Some(positions) = match_and_find_positions(value, terms) {
+                    file_matches.push(metadata_match(file_path, field, value, &positions));
+                }
+            }
+        }
+    }
+
+    if query.terms.is_empty() {
+        return file_matches;
+    }
+    let query_terms = &query.terms;
+
+    let content = match fs::read_to_string(file_path) {
+        Ok(c) => c,
+        Err(_) => return file_matches, // Skip files we can't read
+    };
+
+    let in_scope = callout.map(|kind| callout_line_indices(&content, kind));
+    if in_scope.as_ref().is_some_and(|lines| lines.is_empty()) {
+        return file_matches;
+    }
+
+    for (line_idx, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if in_scope
+            .as_ref()
+            .is_some_and(|lines| !lines.contains(&line_idx))
+        {
+            continue;
+        }
+
+        // Combined matching and position finding in single pass
+        let match_positions = match match_and_find_positions(line, query_terms) {
+            Some(positions) => positions,
+            None => continue, // Line doesn't match, skip it
+        };
+
+        let line_number = (line_idx + 1) as u64;
+
+        // Create context snippet around first match
+        let first_match_start = match_positions
+            .first()
+            .map(|m| m.grapheme_start)
+            .unwrap_or(0);
+
+        // Snippet bounds are counted in graphemes so they never split one
+        let boundaries = grapheme_boundaries(line);
+        let grapheme_count = boundaries.len() - 1;
+        let context_start = first_match_start.saturating_sub(50);
+        let context_end = (first_match_start + 100).min(grapheme_count);
+
+        let context_start_byte = boundaries[context_start];
+        let context_end_byte = boundaries[context_end];
+        let context_snippet = &line[context_start_byte..context_end_byte];
+
+        // Convert match positions to UTF-16 offsets relative to snippet, which is what the
+        // webview's string indices use
+        let utf16_offset = |byte_idx: usize| {
+            line[context_start_byte..byte_idx.clamp(context_start_byte, context_end_byte)]
+                .encode_utf16()
+                .count()
+        };
+
+        let utf16_ranges: Vec<(usize, usize)> = match_positions
+            .iter()
+            .filter(|m| m.grapheme_start >= context_start && m.grapheme_start < context_end)
+            .map(|m| (utf16_offset(m.byte_start), utf16_offset(m.byte_end)))
+            .collect();
+
+        // Simple scoring: more matches = higher score
+        let score = match_positions.len() as f32;
+
+        file_matches.push(SearchMatch {
+            file_path: file_path.to_string(),
+            line_number,
+            match_ranges: utf16_ranges,
+            context_snippet: context_snippet.to_string(),
+            score,
+            field: None,
+        });
+    }
+
+    file_matches
+}
+
+// Search through files and return matches (parallel processing). `description:`, `city:`
+// and `country:` scope part of the query to a note's metadata, e.g. `city:lisbon retro`.
+// When ranking by score, a file's `boosts` entry (0 to 1) raises its matches by up to double
+pub fn search_files(
+    files: &[String],
+    query_str: &str,
+    limit: usize,
+    sort_by_date: bool,
+    callout: Option<&str>,
+    boosts: Option<&HashMap<String, f32>>,
+) -> Result<SearchResults, Box<dyn std::error::Error>> {
+    let _span = perf::span(PerfCategory::Search, "search_files");
+    let start_time = std::time::Instant::now();
+    let query = parse_query(query_str);
+
+    if query.terms.is_empty() && query.filters.is_empty() {
+        return Ok(SearchResults {
+            matches: vec![],
+            total_results: 0,
+            search_time_ms: 0,
+            skipped_placeholders: 0,
+        });
+    }
+
+    // Process all files in parallel and collect matches
+    let mut matches: Vec<SearchMatch> = files
+        .par_iter()
+        .flat_map(|file_path| search_file(file_path, &query, callout))
+        .collect();
+
+    // Sort by date if requested (newest first), otherwise by score
+    if sort_by_date {
+        matches.sort_by(|a, b| {
+            // Extract YYYY-MM-DD directly from path (we know files match the pattern)
+            let get_date_from_path = |path: &str| -> Option<[u8; 10]> {
+                let file_name = Path::new(path).file_name()?.to_str()?;
+                // Files matching YYYY-MM-DD.md pattern have date at start
+                if file_name.len() >= 10 {
+                    let mut date = [0u8; 10];
+                    date.copy_from_slice(&file_name.as_bytes()[0..10]);
+                    Some(date)
+                } else {
+                    None
+                }
+            };
+
+            let date_a = get_date_from_path(&a.file_path);
+            let date_b = get_date_from_path(&b.file_path);
+
+            match (date_a, date_b) {
+                (Some(a), Some(b)) => b.cmp(&a), // Descending order (newest first)
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => std::cmp::Ordering::Equal,
+            }
+        });
+    } else {
+        if let Some(boosts) = boosts {
+            for m in matches.iter_mut() {
+                if let Some(boost) = boosts.get(&m.file_path) {
+                    m.score *= 1.0 + boost.clamp(0.0, 1.0);
+                }
+            }
+        }
+        // Sort by score (highest first)
+        matches.sort_unstable_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    // Apply limit after sorting
+    let total_results = matches.len();
+    matches.truncate(limit);
+
+    let search_time_ms = start_time.elapsed().as_millis() as u64;
+
+    Ok(SearchResults {
+        total_results,
+        matches,
+        search_time_ms,
+        skipped_placeholders: 0,
+    })
+}
+
+pub fn search_markdown_files(
+    folder_path: &str,
+    query: &str,
+    limit: usize,
+    sort_by_date: bool,
+    include_placeholders: bool,
+) -> Result<SearchResults, Box<dyn std::error::Error>> {
+    search_markdown_files_in_callouts(
+        folder_path,
+        query,
+        limit,
+        sort_by_date,
+        include_placeholders,
+        None,
+        None,
+    )
+}
+
+/// `search_markdown_files` limited to callouts of one kind, e.g. `decision` for
+/// `> [!decision]` blocks, when `callout` is given, and ranked with `boosts`
+pub fn search_markdown_files_in_callouts(
+    folder_path: &str,
+    query: &str,
+    limit: usize,
+    sort_by_date: bool,
+    include_placeholders: bool,
+    callout: Option<&str>,
+    boosts: Option<&HashMap<String, f32>>,
+) -> Result<SearchResults, Box<dyn std::error::Error>> {
+    // Find all markdown files
+    let mut files = find_markdown_files(folder_path)
+        .map_err(|e| format!("Failed to find markdown files: {}", e))?;
+
+    // Online-only files would be downloaded just to be searched
+    let total_files = files.len();
+    if !include_placeholders {
+        files.retain(|f| !is_placeholder(Path::new(f)));
+    }
+
+    // Search through files
+    let mut results = search_files(&files, query, limit, sort_by_date, callout, boosts)
+        .map_err(|e| format!("Search failed: {}", e))?;
+    results.skipped_placeholders = total_files - files.len();
+
+    Ok(results)
+}
+
+/// The first `max_lines` lines of a file with every occurrence of the query's free-text
+/// terms marked, for hover previews. Unlike search, a line needn't contain every term.
+/// Online-only files aren't downloaded for a preview
+pub fn get_file_preview(
+    file_path: &Path,
+    query_str: &str,
+    max_lines: usize,
+) -> Result<FilePreview, Box<dyn std::error::Error>> {
+    if is_placeholder(file_path) {
+        return Err(format!("Not downloaded: {}", file_path.display()).into());
+    }
+    let content = fs::read_to_string(file_path)?;
+    let terms = parse_query(query_str).terms;
+
+    let mut lines = content.lines();
+    let preview = lines
+        .by_ref()
+        .take(max_lines)
+        .enumerate()
+        .map(|(line_idx, line)| {
+            let (mut positions, _) = find_term_positions(line, &terms);
+            positions.sort();
+            let utf16_offset = |byte_idx: usize| line[..byte_idx].encode_utf16().count();
+
+            // Different terms can match overlapping text; highlight it once
+            let mut match_ranges: Vec<(usize, usize)> = Vec::new();
+            for m in positions {
+                let (start, end) = (utf16_offset(m.byte_start), utf16_offset(m.byte_end));
+                match match_ranges.last_mut() {
+                    Some(last) if start <= last.1 => last.1 = last.1.max(end),
+                    _ => match_ranges.push((start, end)),
+                }
+            }
+
+            PreviewLine {
+                line_number: (line_idx + 1) as u64,
+                text: line.to_string(),
+                match_ranges,
+            }
+        })
+        .collect();
+
+    Ok(FilePreview {
+        file_path: file_path.to_string_lossy().to_string(),
+        lines: preview,
+        truncated: lines.next().is_some(),
+    })
+}
+
+// Term counts for a note, ignoring stop words, numbers and very short tokens
+pub(crate) fn term_counts(content: &str) -> HashMap<String, f32> {
+    let mut counts = HashMap::new();
+    for term in tokenize(content) {
+        if term.chars().count() < 3
+            || term.chars().all(|c| c.is_ascii_digit())
+            || STOP_WORDS.contains(&term.as_str())
+        {
+            continue;
+        }
+        *counts.entry(term).or_insert(0.0) += 1.0;
+    }
+    counts
+}
+
+// Sublinear TF-IDF weights, normalized to unit length so dot products are cosine similarity
+fn tfidf_vector(counts: &HashMap<String, f32>, idf: &HashMap<&str, f32>) -> HashMap<String, f32> {
+    let mut vector: HashMap<String, f32> = counts
+        .iter()
+        .map(|(term, count)| {
+            let weight = (1.0 + count.ln()) * idf.get(term.as_str()).copied().unwrap_or(0.0);
+            (term.clone(), weight)
+        })
+        .collect();
+    let norm = vector.values().map(|w| w * w).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.values_mut().for_each(|w| *w /= norm);
+    }
+    vector
+}
+
+/// The `k` daily notes in `folder_path` most similar to `file_path` by TF-IDF cosine
+/// similarity, with the terms contributing most to each score. Online-only files are skipped
+pub fn get_related_notes(
+    folder_path: &str,
+    file_path: &str,
+    k: usize,
+) -> Result<Vec<RelatedNote>, Box<dyn std::error::Error>> {
+    let _span = perf::span(PerfCategory::Search, "get_related_notes");
+    let source_counts = term_counts(&fs::read_to_string(file_path)?);
+    if source_counts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let source = Path::new(file_path);
+    let documents: Vec<(String, HashMap<String, f32>)> = find_markdown_files(folder_path)?
+        .into_par_iter()
+        .filter(|path| Path::new(path) != source && !is_placeholder(Path::new(path)))
+        .filter_map(|path| {
+            let counts = term_counts(&fs::read_to_string(&path).ok()?);
+            Some((path, counts))
+        })
+        .collect();
+
+    // Document frequency over the candidates plus the source note itself
+    let total = (documents.len() + 1) as f32;
+    let mut document_frequency: HashMap<&str, f32> = HashMap::new();
+    for counts in documents.iter().map(|(_, c)| c).chain([&source_counts]) {
+        for term in counts.keys() {
+            *document_frequency.entry(term.as_str()).or_insert(0.0) += 1.0;
+        }
+    }
+    let idf: HashMap<&str, f32> = document_frequency
+        .into_iter()
+        .map(|(term, df)| (term, (total / df).ln() + 1.0))
+        .collect();
+
+    let source_vector = tfidf_vector(&source_counts, &idf);
+    let source_terms: HashSet<&String> = source_vector.keys().collect();
+
+    let mut related: Vec<RelatedNote> = documents
+        .par_iter()
+        .filter_map(|(path, counts)| {
+            let vector = tfidf_vector(counts, &idf);
+            let mut shared: Vec<(&String, f32)> = vector
+                .iter()
+                .filter(|(term, _)| source_terms.contains(term))
+                .map(|(term, weight)| (term, weight * source_vector[term]))
+                .collect();
+            if shared.is_empty() {
+                return None;
+            }
+
+            let score: f32 = shared.iter().map(|(_, contribution)| contribution).sum();
+            shared.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+            shared.truncate(MAX_SHARED_TERMS);
+
+            let file_name = Path::new(path).file_name()?.to_str()?;
+            Some(RelatedNote {
+                file_path: path.clone(),
+                date_from_filename: parse_date_from_filename(file_name),
+                score,
+                shared_terms: shared.into_iter().map(|(term, _)| term.clone()).collect(),
+            })
+        })
+        .collect();
+
+    related.sort_by(|a, b| b.score.total_cmp(&a.score));
+    related.truncate(k);
+
+    Ok(related)
+}
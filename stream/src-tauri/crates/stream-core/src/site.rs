@@ -0,0 +1,288 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use chrono::{DateTime, Utc};
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+
+use crate::markdown::{parse_date_from_filename, read_description_xattr, read_location_xattrs};
+
+/// Everything between these markers (inclusive) is removed before publishing
+const PRIVATE_START_MARKER: &str = "<!-- private -->";
+const PRIVATE_END_MARKER: &str = "<!-- /private -->";
+
+pub(crate) static LOCAL_LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(!?\[[^\]]*\])\(([^)\s]+)((?:\s+"[^"]*")?)\)"#)
+        .expect("Failed to compile local link regex")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SiteFlavor {
+    Zola,
+    Hugo,
+}
+
+impl SiteFlavor {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "zola" => Some(SiteFlavor::Zola),
+            "hugo" => Some(SiteFlavor::Hugo),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SiteExportSummary {
+    pub output_path: String,
+    pub pages_written: usize,
+    pub assets_copied: usize,
+    pub skipped: Vec<String>,
+}
+
+struct PageFrontmatter {
+    title: String,
+    date: Option<String>,
+    description: Option<String>,
+    city: Option<String>,
+    country: Option<String>,
+}
+
+fn slugify(value: &str) -> String {
+    let mut slug = String::new();
+    for ch in value.to_lowercase().chars() {
+        if ch.is_alphanumeric() {
+            slug.push(ch);
+        } else if !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_matches('-').to_string()
+}
+
+/// `slug`, or `slug-2`, `slug-3`, ... when an earlier page already took it. Notes whose
+/// names differ only in case or punctuation would otherwise overwrite each other's pages
+fn unique_slug(slug: String, used: &mut HashSet<String>) -> String {
+    let slug = if slug.is_empty() {
+        "note".to_string()
+    } else {
+        slug
+    };
+    let mut candidate = slug.clone();
+    let mut counter = 2;
+    while used.contains(&candidate) {
+        candidate = format!("{}-{}", slug, counter);
+        counter += 1;
+    }
+    used.insert(candidate.clone());
+    candidate
+}
+
+/// Remove `<!-- private -->` ... `<!-- /private -->` blocks. An unterminated block
+/// hides the rest of the note, erring on the side of not publishing
+pub fn strip_private_sections(content: &str) -> String {
+    let mut output = String::with_capacity(content.len());
+    let mut in_private = false;
+
+    for line in content.lines() {
+        let trimmed = line.trim();
+        if trimmed == PRIVATE_START_MARKER {
+            in_private = true;
+            continue;
+        }
+        if trimmed == PRIVATE_END_MARKER {
+            in_private = false;
+            continue;
+        }
+        if !in_private {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    output
+}
+
+fn toml_string(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn render_frontmatter(flavor: SiteFlavor, frontmatter: &PageFrontmatter) -> String {
+    // TOML and YAML both accept JSON-style double quoted strings for our fields
+    let mut lines = vec![format!("title = {}", toml_string(&frontmatter.title))];
+    if let Some(date) = &frontmatter.date {
+        lines.push(format!("date = {}", date));
+    }
+    if let Some(description) = &frontmatter.description {
+        lines.push(format!("description = {}", toml_string(description)));
+    }
+
+    let mut extra = Vec::new();
+    if let Some(city) = &frontmatter.city {
+        extra.push(("city", city));
+    }
+    if let Some(country) = &frontmatter.country {
+        extra.push(("country", country));
+    }
+
+    match flavor {
+        SiteFlavor::Zola => {
+            if !extra.is_empty() {
+                lines.push(String::new());
+                lines.push("[extra]".to_string());
+                for (key, value) in extra {
+                    lines.push(format!("{} = {}", key, toml_string(value)));
+                }
+            }
+            format!("+++\n{}\n+++\n\n", lines.join("\n"))
+        }
+        SiteFlavor::Hugo => {
+            let mut yaml: Vec<String> = lines.iter().map(|l| l.replacen(" = ", ": ", 1)).collect();
+            if !extra.is_empty() {
+                yaml.push("params:".to_string());
+                for (key, value) in extra {
+                    yaml.push(format!("  {}: {}", key, toml_string(value)));
+                }
+            }
+            format!("---\n{}\n---\n\n", yaml.join("\n"))
+        }
+    }
+}
+
+/// Use the first `# Heading` as the title, dropping it from the body
+fn extract_title(content: &str) -> (Option<String>, String) {
+    let mut lines = content.lines();
+    let mut leading = Vec::new();
+
+    for line in lines.by_ref() {
+        if line.trim().is_empty() {
+            leading.push(line);
+            continue;
+        }
+        if let Some(title) = line.trim().strip_prefix("# ") {
+            let rest: Vec<&str> = lines.collect();
+            return (Some(title.trim().to_string()), rest.join("\n"));
+        }
+        break;
+    }
+
+    (None, content.to_string())
+}
+
+/// Copy locally-referenced files next to the page and rewrite the links to match
+fn copy_assets(
+    body: &str,
+    note_dir: &Path,
+    page_dir: &Path,
+    copied: &mut HashSet<PathBuf>,
+) -> String {
+    LOCAL_LINK_REGEX
+        .replace_all(body, |caps: &Captures| {
+            let target = &caps[2];
+            let is_remote =
+                target.contains("://") || target.starts_with('#') || target.starts_with("mailto:");
+            if is_remote {
+                return caps[0].to_string();
+            }
+
+            let source = note_dir.join(target);
+            if !source.is_file() {
+                return caps[0].to_string();
+            }
+
+            let file_name = match source.file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => return caps[0].to_string(),
+            };
+
+            let destination = page_dir.join(&file_name);
+            if !copied.contains(&destination) {
+                if let Err(e) = fs::copy(&source, &destination) {
+                    eprintln!("Failed to copy asset {}: {}", source.display(), e);
+                    return caps[0].to_string();
+                }
+                copied.insert(destination);
+            }
+
+            format!("{}({}{})", &caps[1], file_name, &caps[3])
+        })
+        .to_string()
+}
+
+/// Write the given notes as page bundles (`content/<section>/<slug>/index.md`) for Zola or Hugo
+pub fn export_static_site(
+    file_paths: &[String],
+    output_path: &Path,
+    flavor: SiteFlavor,
+    section: &str,
+) -> Result<SiteExportSummary, Box<dyn std::error::Error>> {
+    let section_dir = output_path.join("content").join(slugify(section));
+    fs::create_dir_all(&section_dir)?;
+
+    let mut pages_written = 0;
+    let mut skipped = Vec::new();
+    let mut copied_assets: HashSet<PathBuf> = HashSet::new();
+    let mut slugs: HashSet<String> = HashSet::new();
+
+    for file_path in file_paths {
+        let path = Path::new(file_path);
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Skipping {}: {}", file_path, e);
+                skipped.push(file_path.clone());
+                continue;
+            }
+        };
+
+        let public = strip_private_sections(&content);
+        if public.trim().is_empty() {
+            skipped.push(file_path.clone());
+            continue;
+        }
+
+        let file_name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("note.md");
+        let stem = path.file_stem().and_then(|n| n.to_str()).unwrap_or("note");
+
+        let date = parse_date_from_filename(file_name)
+            .and_then(|ms| DateTime::<Utc>::from_timestamp_millis(ms as i64))
+            .map(|dt| dt.format("%Y-%m-%d").to_string());
+
+        let (heading, body) = extract_title(&public);
+        let (country, city) = read_location_xattrs(path);
+        let frontmatter = PageFrontmatter {
+            title: heading.unwrap_or_else(|| stem.to_string()),
+            date,
+            description: read_description_xattr(path),
+            city,
+            country,
+        };
+
+        let page_dir = section_dir.join(unique_slug(slugify(stem), &mut slugs));
+        fs::create_dir_all(&page_dir)?;
+
+        let note_dir = path.parent().unwrap_or(Path::new("."));
+        let body = copy_assets(&body, note_dir, &page_dir, &mut copied_assets);
+
+        let page = format!(
+            "{}{}\n",
+            render_frontmatter(flavor, &frontmatter),
+            body.trim()
+        );
+        fs::write(page_dir.join("index.md"), page)?;
+        pages_written += 1;
+    }
+
+    Ok(SiteExportSummary {
+        output_path: output_path.to_string_lossy().to_string(),
+        pages_written,
+        assets_copied: copied_assets.len(),
+        skipped,
+    })
+}
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{config_str, ContentSource};
+
+/// How long a command may run before it's killed and the refresh fails
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Runs a program and uses its stdout, optionally wrapped in a code block
+pub struct CommandSource {
+    program: String,
+    args: Vec<String>,
+    working_dir: Option<String>,
+    code_block: bool,
+}
+
+impl CommandSource {
+    pub fn from_config(
+        config: &Value,
+    ) -> Result<Box<dyn ContentSource>, Box<dyn std::error::Error>> {
+        let args = match config.get("args") {
+            Some(args) => serde_json::from_value(args.clone())
+                .map_err(|_| "Command 'args' must be a list of strings")?,
+            None => Vec::new(),
+        };
+
+        Ok(Box::new(CommandSource {
+            program: config_str(config, "command")?,
+            args,
+            working_dir: config
+                .get("working_dir")
+                .and_then(Value::as_str)
+                .map(|s| s.to_string()),
+            code_block: config
+                .get("code_block")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        }))
+    }
+}
+
+/// Run `command` with no stdin, killing it if it hasn't exited within `timeout`. Output is
+/// read on separate threads so a chatty command can't fill its pipe and stall
+fn run_with_timeout(command: &mut Command, timeout: Duration) -> io::Result<Output> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let read_all = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut bytes = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut bytes);
+            }
+            bytes
+        })
+    };
+    let stdout = read_all(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = read_all(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("timed out after {}s", timeout.as_secs()),
+            ));
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+impl ContentSource for CommandSource {
+    fn name(&self) -> &str {
+        "command"
+    }
+
+    fn fetch(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut command = Command::new(&self.program);
+        command.args(&self.args);
+        if let Some(dir) = &self.working_dir {
+            command.current_dir(dir);
+        }
+
+        let output = run_with_timeout(&mut command, COMMAND_TIMEOUT)
+            .map_err(|e| format!("Failed to run {}: {}", self.program, e))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!(
+                "{} exited with {}: {}",
+                self.program,
+                output.status,
+                stderr.trim()
+            )
+            .into());
+        }
+
+        let stdout = String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string();
+        if self.code_block {
+            Ok(format!("```\n{}\n```\n", stdout))
+        } else {
+            Ok(format!("{}\n", stdout))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredCommand {
+    config: Value,
+    approved: bool,
+}
+
+/// Command sources, kept in the app's own data rather than on the notes so a synced or
+/// imported file can never bring a program with it. Each command has to be approved on
+/// this machine before it runs, and changing it withdraws the approval
+pub struct CommandSourceStore {
+    path: PathBuf,
+    commands: Mutex<BTreeMap<String, StoredCommand>>,
+}
+
+impl CommandSourceStore {
+    pub fn load(path: &Path) -> Self {
+        let commands = fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        CommandSourceStore {
+            path: path.to_path_buf(),
+            commands: Mutex::new(commands),
+        }
+    }
+
+    fn save(
+        &self,
+        commands: &BTreeMap<String, StoredCommand>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_vec(commands)?)?;
+        Ok(())
+    }
+
+    fn key(file_path: &Path) -> String {
+        file_path.to_string_lossy().to_string()
+    }
+
+    /// The command attached to `file_path`, approved or not
+    pub fn get(&self, file_path: &Path) -> Option<Value> {
+        let commands = self.commands.lock().ok()?;
+        commands
+            .get(&Self::key(file_path))
+            .map(|command| command.config.clone())
+    }
+
+    /// The command attached to `file_path`, once it has been approved
+    pub fn approved(&self, file_path: &Path) -> Option<Value> {
+        let commands = self.commands.lock().ok()?;
+        commands
+            .get(&Self::key(file_path))
+            .filter(|command| command.approved)
+            .map(|command| command.config.clone())
+    }
+
+    pub fn is_approved(&self, file_path: &Path) -> bool {
+        self.approved(file_path).is_some()
+    }
+
+    /// Attach a command to `file_path`, or detach it with `None`. A new or changed command
+    /// starts out unapproved
+    pub fn set(
+        &self,
+        file_path: &Path,
+        config: Option<&Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut commands = self
+            .commands
+            .lock()
+            .map_err(|_| "Command sources are unavailable")?;
+        let key = Self::key(file_path);
+        match config {
+            Some(config) => {
+                let approved = commands
+                    .get(&key)
+                    .is_some_and(|command| command.approved && &command.config == config);
+                commands.insert(
+                    key,
+                    StoredCommand {
+                        config: config.clone(),
+                        approved,
+                    },
+                );
+            }
+            None => {
+                if commands.remove(&key).is_none() {
+                    return Ok(());
+                }
+            }
+        }
+        self.save(&commands)
+    }
+
+    /// Allow the command attached to `file_path` to run on this machine
+    pub fn approve(&self, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut commands = self
+            .commands
+            .lock()
+            .map_err(|_| "Command sources are unavailable")?;
+        let command = commands
+            .get_mut(&Self::key(file_path))
+            .ok_or("File has no command source")?;
+        command.approved = true;
+        self.save(&commands)
+    }
+}
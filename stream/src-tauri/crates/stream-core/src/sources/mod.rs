@@ -0,0 +1,218 @@
+mod command;
+mod rss;
+pub(crate) mod url;
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::clock::now_ms;
+use crate::markdown::{
+    is_refresh_due, mark_file_as_refreshed, structured_markdown_paths, write_markdown_file,
+};
+
+pub use command::{CommandSource, CommandSourceStore};
+pub use rss::RssSource;
+pub use url::UrlSource;
+
+const XATTR_SOURCE_KEY: &str = "user.source.config";
+
+/// Something that can produce the markdown body of a structured file
+pub trait ContentSource: Send + Sync {
+    fn name(&self) -> &str;
+    fn fetch(&self) -> Result<String, Box<dyn std::error::Error>>;
+}
+
+/// Builds a source from its JSON config (the `kind` field has already been matched)
+pub type SourceFactory = fn(&Value) -> Result<Box<dyn ContentSource>, Box<dyn std::error::Error>>;
+
+/// Maps a config's `kind` to the factory that builds it
+pub struct SourceRegistry {
+    factories: BTreeMap<String, SourceFactory>,
+}
+
+impl SourceRegistry {
+    pub fn new() -> Self {
+        SourceRegistry {
+            factories: BTreeMap::new(),
+        }
+    }
+
+    /// Registry with the built-in `command`, `url` and `rss` sources
+    pub fn with_builtins() -> Self {
+        let mut registry = SourceRegistry::new();
+        registry.register("command", CommandSource::from_config);
+        registry.register("url", UrlSource::from_config);
+        registry.register("rss", RssSource::from_config);
+        registry
+    }
+
+    pub fn register(&mut self, kind: &str, factory: SourceFactory) {
+        self.factories.insert(kind.to_string(), factory);
+    }
+
+    pub fn kinds(&self) -> Vec<String> {
+        self.factories.keys().cloned().collect()
+    }
+
+    pub fn build(
+        &self,
+        config: &Value,
+    ) -> Result<Box<dyn ContentSource>, Box<dyn std::error::Error>> {
+        let kind = config
+            .get("kind")
+            .and_then(Value::as_str)
+            .ok_or("Source config is missing 'kind'")?;
+        let factory = self
+            .factories
+            .get(kind)
+            .ok_or_else(|| format!("Unknown content source: {}", kind))?;
+        factory(config)
+    }
+}
+
+impl Default for SourceRegistry {
+    fn default() -> Self {
+        SourceRegistry::with_builtins()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceRefresh {
+    pub file_path: String,
+    pub source: String,
+    pub error: Option<String>,
+}
+
+fn is_command(config: &Value) -> bool {
+    config.get("kind").and_then(Value::as_str) == Some("command")
+}
+
+/// The source stored on the file itself. Command configs are never read from here: they
+/// travel with the file through sync and archives, so one there came from elsewhere
+pub fn read_source_config(file_path: &Path) -> Option<Value> {
+    xattr::get(file_path, XATTR_SOURCE_KEY)
+        .ok()
+        .flatten()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .filter(|config| !is_command(config))
+}
+
+/// `user.*` attributes from an archive or a remote, minus any source config: sources are
+/// set up on each machine, never taken from a file's metadata
+pub(crate) fn strip_source_config(
+    attributes: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    let mut attributes = attributes.clone();
+    attributes.remove(XATTR_SOURCE_KEY);
+    attributes
+}
+
+/// The file's source: its command from `commands`, approved or not, or else the one
+/// stored on the file
+pub fn source_config(file_path: &Path, commands: &CommandSourceStore) -> Option<Value> {
+    commands
+        .get(file_path)
+        .or_else(|| read_source_config(file_path))
+}
+
+/// Attach a source to a structured file, or detach it with `None`. The config is
+/// validated by building it first. Commands go to `commands` and need approving before
+/// they run; other sources are stored on the file
+pub fn write_source_config(
+    file_path: &Path,
+    config: Option<&Value>,
+    registry: &SourceRegistry,
+    commands: &CommandSourceStore,
+) -> Result<(), Box<dyn std::error::Error>> {
+    match config {
+        Some(config) => {
+            registry.build(config)?;
+            if is_command(config) {
+                commands.set(file_path, Some(config))?;
+                let _ = xattr::remove(file_path, XATTR_SOURCE_KEY);
+            } else {
+                xattr::set(file_path, XATTR_SOURCE_KEY, &serde_json::to_vec(config)?)?;
+                commands.set(file_path, None)?;
+            }
+        }
+        None => {
+            let _ = xattr::remove(file_path, XATTR_SOURCE_KEY);
+            commands.set(file_path, None)?;
+        }
+    }
+    Ok(())
+}
+
+/// Fetch a file's source and rewrite the file with the result. A command source that
+/// hasn't been approved on this machine is refused
+pub fn refresh_from_source(
+    file_path: &Path,
+    registry: &SourceRegistry,
+    commands: &CommandSourceStore,
+) -> Result<SourceRefresh, Box<dyn std::error::Error>> {
+    let config = match commands.get(file_path) {
+        Some(_) => commands
+            .approved(file_path)
+            .ok_or("This command source hasn't been approved on this machine")?,
+        None => read_source_config(file_path).ok_or("File has no content source")?,
+    };
+    let source = registry.build(&config)?;
+
+    let error = match source.fetch() {
+        Ok(content) => {
+            // Atomic and under the note's lock, like an editor save
+            write_markdown_file(file_path, &content)?;
+            None
+        }
+        Err(e) => Some(e.to_string()),
+    };
+    // A failing source is retried on the next interval rather than on every check
+    mark_file_as_refreshed(file_path)?;
+
+    Ok(SourceRefresh {
+        file_path: file_path.to_string_lossy().to_string(),
+        source: source.name().to_string(),
+        error,
+    })
+}
+
+/// Refresh every structured file with a content source whose interval has elapsed.
+/// Commands that haven't been approved are skipped
+pub fn refresh_due_sources(
+    directory_path: &Path,
+    registry: &SourceRegistry,
+    commands: &CommandSourceStore,
+) -> Vec<SourceRefresh> {
+    let now = now_ms();
+
+    structured_markdown_paths(directory_path)
+        .into_iter()
+        .filter(|path| {
+            let runnable = match commands.get(path) {
+                Some(_) => commands.is_approved(path),
+                None => read_source_config(path).is_some(),
+            };
+            runnable && is_refresh_due(path, now)
+        })
+        .map(|path| {
+            refresh_from_source(&path, registry, commands).unwrap_or_else(|e| SourceRefresh {
+                file_path: path.to_string_lossy().to_string(),
+                source: String::new(),
+                error: Some(e.to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Required string field of a source config
+fn config_str(config: &Value, key: &str) -> Result<String, Box<dyn std::error::Error>> {
+    config
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.trim().is_empty())
+        .map(|s| s.to_string())
+        .ok_or_else(|| format!("Source config is missing '{}'", key).into())
+}
@@ -0,0 +1,108 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde_json::Value;
+
+use super::url::{decode_entities, fetch_text};
+use super::{config_str, ContentSource};
+
+const DEFAULT_ITEM_LIMIT: usize = 10;
+
+// RSS `<item>` and Atom `<entry>` blocks; feeds are small enough that regex beats a parser
+static ITEM_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<(item|entry)\b[^>]*>(.*?)</(item|entry)>")
+        .expect("Failed to compile feed item regex")
+});
+static TITLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<title[^>]*>(.*?)</title>").expect("Failed to compile feed title regex")
+});
+static LINK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)<link[^>]*?(?:href="([^"]+)"[^>]*/?>|>(.*?)</link>)"#)
+        .expect("Failed to compile feed link regex")
+});
+static DATE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<(pubDate|published|updated)[^>]*>(.*?)</(pubDate|published|updated)>")
+        .expect("Failed to compile feed date regex")
+});
+
+fn strip_cdata(text: &str) -> &str {
+    text.trim()
+        .strip_prefix("<![CDATA[")
+        .and_then(|t| t.strip_suffix("]]>"))
+        .unwrap_or(text.trim())
+}
+
+/// Renders the latest items of an RSS or Atom feed as a list of links
+pub struct RssSource {
+    url: String,
+    limit: usize,
+}
+
+impl RssSource {
+    pub fn from_config(
+        config: &Value,
+    ) -> Result<Box<dyn ContentSource>, Box<dyn std::error::Error>> {
+        Ok(Box::new(RssSource {
+            url: config_str(config, "url")?,
+            limit: config
+                .get("limit")
+                .and_then(Value::as_u64)
+                .map(|l| l as usize)
+                .unwrap_or(DEFAULT_ITEM_LIMIT),
+        }))
+    }
+}
+
+impl ContentSource for RssSource {
+    fn name(&self) -> &str {
+        "rss"
+    }
+
+    fn fetch(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let (body, _) = fetch_text(&self.url)?;
+
+        let feed_title = ITEM_REGEX
+            .splitn(&body, 2)
+            .next()
+            .and_then(|head| TITLE_REGEX.captures(head))
+            .map(|caps| decode_entities(strip_cdata(&caps[1])));
+
+        let items: Vec<String> = ITEM_REGEX
+            .captures_iter(&body)
+            .take(self.limit)
+            .map(|item| {
+                let item = &item[2];
+                let title = TITLE_REGEX
+                    .captures(item)
+                    .map(|c| decode_entities(strip_cdata(&c[1])))
+                    .unwrap_or_else(|| "Untitled".to_string());
+                let link = LINK_REGEX.captures(item).and_then(|c| {
+                    c.get(1)
+                        .or_else(|| c.get(2))
+                        .map(|m| decode_entities(strip_cdata(m.as_str())))
+                });
+                let date = DATE_REGEX
+                    .captures(item)
+                    .map(|c| format!(" — {}", strip_cdata(&c[2])))
+                    .unwrap_or_default();
+
+                match link {
+                    Some(link) => format!("- [{}]({}){}", title.trim(), link.trim(), date),
+                    None => format!("- {}{}", title.trim(), date),
+                }
+            })
+            .collect();
+
+        if items.is_empty() {
+            return Err(format!("No items found in feed {}", self.url).into());
+        }
+
+        let mut out = String::new();
+        if let Some(title) = feed_title {
+            out.push_str(&format!("# {}\n\n", title.trim()));
+        }
+        out.push_str(&items.join("\n"));
+        out.push('\n');
+        Ok(out)
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::Read;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use regex::Regex;
+use serde_json::Value;
+
+use super::{config_str, ContentSource};
+
+pub(super) const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+pub(super) const MAX_RESPONSE_BYTES: u64 = 2 * 1024 * 1024;
+
+static SCRIPT_STYLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<(script|style)[^>]*>.*?</(script|style)>")
+        .expect("Failed to compile script regex")
+});
+static TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<[^>]+>").expect("Failed to compile HTML tag regex"));
+static BLANK_LINES_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\n\s*\n(\s*\n)+").expect("Failed to compile blank line regex"));
+
+/// GET a URL and return the body with its content type, capped at `MAX_RESPONSE_BYTES`
+pub(crate) fn fetch_text(url: &str) -> Result<(String, String), Box<dyn std::error::Error>> {
+    let response = crate::net::agent_builder()
+        .timeout(FETCH_TIMEOUT)
+        .build()
+        .get(url)
+        .call()?;
+    let content_type = response.content_type().to_lowercase();
+
+    let mut body = String::new();
+    response
+        .into_reader()
+        .take(MAX_RESPONSE_BYTES)
+        .read_to_string(&mut body)?;
+
+    Ok((body, content_type))
+}
+
+/// Decode the handful of entities that show up in titles and feed text
+pub(crate) fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+/// Fetches a page. Markdown and plain text are used as-is; HTML is reduced to its text
+pub struct UrlSource {
+    url: String,
+}
+
+impl UrlSource {
+    pub fn from_config(
+        config: &Value,
+    ) -> Result<Box<dyn ContentSource>, Box<dyn std::error::Error>> {
+        Ok(Box::new(UrlSource {
+            url: config_str(config, "url")?,
+        }))
+    }
+}
+
+impl ContentSource for UrlSource {
+    fn name(&self) -> &str {
+        "url"
+    }
+
+    fn fetch(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let (body, content_type) = fetch_text(&self.url)?;
+        if !content_type.contains("html") {
+            return Ok(body);
+        }
+
+        let text = SCRIPT_STYLE_REGEX.replace_all(&body, "");
+        let text = TAG_REGEX.replace_all(&text, "\n");
+        let text = decode_entities(&text);
+        let text = BLANK_LINES_REGEX.replace_all(text.trim(), "\n\n");
+        Ok(format!("{}\n", text))
+    }
+}
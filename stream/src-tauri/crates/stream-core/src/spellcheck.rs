@@ -0,0 +1,616 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::lock::is_locked_path;
+
+const PERSONAL_DICTIONARY_FILE: &str = "personal.dic";
+const DICTIONARIES_DIR: &str = "dictionaries";
+
+/// Where the OS and other apps install hunspell dictionaries, searched after the app's own
+/// `dictionaries` folder
+const SYSTEM_DICTIONARY_DIRS: &[&str] = &[
+    "/usr/share/hunspell",
+    "/usr/share/myspell",
+    "/usr/share/myspell/dicts",
+    "/Library/Spelling",
+];
+
+// Runs of letters, with apostrophes allowed inside as in "don't"
+static WORD_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[\p{L}\p{M}]+(?:['’][\p{L}\p{M}]+)*").expect("Failed to compile word regex")
+});
+
+// Inline code, URLs, link targets, tags and email addresses aren't prose
+static SKIP_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"`[^`]*`|\b\w+://\S+|\]\([^)]*\)|(?:^|\s)#[\w/-]+|\S+@\S+\.\w+")
+        .expect("Failed to compile spellcheck skip regex")
+});
+
+/// A misspelled word, located like a search match: a line and UTF-16 offsets within it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Misspelling {
+    pub word: String,
+    pub line_number: usize,
+    pub range: (usize, usize),
+}
+
+#[derive(Clone, Copy)]
+enum FlagMode {
+    /// One character per flag, the default
+    Char,
+    /// Two characters per flag
+    Long,
+    /// Comma-separated numbers
+    Num,
+}
+
+fn parse_flags(mode: FlagMode, flags: &str) -> Vec<u32> {
+    match mode {
+        FlagMode::Char => flags.chars().map(|c| c as u32).collect(),
+        FlagMode::Long => flags
+            .chars()
+            .collect::<Vec<_>>()
+            .chunks(2)
+            .map(|pair| pair.iter().fold(0, |flag, &c| (flag << 16) | c as u32))
+            .collect(),
+        FlagMode::Num => flags
+            .split(',')
+            .filter_map(|flag| flag.trim().parse().ok())
+            .collect(),
+    }
+}
+
+enum ConditionPart {
+    Any,
+    /// Characters in brackets, or a single literal character; `true` when negated
+    Set(Vec<char>, bool),
+}
+
+impl ConditionPart {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            ConditionPart::Any => true,
+            ConditionPart::Set(chars, negated) => chars.contains(&c) != *negated,
+        }
+    }
+}
+
+fn parse_condition(condition: &str) -> Vec<ConditionPart> {
+    let mut parts = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => parts.push(ConditionPart::Any),
+            '[' => {
+                let mut set: Vec<char> = chars.by_ref().take_while(|&c| c != ']').collect();
+                let negated = set.first() == Some(&'^');
+                if negated {
+                    set.remove(0);
+                }
+                parts.push(ConditionPart::Set(set, negated));
+            }
+            c => parts.push(ConditionPart::Set(vec![c], false)),
+        }
+    }
+    parts
+}
+
+/// One prefix or suffix rule from an `.aff` file
+struct Affix {
+    flag: u32,
+    strip: String,
+    add: String,
+    condition: Vec<ConditionPart>,
+    cross_product: bool,
+}
+
+impl Affix {
+    /// The stem `word` would come from by this suffix rule, if it fits
+    fn strip_suffix(&self, word: &str) -> Option<String> {
+        let rest = word.strip_suffix(self.add.as_str())?;
+        if rest.is_empty() {
+            return None;
+        }
+        let stem = format!("{}{}", rest, self.strip);
+        let tail: Vec<char> = stem.chars().rev().take(self.condition.len()).collect();
+        (tail.len() == self.condition.len()
+            && self
+                .condition
+                .iter()
+                .rev()
+                .zip(&tail)
+                .all(|(part, &c)| part.matches(c)))
+        .then_some(stem)
+    }
+
+    /// The stem `word` would come from by this prefix rule, if it fits
+    fn strip_prefix(&self, word: &str) -> Option<String> {
+        let rest = word.strip_prefix(self.add.as_str())?;
+        if rest.is_empty() {
+            return None;
+        }
+        let stem = format!("{}{}", self.strip, rest);
+        let head: Vec<char> = stem.chars().take(self.condition.len()).collect();
+        (head.len() == self.condition.len()
+            && self
+                .condition
+                .iter()
+                .zip(&head)
+                .all(|(part, &c)| part.matches(c)))
+        .then_some(stem)
+    }
+}
+
+/// A hunspell dictionary: stems with their affix flags and the prefix and suffix rules.
+/// Compounding, suggestions and conversion tables aren't supported
+#[derive(Default)]
+pub struct Dictionary {
+    words: HashMap<String, Vec<u32>>,
+    prefixes: Vec<Affix>,
+    suffixes: Vec<Affix>,
+    need_affix: Option<u32>,
+    forbidden: Option<u32>,
+    only_in_compound: Option<u32>,
+}
+
+/// Decode a dictionary file in the encoding its `.aff` names with `SET`. Anything other
+/// than UTF-8 is read as Latin-1, which covers the common ISO-8859 dictionaries closely
+fn decode(bytes: &[u8], utf8: bool) -> String {
+    if utf8 {
+        String::from_utf8_lossy(bytes).into_owned()
+    } else {
+        bytes.iter().map(|&b| b as char).collect()
+    }
+}
+
+fn is_utf8_affix_file(bytes: &[u8]) -> bool {
+    String::from_utf8_lossy(bytes).lines().any(|line| {
+        let mut tokens = line.split_whitespace();
+        tokens.next() == Some("SET")
+            && tokens
+                .next()
+                .is_some_and(|set| set.eq_ignore_ascii_case("UTF-8"))
+    })
+}
+
+impl Dictionary {
+    pub fn load(aff_path: &Path, dic_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let aff = fs::read(aff_path)?;
+        let dic = fs::read(dic_path)?;
+        let utf8 = is_utf8_affix_file(&aff);
+        Ok(Self::parse(&decode(&aff, utf8), &decode(&dic, utf8)))
+    }
+
+    pub fn parse(aff: &str, dic: &str) -> Self {
+        let lines: Vec<Vec<&str>> = aff
+            .lines()
+            .map(|line| line.split_whitespace().collect::<Vec<_>>())
+            .filter(|tokens| !tokens.is_empty() && !tokens[0].starts_with('#'))
+            .collect();
+
+        let mode = lines
+            .iter()
+            .find(|tokens| tokens[0] == "FLAG" && tokens.len() > 1)
+            .map(|tokens| match tokens[1] {
+                "long" => FlagMode::Long,
+                "num" => FlagMode::Num,
+                _ => FlagMode::Char,
+            })
+            .unwrap_or(FlagMode::Char);
+        let single_flag = |flags: &str| parse_flags(mode, flags).first().copied();
+
+        let mut dictionary = Dictionary::default();
+        // Rules still expected after each `PFX`/`SFX` header, keyed by kind and flag
+        let mut remaining: HashMap<(&str, &str), (usize, bool)> = HashMap::new();
+        for tokens in &lines {
+            match (tokens[0], tokens.len()) {
+                ("NEEDAFFIX", 2..) => dictionary.need_affix = single_flag(tokens[1]),
+                ("FORBIDDENWORD", 2..) => dictionary.forbidden = single_flag(tokens[1]),
+                ("ONLYINCOMPOUND", 2..) => dictionary.only_in_compound = single_flag(tokens[1]),
+                (kind @ ("PFX" | "SFX"), 4..) => {
+                    let key = (kind, tokens[1]);
+                    match remaining.get_mut(&key) {
+                        Some((count, cross_product)) if *count > 0 => {
+                            *count -= 1;
+                            let Some(flag) = single_flag(tokens[1]) else {
+                                continue;
+                            };
+                            let empty_if_zero = |s: &str| {
+                                if s == "0" {
+                                    String::new()
+                                } else {
+                                    s.to_string()
+                                }
+                            };
+                            // Continuation flags after `/` aren't supported
+                            let add = tokens[3].split('/').next().unwrap_or("");
+                            let affix = Affix {
+                                flag,
+                                strip: empty_if_zero(tokens[2]),
+                                add: empty_if_zero(add),
+                                condition: parse_condition(tokens.get(4).unwrap_or(&".")),
+                                cross_product: *cross_product,
+                            };
+                            if kind == "PFX" {
+                                dictionary.prefixes.push(affix);
+                            } else {
+                                dictionary.suffixes.push(affix);
+                            }
+                        }
+                        _ => {
+                            let count = tokens[3].parse().unwrap_or(0);
+                            remaining.insert(key, (count, tokens[2] == "Y"));
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // The first line is a word count
+        for line in dic.lines().skip(1) {
+            let entry = match line.split_whitespace().next() {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let (word, flags) = match entry.split_once('/') {
+                Some((word, flags)) => (word, parse_flags(mode, flags)),
+                None => (entry, Vec::new()),
+            };
+            dictionary
+                .words
+                .entry(word.to_string())
+                .or_default()
+                .extend(flags);
+        }
+
+        dictionary
+    }
+
+    /// Whether `stem` is in the dictionary, standing alone or carrying `flag`
+    fn has_stem(&self, stem: &str, flags: &[u32]) -> bool {
+        let stem_flags = match self.words.get(stem) {
+            Some(stem_flags) => stem_flags,
+            None => return false,
+        };
+        let has = |flag: Option<u32>| flag.is_some_and(|flag| stem_flags.contains(&flag));
+        if has(self.forbidden) {
+            return false;
+        }
+        if flags.is_empty() {
+            !has(self.need_affix) && !has(self.only_in_compound)
+        } else {
+            flags.iter().all(|flag| stem_flags.contains(flag))
+        }
+    }
+
+    fn check_form(&self, word: &str) -> bool {
+        if self.has_stem(word, &[]) {
+            return true;
+        }
+        for suffix in &self.suffixes {
+            let Some(stem) = suffix.strip_suffix(word) else {
+                continue;
+            };
+            if self.has_stem(&stem, &[suffix.flag]) {
+                return true;
+            }
+            if !suffix.cross_product {
+                continue;
+            }
+            for prefix in self.prefixes.iter().filter(|p| p.cross_product) {
+                if let Some(root) = prefix.strip_prefix(&stem) {
+                    if self.has_stem(&root, &[prefix.flag, suffix.flag]) {
+                        return true;
+                    }
+                }
+            }
+        }
+        self.prefixes.iter().any(|prefix| {
+            prefix
+                .strip_prefix(word)
+                .is_some_and(|stem| self.has_stem(&stem, &[prefix.flag]))
+        })
+    }
+
+    /// Whether `word` is spelled correctly. Capitalised and all-caps words also match their
+    /// lowercase and title-case forms
+    pub fn check(&self, word: &str) -> bool {
+        let word = word.replace('’', "'");
+        if self.check_form(&word) {
+            return true;
+        }
+        let lower = word.to_lowercase();
+        let mut chars = lower.chars();
+        let title: String = chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default();
+        (lower != word && self.check_form(&lower)) || (title != word && self.check_form(&title))
+    }
+}
+
+/// Loaded dictionaries and the personal word list, kept under `dir`
+pub struct Spellchecker {
+    dir: PathBuf,
+    dictionaries: Mutex<HashMap<String, Arc<Dictionary>>>,
+    personal: Mutex<HashSet<String>>,
+}
+
+impl Spellchecker {
+    pub fn load(dir: &Path) -> Self {
+        let personal = fs::read_to_string(dir.join(PERSONAL_DICTIONARY_FILE))
+            .map(|content| {
+                content
+                    .lines()
+                    .map(str::trim)
+                    .filter(|word| !word.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        Spellchecker {
+            dir: dir.to_path_buf(),
+            dictionaries: Mutex::new(HashMap::new()),
+            personal: Mutex::new(personal),
+        }
+    }
+
+    fn dictionary_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = vec![self.dir.join(DICTIONARIES_DIR)];
+        if let Some(home) = std::env::var_os("HOME") {
+            dirs.push(Path::new(&home).join("Library/Spelling"));
+        }
+        dirs.extend(SYSTEM_DICTIONARY_DIRS.iter().map(PathBuf::from));
+        dirs
+    }
+
+    /// The dictionary for `language`, e.g. `en_US` or `en-US`, loaded on first use
+    fn dictionary(&self, language: &str) -> Result<Arc<Dictionary>, Box<dyn std::error::Error>> {
+        let language = language.trim().replace('-', "_");
+        if language.is_empty() || language.contains(['/', '\\', '.']) {
+            return Err(format!("Invalid language: {}", language).into());
+        }
+        if let Some(dictionary) = self
+            .dictionaries
+            .lock()
+            .map_err(|_| "Dictionary cache lock poisoned")?
+            .get(&language)
+        {
+            return Ok(dictionary.clone());
+        }
+
+        let dirs = self.dictionary_dirs();
+        let (aff_path, dic_path) = dirs
+            .iter()
+            .map(|dir| {
+                (
+                    dir.join(format!("{}.aff", language)),
+                    dir.join(format!("{}.dic", language)),
+                )
+            })
+            .find(|(aff, dic)| aff.is_file() && dic.is_file())
+            .ok_or_else(|| {
+                format!(
+                    "No hunspell dictionary for {}; add {}.aff and {}.dic to {}",
+                    language,
+                    language,
+                    language,
+                    dirs[0].display()
+                )
+            })?;
+        let dictionary = Arc::new(Dictionary::load(&aff_path, &dic_path)?);
+        self.dictionaries
+            .lock()
+            .map_err(|_| "Dictionary cache lock poisoned")?
+            .insert(language, dictionary.clone());
+        Ok(dictionary)
+    }
+
+    /// Accept `word` everywhere from now on
+    pub fn add_word(&self, word: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let word = word.trim();
+        if word.is_empty() || word.contains(char::is_whitespace) {
+            return Err(format!("Not a single word: '{}'", word).into());
+        }
+
+        let mut personal = self
+            .personal
+            .lock()
+            .map_err(|_| "Personal dictionary lock poisoned")?;
+        if !personal.insert(word.to_string()) {
+            return Ok(());
+        }
+        let mut words: Vec<&String> = personal.iter().collect();
+        words.sort();
+        let content: String = words.iter().map(|word| format!("{}\n", word)).collect();
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join(PERSONAL_DICTIONARY_FILE), content)?;
+        Ok(())
+    }
+
+    /// Misspelled words in markdown `content`. Fenced code blocks, inline code, URLs,
+    /// tags and words run together with digits are skipped
+    pub fn check_content(
+        &self,
+        content: &str,
+        language: &str,
+    ) -> Result<Vec<Misspelling>, Box<dyn std::error::Error>> {
+        let dictionary = self.dictionary(language)?;
+        let personal = self
+            .personal
+            .lock()
+            .map_err(|_| "Personal dictionary lock poisoned")?;
+        let is_known = |word: &str| {
+            personal.contains(word)
+                || personal.contains(&word.to_lowercase())
+                || dictionary.check(word)
+        };
+
+        let mut misspellings = Vec::new();
+        let mut in_code_block = false;
+        for (index, line) in content.lines().enumerate() {
+            if line.trim_start().starts_with("```") {
+                in_code_block = !in_code_block;
+                continue;
+            }
+            if in_code_block {
+                continue;
+            }
+
+            let skipped: Vec<(usize, usize)> = SKIP_REGEX
+                .find_iter(line)
+                .map(|m| (m.start(), m.end()))
+                .collect();
+            for m in WORD_REGEX.find_iter(line) {
+                let word = m.as_str();
+                let touches_digit = line[..m.start()]
+                    .chars()
+                    .next_back()
+                    .into_iter()
+                    .chain(line[m.end()..].chars().next())
+                    .any(|c| c.is_ascii_digit() || c == '_');
+                if word.chars().count() < 2
+                    || touches_digit
+                    || skipped
+                        .iter()
+                        .any(|&(start, end)| m.start() < end && start < m.end())
+                    || is_known(word)
+                {
+                    continue;
+                }
+
+                let utf16_offset = |byte_idx: usize| line[..byte_idx].encode_utf16().count();
+                misspellings.push(Misspelling {
+                    word: word.to_string(),
+                    line_number: index + 1,
+                    range: (utf16_offset(m.start()), utf16_offset(m.end())),
+                });
+            }
+        }
+        Ok(misspellings)
+    }
+
+    pub fn check_file(
+        &self,
+        file_path: &Path,
+        language: &str,
+    ) -> Result<Vec<Misspelling>, Box<dyn std::error::Error>> {
+        if is_locked_path(file_path) {
+            return Err("Note is locked; unlock it to spellcheck it".into());
+        }
+        self.check_content(&fs::read_to_string(file_path)?, language)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "SET UTF-8
+NEEDAFFIX X
+FORBIDDENWORD !
+
+PFX U Y 1
+PFX U 0 un .
+
+SFX S Y 2
+SFX S y ies [^aeiou]y
+SFX S 0 s [^y]
+
+SFX D N 1
+SFX D 0 ed [^e]
+";
+
+    const DIC: &str = "6
+cat/S
+fly/S
+lock/UDS
+kangaroo/XS
+teh/!
+the
+";
+
+    fn dictionary() -> Dictionary {
+        Dictionary::parse(AFF, DIC)
+    }
+
+    #[test]
+    fn suffixes_apply_only_when_their_condition_matches() {
+        let dictionary = dictionary();
+        assert!(dictionary.check("cats"));
+        assert!(dictionary.check("flies"));
+        assert!(dictionary.check("locked"));
+        // "fly" ends in y, so only the "ies" rule fits
+        assert!(!dictionary.check("flys"));
+        assert!(!dictionary.check("flyies"));
+        // `cat` doesn't carry the D flag
+        assert!(!dictionary.check("cated"));
+    }
+
+    #[test]
+    fn prefixes_apply() {
+        let dictionary = dictionary();
+        assert!(dictionary.check("unlock"));
+        assert!(!dictionary.check("uncat"));
+    }
+
+    #[test]
+    fn cross_product_combines_prefix_and_suffix() {
+        let dictionary = dictionary();
+        assert!(dictionary.check("unlocks"));
+        // D isn't marked for cross product
+        assert!(!dictionary.check("unlocked"));
+    }
+
+    #[test]
+    fn need_affix_stems_only_match_with_an_affix() {
+        let dictionary = dictionary();
+        assert!(!dictionary.check("kangaroo"));
+        assert!(dictionary.check("kangaroos"));
+    }
+
+    #[test]
+    fn forbidden_words_are_rejected() {
+        let dictionary = dictionary();
+        assert!(!dictionary.check("teh"));
+        assert!(dictionary.check("the"));
+    }
+
+    #[test]
+    fn capitalised_words_match_their_lowercase_form() {
+        let dictionary = dictionary();
+        assert!(dictionary.check("Cats"));
+        assert!(dictionary.check("UNLOCK"));
+        assert!(!dictionary.check("Teh"));
+    }
+
+    #[test]
+    fn malformed_affix_lines_are_skipped() {
+        let aff = "FLAG
+NEEDAFFIX
+PFX
+SFX S Y
+SFX Z Y many
+SFX Z 0 s [abc
+SFX S Y 1
+SFX S 0 s .
+PFX U Y 1
+PFX U 0
+";
+        let dictionary = Dictionary::parse(aff, "2\ncat/SZ\nlock/U\n");
+        assert!(dictionary.check("cat"));
+        assert!(dictionary.check("cats"));
+        assert!(!dictionary.check("unlock"));
+
+        let long = Dictionary::parse("FLAG long\nSFX Aa Y 1\nSFX Aa 0 s .\n", "1\ncat/AaB\n");
+        assert!(long.check("cats"));
+        let num = Dictionary::parse("FLAG num\nSFX 1 Y 1\nSFX 1 0 s .\n", "1\ncat/1,x,\n");
+        assert!(num.check("cats"));
+    }
+}
@@ -0,0 +1,499 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use chrono::{Datelike, NaiveDate};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::capture::ASSETS_DIR;
+use crate::cloud::is_placeholder;
+use crate::git::RepoCommits;
+use crate::lock::{is_locked_path, unlocked_path};
+use crate::markdown::{daily_notes_in_range, extract_tags};
+use crate::search::term_counts;
+use crate::tasks::tasks;
+use crate::walk::walk_files;
+
+/// Average silent reading speed for prose
+const WORDS_PER_MINUTE: f64 = 238.0;
+const MAX_TOP_TERMS: usize = 10;
+const MAX_TOP_TAGS: usize = 10;
+
+// Markdown links and images, keeping only their text
+static LINK_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").expect("Failed to compile link regex"));
+
+// Heading, quote, list and checkbox markers at the start of a line
+static LINE_MARKER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?:#{1,6}\s+|>\s*|[-*+]\s+(?:\[[ xX]\]\s+)?|\d+[.)]\s+)")
+        .expect("Failed to compile line marker regex")
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TermCount {
+    pub term: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NoteStats {
+    pub word_count: usize,
+    pub sentence_count: usize,
+    pub syllable_count: usize,
+    pub reading_time_minutes: f64,
+    pub average_sentence_length: f64,
+    pub flesch_reading_ease: f64,
+    pub flesch_kincaid_grade: f64,
+    pub top_terms: Vec<TermCount>,
+}
+
+/// Words written and tasks in a note, cheap enough to recompute on every save
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NoteProgress {
+    pub word_count: usize,
+    /// Unchecked tasks, without their `- [ ]` marker
+    pub pending_tasks: Vec<String>,
+    pub completed_tasks: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DailyNoteStats {
+    pub date: String,
+    pub file_path: String,
+    pub stats: NoteStats,
+}
+
+/// Per-note stats for a date range plus the same figures over all of it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RangeStats {
+    pub notes: Vec<DailyNoteStats>,
+    pub total: NoteStats,
+}
+
+/// Inclusive range of `YYYY-MM-DD` dates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DateRange {
+    pub start: String,
+    pub end: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagCount {
+    pub tag: String,
+    /// Notes using the tag
+    pub count: usize,
+}
+
+/// Commit and writing activity over one date range
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeriodSummary {
+    pub range: DateRange,
+    pub commits: usize,
+    pub notes: usize,
+    pub words: usize,
+    /// Repositories with at least one commit in the range
+    pub active_repos: Vec<String>,
+    /// Most used first
+    pub top_tags: Vec<TagCount>,
+}
+
+/// `a` minus `b` for each count
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PeriodDifference {
+    pub commits: i64,
+    pub notes: i64,
+    pub words: i64,
+    pub active_repos: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RangeComparison {
+    pub a: PeriodSummary,
+    pub b: PeriodSummary,
+    pub difference: PeriodDifference,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct YearCount {
+    pub year: i32,
+    pub notes: usize,
+}
+
+/// Figures for a whole notes directory, from one walk over it
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct VaultStats {
+    /// Every note, dated or not, locked ones included
+    pub total_notes: usize,
+    pub daily_notes: usize,
+    /// Words in notes that could be read; locked and online-only notes aren't counted
+    pub total_words: usize,
+    pub size_bytes: u64,
+    /// Files that aren't notes, including captured assets
+    pub attachment_count: usize,
+    pub earliest_entry: Option<String>,
+    pub latest_entry: Option<String>,
+    /// Daily notes per year, oldest first
+    pub notes_per_year: Vec<YearCount>,
+    pub skipped_placeholders: usize,
+}
+
+/// Raw counts that scores are derived from, kept so ranges can be summed before scoring
+#[derive(Default)]
+struct Counts {
+    words: usize,
+    sentences: usize,
+    syllables: usize,
+    terms: HashMap<String, f32>,
+}
+
+/// Estimated English syllables: groups of vowels, less a silent trailing `e`, at least one
+fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let is_vowel = |c: char| matches!(c, 'a' | 'e' | 'i' | 'o' | 'u' | 'y');
+
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = is_vowel(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    if word.ends_with('e') && !word.ends_with("le") && count > 1 {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+/// Prose lines of a note with markdown syntax removed, skipping fenced code blocks
+fn prose_lines(content: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut in_code_block = false;
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let line = LINE_MARKER_REGEX.replace(line, "");
+        let line = LINK_REGEX.replace_all(&line, "$1");
+        if !line.trim().is_empty() {
+            lines.push(line.into_owned());
+        }
+    }
+    lines
+}
+
+fn count(content: &str) -> Counts {
+    let lines = prose_lines(content);
+    let mut counts = Counts {
+        terms: term_counts(&lines.join("\n")),
+        ..Counts::default()
+    };
+
+    // Lines are segmented separately so list items and headings without a full stop still
+    // end their sentence
+    for line in &lines {
+        for sentence in line.unicode_sentences() {
+            let words: Vec<&str> = sentence.unicode_words().collect();
+            if words.is_empty() {
+                continue;
+            }
+            counts.sentences += 1;
+            counts.words += words.len();
+            counts.syllables += words.iter().map(|w| syllables(w)).sum::<usize>();
+        }
+    }
+    counts
+}
+
+impl Counts {
+    fn add(&mut self, other: Counts) {
+        self.words += other.words;
+        self.sentences += other.sentences;
+        self.syllables += other.syllables;
+        for (term, count) in other.terms {
+            *self.terms.entry(term).or_insert(0.0) += count;
+        }
+    }
+
+    fn stats(&self) -> NoteStats {
+        let (words, sentences) = (self.words as f64, self.sentences.max(1) as f64);
+        let words_per_sentence = words / sentences;
+        let syllables_per_word = if self.words == 0 {
+            0.0
+        } else {
+            self.syllables as f64 / words
+        };
+        let (reading_ease, grade) = if self.words == 0 {
+            (0.0, 0.0)
+        } else {
+            (
+                206.835 - 1.015 * words_per_sentence - 84.6 * syllables_per_word,
+                0.39 * words_per_sentence + 11.8 * syllables_per_word - 15.59,
+            )
+        };
+
+        let mut top_terms: Vec<TermCount> = self
+            .terms
+            .iter()
+            .map(|(term, count)| TermCount {
+                term: term.clone(),
+                count: *count as usize,
+            })
+            .collect();
+        top_terms.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(&b.term)));
+        top_terms.truncate(MAX_TOP_TERMS);
+
+        NoteStats {
+            word_count: self.words,
+            sentence_count: self.sentences,
+            syllable_count: self.syllables,
+            reading_time_minutes: words / WORDS_PER_MINUTE,
+            average_sentence_length: if self.sentences == 0 {
+                0.0
+            } else {
+                words_per_sentence
+            },
+            flesch_reading_ease: reading_ease,
+            flesch_kincaid_grade: grade,
+            top_terms,
+        }
+    }
+}
+
+/// Word, sentence and readability figures for a piece of markdown
+pub fn compute_note_stats(content: &str) -> NoteStats {
+    count(content).stats()
+}
+
+pub fn get_note_stats(file_path: &Path) -> Result<NoteStats, Box<dyn std::error::Error>> {
+    if is_locked_path(file_path) {
+        return Err("Note is locked; unlock it to read its stats".into());
+    }
+    Ok(compute_note_stats(&fs::read_to_string(file_path)?))
+}
+
+/// Stats for each daily note in `start..=end` and for the range as a whole. Online-only
+/// files are skipped rather than downloaded
+pub fn get_range_stats(
+    directory_path: &Path,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<RangeStats, Box<dyn std::error::Error>> {
+    let mut total = Counts::default();
+    let mut notes = Vec::new();
+
+    for (date, path) in daily_notes_in_range(directory_path, start, end)? {
+        if is_placeholder(&path) {
+            continue;
+        }
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        let counts = count(&content);
+        notes.push(DailyNoteStats {
+            date: date.format("%Y-%m-%d").to_string(),
+            file_path: path.to_string_lossy().to_string(),
+            stats: counts.stats(),
+        });
+        total.add(counts);
+    }
+
+    Ok(RangeStats {
+        notes,
+        total: total.stats(),
+    })
+}
+
+/// What one file adds to the vault's stats
+enum VaultFile {
+    Note {
+        date: Option<NaiveDate>,
+        /// None when the note is locked, online-only or unreadable
+        words: Option<usize>,
+        placeholder: bool,
+        size: u64,
+    },
+    Attachment {
+        size: u64,
+    },
+}
+
+fn word_count(content: &str) -> usize {
+    prose_lines(content)
+        .iter()
+        .map(|line| line.unicode_words().count())
+        .sum()
+}
+
+pub fn note_progress(content: &str) -> NoteProgress {
+    let mut progress = NoteProgress {
+        word_count: word_count(content),
+        ..NoteProgress::default()
+    };
+    for (_, task) in tasks(content) {
+        if task.done {
+            progress.completed_tasks += 1;
+        } else {
+            progress.pending_tasks.push(task.text.to_string());
+        }
+    }
+    progress
+}
+
+fn vault_file(path: &Path, size: u64) -> Option<VaultFile> {
+    let name = path.file_name()?.to_str()?;
+    let is_locked = is_locked_path(path);
+    let note_path = unlocked_path(path);
+    if note_path.extension().and_then(|ext| ext.to_str()) != Some("md") {
+        // Hidden files are app or OS clutter such as `.DS_Store`
+        return (!name.starts_with('.')).then_some(VaultFile::Attachment { size });
+    }
+
+    let date = note_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| NaiveDate::parse_from_str(stem, "%Y-%m-%d").ok());
+    let placeholder = is_placeholder(path);
+    let words = if is_locked || placeholder {
+        None
+    } else {
+        fs::read_to_string(path)
+            .ok()
+            .map(|content| word_count(&content))
+    };
+    Some(VaultFile::Note {
+        date,
+        words,
+        placeholder,
+        size,
+    })
+}
+
+/// Note, word, size and attachment counts for the whole notes directory, with the first and
+/// last daily entry and daily notes per year. Notes are read in parallel during a single
+/// walk; online-only ones are counted without being downloaded
+pub fn get_vault_stats(directory_path: &Path) -> Result<VaultStats, Box<dyn std::error::Error>> {
+    if !directory_path.is_dir() {
+        return Err(format!("Not a directory: {}", directory_path.display()).into());
+    }
+
+    let visit = |path: &Path, metadata: &fs::Metadata| vault_file(path, metadata.len());
+    let mut files = walk_files(directory_path, visit);
+    // The walk leaves out captured assets, which are attachments like any other
+    files.extend(walk_files(&directory_path.join(ASSETS_DIR), visit));
+
+    let mut stats = VaultStats::default();
+    let mut per_year: BTreeMap<i32, usize> = BTreeMap::new();
+    let (mut earliest, mut latest): (Option<NaiveDate>, Option<NaiveDate>) = (None, None);
+    for file in files {
+        match file {
+            VaultFile::Note {
+                date,
+                words,
+                placeholder,
+                size,
+            } => {
+                stats.total_notes += 1;
+                stats.size_bytes += size;
+                stats.total_words += words.unwrap_or(0);
+                if placeholder {
+                    stats.skipped_placeholders += 1;
+                }
+                if let Some(date) = date {
+                    stats.daily_notes += 1;
+                    *per_year.entry(date.year()).or_insert(0) += 1;
+                    earliest = Some(earliest.map_or(date, |e| e.min(date)));
+                    latest = Some(latest.map_or(date, |l| l.max(date)));
+                }
+            }
+            VaultFile::Attachment { size } => {
+                stats.attachment_count += 1;
+                stats.size_bytes += size;
+            }
+        }
+    }
+
+    stats.earliest_entry = earliest.map(|date| date.to_string());
+    stats.latest_entry = latest.map(|date| date.to_string());
+    stats.notes_per_year = per_year
+        .into_iter()
+        .map(|(year, notes)| YearCount { year, notes })
+        .collect();
+    Ok(stats)
+}
+
+/// Summarise the daily notes dated within `start..=end` and `repos`, the commits made in it
+pub fn summarize_period(
+    directory_path: &Path,
+    start: NaiveDate,
+    end: NaiveDate,
+    repos: &[RepoCommits],
+) -> Result<PeriodSummary, Box<dyn std::error::Error>> {
+    let mut notes = 0;
+    let mut words = 0;
+    let mut tag_counts: HashMap<String, usize> = HashMap::new();
+
+    for (_, path) in daily_notes_in_range(directory_path, start, end)? {
+        if is_placeholder(&path) || is_locked_path(&path) {
+            continue;
+        }
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(_) => continue,
+        };
+
+        notes += 1;
+        words += count(&content).words;
+        let mut tags = extract_tags(&content);
+        tags.sort();
+        tags.dedup();
+        for tag in tags {
+            *tag_counts.entry(tag).or_insert(0) += 1;
+        }
+    }
+
+    let mut top_tags: Vec<TagCount> = tag_counts
+        .into_iter()
+        .map(|(tag, count)| TagCount { tag, count })
+        .collect();
+    top_tags.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    top_tags.truncate(MAX_TOP_TAGS);
+
+    Ok(PeriodSummary {
+        range: DateRange {
+            start: start.format("%Y-%m-%d").to_string(),
+            end: end.format("%Y-%m-%d").to_string(),
+        },
+        commits: repos.iter().map(|repo| repo.commits.len()).sum(),
+        notes,
+        words,
+        active_repos: repos
+            .iter()
+            .filter(|repo| !repo.commits.is_empty())
+            .map(|repo| repo.repo_path.clone())
+            .collect(),
+        top_tags,
+    })
+}
+
+/// Put two periods side by side, e.g. this week and last week
+pub fn compare_periods(a: PeriodSummary, b: PeriodSummary) -> RangeComparison {
+    let difference = PeriodDifference {
+        commits: a.commits as i64 - b.commits as i64,
+        notes: a.notes as i64 - b.notes as i64,
+        words: a.words as i64 - b.words as i64,
+        active_repos: a.active_repos.len() as i64 - b.active_repos.len() as i64,
+    };
+    RangeComparison { a, b, difference }
+}
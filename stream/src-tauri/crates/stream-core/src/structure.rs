@@ -0,0 +1,314 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::lock::is_locked_path;
+
+// `> [!kind]`, an optional `+`/`-` fold marker, then an optional title
+pub(crate) static CALLOUT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*>\s*\[!([\w-]+)\]([+-])?\s*(.*)$").expect("Failed to compile callout regex")
+});
+
+pub(crate) static FOOTNOTE_DEFINITION_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\[\^([^\]\s]+)\]:\s?(.*)$").expect("Failed to compile footnote regex")
+});
+
+pub(crate) static FOOTNOTE_REFERENCE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\[\^([^\]\s]+)\]").expect("Failed to compile footnote reference regex")
+});
+
+pub(crate) static TABLE_DELIMITER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*\|?\s*:?-+:?\s*(\|\s*:?-+:?\s*)*\|?\s*$")
+        .expect("Failed to compile table delimiter regex")
+});
+
+pub(crate) static HEADING_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^#{1,6}\s+(.+?)\s*#*\s*$").expect("Failed to compile heading regex")
+});
+
+/// An admonition block such as `> [!note] Title`. Lines are 1-based and inclusive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Callout {
+    pub kind: String,
+    pub title: Option<String>,
+    /// Some(true) for `+` (expanded), Some(false) for `-` (collapsed), None when not foldable
+    pub folded_open: Option<bool>,
+    pub content: String,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+/// A footnote label with its definition, if the note has one, and the lines citing it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Footnote {
+    pub label: String,
+    pub content: Option<String>,
+    pub definition_line: Option<usize>,
+    pub reference_lines: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColumnAlignment {
+    None,
+    Left,
+    Center,
+    Right,
+}
+
+/// A GFM table. Every row has one cell per header; lines are 1-based and inclusive
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarkdownTable {
+    /// Nearest heading above the table, to tell tables in the same note apart
+    pub heading: Option<String>,
+    pub headers: Vec<String>,
+    pub alignments: Vec<ColumnAlignment>,
+    pub rows: Vec<Vec<String>>,
+    pub start_line: usize,
+    pub end_line: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NoteStructure {
+    pub callouts: Vec<Callout>,
+    pub footnotes: Vec<Footnote>,
+}
+
+/// Lines outside fenced code blocks, with their 0-based index
+fn prose_lines(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut in_code_block = false;
+    content.lines().enumerate().filter(move |(_, line)| {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            return false;
+        }
+        !in_code_block
+    })
+}
+
+fn footnote_mut<'a>(footnotes: &'a mut Vec<Footnote>, label: &str) -> &'a mut Footnote {
+    let index = match footnotes.iter().position(|f| f.label == label) {
+        Some(index) => index,
+        None => {
+            footnotes.push(Footnote {
+                label: label.to_string(),
+                content: None,
+                definition_line: None,
+                reference_lines: Vec::new(),
+            });
+            footnotes.len() - 1
+        }
+    };
+    &mut footnotes[index]
+}
+
+fn record_references(footnotes: &mut Vec<Footnote>, line: &str, line_number: usize) {
+    for caps in FOOTNOTE_REFERENCE_REGEX.captures_iter(line) {
+        let footnote = footnote_mut(footnotes, &caps[1]);
+        if footnote.reference_lines.last() != Some(&line_number) {
+            footnote.reference_lines.push(line_number);
+        }
+    }
+}
+
+/// Callouts and footnotes in a note, in order of appearance. Nested callouts are folded
+/// into their parent's content
+pub fn parse_note_structure(content: &str) -> NoteStructure {
+    let mut structure = NoteStructure::default();
+    let mut callout: Option<Callout> = None;
+    // Footnote whose definition is still collecting indented continuation lines
+    let mut open_definition: Option<String> = None;
+
+    for (index, line) in prose_lines(content) {
+        let line_number = index + 1;
+
+        if let Some(current) = callout.as_mut() {
+            match line.trim_start().strip_prefix('>') {
+                Some(rest) => {
+                    if !current.content.is_empty() {
+                        current.content.push('\n');
+                    }
+                    current
+                        .content
+                        .push_str(rest.strip_prefix(' ').unwrap_or(rest));
+                    current.end_line = line_number;
+                    record_references(&mut structure.footnotes, rest, line_number);
+                    continue;
+                }
+                None => structure.callouts.extend(callout.take()),
+            }
+        }
+
+        if let Some(caps) = CALLOUT_REGEX.captures(line) {
+            open_definition = None;
+            let title = caps[3].trim();
+            callout = Some(Callout {
+                kind: caps[1].to_lowercase(),
+                title: (!title.is_empty()).then(|| title.to_string()),
+                folded_open: caps.get(2).map(|marker| marker.as_str() == "+"),
+                content: String::new(),
+                start_line: line_number,
+                end_line: line_number,
+            });
+            record_references(&mut structure.footnotes, title, line_number);
+            continue;
+        }
+
+        if let Some(caps) = FOOTNOTE_DEFINITION_REGEX.captures(line) {
+            let footnote = footnote_mut(&mut structure.footnotes, &caps[1]);
+            footnote.content = Some(caps[2].trim().to_string());
+            footnote.definition_line = Some(line_number);
+            open_definition = Some(caps[1].to_string());
+            continue;
+        }
+
+        if let Some(label) = open_definition.as_deref() {
+            if line.starts_with("    ") || line.starts_with('\t') {
+                let footnote = footnote_mut(&mut structure.footnotes, label);
+                if let Some(text) = footnote.content.as_mut() {
+                    text.push('\n');
+                    text.push_str(line.trim());
+                }
+                continue;
+            }
+            if !line.trim().is_empty() {
+                open_definition = None;
+            }
+        }
+
+        record_references(&mut structure.footnotes, line, line_number);
+    }
+    structure.callouts.extend(callout);
+
+    structure
+}
+
+pub fn read_note_structure(file_path: &Path) -> Result<NoteStructure, Box<dyn std::error::Error>> {
+    if is_locked_path(file_path) {
+        return Err("Note is locked; unlock it to read its structure".into());
+    }
+    Ok(parse_note_structure(&fs::read_to_string(file_path)?))
+}
+
+/// 0-based indices of the lines inside callouts of `kind` (case-insensitive), title included
+pub(crate) fn callout_line_indices(content: &str, kind: &str) -> HashSet<usize> {
+    let kind = kind
+        .trim_start_matches("[!")
+        .trim_end_matches(']')
+        .to_lowercase();
+    parse_note_structure(content)
+        .callouts
+        .iter()
+        .filter(|callout| callout.kind == kind)
+        .flat_map(|callout| callout.start_line - 1..callout.end_line)
+        .collect()
+}
+
+/// Cells of a table row. Pipes escaped as `\|` or inside code spans don't split cells
+pub(crate) fn split_row(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_code = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => {
+                cell.push('|');
+                chars.next();
+            }
+            '`' => {
+                in_code = !in_code;
+                cell.push(c);
+            }
+            '|' if !in_code => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    // A trailing pipe closes the last cell rather than starting an empty one
+    if !cell.trim().is_empty() || !line.trim_end().ends_with('|') {
+        cells.push(cell.trim().to_string());
+    }
+    cells
+}
+
+fn alignment(delimiter: &str) -> ColumnAlignment {
+    match (delimiter.starts_with(':'), delimiter.ends_with(':')) {
+        (true, true) => ColumnAlignment::Center,
+        (true, false) => ColumnAlignment::Left,
+        (false, true) => ColumnAlignment::Right,
+        (false, false) => ColumnAlignment::None,
+    }
+}
+
+/// Tables in a note, in order, skipping fenced code blocks. A table is a header row, a
+/// delimiter row with the same number of columns, then rows up to the first line without a
+/// pipe. Short rows are padded and long ones cut to the header's width
+pub fn parse_tables(content: &str) -> Vec<MarkdownTable> {
+    let lines: Vec<(usize, &str)> = prose_lines(content).collect();
+    let mut tables = Vec::new();
+    let mut heading: Option<String> = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let (index, line) = lines[i];
+        if let Some(caps) = HEADING_REGEX.captures(line) {
+            heading = Some(caps[1].to_string());
+        }
+
+        let delimiter = lines.get(i + 1).map(|(_, next)| *next);
+        let is_table = line.contains('|')
+            && delimiter.is_some_and(|delimiter| TABLE_DELIMITER_REGEX.is_match(delimiter));
+        if !is_table {
+            i += 1;
+            continue;
+        }
+
+        let headers = split_row(line);
+        let alignments: Vec<ColumnAlignment> = split_row(delimiter.unwrap_or_default())
+            .iter()
+            .map(|cell| alignment(cell))
+            .collect();
+        if headers.len() != alignments.len() {
+            i += 1;
+            continue;
+        }
+
+        let mut rows = Vec::new();
+        let mut end = i + 1;
+        while let Some((_, row)) = lines.get(end + 1) {
+            if row.trim().is_empty() || !row.contains('|') || lines[end + 1].0 != lines[end].0 + 1 {
+                break;
+            }
+            let mut cells = split_row(row);
+            cells.resize(headers.len(), String::new());
+            rows.push(cells);
+            end += 1;
+        }
+
+        tables.push(MarkdownTable {
+            heading: heading.clone(),
+            headers,
+            alignments,
+            rows,
+            start_line: index + 1,
+            end_line: lines[end].0 + 1,
+        });
+        i = end + 1;
+    }
+
+    tables
+}
+
+pub fn extract_tables(file_path: &Path) -> Result<Vec<MarkdownTable>, Box<dyn std::error::Error>> {
+    if is_locked_path(file_path) {
+        return Err("Note is locked; unlock it to read its tables".into());
+    }
+    Ok(parse_tables(&fs::read_to_string(file_path)?))
+}
@@ -0,0 +1,596 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use std::collections::BTreeSet;
+
+use super::{file_key, load_manifest, SyncBackend, SyncProgress, MANIFEST_KEY};
+
+/// Stored unencrypted next to the data so any device can derive the key
+const KEY_FILE: &str = "crypto.json";
+const KEY_FILE_VERSION: u32 = 2;
+/// From this key file version each object is sealed with its key as associated data, so
+/// an object moved to another name on the remote fails to open
+const OBJECT_AAD_VERSION: u32 = 2;
+/// Progress of an unfinished key rotation, so it can resume where it stopped
+const ROTATION_FILE: &str = "rotation.json";
+const ROTATION_VERSION: u32 = 1;
+/// Objects re-encrypted between journal saves
+const ROTATION_CHECKPOINT: usize = 25;
+const NONCE_LEN: usize = 24;
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeyFile {
+    version: u32,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: String,
+    wrapped_key: String,
+    recovery_salt: String,
+    recovery_wrapped_key: String,
+}
+
+/// Written before anything is re-encrypted. Holds the new key file and, sealed with the new
+/// data key, the new recovery code; nothing in it is readable without the new passphrase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RotationJournal {
+    version: u32,
+    key_file: KeyFile,
+    recovery_code: String,
+    /// Objects under the old key, deleted once the new key file is in place
+    old_objects: Vec<String>,
+    /// Keys already re-encrypted under the new key
+    copied: BTreeSet<String>,
+}
+
+/// Wraps a backend so every object is encrypted with XChaCha20-Poly1305 before upload
+/// and object names are replaced with keyed hashes, leaving the provider nothing readable
+pub struct EncryptedBackend {
+    inner: Box<dyn SyncBackend>,
+    data_key: Vec<u8>,
+    name_key: Vec<u8>,
+    /// The key file's version, which decides whether objects carry associated data
+    version: u32,
+}
+
+fn keyed_hash(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn name_key(data_key: &[u8]) -> Vec<u8> {
+    keyed_hash(data_key, b"stream object names")
+}
+
+fn object_name(name_key: &[u8], key: &str) -> String {
+    format!("data/{}", hex::encode(keyed_hash(name_key, key.as_bytes())))
+}
+
+pub(crate) fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+pub(crate) fn derive_key(
+    secret: &[u8],
+    salt: &[u8],
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+) -> Result<[u8; 32], Box<dyn std::error::Error>> {
+    let params = Params::new(m_cost, t_cost, p_cost, Some(32))
+        .map_err(|e| format!("Invalid key derivation parameters: {}", e))?;
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(secret, salt, &mut key)
+        .map_err(|e| format!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+/// Encrypt `plaintext`, authenticating `aad` with it: `open` fails unless given the same
+pub(crate) fn seal(
+    key: &[u8],
+    aad: &[u8],
+    plaintext: &[u8],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let cipher = XChaCha20Poly1305::new_from_slice(key).map_err(|e| e.to_string())?;
+    let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| "Encryption failed")?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend_from_slice(&ciphertext);
+    Ok(sealed)
+}
+
+pub(crate) fn open(key: &[u8], aad: &[u8], sealed: &[u8]) -> Option<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return None;
+    }
+    let cipher = XChaCha20Poly1305::new_from_slice(key).ok()?;
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce: [u8; NONCE_LEN] = nonce.try_into().ok()?;
+    cipher
+        .decrypt(
+            &XNonce::from(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .ok()
+}
+
+/// Associated data for the object stored under `key`. Remotes set up before
+/// `OBJECT_AAD_VERSION` keep sealing without it until their key is rotated
+fn object_aad(version: u32, key: &str) -> &[u8] {
+    if version >= OBJECT_AAD_VERSION {
+        key.as_bytes()
+    } else {
+        &[]
+    }
+}
+
+/// Recovery codes are 128 random bits shown as eight groups of four hex digits
+fn generate_recovery_code() -> String {
+    let hex = hex::encode_upper(random_bytes(16));
+    hex.as_bytes()
+        .chunks(4)
+        .map(|chunk| String::from_utf8_lossy(chunk).to_string())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+fn read_key_file(backend: &dyn SyncBackend) -> Result<Option<KeyFile>, Box<dyn std::error::Error>> {
+    match backend.get(KEY_FILE)? {
+        Some(bytes) => {
+            let key_file: KeyFile = serde_json::from_slice(&bytes)?;
+            if key_file.version > KEY_FILE_VERSION {
+                return Err("Remote was encrypted by a newer version of Stream".into());
+            }
+            Ok(Some(key_file))
+        }
+        None => Ok(None),
+    }
+}
+
+fn unwrap_with_passphrase(key_file: &KeyFile, passphrase: &str) -> Option<Vec<u8>> {
+    let salt = STANDARD.decode(&key_file.salt).ok()?;
+    let wrapped = STANDARD.decode(&key_file.wrapped_key).ok()?;
+    let kek = derive_key(
+        passphrase.as_bytes(),
+        &salt,
+        key_file.m_cost,
+        key_file.t_cost,
+        key_file.p_cost,
+    )
+    .ok()?;
+    open(&kek, &[], &wrapped)
+}
+
+pub(crate) fn check_passphrase(passphrase: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Whether the remote has been set up for end-to-end encryption
+pub fn is_encrypted(backend: &dyn SyncBackend) -> Result<bool, Box<dyn std::error::Error>> {
+    Ok(read_key_file(backend)?.is_some())
+}
+
+/// Generate a data key for an empty remote, wrap it with the passphrase and a fresh
+/// recovery code, and upload the key file. Returns the recovery code to show the user once
+pub fn setup_encryption(
+    backend: &dyn SyncBackend,
+    passphrase: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    check_passphrase(passphrase)?;
+    if read_key_file(backend)?.is_some() {
+        return Err("Remote is already encrypted".into());
+    }
+    if backend.get(super::MANIFEST_KEY)?.is_some() {
+        return Err("Remote already holds unencrypted notes; choose an empty location".into());
+    }
+
+    let data_key = random_bytes(32);
+    let recovery_code = generate_recovery_code();
+    let key_file = new_key_file(&data_key, passphrase, &recovery_code)?;
+    backend.put(KEY_FILE, &serde_json::to_vec_pretty(&key_file)?)?;
+
+    Ok(recovery_code)
+}
+
+/// A key file wrapping `data_key` with the passphrase and the recovery code
+fn new_key_file(
+    data_key: &[u8],
+    passphrase: &str,
+    recovery_code: &str,
+) -> Result<KeyFile, Box<dyn std::error::Error>> {
+    let params = Params::default();
+    let (m_cost, t_cost, p_cost) = (params.m_cost(), params.t_cost(), params.p_cost());
+
+    let salt = random_bytes(16);
+    let recovery_salt = random_bytes(16);
+
+    let kek = derive_key(passphrase.as_bytes(), &salt, m_cost, t_cost, p_cost)?;
+    let recovery_kek = derive_key(
+        normalize_recovery_code(recovery_code).as_bytes(),
+        &recovery_salt,
+        m_cost,
+        t_cost,
+        p_cost,
+    )?;
+
+    Ok(KeyFile {
+        version: KEY_FILE_VERSION,
+        m_cost,
+        t_cost,
+        p_cost,
+        salt: STANDARD.encode(&salt),
+        wrapped_key: STANDARD.encode(seal(&kek, &[], data_key)?),
+        recovery_salt: STANDARD.encode(&recovery_salt),
+        recovery_wrapped_key: STANDARD.encode(seal(&recovery_kek, &[], data_key)?),
+    })
+}
+
+/// Check a passphrase against the remote key file without syncing
+pub fn verify_passphrase(
+    backend: &dyn SyncBackend,
+    passphrase: &str,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let key_file = read_key_file(backend)?.ok_or("Remote is not encrypted")?;
+    Ok(unwrap_with_passphrase(&key_file, passphrase).is_some())
+}
+
+/// Use a recovery code to re-wrap the data key under a new passphrase. Existing
+/// notes stay readable since the data key itself doesn't change
+pub fn reset_passphrase(
+    backend: &dyn SyncBackend,
+    recovery_code: &str,
+    new_passphrase: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    check_passphrase(new_passphrase)?;
+    let mut key_file = read_key_file(backend)?.ok_or("Remote is not encrypted")?;
+
+    let recovery_salt = STANDARD.decode(&key_file.recovery_salt)?;
+    let recovery_kek = derive_key(
+        normalize_recovery_code(recovery_code).as_bytes(),
+        &recovery_salt,
+        key_file.m_cost,
+        key_file.t_cost,
+        key_file.p_cost,
+    )?;
+    let data_key = open(
+        &recovery_kek,
+        &[],
+        &STANDARD.decode(&key_file.recovery_wrapped_key)?,
+    )
+    .ok_or("Incorrect recovery code")?;
+
+    let salt = random_bytes(16);
+    let kek = derive_key(
+        new_passphrase.as_bytes(),
+        &salt,
+        key_file.m_cost,
+        key_file.t_cost,
+        key_file.p_cost,
+    )?;
+    key_file.salt = STANDARD.encode(&salt);
+    key_file.wrapped_key = STANDARD.encode(seal(&kek, &[], &data_key)?);
+    backend.put(KEY_FILE, &serde_json::to_vec_pretty(&key_file)?)?;
+
+    Ok(())
+}
+
+fn save_rotation(
+    backend: &dyn SyncBackend,
+    journal: &RotationJournal,
+) -> Result<(), Box<dyn std::error::Error>> {
+    backend.put(ROTATION_FILE, &serde_json::to_vec_pretty(journal)?)
+}
+
+/// Re-encrypt every object on the remote under a new data key wrapped with
+/// `new_passphrase`, replacing the recovery code too. Returns the new recovery code.
+///
+/// Objects are copied to their new names first and the manifest last; swapping in the new
+/// key file is the commit point, after which the old objects are deleted. Progress is kept
+/// in `rotation.json`, so an interrupted rotation resumes when called again with the same
+/// new passphrase. Other devices must not sync until it finishes
+pub fn rotate_encryption_key(
+    backend: &dyn SyncBackend,
+    old_passphrase: &str,
+    new_passphrase: &str,
+    progress: &dyn Fn(SyncProgress),
+) -> Result<String, Box<dyn std::error::Error>> {
+    check_passphrase(new_passphrase)?;
+    let key_file = read_key_file(backend)?.ok_or("Remote is not encrypted")?;
+
+    let pending: Option<RotationJournal> = match backend.get(ROTATION_FILE)? {
+        Some(bytes) => Some(serde_json::from_slice(&bytes)?),
+        None => None,
+    };
+    let mut old_key = None;
+    let (mut journal, new_key) = match pending {
+        Some(journal) => {
+            if journal.version > ROTATION_VERSION {
+                return Err("Key rotation was started by a newer version of Stream".into());
+            }
+            let new_key = unwrap_with_passphrase(&journal.key_file, new_passphrase).ok_or(
+                "An interrupted key rotation is using a different new passphrase; finish it first",
+            )?;
+            (journal, new_key)
+        }
+        None => {
+            old_key = Some(
+                unwrap_with_passphrase(&key_file, old_passphrase).ok_or("Incorrect passphrase")?,
+            );
+            let new_key = random_bytes(32);
+            let recovery_code = generate_recovery_code();
+            let journal = RotationJournal {
+                version: ROTATION_VERSION,
+                key_file: new_key_file(&new_key, new_passphrase, &recovery_code)?,
+                recovery_code: STANDARD.encode(seal(
+                    &new_key,
+                    ROTATION_FILE.as_bytes(),
+                    recovery_code.as_bytes(),
+                )?),
+                old_objects: Vec::new(),
+                copied: BTreeSet::new(),
+            };
+            save_rotation(backend, &journal)?;
+            (journal, new_key)
+        }
+    };
+    let new_names = name_key(&new_key);
+    let recovery_code = open(
+        &new_key,
+        ROTATION_FILE.as_bytes(),
+        &STANDARD.decode(&journal.recovery_code)?,
+    )
+    .and_then(|code| String::from_utf8(code).ok())
+    .ok_or("Key rotation journal is damaged")?;
+
+    // Once the new key file is in place only the old objects are left to delete
+    if key_file.wrapped_key != journal.key_file.wrapped_key {
+        let old_key = match old_key {
+            Some(old_key) => old_key,
+            None => {
+                unwrap_with_passphrase(&key_file, old_passphrase).ok_or("Incorrect passphrase")?
+            }
+        };
+        let old_names = name_key(&old_key);
+
+        let manifest = backend.get(&object_name(&old_names, MANIFEST_KEY))?;
+        let mut keys: Vec<String> = match &manifest {
+            Some(sealed) => {
+                let manifest = open(&old_key, object_aad(key_file.version, MANIFEST_KEY), sealed)
+                    .ok_or("Failed to decrypt the remote manifest")?;
+                load_manifest(&manifest)?
+                    .files
+                    .keys()
+                    .map(|p| file_key(p))
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+        keys.push(MANIFEST_KEY.to_string());
+
+        let total = keys.len();
+        let mut since_save = 0;
+        for (index, key) in keys.iter().enumerate() {
+            progress(SyncProgress {
+                phase: "re-encrypting".to_string(),
+                current: index + 1,
+                total,
+                file_path: key.strip_prefix(super::FILES_PREFIX).map(str::to_string),
+            });
+            // The manifest is always copied last, as it may have changed since it was read
+            if key != MANIFEST_KEY && journal.copied.contains(key) {
+                continue;
+            }
+
+            let old_name = object_name(&old_names, key);
+            match backend.get(&old_name)? {
+                Some(sealed) => {
+                    let data = open(&old_key, object_aad(key_file.version, key), &sealed)
+                        .ok_or_else(|| format!("Failed to decrypt {}", key))?;
+                    let sealed = seal(&new_key, object_aad(journal.key_file.version, key), &data)?;
+                    backend.put(&object_name(&new_names, key), &sealed)?;
+                }
+                None => eprintln!("Remote object for {} is missing; skipping", key),
+            }
+            if !journal.old_objects.contains(&old_name) {
+                journal.old_objects.push(old_name);
+            }
+            journal.copied.insert(key.clone());
+
+            since_save += 1;
+            if since_save >= ROTATION_CHECKPOINT {
+                save_rotation(backend, &journal)?;
+                since_save = 0;
+            }
+        }
+        save_rotation(backend, &journal)?;
+
+        backend.put(KEY_FILE, &serde_json::to_vec_pretty(&journal.key_file)?)?;
+    }
+
+    let total = journal.old_objects.len();
+    for (index, name) in journal.old_objects.iter().enumerate() {
+        progress(SyncProgress {
+            phase: "cleaning".to_string(),
+            current: index + 1,
+            total,
+            file_path: None,
+        });
+        if let Err(e) = backend.delete(name) {
+            eprintln!("Failed to delete old object {}: {}", name, e);
+        }
+    }
+    backend.delete(ROTATION_FILE)?;
+
+    Ok(recovery_code)
+}
+
+impl EncryptedBackend {
+    /// Unlock an encrypted remote with the user's passphrase
+    pub fn unlock(
+        inner: Box<dyn SyncBackend>,
+        passphrase: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let key_file = read_key_file(inner.as_ref())?.ok_or("Remote is not encrypted")?;
+        let data_key =
+            unwrap_with_passphrase(&key_file, passphrase).ok_or("Incorrect passphrase")?;
+
+        let name_key = name_key(&data_key);
+
+        Ok(EncryptedBackend {
+            inner,
+            data_key,
+            name_key,
+            version: key_file.version,
+        })
+    }
+
+    fn object_name(&self, key: &str) -> String {
+        object_name(&self.name_key, key)
+    }
+}
+
+impl SyncBackend for EncryptedBackend {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        match self.inner.get(&self.object_name(key))? {
+            Some(sealed) => open(&self.data_key, object_aad(self.version, key), &sealed)
+                .map(Some)
+                .ok_or_else(|| format!("Failed to decrypt {}", key).into()),
+            None => Ok(None),
+        }
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        let sealed = seal(&self.data_key, object_aad(self.version, key), data)?;
+        self.inner.put(&self.object_name(key), &sealed)
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.inner.delete(&self.object_name(key))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// In-memory remote whose objects the test can still reach after handing it over
+    #[derive(Clone, Default)]
+    struct MemoryBackend(Arc<Mutex<HashMap<String, Vec<u8>>>>);
+
+    impl SyncBackend for MemoryBackend {
+        fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+            Ok(self.0.lock().unwrap().get(key).cloned())
+        }
+
+        fn put(&self, key: &str, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(key.to_string(), data.to_vec());
+            Ok(())
+        }
+
+        fn delete(&self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
+            self.0.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn seal_then_open_round_trips() {
+        let key = random_bytes(32);
+        let sealed = seal(&key, b"files/a.md", b"# Monday").unwrap();
+        assert_eq!(
+            open(&key, b"files/a.md", &sealed).as_deref(),
+            Some(&b"# Monday"[..])
+        );
+    }
+
+    #[test]
+    fn open_with_wrong_key_fails() {
+        let sealed = seal(&random_bytes(32), b"files/a.md", b"# Monday").unwrap();
+        assert!(open(&random_bytes(32), b"files/a.md", &sealed).is_none());
+    }
+
+    #[test]
+    fn open_with_other_associated_data_fails() {
+        let key = random_bytes(32);
+        let sealed = seal(&key, b"files/a.md", b"# Monday").unwrap();
+        assert!(open(&key, b"files/b.md", &sealed).is_none());
+        assert!(open(&key, b"", &sealed).is_none());
+    }
+
+    #[test]
+    fn swapped_objects_fail_to_open() {
+        let remote = MemoryBackend::default();
+        setup_encryption(&remote, "correct horse").unwrap();
+        let backend = EncryptedBackend::unlock(Box::new(remote.clone()), "correct horse").unwrap();
+        backend.put("files/a.md", b"# A").unwrap();
+        backend.put("files/b.md", b"# B").unwrap();
+        assert_eq!(
+            backend.get("files/a.md").unwrap().as_deref(),
+            Some(&b"# A"[..])
+        );
+
+        let (a, b) = (
+            backend.object_name("files/a.md"),
+            backend.object_name("files/b.md"),
+        );
+        {
+            let mut objects = remote.0.lock().unwrap();
+            let sealed_a = objects.remove(&a).unwrap();
+            let sealed_b = objects.remove(&b).unwrap();
+            objects.insert(a, sealed_b);
+            objects.insert(b, sealed_a);
+        }
+        assert!(backend.get("files/a.md").is_err());
+        assert!(backend.get("files/b.md").is_err());
+    }
+
+    #[test]
+    fn unlock_with_wrong_passphrase_fails() {
+        let remote = MemoryBackend::default();
+        setup_encryption(&remote, "correct horse").unwrap();
+        assert!(EncryptedBackend::unlock(Box::new(remote), "battery staple").is_err());
+    }
+}
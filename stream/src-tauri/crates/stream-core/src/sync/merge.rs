@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+
+/// A region both sides changed differently. Line numbers are 1-based in the merged output
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConflictHunk {
+    pub start_line: usize,
+    pub base: Vec<String>,
+    pub local: Vec<String>,
+    pub remote: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeResult {
+    /// Merged text, with git-style conflict markers around any unresolved hunks
+    pub text: String,
+    pub conflicts: Vec<ConflictHunk>,
+}
+
+/// Map each base line that survives unchanged to its index on the other side
+fn matched_lines(base: &[&str], other: &[&str]) -> HashMap<usize, usize> {
+    let mut matches = HashMap::new();
+    for op in capture_diff_slices(Algorithm::Myers, base, other) {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = op
+        {
+            for offset in 0..len {
+                matches.insert(old_index + offset, new_index + offset);
+            }
+        }
+    }
+    matches
+}
+
+fn to_owned(lines: &[&str]) -> Vec<String> {
+    lines.iter().map(|l| l.to_string()).collect()
+}
+
+/// Append lines ahead of a conflict marker, making sure the marker starts on its own line
+fn push_lines<'a>(output: &mut Vec<&'a str>, lines: &[&'a str]) {
+    output.extend(lines);
+    if lines.last().is_some_and(|l| !l.ends_with('\n')) {
+        output.push("\n");
+    }
+}
+
+/// Resolve one unstable region. Returns None when it's a true conflict
+fn resolve_chunk<'a>(
+    base: &[&'a str],
+    local: &[&'a str],
+    remote: &[&'a str],
+) -> Option<Vec<&'a str>> {
+    if local == remote || remote == base {
+        return Some(local.to_vec());
+    }
+    if local == base {
+        return Some(remote.to_vec());
+    }
+    // Both sides only inserted lines at the same spot, which is how daily notes usually
+    // diverge (two devices appending). Keep both, local first
+    if base.is_empty() {
+        if local.starts_with(remote) {
+            return Some(local.to_vec());
+        }
+        if remote.starts_with(local) {
+            return Some(remote.to_vec());
+        }
+        return Some(local.iter().chain(remote.iter()).copied().collect());
+    }
+    None
+}
+
+/// Line-based three-way merge of `local` and `remote` against their common `base`
+pub fn merge_lines(base: &str, local: &str, remote: &str) -> MergeResult {
+    let base_lines: Vec<&str> = base.split_inclusive('\n').collect();
+    let local_lines: Vec<&str> = local.split_inclusive('\n').collect();
+    let remote_lines: Vec<&str> = remote.split_inclusive('\n').collect();
+
+    let local_matches = matched_lines(&base_lines, &local_lines);
+    let remote_matches = matched_lines(&base_lines, &remote_lines);
+
+    let mut output: Vec<&str> = Vec::new();
+    let mut conflicts = Vec::new();
+    let (mut i, mut j, mut k) = (0, 0, 0);
+
+    loop {
+        // Next base line kept by both sides, or the end of all three texts
+        let stable = (i..base_lines.len()).find_map(|index| {
+            let l = *local_matches.get(&index)?;
+            let r = *remote_matches.get(&index)?;
+            (l >= j && r >= k).then_some((index, l, r))
+        });
+        let (next_i, next_j, next_k) =
+            stable.unwrap_or((base_lines.len(), local_lines.len(), remote_lines.len()));
+
+        let base_chunk = &base_lines[i..next_i];
+        let local_chunk = &local_lines[j..next_j];
+        let remote_chunk = &remote_lines[k..next_k];
+
+        if !(base_chunk.is_empty() && local_chunk.is_empty() && remote_chunk.is_empty()) {
+            match resolve_chunk(base_chunk, local_chunk, remote_chunk) {
+                Some(lines) => output.extend(lines),
+                None => {
+                    conflicts.push(ConflictHunk {
+                        start_line: output.len() + 1,
+                        base: to_owned(base_chunk),
+                        local: to_owned(local_chunk),
+                        remote: to_owned(remote_chunk),
+                    });
+                    output.push("<<<<<<< local\n");
+                    push_lines(&mut output, local_chunk);
+                    output.push("=======\n");
+                    push_lines(&mut output, remote_chunk);
+                    output.push(">>>>>>> remote\n");
+                }
+            }
+        }
+
+        match stable {
+            Some((index, l, r)) => {
+                output.push(base_lines[index]);
+                i = index + 1;
+                j = l + 1;
+                k = r + 1;
+            }
+            None => break,
+        }
+    }
+
+    MergeResult {
+        text: output.concat(),
+        conflicts,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_edits_to_different_lines() {
+        let base = "one\ntwo\nthree\nfour\n";
+        let local = "ONE\ntwo\nthree\nfour\n";
+        let remote = "one\ntwo\nthree\nFOUR\n";
+
+        let merged = merge_lines(base, local, remote);
+        assert_eq!(merged.text, "ONE\ntwo\nthree\nFOUR\n");
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn same_edit_on_both_sides_is_not_a_conflict() {
+        let merged = merge_lines("a\nb\nc\n", "a\nB\nc\n", "a\nB\nc\n");
+        assert_eq!(merged.text, "a\nB\nc\n");
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn different_edits_to_the_same_line_conflict() {
+        let merged = merge_lines("a\nb\nc\n", "a\nlocal\nc\n", "a\nremote\nc\n");
+
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< local\nlocal\n=======\nremote\n>>>>>>> remote\nc\n"
+        );
+        assert_eq!(merged.conflicts.len(), 1);
+        let hunk = &merged.conflicts[0];
+        assert_eq!(hunk.start_line, 2);
+        assert_eq!(hunk.base, vec!["b\n"]);
+        assert_eq!(hunk.local, vec!["local\n"]);
+        assert_eq!(hunk.remote, vec!["remote\n"]);
+    }
+
+    #[test]
+    fn keeps_a_delete_and_an_insert_elsewhere() {
+        let base = "a\nb\nc\nd\n";
+        let local = "a\nc\nd\n";
+        let remote = "a\nb\nc\nd\ne\n";
+
+        let merged = merge_lines(base, local, remote);
+        assert_eq!(merged.text, "a\nc\nd\ne\n");
+        assert!(merged.conflicts.is_empty());
+    }
+
+    #[test]
+    fn deleting_a_line_the_other_side_edited_conflicts() {
+        let merged = merge_lines("a\nb\nc\n", "a\nc\n", "a\nb changed\nc\n");
+
+        assert_eq!(merged.conflicts.len(), 1);
+        assert!(merged.conflicts[0].local.is_empty());
+        assert_eq!(merged.conflicts[0].remote, vec!["b changed\n"]);
+    }
+
+    #[test]
+    fn empty_base_keeps_both_sides_local_first() {
+        let merged = merge_lines("", "from laptop\n", "from phone\n");
+        assert_eq!(merged.text, "from laptop\nfrom phone\n");
+        assert!(merged.conflicts.is_empty());
+
+        let merged = merge_lines("", "same\n", "same\n");
+        assert_eq!(merged.text, "same\n");
+    }
+
+    #[test]
+    fn conflict_markers_start_on_their_own_line() {
+        let merged = merge_lines("a\nb", "a\nlocal", "a\nremote");
+        assert_eq!(
+            merged.text,
+            "a\n<<<<<<< local\nlocal\n=======\nremote\n>>>>>>> remote\n"
+        );
+    }
+}
@@ -0,0 +1,605 @@
+pub(crate) mod crypto;
+mod merge;
+mod s3;
+mod webdav;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::archive::{collect_files, relative_key};
+use crate::clock::now_ms;
+use crate::cloud::is_placeholder;
+use crate::file_lock::with_note_lock;
+use crate::lock::is_being_locked;
+use crate::markdown::{read_all_user_xattrs, write_user_xattrs};
+use crate::sources::strip_source_config;
+use crate::undo::{UndoJournal, UndoKind};
+
+pub use crypto::{
+    is_encrypted, reset_passphrase, rotate_encryption_key, setup_encryption, verify_passphrase,
+    EncryptedBackend,
+};
+pub use merge::{merge_lines, ConflictHunk, MergeResult};
+pub use s3::S3Backend;
+pub use webdav::WebDavBackend;
+
+const MANIFEST_KEY: &str = "manifest.json";
+const FILES_PREFIX: &str = "files/";
+const MANIFEST_VERSION: u32 = 1;
+
+/// A remote object store the sync engine can read and write whole objects in
+pub trait SyncBackend: Send + Sync {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>>;
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Box<dyn std::error::Error>>;
+    fn delete(&self, key: &str) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum SyncBackendConfig {
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key_id: String,
+        secret_access_key: String,
+        prefix: Option<String>,
+    },
+    Webdav {
+        url: String,
+        username: Option<String>,
+        password: Option<String>,
+    },
+}
+
+impl SyncBackendConfig {
+    pub fn build(&self) -> Box<dyn SyncBackend> {
+        match self {
+            SyncBackendConfig::S3 {
+                endpoint,
+                bucket,
+                region,
+                access_key_id,
+                secret_access_key,
+                prefix,
+            } => Box::new(S3Backend::new(
+                endpoint,
+                bucket,
+                region,
+                access_key_id,
+                secret_access_key,
+                prefix.as_deref().unwrap_or(""),
+            )),
+            SyncBackendConfig::Webdav {
+                url,
+                username,
+                password,
+            } => Box::new(WebDavBackend::new(
+                url,
+                username.as_deref(),
+                password.as_deref(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub hash: String,
+    pub size: u64,
+    pub modified_at: u64,
+    pub xattrs: BTreeMap<String, String>,
+}
+
+impl ManifestEntry {
+    /// Content and metadata together decide whether an entry changed
+    fn fingerprint(&self) -> (&str, &BTreeMap<String, String>) {
+        (&self.hash, &self.xattrs)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncManifest {
+    pub version: u32,
+    pub updated_at: u64,
+    pub files: BTreeMap<String, ManifestEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncProgress {
+    pub phase: String,
+    pub current: usize,
+    pub total: usize,
+    pub file_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncConflict {
+    pub file_path: String,
+    pub hunks: Vec<ConflictHunk>,
+    /// Set when the file couldn't be merged line by line and the remote copy was saved beside it
+    pub conflict_copy: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncReport {
+    pub uploaded: Vec<String>,
+    pub downloaded: Vec<String>,
+    pub deleted_local: Vec<String>,
+    pub deleted_remote: Vec<String>,
+    pub merged: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+    pub finished_at: u64,
+}
+
+enum SyncAction {
+    Upload(String),
+    Download(String),
+    DeleteLocal(String),
+    DeleteRemote(String),
+    Conflict(String),
+}
+
+pub(crate) fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
+/// Percent-encode a key the way SigV4 expects, leaving `/` intact
+pub(crate) fn encode_key(key: &str) -> String {
+    let mut encoded = String::with_capacity(key.len());
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+fn file_key(path: &str) -> String {
+    format!("{}{}", FILES_PREFIX, path)
+}
+
+/// File name for a notes folder's sync base state, stable across runs
+pub fn state_file_name(directory_path: &Path) -> String {
+    let digest = sha256_hex(directory_path.to_string_lossy().as_bytes());
+    format!("{}.json", &digest[..16])
+}
+
+/// Hash every file in the notes directory along with its xattrs. Online-only
+/// placeholders already known from `previous` are assumed unchanged rather than downloaded
+pub fn build_local_manifest(
+    directory_path: &Path,
+    previous: &SyncManifest,
+) -> Result<SyncManifest, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    collect_files(directory_path, &mut files)?;
+
+    let mut manifest = SyncManifest {
+        version: MANIFEST_VERSION,
+        updated_at: now_ms(),
+        files: BTreeMap::new(),
+    };
+
+    for file in files {
+        let key = match relative_key(directory_path, &file) {
+            Some(key) => key,
+            None => continue,
+        };
+        // Synced as its locked copy once the lock finishes
+        if is_being_locked(&file) {
+            continue;
+        }
+        if is_placeholder(&file) {
+            if let Some(entry) = previous.files.get(&key) {
+                manifest.files.insert(key, entry.clone());
+                continue;
+            }
+        }
+        let data = match fs::read(&file) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Sync: skipping unreadable file {}: {}", file.display(), e);
+                continue;
+            }
+        };
+        let modified_at = fs::metadata(&file)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        manifest.files.insert(
+            key,
+            ManifestEntry {
+                hash: sha256_hex(&data),
+                size: data.len() as u64,
+                modified_at,
+                xattrs: read_all_user_xattrs(&file),
+            },
+        );
+    }
+
+    Ok(manifest)
+}
+
+fn load_manifest(bytes: &[u8]) -> Result<SyncManifest, Box<dyn std::error::Error>> {
+    Ok(serde_json::from_slice(bytes)?)
+}
+
+/// Base copies kept beside `state_path` for files its manifest no longer lists, e.g. left
+/// behind by an interrupted sync
+pub fn stale_base_copies(state_path: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let base_dir = state_path.with_extension("base");
+    let manifest = match fs::read(state_path) {
+        Ok(bytes) => load_manifest(&bytes)?,
+        Err(_) => SyncManifest::default(),
+    };
+
+    let mut copies = Vec::new();
+    collect_files(&base_dir, &mut copies)?;
+    copies.retain(|copy| {
+        relative_key(&base_dir, copy).is_some_and(|key| !manifest.files.contains_key(&key))
+    });
+    Ok(copies)
+}
+
+/// Compare local, remote and the last-synced base to decide what moves where
+fn plan_actions(
+    local: &SyncManifest,
+    remote: &SyncManifest,
+    base: &SyncManifest,
+) -> Vec<SyncAction> {
+    let paths: BTreeSet<&String> = local
+        .files
+        .keys()
+        .chain(remote.files.keys())
+        .chain(base.files.keys())
+        .collect();
+
+    let mut actions = Vec::new();
+
+    for path in paths {
+        let l = local.files.get(path).map(|e| e.fingerprint());
+        let r = remote.files.get(path).map(|e| e.fingerprint());
+        let b = base.files.get(path).map(|e| e.fingerprint());
+
+        if l == r {
+            continue;
+        }
+
+        let local_changed = l != b;
+        let remote_changed = r != b;
+
+        let action = match (local_changed, remote_changed) {
+            (true, false) if l.is_some() => SyncAction::Upload(path.clone()),
+            (true, false) => SyncAction::DeleteRemote(path.clone()),
+            (false, true) if r.is_some() => SyncAction::Download(path.clone()),
+            (false, true) => SyncAction::DeleteLocal(path.clone()),
+            _ => match (l.is_some(), r.is_some()) {
+                (true, true) => SyncAction::Conflict(path.clone()),
+                (true, false) => SyncAction::Upload(path.clone()),
+                (false, true) => SyncAction::Download(path.clone()),
+                (false, false) => continue,
+            },
+        };
+        actions.push(action);
+    }
+
+    actions
+}
+
+/// Text files get a base copy kept locally so later conflicts can be merged
+fn is_mergeable(path: &str) -> bool {
+    let lower = path.to_lowercase();
+    lower.ends_with(".md") || lower.ends_with(".markdown") || lower.ends_with(".txt")
+}
+
+/// A file still carrying markers from an earlier merge is held back until resolved
+fn has_conflict_markers(data: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(data);
+    text.lines().any(|l| l == "<<<<<<< local") && text.lines().any(|l| l == ">>>>>>> remote")
+}
+
+fn unresolved_conflict(path: String) -> SyncConflict {
+    SyncConflict {
+        file_path: path,
+        hunks: Vec::new(),
+        conflict_copy: None,
+    }
+}
+
+fn base_copy_path(state_path: &Path, path: &str) -> PathBuf {
+    state_path.with_extension("base").join(path)
+}
+
+fn conflict_copy_path(path: &str) -> String {
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    match path.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !stem.ends_with('/') => {
+            format!("{} (conflict {}).{}", stem, stamp, ext)
+        }
+        _ => format!("{} (conflict {})", path, stamp),
+    }
+}
+
+/// Whether a path from the remote manifest is plain and relative: no root, drive, `.` or
+/// `..` components that could point outside the vault
+fn is_relative_key(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Where a remote path lives in the vault. Refuses keys that aren't plain relative paths,
+/// and ones that resolve outside the vault through a symlink
+fn local_path(directory_path: &Path, path: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    if !is_relative_key(path) {
+        return Err(format!("Refusing remote path outside the vault: {}", path).into());
+    }
+    let root = directory_path.canonicalize()?;
+    let target = directory_path.join(path);
+    // The nearest part of the path that exists, the file itself when it does
+    let existing = target
+        .ancestors()
+        .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        .unwrap_or(directory_path);
+    if !existing.canonicalize()?.starts_with(&root) {
+        return Err(format!("Refusing remote path outside the vault: {}", path).into());
+    }
+    Ok(target)
+}
+
+fn write_local_file(
+    directory_path: &Path,
+    path: &str,
+    data: &[u8],
+    xattrs: Option<&BTreeMap<String, String>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let target = local_path(directory_path, path)?;
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    with_note_lock(&target, || Ok(fs::write(&target, data)?))?;
+    if let Some(xattrs) = xattrs {
+        write_user_xattrs(&target, &strip_source_config(xattrs))?;
+    }
+    Ok(())
+}
+
+/// Keep a copy of each text file as last agreed with the remote, for three-way merges
+fn update_base_copies(
+    directory_path: &Path,
+    state_path: &Path,
+    base: &SyncManifest,
+    next: &SyncManifest,
+    mut pending: HashMap<String, Vec<u8>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    for (path, entry) in next.files.iter().filter(|(p, _)| is_mergeable(p)) {
+        let copy = base_copy_path(state_path, path);
+        let unchanged = base.files.get(path).map(|e| &e.hash) == Some(&entry.hash);
+        if unchanged && copy.exists() && !pending.contains_key(path) {
+            continue;
+        }
+
+        let data = match pending.remove(path) {
+            Some(data) => data,
+            None => match fs::read(directory_path.join(path)) {
+                Ok(data) if sha256_hex(&data) == entry.hash => data,
+                _ => continue,
+            },
+        };
+        if let Some(parent) = copy.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&copy, data)?;
+    }
+
+    for path in base.files.keys().filter(|p| !next.files.contains_key(*p)) {
+        let _ = fs::remove_file(base_copy_path(state_path, path));
+    }
+
+    Ok(())
+}
+
+/// Two-way sync of the notes directory with a remote backend. `state_path` stores the
+/// manifest from the last successful sync so deletions and edits can be told apart, and
+/// a sibling `.base` directory holds the matching text so conflicting edits can be merged.
+/// Local deletions and merges are recorded in `journal` when one is given
+pub fn sync_directory(
+    directory_path: &Path,
+    backend: &dyn SyncBackend,
+    state_path: &Path,
+    journal: Option<&UndoJournal>,
+    progress: &dyn Fn(SyncProgress),
+) -> Result<SyncReport, Box<dyn std::error::Error>> {
+    let record = |kind: UndoKind, description: String, target: &Path| {
+        if let Some(journal) = journal {
+            if let Err(e) = journal.record_files(kind, &description, &[target.to_path_buf()]) {
+                eprintln!("Failed to record undo for {}: {}", target.display(), e);
+            }
+        }
+    };
+
+    progress(SyncProgress {
+        phase: "scanning".to_string(),
+        current: 0,
+        total: 0,
+        file_path: None,
+    });
+
+    let base = match fs::read(state_path) {
+        Ok(bytes) => load_manifest(&bytes).unwrap_or_default(),
+        Err(_) => SyncManifest::default(),
+    };
+    let local = build_local_manifest(directory_path, &base)?;
+    let remote = match backend.get(MANIFEST_KEY)? {
+        Some(bytes) => load_manifest(&bytes)?,
+        None => SyncManifest::default(),
+    };
+    if let Some(path) = remote.files.keys().find(|path| !is_relative_key(path)) {
+        return Err(format!("Remote manifest lists a path outside the vault: {}", path).into());
+    }
+
+    let actions = plan_actions(&local, &remote, &base);
+    let total = actions.len();
+    let mut report = SyncReport::default();
+    let mut next = remote.clone();
+    next.version = MANIFEST_VERSION;
+    let mut pending_base: HashMap<String, Vec<u8>> = HashMap::new();
+
+    for (index, action) in actions.into_iter().enumerate() {
+        let (phase, path) = match &action {
+            SyncAction::Upload(p) | SyncAction::Conflict(p) => ("uploading", p),
+            SyncAction::Download(p) => ("downloading", p),
+            SyncAction::DeleteLocal(p) | SyncAction::DeleteRemote(p) => ("deleting", p),
+        };
+        progress(SyncProgress {
+            phase: phase.to_string(),
+            current: index + 1,
+            total,
+            file_path: Some(path.clone()),
+        });
+
+        match action {
+            SyncAction::Upload(path) => {
+                let data = fs::read(directory_path.join(&path))?;
+                if has_conflict_markers(&data) {
+                    report.conflicts.push(unresolved_conflict(path));
+                    continue;
+                }
+                backend.put(&file_key(&path), &data)?;
+                if let Some(entry) = local.files.get(&path) {
+                    next.files.insert(path.clone(), entry.clone());
+                }
+                report.uploaded.push(path);
+            }
+            SyncAction::Download(path) => {
+                let data = backend
+                    .get(&file_key(&path))?
+                    .ok_or_else(|| format!("Remote file is missing: {}", path))?;
+                let xattrs = remote.files.get(&path).map(|e| &e.xattrs);
+                write_local_file(directory_path, &path, &data, xattrs)?;
+                report.downloaded.push(path);
+            }
+            SyncAction::DeleteLocal(path) => {
+                let target = local_path(directory_path, &path)?;
+                if target.exists() {
+                    record(UndoKind::Delete, format!("Sync deleted {}", path), &target);
+                    fs::remove_file(&target)?;
+                }
+                report.deleted_local.push(path);
+            }
+            SyncAction::DeleteRemote(path) => {
+                backend.delete(&file_key(&path))?;
+                next.files.remove(&path);
+                report.deleted_remote.push(path);
+            }
+            SyncAction::Conflict(path) => {
+                let remote_data = backend
+                    .get(&file_key(&path))?
+                    .ok_or_else(|| format!("Remote file is missing: {}", path))?;
+                let local_data = fs::read(directory_path.join(&path))?;
+                if has_conflict_markers(&local_data) {
+                    report.conflicts.push(unresolved_conflict(path));
+                    continue;
+                }
+                let base_data = fs::read(base_copy_path(state_path, &path)).ok();
+
+                let texts = base_data
+                    .as_deref()
+                    .filter(|_| is_mergeable(&path))
+                    .and_then(|b| {
+                        Some((
+                            std::str::from_utf8(b).ok()?,
+                            std::str::from_utf8(&local_data).ok()?,
+                            std::str::from_utf8(&remote_data).ok()?,
+                        ))
+                    });
+
+                match texts {
+                    Some((base_text, local_text, remote_text)) => {
+                        let merged = merge_lines(base_text, local_text, remote_text);
+                        record(
+                            UndoKind::Merge,
+                            format!("Sync merged {}", path),
+                            &directory_path.join(&path),
+                        );
+                        write_local_file(directory_path, &path, merged.text.as_bytes(), None)?;
+
+                        if merged.conflicts.is_empty() {
+                            backend.put(&file_key(&path), merged.text.as_bytes())?;
+                            let xattrs = local
+                                .files
+                                .get(&path)
+                                .map(|e| e.xattrs.clone())
+                                .unwrap_or_default();
+                            next.files.insert(
+                                path.clone(),
+                                ManifestEntry {
+                                    hash: sha256_hex(merged.text.as_bytes()),
+                                    size: merged.text.len() as u64,
+                                    modified_at: now_ms(),
+                                    xattrs,
+                                },
+                            );
+                            report.merged.push(path);
+                        } else {
+                            // Remote becomes the base; the marked-up local file uploads
+                            // on the next sync once the user has resolved it
+                            pending_base.insert(path.clone(), remote_data);
+                            report.conflicts.push(SyncConflict {
+                                file_path: path,
+                                hunks: merged.conflicts,
+                                conflict_copy: None,
+                            });
+                        }
+                    }
+                    None => {
+                        // Keep the local version in place, save the remote one alongside it
+                        let copy_path = conflict_copy_path(&path);
+                        write_local_file(directory_path, &copy_path, &remote_data, None)?;
+
+                        backend.put(&file_key(&path), &local_data)?;
+                        if let Some(entry) = local.files.get(&path) {
+                            next.files.insert(path.clone(), entry.clone());
+                        }
+                        report.conflicts.push(SyncConflict {
+                            file_path: path,
+                            hunks: Vec::new(),
+                            conflict_copy: Some(copy_path),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    progress(SyncProgress {
+        phase: "finalizing".to_string(),
+        current: total,
+        total,
+        file_path: None,
+    });
+
+    next.updated_at = now_ms();
+    backend.put(MANIFEST_KEY, &serde_json::to_vec_pretty(&next)?)?;
+
+    // The new base is what both sides now agree on
+    if let Some(parent) = state_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(state_path, serde_json::to_vec(&next)?)?;
+    update_base_copies(directory_path, state_path, &base, &next, pending_base)?;
+
+    report.finished_at = now_ms();
+    Ok(report)
+}
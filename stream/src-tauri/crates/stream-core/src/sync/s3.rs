@@ -0,0 +1,141 @@
+use std::io::Read;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::{encode_key, sha256_hex, SyncBackend};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// S3-compatible object storage (AWS, R2, MinIO, ...) using path-style URLs and SigV4
+pub struct S3Backend {
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key_id: String,
+    secret_access_key: String,
+    prefix: String,
+    agent: ureq::Agent,
+}
+
+fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+impl S3Backend {
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        region: &str,
+        access_key_id: &str,
+        secret_access_key: &str,
+        prefix: &str,
+    ) -> Self {
+        let mut prefix = prefix.trim_matches('/').to_string();
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        S3Backend {
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            region: if region.is_empty() {
+                "us-east-1".to_string()
+            } else {
+                region.to_string()
+            },
+            access_key_id: access_key_id.to_string(),
+            secret_access_key: secret_access_key.to_string(),
+            prefix,
+            agent: crate::net::agent(),
+        }
+    }
+
+    fn host(&self) -> &str {
+        self.endpoint
+            .split("://")
+            .nth(1)
+            .unwrap_or(&self.endpoint)
+            .split('/')
+            .next()
+            .unwrap_or("")
+    }
+
+    fn request(&self, method: &str, key: &str, body: &[u8]) -> ureq::Request {
+        let path = encode_key(&format!("/{}/{}{}", self.bucket, self.prefix, key));
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date_stamp = now.format("%Y%m%d").to_string();
+        let payload_hash = sha256_hex(body);
+
+        let canonical_headers = format!(
+            "host:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n",
+            self.host(),
+            payload_hash,
+            amz_date
+        );
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\n{}\n{}\n{}",
+            method, path, canonical_headers, signed_headers, payload_hash
+        );
+
+        let scope = format!("{}/{}/s3/aws4_request", date_stamp, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            sha256_hex(canonical_request.as_bytes())
+        );
+
+        let k_date = hmac(
+            format!("AWS4{}", self.secret_access_key).as_bytes(),
+            &date_stamp,
+        );
+        let k_region = hmac(&k_date, &self.region);
+        let k_service = hmac(&k_region, "s3");
+        let k_signing = hmac(&k_service, "aws4_request");
+        let signature = hex::encode(hmac(&k_signing, &string_to_sign));
+
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key_id, scope, signed_headers, signature
+        );
+
+        self.agent
+            .request(method, &format!("{}{}", self.endpoint, path))
+            .set("x-amz-date", &amz_date)
+            .set("x-amz-content-sha256", &payload_hash)
+            .set("Authorization", &authorization)
+    }
+}
+
+impl SyncBackend for S3Backend {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        match self.request("GET", key, b"").call() {
+            Ok(response) => {
+                let mut data = Vec::new();
+                response.into_reader().read_to_end(&mut data)?;
+                Ok(Some(data))
+            }
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(format!("S3 GET {} failed: {}", key, e).into()),
+        }
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.request("PUT", key, data)
+            .send_bytes(data)
+            .map_err(|e| format!("S3 PUT {} failed: {}", key, e))?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
+        match self.request("DELETE", key, b"").call() {
+            Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+            Err(e) => Err(format!("S3 DELETE {} failed: {}", key, e).into()),
+        }
+    }
+}
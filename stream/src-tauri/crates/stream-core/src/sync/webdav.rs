@@ -0,0 +1,82 @@
+use std::io::Read;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+use super::{encode_key, SyncBackend};
+
+/// A folder on a WebDAV server (Nextcloud, ownCloud, Apache mod_dav, ...)
+pub struct WebDavBackend {
+    base_url: String,
+    authorization: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl WebDavBackend {
+    pub fn new(url: &str, username: Option<&str>, password: Option<&str>) -> Self {
+        let authorization = username.map(|user| {
+            let credentials = format!("{}:{}", user, password.unwrap_or(""));
+            format!("Basic {}", STANDARD.encode(credentials))
+        });
+        WebDavBackend {
+            base_url: format!("{}/", url.trim_end_matches('/')),
+            authorization,
+            agent: crate::net::agent(),
+        }
+    }
+
+    fn url(&self, key: &str) -> String {
+        format!("{}{}", self.base_url, encode_key(key))
+    }
+
+    fn request(&self, method: &str, key: &str) -> ureq::Request {
+        let request = self.agent.request(method, &self.url(key));
+        match &self.authorization {
+            Some(auth) => request.set("Authorization", auth),
+            None => request,
+        }
+    }
+
+    /// WebDAV won't create intermediate collections on PUT, so make them first
+    fn ensure_parents(&self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let segments: Vec<&str> = key.split('/').collect();
+        for depth in 1..segments.len() {
+            let collection = format!("{}/", segments[..depth].join("/"));
+            match self.request("MKCOL", &collection).call() {
+                // 405 means the collection already exists
+                Ok(_) | Err(ureq::Error::Status(405, _)) => {}
+                Err(e) => return Err(format!("WebDAV MKCOL {} failed: {}", collection, e).into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl SyncBackend for WebDavBackend {
+    fn get(&self, key: &str) -> Result<Option<Vec<u8>>, Box<dyn std::error::Error>> {
+        match self.request("GET", key).call() {
+            Ok(response) => {
+                let mut data = Vec::new();
+                response.into_reader().read_to_end(&mut data)?;
+                Ok(Some(data))
+            }
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(format!("WebDAV GET {} failed: {}", key, e).into()),
+        }
+    }
+
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), Box<dyn std::error::Error>> {
+        self.ensure_parents(key)?;
+        self.request("PUT", key)
+            .send_bytes(data)
+            .map_err(|e| format!("WebDAV PUT {} failed: {}", key, e))?;
+        Ok(())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), Box<dyn std::error::Error>> {
+        match self.request("DELETE", key).call() {
+            Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+            Err(e) => Err(format!("WebDAV DELETE {} failed: {}", key, e).into()),
+        }
+    }
+}
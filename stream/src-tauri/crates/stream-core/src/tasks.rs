@@ -0,0 +1,44 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// A checkbox list item at any indent, marked with `-`, `*` or `+`
+static TASK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(\s*)[-*+]\s+\[([ xX])\](?:\s+(.*?))?\s*$").expect("Failed to compile task regex")
+});
+
+/// One checkbox line, e.g. `  - [x] Ship the release`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskLine<'a> {
+    /// Leading whitespace, in bytes
+    pub indent: usize,
+    pub done: bool,
+    /// The text after the checkbox, trimmed; empty for a bare `- [ ]`
+    pub text: &'a str,
+}
+
+/// The task on `line`, if it's a checkbox list item. Both `[x]` and `[X]` count as done
+pub fn parse_task_line(line: &str) -> Option<TaskLine<'_>> {
+    let captures = TASK_REGEX.captures(line)?;
+    Some(TaskLine {
+        indent: captures[1].len(),
+        done: &captures[2] != " ",
+        text: captures.get(3).map_or("", |text| text.as_str()),
+    })
+}
+
+/// Each task in `content` with the line it's on, skipping fenced code blocks
+pub fn tasks(content: &str) -> impl Iterator<Item = (&str, TaskLine<'_>)> {
+    let mut in_code_block = false;
+    content.lines().filter_map(move |line| {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code_block = !in_code_block;
+            return None;
+        }
+        if in_code_block {
+            return None;
+        }
+        parse_task_line(line).map(|task| (line, task))
+    })
+}
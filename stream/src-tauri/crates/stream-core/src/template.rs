@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate, TimeZone};
+use regex::{Captures, Regex};
+use serde_json::Value;
+
+use crate::calendar::{events_on, fetch_ics, CalendarEvent};
+use crate::git::get_git_commits_for_repos;
+use crate::markdown::{daily_notes_in_range, entry_day_bounds, read_location_xattrs};
+use crate::tasks::tasks;
+
+static PLACEHOLDER_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\{\{\s*([a-z_]+)\s*\}\}").expect("Failed to compile placeholder regex")
+});
+
+/// Folder in the notes directory holding named note templates, e.g. `templates/standup.md`.
+/// Directory walks skip it, so templates never show up as notes
+pub const TEMPLATES_DIR: &str = "templates";
+
+/// How far back to look for the previous note when carrying over tasks or location
+const PREVIOUS_NOTE_LOOKBACK_DAYS: i64 = 30;
+const WEATHER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Backend data available to placeholders when a note is created
+#[derive(Debug, Default)]
+pub struct TemplateContext {
+    pub repo_paths: Vec<String>,
+    /// City for `{{location}}` and `{{weather}}`; defaults to the city recorded on the previous note
+    pub location: Option<String>,
+    /// iCalendar feed for `{{calendar_today}}`
+    pub calendar_feed: Option<String>,
+}
+
+/// Fill in `{{placeholders}}` for the note dated `date`. Each placeholder is only resolved
+/// if the template uses it, so a plain template never touches git or the network.
+/// Unknown placeholders are left as-is
+pub fn render_template(
+    template: &str,
+    directory_path: &Path,
+    date: NaiveDate,
+    context: &TemplateContext,
+) -> String {
+    PLACEHOLDER_REGEX
+        .replace_all(template, |caps: &Captures| {
+            let value = match &caps[1] {
+                "date" => Some(date.format("%Y-%m-%d").to_string()),
+                "weekday" => Some(date.format("%A").to_string()),
+                "yesterday" => date.pred_opt().map(|d| d.format("%Y-%m-%d").to_string()),
+                "location" => Some(location(directory_path, date, context).unwrap_or_default()),
+                "commits_yesterday" => Some(commits_yesterday(directory_path, date, context)),
+                "open_tasks" => Some(open_tasks(directory_path, date)),
+                "weather" => Some(weather(directory_path, date, context)),
+                "calendar_today" => Some(calendar_today(date, context)),
+                _ => None,
+            };
+            value.unwrap_or_else(|| caps[0].to_string())
+        })
+        .to_string()
+}
+
+/// The template `templates/<name>.md` in the notes directory. `name` may include the
+/// extension but not a folder
+pub fn read_named_template(
+    directory_path: &Path,
+    name: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let name = name.trim();
+    let name = name.strip_suffix(".md").unwrap_or(name);
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(format!("Invalid template name '{}'", name).into());
+    }
+
+    let path = directory_path
+        .join(TEMPLATES_DIR)
+        .join(format!("{}.md", name));
+    match fs::read_to_string(&path) {
+        Ok(template) => Ok(template),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(format!("No template named '{}' in {}", name, TEMPLATES_DIR).into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn previous_note(directory_path: &Path, date: NaiveDate) -> Option<std::path::PathBuf> {
+    let start = date - chrono::Duration::days(PREVIOUS_NOTE_LOOKBACK_DAYS);
+    let end = date.pred_opt()?;
+    daily_notes_in_range(directory_path, start, end)
+        .ok()?
+        .pop()
+        .map(|(_, path)| path)
+}
+
+fn commits_yesterday(directory_path: &Path, date: NaiveDate, context: &TemplateContext) -> String {
+    let bounds = date
+        .pred_opt()
+        .and_then(|yesterday| entry_day_bounds(directory_path, yesterday));
+    let Some((start_seconds, end_seconds)) = bounds else {
+        return String::new();
+    };
+
+    let repos = get_git_commits_for_repos(
+        &context.repo_paths,
+        start_seconds as u64 * 1000,
+        end_seconds as u64 * 1000,
+    );
+
+    let mut lines = Vec::new();
+    for repo in &repos {
+        let name = Path::new(&repo.repo_path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| repo.repo_path.clone());
+        for commit in &repo.commits {
+            let summary = commit.message.lines().next().unwrap_or("").trim();
+            lines.push(format!("- {} ({})", summary, name));
+        }
+    }
+
+    if lines.is_empty() {
+        "- No commits".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+/// Unchecked tasks carried over from the most recent earlier note
+fn open_tasks(directory_path: &Path, date: NaiveDate) -> String {
+    let content = previous_note(directory_path, date)
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+
+    tasks(&content)
+        .filter(|(_, task)| !task.done)
+        .map(|(line, _)| line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The given city, else the one recorded on the previous note
+fn location(directory_path: &Path, date: NaiveDate, context: &TemplateContext) -> Option<String> {
+    context.location.clone().or_else(|| {
+        previous_note(directory_path, date).and_then(|path| read_location_xattrs(&path).1)
+    })
+}
+
+fn weather(directory_path: &Path, date: NaiveDate, context: &TemplateContext) -> String {
+    match location(directory_path, date, context) {
+        Some(city) => fetch_weather(&city, date).unwrap_or_else(|e| {
+            eprintln!("Failed to fetch weather for {}: {}", city, e);
+            String::new()
+        }),
+        None => String::new(),
+    }
+}
+
+/// Daily forecast from Open-Meteo, which needs no API key
+fn fetch_weather(city: &str, date: NaiveDate) -> Result<String, Box<dyn std::error::Error>> {
+    let agent = crate::net::agent_builder().timeout(WEATHER_TIMEOUT).build();
+
+    let places: Value = agent
+        .get("https://geocoding-api.open-meteo.com/v1/search")
+        .query("name", city)
+        .query("count", "1")
+        .call()?
+        .into_json()?;
+    let place = places
+        .get("results")
+        .and_then(|r| r.get(0))
+        .ok_or_else(|| format!("Unknown location: {}", city))?;
+    let latitude = place.get("latitude").and_then(Value::as_f64).unwrap_or(0.0);
+    let longitude = place
+        .get("longitude")
+        .and_then(Value::as_f64)
+        .unwrap_or(0.0);
+
+    let day = date.format("%Y-%m-%d").to_string();
+    let forecast: Value = agent
+        .get("https://api.open-meteo.com/v1/forecast")
+        .query("latitude", &latitude.to_string())
+        .query("longitude", &longitude.to_string())
+        .query(
+            "daily",
+            "weather_code,temperature_2m_max,temperature_2m_min",
+        )
+        .query("timezone", "auto")
+        .query("start_date", &day)
+        .query("end_date", &day)
+        .call()?
+        .into_json()?;
+
+    let daily = forecast.get("daily").ok_or("Forecast missing daily data")?;
+    let first = |key: &str| {
+        daily
+            .get(key)
+            .and_then(|v| v.get(0))
+            .and_then(Value::as_f64)
+    };
+    let code = first("weather_code").unwrap_or(-1.0) as i64;
+    let (min, max) = match (first("temperature_2m_min"), first("temperature_2m_max")) {
+        (Some(min), Some(max)) => (min, max),
+        _ => return Err("Forecast missing temperatures".into()),
+    };
+
+    Ok(format!(
+        "{}, {:.0}–{:.0}°C in {}",
+        weather_description(code),
+        min,
+        max,
+        city
+    ))
+}
+
+/// WMO weather interpretation codes, grouped
+fn event_line(event: &CalendarEvent) -> String {
+    let mut line = if event.all_day {
+        format!("- All day: {}", event.title)
+    } else {
+        let time = Local
+            .timestamp_millis_opt(event.start as i64)
+            .single()
+            .map(|start| start.format("%H:%M").to_string())
+            .unwrap_or_default();
+        format!("- {} {}", time, event.title)
+    };
+    if let Some(location) = &event.location {
+        line.push_str(&format!(" ({})", location));
+    }
+    line
+}
+
+/// The day's events from the calendar feed, one per line
+fn calendar_today(date: NaiveDate, context: &TemplateContext) -> String {
+    let Some(url) = &context.calendar_feed else {
+        return String::new();
+    };
+    let ics = match fetch_ics(url) {
+        Ok(ics) => ics,
+        Err(e) => {
+            eprintln!("Failed to fetch calendar feed: {}", e);
+            return String::new();
+        }
+    };
+
+    let lines: Vec<String> = events_on(&ics, date).iter().map(event_line).collect();
+    if lines.is_empty() {
+        "- No events".to_string()
+    } else {
+        lines.join("\n")
+    }
+}
+
+fn weather_description(code: i64) -> &'static str {
+    match code {
+        0 => "Clear",
+        1 | 2 => "Partly cloudy",
+        3 => "Overcast",
+        45 | 48 => "Fog",
+        51..=57 => "Drizzle",
+        61..=67 | 80..=82 => "Rain",
+        71..=77 | 85 | 86 => "Snow",
+        95..=99 => "Thunderstorms",
+        _ => "Unknown conditions",
+    }
+}
@@ -0,0 +1,232 @@
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::UNIX_EPOCH;
+
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::capture::ASSETS_DIR;
+use crate::focus::CompletedFocusSession;
+use crate::git;
+use crate::markdown::{entry_day_bounds, structured_markdown_paths};
+use crate::search::find_markdown_files;
+
+/// Assets are named `<prefix>-YYYYMMDD-HHMMSS[-n].<ext>` by `save_asset`
+static ASSET_NAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^([a-z]+)-(\d{8}-\d{6})(?:-\d+)?\.\w+$").expect("Failed to compile asset regex")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimelineSource {
+    Commits,
+    Notes,
+    Focus,
+    Captures,
+}
+
+impl TimelineSource {
+    pub const ALL: [TimelineSource; 4] = [
+        TimelineSource::Commits,
+        TimelineSource::Notes,
+        TimelineSource::Focus,
+        TimelineSource::Captures,
+    ];
+
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "commits" => Some(TimelineSource::Commits),
+            "notes" => Some(TimelineSource::Notes),
+            "focus" => Some(TimelineSource::Focus),
+            "captures" => Some(TimelineSource::Captures),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineEventKind {
+    Commit,
+    NoteCreated,
+    NoteEdited,
+    FocusSession,
+    Capture,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineEvent {
+    pub kind: TimelineEventKind,
+    /// Unix milliseconds
+    pub timestamp: u64,
+    /// Set for events with a duration, like focus sessions
+    pub end_timestamp: Option<u64>,
+    pub title: String,
+    pub detail: Option<String>,
+    pub path: Option<String>,
+    pub url: Option<String>,
+}
+
+/// Everything the day's timeline is built from, beyond what's on disk
+pub struct TimelineInputs<'a> {
+    pub repo_paths: &'a [String],
+    pub focus_sessions: &'a [CompletedFocusSession],
+}
+
+fn millis(time: std::io::Result<std::time::SystemTime>) -> Option<u64> {
+    time.ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+fn commit_events(repo_paths: &[String], start_ms: u64, end_ms: u64) -> Vec<TimelineEvent> {
+    git::get_git_commits_for_repos(repo_paths, start_ms, end_ms)
+        .into_iter()
+        // Oldest first, so commits made in the same second keep their order after sorting
+        .flat_map(|repo| repo.commits.into_iter().rev())
+        .map(|commit| TimelineEvent {
+            kind: TimelineEventKind::Commit,
+            timestamp: commit.timestamp,
+            end_timestamp: None,
+            title: commit
+                .message
+                .lines()
+                .next()
+                .unwrap_or("")
+                .trim()
+                .to_string(),
+            detail: Some(commit.id[..7.min(commit.id.len())].to_string()),
+            path: Some(commit.repo_path),
+            url: commit.url,
+        })
+        .collect()
+}
+
+/// Creation and last edit of daily and structured notes that fall within the day
+fn note_events(directory_path: &Path, start_ms: u64, end_ms: u64) -> Vec<TimelineEvent> {
+    let mut paths: Vec<String> =
+        find_markdown_files(&directory_path.to_string_lossy()).unwrap_or_default();
+    paths.extend(
+        structured_markdown_paths(directory_path)
+            .into_iter()
+            .map(|path| path.to_string_lossy().to_string()),
+    );
+
+    let in_day = |ms: u64| ms >= start_ms && ms <= end_ms;
+    let mut events = Vec::new();
+    for path in paths {
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        let title = Path::new(&path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        let created = millis(metadata.created());
+        let modified = millis(metadata.modified());
+
+        let mut push = |kind, timestamp| {
+            events.push(TimelineEvent {
+                kind,
+                timestamp,
+                end_timestamp: None,
+                title: title.clone(),
+                detail: None,
+                path: Some(path.clone()),
+                url: None,
+            })
+        };
+        if let Some(created) = created.filter(|ms| in_day(*ms)) {
+            push(TimelineEventKind::NoteCreated, created);
+        }
+        // Writing a new file bumps both timestamps; only report later edits
+        if let Some(modified) = modified.filter(|ms| in_day(*ms) && Some(*ms) != created) {
+            push(TimelineEventKind::NoteEdited, modified);
+        }
+    }
+    events
+}
+
+fn focus_events(
+    sessions: &[CompletedFocusSession],
+    start_ms: u64,
+    end_ms: u64,
+) -> Vec<TimelineEvent> {
+    sessions
+        .iter()
+        .filter(|session| session.started_at <= end_ms && session.ended_at >= start_ms)
+        .map(|session| TimelineEvent {
+            kind: TimelineEventKind::FocusSession,
+            timestamp: session.started_at,
+            end_timestamp: Some(session.ended_at),
+            title: session.label.clone(),
+            detail: Some(format!("{} min", session.duration_ms / 60_000)),
+            path: None,
+            url: None,
+        })
+        .collect()
+}
+
+/// Screenshots, clipboard images, voice memos and archived pages saved to the assets folder
+fn capture_events(directory_path: &Path, start_ms: u64, end_ms: u64) -> Vec<TimelineEvent> {
+    let entries = match fs::read_dir(directory_path.join(ASSETS_DIR)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|path| {
+            let file_name = path.file_name()?.to_str()?.to_string();
+            let caps = ASSET_NAME_REGEX.captures(&file_name)?;
+            let captured_at = NaiveDateTime::parse_from_str(&caps[2], "%Y%m%d-%H%M%S").ok()?;
+            let timestamp = Local
+                .from_local_datetime(&captured_at)
+                .earliest()?
+                .timestamp_millis() as u64;
+            if timestamp < start_ms || timestamp > end_ms {
+                return None;
+            }
+
+            Some(TimelineEvent {
+                kind: TimelineEventKind::Capture,
+                timestamp,
+                end_timestamp: None,
+                title: caps[1].to_string(),
+                detail: None,
+                path: Some(path.to_string_lossy().to_string()),
+                url: None,
+            })
+        })
+        .collect()
+}
+
+/// Merge the enabled sources into one list of events for the day a daily note covers,
+/// oldest first
+pub fn build_timeline(
+    directory_path: &Path,
+    date: NaiveDate,
+    sources: &[TimelineSource],
+    inputs: &TimelineInputs,
+) -> Result<Vec<TimelineEvent>, Box<dyn std::error::Error>> {
+    let (start_seconds, end_seconds) = entry_day_bounds(directory_path, date)
+        .ok_or_else(|| format!("Invalid local date: {}", date))?;
+    let (start_ms, end_ms) = (start_seconds as u64 * 1000, end_seconds as u64 * 1000 + 999);
+
+    let mut events = Vec::new();
+    for source in sources {
+        events.extend(match source {
+            TimelineSource::Commits => commit_events(inputs.repo_paths, start_ms, end_ms),
+            TimelineSource::Notes => note_events(directory_path, start_ms, end_ms),
+            TimelineSource::Focus => focus_events(inputs.focus_sessions, start_ms, end_ms),
+            TimelineSource::Captures => capture_events(directory_path, start_ms, end_ms),
+        });
+    }
+
+    events.sort_by_key(|event| event.timestamp);
+    Ok(events)
+}
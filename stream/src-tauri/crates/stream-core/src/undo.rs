@@ -0,0 +1,305 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+use crate::clock::now_ms;
+use crate::file_lock::with_note_lock;
+use crate::markdown::{read_all_user_xattrs, write_user_xattrs};
+
+const JOURNAL_FILE: &str = "journal.json";
+const BLOBS_DIR: &str = "blobs";
+
+/// Oldest operations are dropped once the saved copies exceed this
+const MAX_UNDO_BYTES: u64 = 100 * 1024 * 1024;
+const MAX_UNDO_ENTRIES: usize = 100;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UndoKind {
+    Delete,
+    Merge,
+    MetadataOverwrite,
+    Import,
+    Rollover,
+    /// A bulk change across the vault, such as a passphrase rotation or metadata repair
+    Migration,
+}
+
+/// State of one file before an operation. `blob` names the saved content and `xattrs` the
+/// saved metadata; either is absent when the operation didn't touch it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileSnapshot {
+    pub file_path: String,
+    pub existed: bool,
+    pub blob: Option<String>,
+    pub xattrs: Option<BTreeMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndoEntry {
+    pub id: String,
+    pub kind: UndoKind,
+    pub description: String,
+    pub created_at: u64,
+    pub files: Vec<FileSnapshot>,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UndoSummary {
+    pub kind: UndoKind,
+    pub description: String,
+    pub restored: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+/// Journal of the prior state of files changed by destructive operations, kept under `dir`
+/// so the most recent operation can be rolled back
+pub struct UndoJournal {
+    dir: PathBuf,
+    entries: Mutex<Vec<UndoEntry>>,
+}
+
+impl UndoJournal {
+    pub fn load(dir: &Path) -> Self {
+        let entries = fs::read(dir.join(JOURNAL_FILE))
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default();
+        UndoJournal {
+            dir: dir.to_path_buf(),
+            entries: Mutex::new(entries),
+        }
+    }
+
+    fn save(&self, entries: &[UndoEntry]) -> Result<(), Box<dyn std::error::Error>> {
+        fs::create_dir_all(&self.dir)?;
+        fs::write(
+            self.dir.join(JOURNAL_FILE),
+            serde_json::to_vec_pretty(entries)?,
+        )?;
+        Ok(())
+    }
+
+    fn blob_path(&self, blob: &str) -> PathBuf {
+        self.dir.join(BLOBS_DIR).join(blob)
+    }
+
+    fn remove_blobs(&self, entry: &UndoEntry) {
+        for blob in entry.files.iter().filter_map(|f| f.blob.as_deref()) {
+            let _ = fs::remove_file(self.blob_path(blob));
+        }
+    }
+
+    /// Save the content and metadata of `paths` before `kind` changes or removes them.
+    /// Paths that don't exist yet are recorded so undo removes what the operation created.
+    /// Returns the entry's id, which `undo` takes
+    pub fn record_files(
+        &self,
+        kind: UndoKind,
+        description: &str,
+        paths: &[PathBuf],
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let id = format!("{}-{}", now_ms(), NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        fs::create_dir_all(self.dir.join(BLOBS_DIR))?;
+
+        let mut files = Vec::with_capacity(paths.len());
+        let mut size = 0;
+        for (index, path) in paths.iter().enumerate() {
+            let mut snapshot = FileSnapshot {
+                file_path: path.to_string_lossy().to_string(),
+                existed: path.is_file(),
+                blob: None,
+                xattrs: None,
+            };
+
+            if snapshot.existed {
+                let content = fs::read(path)?;
+                size += content.len() as u64;
+                if size > MAX_UNDO_BYTES {
+                    for blob in files
+                        .iter()
+                        .filter_map(|f: &FileSnapshot| f.blob.as_deref())
+                    {
+                        let _ = fs::remove_file(self.blob_path(blob));
+                    }
+                    return Err("Operation is too large to keep an undo copy".into());
+                }
+
+                let blob = format!("{}-{}", id, index);
+                fs::write(self.blob_path(&blob), content)?;
+                snapshot.blob = Some(blob);
+                snapshot.xattrs = Some(read_all_user_xattrs(path));
+            }
+            files.push(snapshot);
+        }
+
+        self.push(UndoEntry {
+            id: id.clone(),
+            kind,
+            description: description.to_string(),
+            created_at: now_ms(),
+            files,
+            size,
+        })?;
+        Ok(id)
+    }
+
+    /// Save the metadata of `paths` before `kind` rewrites their xattrs, leaving content
+    /// alone. Returns the entry's id
+    pub fn record_xattrs(
+        &self,
+        kind: UndoKind,
+        description: &str,
+        paths: &[PathBuf],
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let id = format!("{}-{}", now_ms(), NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        let files = paths
+            .iter()
+            .map(|path| FileSnapshot {
+                file_path: path.to_string_lossy().to_string(),
+                existed: path.is_file(),
+                blob: None,
+                xattrs: Some(read_all_user_xattrs(path)),
+            })
+            .collect();
+        self.push(UndoEntry {
+            id: id.clone(),
+            kind,
+            description: description.to_string(),
+            created_at: now_ms(),
+            files,
+            size: 0,
+        })?;
+        Ok(id)
+    }
+
+    /// Save a file's metadata before its xattrs are overwritten
+    pub fn record_metadata(
+        &self,
+        description: &str,
+        path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let id = format!("{}-{}", now_ms(), NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        self.push(UndoEntry {
+            id,
+            kind: UndoKind::MetadataOverwrite,
+            description: description.to_string(),
+            created_at: now_ms(),
+            files: vec![FileSnapshot {
+                file_path: path.to_string_lossy().to_string(),
+                existed: path.is_file(),
+                blob: None,
+                xattrs: Some(read_all_user_xattrs(path)),
+            }],
+            size: 0,
+        })
+    }
+
+    fn push(&self, entry: UndoEntry) -> Result<(), Box<dyn std::error::Error>> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| "Undo journal is unavailable")?;
+        entries.push(entry);
+
+        let mut total: u64 = entries.iter().map(|e| e.size).sum();
+        while entries.len() > MAX_UNDO_ENTRIES || (total > MAX_UNDO_BYTES && entries.len() > 1) {
+            let evicted = entries.remove(0);
+            total -= evicted.size;
+            self.remove_blobs(&evicted);
+        }
+
+        self.save(&entries)
+    }
+
+    /// Recorded operations, oldest first
+    pub fn entries(&self) -> Vec<UndoEntry> {
+        self.entries
+            .lock()
+            .map(|entries| entries.clone())
+            .unwrap_or_default()
+    }
+
+    /// Put back every file the most recent operation changed. Returns None when there is
+    /// nothing to undo
+    pub fn undo_last(&self) -> Result<Option<UndoSummary>, Box<dyn std::error::Error>> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| "Undo journal is unavailable")?;
+        match entries.len() {
+            0 => Ok(None),
+            len => self.restore(&mut entries, len - 1).map(Some),
+        }
+    }
+
+    /// Put back every file the operation recorded as `id` changed, whether or not it was
+    /// the most recent. Changes made to those files since are lost. Returns None when the
+    /// entry isn't in the journal, e.g. because it was dropped to make room
+    pub fn undo(&self, id: &str) -> Result<Option<UndoSummary>, Box<dyn std::error::Error>> {
+        let mut entries = self
+            .entries
+            .lock()
+            .map_err(|_| "Undo journal is unavailable")?;
+        match entries.iter().position(|entry| entry.id == id) {
+            Some(index) => self.restore(&mut entries, index).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn restore(
+        &self,
+        entries: &mut Vec<UndoEntry>,
+        index: usize,
+    ) -> Result<UndoSummary, Box<dyn std::error::Error>> {
+        let entry = entries[index].clone();
+        let mut summary = UndoSummary {
+            kind: entry.kind,
+            description: entry.description.clone(),
+            restored: Vec::new(),
+            removed: Vec::new(),
+        };
+
+        for snapshot in entry.files.iter().rev() {
+            let path = Path::new(&snapshot.file_path);
+
+            if !snapshot.existed {
+                if path.exists() {
+                    fs::remove_file(path)?;
+                    summary.removed.push(snapshot.file_path.clone());
+                }
+                continue;
+            }
+
+            if let Some(blob) = &snapshot.blob {
+                let content = fs::read(self.blob_path(blob))?;
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                with_note_lock(path, || Ok(fs::write(path, content)?))?;
+            }
+            if let Some(xattrs) = &snapshot.xattrs {
+                for name in read_all_user_xattrs(path).keys() {
+                    if !xattrs.contains_key(name) {
+                        let _ = xattr::remove(path, name);
+                    }
+                }
+                write_user_xattrs(path, xattrs)?;
+            }
+            summary.restored.push(snapshot.file_path.clone());
+        }
+
+        entries.remove(index);
+        self.remove_blobs(&entry);
+        self.save(entries)?;
+
+        Ok(summary)
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::LazyLock;
+
+use rayon::prelude::*;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::clock::now_ms;
+use crate::sources::url::{decode_entities, fetch_text};
+
+/// Cached previews are refetched after this long, but still used if the refetch fails
+const CACHE_TTL_MS: u64 = 30 * 24 * 60 * 60 * 1000;
+
+static TITLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?is)<title[^>]*>(.*?)</title>").expect("Failed to compile title regex")
+});
+static META_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?is)<meta\s[^>]*>").expect("Failed to compile meta regex"));
+static ATTR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?is)(name|property|content)\s*=\s*("([^"]*)"|'([^']*)')"#)
+        .expect("Failed to compile attribute regex")
+});
+// A URL at the start of a line or after whitespace, so `[text](url)` and `<url>` are skipped.
+// Trailing punctuation is left out of the link
+static BARE_URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(^|\s)(https?://[^\s<>\[\]()]*[^\s<>\[\]().,;:!?'"])"#)
+        .expect("Failed to compile bare URL regex")
+});
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UrlPreview {
+    pub url: String,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub fetched_at: u64,
+}
+
+/// Previews keyed by URL, persisted so links resolve offline
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UnfurlCache {
+    entries: HashMap<String, UrlPreview>,
+}
+
+impl UnfurlCache {
+    pub fn load(path: &Path) -> Self {
+        fs::read(path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, serde_json::to_vec(self)?)?;
+        Ok(())
+    }
+
+    fn fresh(&self, url: &str, now: u64) -> Option<&UrlPreview> {
+        self.entries
+            .get(url)
+            .filter(|p| now.saturating_sub(p.fetched_at) < CACHE_TTL_MS)
+    }
+}
+
+fn clean(text: &str) -> Option<String> {
+    let text = decode_entities(
+        text.split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .as_str(),
+    );
+    (!text.is_empty()).then_some(text)
+}
+
+/// Title and description from a page, preferring Open Graph tags
+pub(crate) fn parse_preview(url: &str, html: &str) -> UrlPreview {
+    let mut meta: HashMap<String, String> = HashMap::new();
+    for tag in META_REGEX.find_iter(html) {
+        let mut key = None;
+        let mut content = None;
+        for attr in ATTR_REGEX.captures_iter(tag.as_str()) {
+            let value = attr.get(3).or_else(|| attr.get(4)).map(|m| m.as_str());
+            match attr[1].to_lowercase().as_str() {
+                "content" => content = value,
+                _ => key = value.map(|v| v.to_lowercase()),
+            }
+        }
+        if let (Some(key), Some(content)) = (key, content) {
+            meta.entry(key).or_insert_with(|| content.to_string());
+        }
+    }
+
+    let title = meta
+        .get("og:title")
+        .and_then(|t| clean(t))
+        .or_else(|| TITLE_REGEX.captures(html).and_then(|c| clean(&c[1])));
+    let description = meta
+        .get("og:description")
+        .or_else(|| meta.get("description"))
+        .and_then(|d| clean(d));
+
+    UrlPreview {
+        url: url.to_string(),
+        title,
+        description,
+        fetched_at: now_ms(),
+    }
+}
+
+fn fetch_preview(url: &str) -> Result<UrlPreview, Box<dyn std::error::Error>> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(format!("Not a web URL: {}", url).into());
+    }
+    let (body, _) = fetch_text(url)?;
+    Ok(parse_preview(url, &body))
+}
+
+/// Title and description for a URL, from the cache when fresh. A stale cache entry is
+/// returned if the page can't be fetched, e.g. while offline
+pub fn unfurl_url(
+    url: &str,
+    cache: &mut UnfurlCache,
+) -> Result<UrlPreview, Box<dyn std::error::Error>> {
+    unfurl_urls(&[url.to_string()], cache)
+        .pop()
+        .ok_or("No preview")?
+        .map_err(|e| e.into())
+}
+
+/// Batch `unfurl_url`; uncached pages are fetched in parallel. Results keep input order
+pub fn unfurl_urls(urls: &[String], cache: &mut UnfurlCache) -> Vec<Result<UrlPreview, String>> {
+    let now = now_ms();
+    let mut missing: Vec<&String> = urls
+        .iter()
+        .filter(|u| cache.fresh(u, now).is_none())
+        .collect();
+    missing.sort();
+    missing.dedup();
+
+    let fetched: Vec<(String, Result<UrlPreview, String>)> = missing
+        .par_iter()
+        .map(|url| {
+            (
+                url.to_string(),
+                fetch_preview(url).map_err(|e| e.to_string()),
+            )
+        })
+        .collect();
+
+    let mut errors: HashMap<String, String> = HashMap::new();
+    for (url, result) in fetched {
+        match result {
+            Ok(preview) => {
+                cache.entries.insert(url, preview);
+            }
+            Err(e) => {
+                errors.insert(url, e);
+            }
+        }
+    }
+
+    urls.iter()
+        .map(|url| match cache.entries.get(url) {
+            Some(preview) => Ok(preview.clone()),
+            None => Err(errors
+                .get(url)
+                .cloned()
+                .unwrap_or_else(|| "No preview".to_string())),
+        })
+        .collect()
+}
+
+/// Rewrite bare URLs in a note as `[title](url)` links. Code blocks and inline code are
+/// left alone, as are URLs whose title can't be found. Returns the new text and the
+/// number of links rewritten
+pub fn link_bare_urls(content: &str, cache: &mut UnfurlCache) -> (String, usize) {
+    let mut in_code_block = false;
+    let mut urls = Vec::new();
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+        // Even segments between backticks are outside inline code
+        for segment in line.split('`').step_by(2) {
+            urls.extend(
+                BARE_URL_REGEX
+                    .captures_iter(segment)
+                    .map(|c| c[2].to_string()),
+            );
+        }
+    }
+    if urls.is_empty() {
+        return (content.to_string(), 0);
+    }
+
+    let titles: HashMap<String, String> = urls
+        .iter()
+        .zip(unfurl_urls(&urls, cache))
+        .filter_map(|(url, result)| Some((url.clone(), result.ok()?.title?)))
+        .collect();
+
+    let mut replaced = 0;
+    let mut in_code_block = false;
+    let mut out: Vec<String> = Vec::new();
+    for line in content.split('\n') {
+        if line.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+            out.push(line.to_string());
+            continue;
+        }
+        if in_code_block {
+            out.push(line.to_string());
+            continue;
+        }
+
+        let segments: Vec<String> = line
+            .split('`')
+            .enumerate()
+            .map(|(index, segment)| {
+                if index % 2 == 1 {
+                    return segment.to_string();
+                }
+                BARE_URL_REGEX
+                    .replace_all(segment, |caps: &regex::Captures| {
+                        match titles.get(&caps[2]) {
+                            Some(title) => {
+                                replaced += 1;
+                                let title = title.replace('[', "(").replace(']', ")");
+                                format!("{}[{}]({})", &caps[1], title, &caps[2])
+                            }
+                            None => caps[0].to_string(),
+                        }
+                    })
+                    .to_string()
+            })
+            .collect();
+        out.push(segments.join("`"));
+    }
+
+    (out.join("\n"), replaced)
+}
@@ -0,0 +1,97 @@
+use std::fs::Metadata;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use ignore::{DirEntry, WalkBuilder, WalkState};
+
+use crate::capture::ASSETS_DIR;
+use crate::template::TEMPLATES_DIR;
+
+/// Folders never scanned at any depth: git's own data and the trash
+const EXCLUDED_DIRS: &[&str] = &[".git", ".trash"];
+
+/// Worker threads for directory walks. 0 picks a count from the available cores
+static WALK_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Set how many threads directory walks use; 0 restores the automatic default
+pub fn set_walk_threads(threads: usize) {
+    WALK_THREADS.store(threads, Ordering::Relaxed);
+}
+
+pub fn walk_threads() -> usize {
+    WALK_THREADS.load(Ordering::Relaxed)
+}
+
+fn is_excluded(entry: &DirEntry) -> bool {
+    if !entry
+        .file_type()
+        .is_some_and(|file_type| file_type.is_dir())
+    {
+        return false;
+    }
+    let name = entry.file_name().to_string_lossy();
+    EXCLUDED_DIRS.contains(&name.as_ref())
+        || (entry.depth() == 1 && (name == ASSETS_DIR || name == TEMPLATES_DIR))
+}
+
+/// Whether `path` lies in a folder the walks under `root` skip
+pub fn is_excluded_path(root: &Path, path: &Path) -> bool {
+    let Ok(relative) = path.strip_prefix(root) else {
+        return false;
+    };
+    let mut folders: Vec<_> = relative.components().collect();
+    folders.pop();
+    folders.iter().enumerate().any(|(depth, folder)| {
+        let name = folder.as_os_str().to_string_lossy();
+        EXCLUDED_DIRS.contains(&name.as_ref())
+            || (depth == 0 && (name == ASSETS_DIR || name == TEMPLATES_DIR))
+    })
+}
+
+/// Walk every file under `root` on a pool of threads, collecting what `visit` returns.
+/// Symlinks are followed (with loop detection) and hidden files are included. When `root`
+/// is in a git repository its `.gitignore` and exclude files apply, and `.git`, `.trash`
+/// and the captured assets folder are always skipped. Entries that can't be read are
+/// skipped rather than failing the walk. Results are in no particular order
+pub fn walk_files<T, F>(root: &Path, visit: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(&Path, &Metadata) -> Option<T> + Sync,
+{
+    if !root.is_dir() {
+        return Vec::new();
+    }
+
+    let results = Mutex::new(Vec::new());
+    WalkBuilder::new(root)
+        .standard_filters(false)
+        .git_ignore(true)
+        .git_exclude(true)
+        .git_global(true)
+        .filter_entry(|entry| !is_excluded(entry))
+        .follow_links(true)
+        .threads(walk_threads())
+        .build_parallel()
+        .run(|| {
+            let (visit, results) = (&visit, &results);
+            Box::new(move |entry| {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(_) => return WalkState::Continue,
+                };
+                let metadata = match entry.metadata() {
+                    Ok(metadata) if metadata.is_file() => metadata,
+                    _ => return WalkState::Continue,
+                };
+                if let Some(item) = visit(entry.path(), &metadata) {
+                    if let Ok(mut results) = results.lock() {
+                        results.push(item);
+                    }
+                }
+                WalkState::Continue
+            })
+        });
+
+    results.into_inner().unwrap_or_default()
+}
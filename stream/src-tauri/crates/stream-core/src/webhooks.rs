@@ -0,0 +1,177 @@
+use std::thread;
+use std::time::Duration;
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+
+use crate::clock::now_ms;
+
+const USER_AGENT: &str = "stream-webhooks";
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Attempts per delivery, waiting twice as long after each failure
+const MAX_ATTEMPTS: u32 = 4;
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Receivers verify this HMAC-SHA256 of the raw body, keyed with the webhook's secret
+pub const SIGNATURE_HEADER: &str = "X-Stream-Signature";
+pub const EVENT_HEADER: &str = "X-Stream-Event";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    NoteCreated,
+    /// A weekly digest or an AI summary of the day was written
+    SummaryGenerated,
+    /// A structured file or daily note was refreshed
+    RefreshCompleted,
+}
+
+impl WebhookEvent {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::NoteCreated => "note_created",
+            WebhookEvent::SummaryGenerated => "summary_generated",
+            WebhookEvent::RefreshCompleted => "refresh_completed",
+        }
+    }
+
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s {
+            "note_created" => Some(WebhookEvent::NoteCreated),
+            "summary_generated" => Some(WebhookEvent::SummaryGenerated),
+            "refresh_completed" => Some(WebhookEvent::RefreshCompleted),
+            _ => None,
+        }
+    }
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// A URL that activity is POSTed to. Its signing secret is kept by the caller
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub id: String,
+    pub url: String,
+    pub events: Vec<WebhookEvent>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+impl Webhook {
+    pub fn wants(&self, event: WebhookEvent) -> bool {
+        self.enabled && self.events.contains(&event)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    pub webhook_id: String,
+    pub event: WebhookEvent,
+    /// Status of the last response, if the receiver answered at all
+    pub status: Option<u16>,
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+pub fn validate_webhook(webhook: &Webhook) -> Result<(), Box<dyn std::error::Error>> {
+    if webhook.id.trim().is_empty() {
+        return Err("Webhook id is empty".into());
+    }
+    if !webhook.url.starts_with("https://") && !webhook.url.starts_with("http://") {
+        return Err(format!("Webhook URL must be http(s): {}", webhook.url).into());
+    }
+    Ok(())
+}
+
+/// Hex HMAC-SHA256 of `body`, sent as `sha256=<hex>`
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts any key length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// The JSON body for `event`, with event-specific fields under `data`
+pub fn webhook_payload(event: WebhookEvent, data: &Value) -> Value {
+    let timestamp = now_ms();
+    json!({
+        "event": event.as_str(),
+        "timestamp": timestamp,
+        "data": data,
+    })
+}
+
+/// Network failures, rate limiting and server errors may pass; other statuses won't
+fn is_retryable(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::Status(code, _) => *code == 429 || *code >= 500,
+        ureq::Error::Transport(_) => true,
+    }
+}
+
+/// POST `event` to the webhook, signed with `secret` when there is one, retrying with
+/// backoff on failures that may pass. Blocks for the retries, so run it off the main thread
+pub fn deliver_webhook(
+    webhook: &Webhook,
+    secret: Option<&str>,
+    event: WebhookEvent,
+    data: &Value,
+) -> WebhookDelivery {
+    let mut delivery = WebhookDelivery {
+        webhook_id: webhook.id.clone(),
+        event,
+        status: None,
+        attempts: 0,
+        error: None,
+    };
+    let body = match serde_json::to_vec(&webhook_payload(event, data)) {
+        Ok(body) => body,
+        Err(e) => {
+            delivery.error = Some(e.to_string());
+            return delivery;
+        }
+    };
+    let signature = secret.map(|secret| sign_payload(secret, &body));
+    let agent = crate::net::agent_builder()
+        .timeout(DELIVERY_TIMEOUT)
+        .build();
+
+    let mut delay = FIRST_RETRY_DELAY;
+    loop {
+        delivery.attempts += 1;
+        let mut request = agent
+            .post(&webhook.url)
+            .set("User-Agent", USER_AGENT)
+            .set("Content-Type", "application/json")
+            .set(EVENT_HEADER, event.as_str());
+        if let Some(signature) = &signature {
+            request = request.set(SIGNATURE_HEADER, signature);
+        }
+
+        match request.send_bytes(&body) {
+            Ok(response) => {
+                delivery.status = Some(response.status());
+                delivery.error = None;
+                return delivery;
+            }
+            Err(e) => {
+                if let ureq::Error::Status(code, _) = &e {
+                    delivery.status = Some(*code);
+                }
+                let retry = is_retryable(&e) && delivery.attempts < MAX_ATTEMPTS;
+                delivery.error = Some(e.to_string());
+                if !retry {
+                    return delivery;
+                }
+            }
+        }
+
+        thread::sleep(delay);
+        delay *= 2;
+    }
+}
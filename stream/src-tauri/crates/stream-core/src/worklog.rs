@@ -0,0 +1,240 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+use crate::focus::CompletedFocusSession;
+use crate::git;
+use crate::markdown::{find_daily_note, read_description_xattr};
+
+/// Commits further apart than this start a new work session
+const SESSION_GAP_MS: u64 = 2 * 60 * 60 * 1000;
+/// Time credited for the work leading up to the first commit of a session
+const FIRST_COMMIT_ALLOWANCE_MS: u64 = 30 * 60 * 1000;
+/// Estimates are rounded up to a quarter hour, as timesheets usually are
+const ROUNDING_MINUTES: u64 = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorklogFormat {
+    Csv,
+    Json,
+}
+
+impl WorklogFormat {
+    pub fn from_string(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "csv" => Some(WorklogFormat::Csv),
+            "json" => Some(WorklogFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// Estimated time spent on one project on one day
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorklogEntry {
+    pub date: String,
+    pub project: String,
+    pub minutes: u64,
+    pub commit_minutes: u64,
+    pub focus_minutes: u64,
+    pub commit_count: usize,
+    /// Description of the day's note, for the invoice line
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WorklogSummary {
+    pub output_path: String,
+    pub entry_count: usize,
+    pub total_minutes: u64,
+}
+
+#[derive(Default)]
+struct DayProject {
+    commit_times: Vec<u64>,
+    focus_ms: u64,
+}
+
+fn local_date(timestamp_ms: u64) -> Option<NaiveDate> {
+    DateTime::from_timestamp_millis(timestamp_ms as i64)
+        .map(|dt| dt.with_timezone(&Local).date_naive())
+}
+
+fn project_name(repo_path: &str) -> String {
+    Path::new(repo_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| repo_path.to_string())
+}
+
+/// Estimate working time from commit timestamps: commits less than two hours apart
+/// belong to one session, and each session is credited half an hour before its first commit
+pub fn estimate_commit_ms(timestamps: &[u64]) -> u64 {
+    let mut sorted = timestamps.to_vec();
+    sorted.sort_unstable();
+
+    let mut total = 0;
+    let mut previous: Option<u64> = None;
+    for timestamp in sorted {
+        total += match previous {
+            Some(previous) if timestamp - previous <= SESSION_GAP_MS => timestamp - previous,
+            _ => FIRST_COMMIT_ALLOWANCE_MS,
+        };
+        previous = Some(timestamp);
+    }
+    total
+}
+
+fn round_minutes(ms: u64) -> u64 {
+    let minutes = ms.div_ceil(60_000);
+    minutes.div_ceil(ROUNDING_MINUTES) * ROUNDING_MINUTES
+}
+
+/// Per-day, per-project time estimates between `start` and `end` (inclusive). Focus
+/// sessions count towards the repository whose name matches their label, or else form
+/// their own project; where both exist the larger estimate is used, since they overlap
+pub fn build_worklog(
+    directory_path: Option<&Path>,
+    repo_paths: &[String],
+    focus_sessions: &[CompletedFocusSession],
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<WorklogEntry>, Box<dyn std::error::Error>> {
+    if end < start {
+        return Err("End date is before start date".into());
+    }
+    let start_ms = git::local_day_bounds(start)
+        .ok_or_else(|| format!("Invalid local date: {}", start))?
+        .0 as u64
+        * 1000;
+    let end_ms = (git::local_day_bounds(end)
+        .ok_or_else(|| format!("Invalid local date: {}", end))?
+        .1 as u64
+        + 1)
+        * 1000
+        - 1;
+
+    let mut days: BTreeMap<(NaiveDate, String), DayProject> = BTreeMap::new();
+
+    for repo in git::get_git_commits_for_repos(repo_paths, start_ms, end_ms) {
+        let project = project_name(&repo.repo_path);
+        for commit in repo.commits {
+            if let Some(date) = local_date(commit.timestamp) {
+                days.entry((date, project.clone()))
+                    .or_default()
+                    .commit_times
+                    .push(commit.timestamp);
+            }
+        }
+    }
+
+    let projects: Vec<String> = repo_paths.iter().map(|p| project_name(p)).collect();
+    for session in focus_sessions {
+        if session.started_at > end_ms || session.ended_at < start_ms {
+            continue;
+        }
+        let date = match local_date(session.started_at) {
+            Some(date) => date,
+            None => continue,
+        };
+        let label = session.label.trim();
+        let project = projects
+            .iter()
+            .find(|project| project.eq_ignore_ascii_case(label))
+            .cloned()
+            .unwrap_or_else(|| {
+                if label.is_empty() {
+                    "Focus".to_string()
+                } else {
+                    label.to_string()
+                }
+            });
+        days.entry((date, project)).or_default().focus_ms += session.duration_ms;
+    }
+
+    let mut descriptions: BTreeMap<NaiveDate, Option<String>> = BTreeMap::new();
+    let mut entries = Vec::new();
+    for ((date, project), day) in days {
+        let commit_minutes = if day.commit_times.is_empty() {
+            0
+        } else {
+            round_minutes(estimate_commit_ms(&day.commit_times))
+        };
+        let focus_minutes = if day.focus_ms == 0 {
+            0
+        } else {
+            round_minutes(day.focus_ms)
+        };
+        let description = descriptions
+            .entry(date)
+            .or_insert_with(|| {
+                directory_path
+                    .and_then(|dir| find_daily_note(dir, date))
+                    .and_then(|path| read_description_xattr(&path))
+            })
+            .clone();
+
+        entries.push(WorklogEntry {
+            date: date.format("%Y-%m-%d").to_string(),
+            project,
+            minutes: commit_minutes.max(focus_minutes),
+            commit_minutes,
+            focus_minutes,
+            commit_count: day.commit_times.len(),
+            description,
+        });
+    }
+
+    Ok(entries)
+}
+
+pub(crate) fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn render_worklog_csv(entries: &[WorklogEntry]) -> String {
+    let mut csv = String::from("date,project,hours,minutes,commits,focus_minutes,description\n");
+    for entry in entries {
+        csv.push_str(&format!(
+            "{},{},{:.2},{},{},{},{}\n",
+            entry.date,
+            csv_field(&entry.project),
+            entry.minutes as f64 / 60.0,
+            entry.minutes,
+            entry.commit_count,
+            entry.focus_minutes,
+            csv_field(entry.description.as_deref().unwrap_or(""))
+        ));
+    }
+    csv
+}
+
+/// Write a timesheet built by `build_worklog` to `output_path`
+pub fn export_worklog(
+    entries: &[WorklogEntry],
+    output_path: &Path,
+    format: WorklogFormat,
+) -> Result<WorklogSummary, Box<dyn std::error::Error>> {
+    let contents = match format {
+        WorklogFormat::Csv => render_worklog_csv(entries),
+        WorklogFormat::Json => serde_json::to_string_pretty(entries)?,
+    };
+    if let Some(parent) = output_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(output_path, contents)?;
+
+    Ok(WorklogSummary {
+        output_path: output_path.to_string_lossy().to_string(),
+        entry_count: entries.len(),
+        total_minutes: entries.iter().map(|e| e.minutes).sum(),
+    })
+}
@@ -0,0 +1,399 @@
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use serde::{Deserialize, Serialize};
+use stream_core::capture::{append_capture, save_asset};
+use stream_core::paths::normalize_path_string;
+use tauri::{AppHandle, Emitter};
+
+use crate::vaults::ensure_writable;
+
+/// whisper.cpp expects 16kHz mono input
+#[cfg(feature = "transcription")]
+const WHISPER_SAMPLE_RATE: u32 = 16_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioMemo {
+    pub note_path: String,
+    pub asset_path: String,
+    pub duration_ms: u64,
+    pub transcript: Option<String>,
+    /// Why transcription failed. The memo is still saved and linked
+    pub transcription_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingStatus {
+    pub recording: bool,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AudioMemoProgress {
+    pub phase: String,
+    pub percent: Option<i32>,
+}
+
+struct ActiveRecording {
+    directory_path: String,
+    started_at: Instant,
+    sample_rate: u32,
+    samples: Arc<Mutex<Vec<f32>>>,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// Managed state holding the in-progress microphone recording, if any
+#[derive(Default)]
+pub struct RecordingState {
+    recording: Mutex<Option<ActiveRecording>>,
+}
+
+fn emit_progress(app: &AppHandle, phase: &str, percent: Option<i32>) {
+    let progress = AudioMemoProgress {
+        phase: phase.to_string(),
+        percent,
+    };
+    if let Err(e) = app.emit("audio-memo-progress", &progress) {
+        eprintln!("Failed to emit audio-memo-progress event: {}", e);
+    }
+}
+
+/// Mix each frame down to mono as it arrives
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    samples: Arc<Mutex<Vec<f32>>>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    let channels = config.channels.max(1) as usize;
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                if let Ok(mut samples) = samples.lock() {
+                    samples.extend(data.chunks(channels).map(|frame| {
+                        frame.iter().map(|s| s.to_sample::<f32>()).sum::<f32>() / frame.len() as f32
+                    }));
+                }
+            },
+            |e| eprintln!("Audio input error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open microphone: {}", e))
+}
+
+/// The input stream isn't `Send` on every platform, so it lives on its own thread
+/// until `stop` is set. Reports the device sample rate once recording has started
+fn record(
+    samples: Arc<Mutex<Vec<f32>>>,
+    stop: Arc<AtomicBool>,
+    started: mpsc::Sender<Result<u32, String>>,
+) {
+    let stream = (|| {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or("No microphone available")?;
+        let supported = device
+            .default_input_config()
+            .map_err(|e| format!("Failed to read microphone config: {}", e))?;
+        let sample_format = supported.sample_format();
+        let config: cpal::StreamConfig = supported.into();
+
+        let stream = match sample_format {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, samples),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, samples),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, samples),
+            SampleFormat::I32 => build_stream::<i32>(&device, &config, samples),
+            other => Err(format!("Unsupported sample format: {:?}", other)),
+        }?;
+        stream
+            .play()
+            .map_err(|e| format!("Failed to start recording: {}", e))?;
+        Ok::<_, String>((stream, config.sample_rate.0))
+    })();
+
+    let stream = match stream {
+        Ok((stream, sample_rate)) => {
+            let _ = started.send(Ok(sample_rate));
+            stream
+        }
+        Err(e) => {
+            let _ = started.send(Err(e));
+            return;
+        }
+    };
+
+    while !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    drop(stream);
+}
+
+fn encode_wav(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>, String> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut cursor = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut cursor, spec)
+        .map_err(|e| format!("Failed to encode recording: {}", e))?;
+    for sample in samples {
+        writer
+            .write_sample((sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+            .map_err(|e| format!("Failed to encode recording: {}", e))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Failed to encode recording: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+/// Linear resampling is plenty for speech going into whisper
+#[cfg(feature = "transcription")]
+fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from_rate as f64 / to_rate as f64;
+    let output_len = (samples.len() as f64 / ratio) as usize;
+    (0..output_len)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            let index = position as usize;
+            let fraction = (position - index as f64) as f32;
+            let current = samples[index];
+            let next = samples.get(index + 1).copied().unwrap_or(current);
+            current + (next - current) * fraction
+        })
+        .collect()
+}
+
+#[cfg(feature = "transcription")]
+fn transcribe(
+    app: &AppHandle,
+    samples: &[f32],
+    sample_rate: u32,
+    model_path: &str,
+    language: Option<&str>,
+) -> Result<String, String> {
+    use whisper_rs::{FullParams, SamplingStrategy, WhisperContext, WhisperContextParameters};
+
+    let context = WhisperContext::new_with_params(model_path, WhisperContextParameters::default())
+        .map_err(|e| format!("Failed to load whisper model: {}", e))?;
+    let mut state = context
+        .create_state()
+        .map_err(|e| format!("Failed to initialise whisper: {}", e))?;
+
+    let mut params = FullParams::new(SamplingStrategy::Greedy { best_of: 1 });
+    params.set_language(Some(language.unwrap_or("auto")));
+    params.set_print_progress(false);
+    params.set_print_realtime(false);
+    params.set_print_special(false);
+    params.set_print_timestamps(false);
+    let progress_app = app.clone();
+    params.set_progress_callback_safe(move |percent: i32| {
+        emit_progress(&progress_app, "transcribing", Some(percent));
+    });
+
+    let audio = resample(samples, sample_rate, WHISPER_SAMPLE_RATE);
+    state
+        .full(params, &audio)
+        .map_err(|e| format!("Transcription failed: {}", e))?;
+
+    let segments = state
+        .full_n_segments()
+        .map_err(|e| format!("Transcription failed: {}", e))?;
+    let mut transcript = String::new();
+    for segment in 0..segments {
+        if let Ok(text) = state.full_get_segment_text(segment) {
+            transcript.push_str(text.trim());
+            transcript.push(' ');
+        }
+    }
+
+    Ok(transcript.trim().to_string())
+}
+
+#[cfg(not(feature = "transcription"))]
+fn transcribe(
+    _app: &AppHandle,
+    _samples: &[f32],
+    _sample_rate: u32,
+    _model_path: &str,
+    _language: Option<&str>,
+) -> Result<String, String> {
+    Err("This build doesn't include transcription support".to_string())
+}
+
+fn current_status(state: &RecordingState) -> RecordingStatus {
+    let elapsed = state
+        .recording
+        .lock()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(|r| r.started_at.elapsed()));
+
+    RecordingStatus {
+        recording: elapsed.is_some(),
+        elapsed_ms: elapsed.map(|e| e.as_millis() as u64).unwrap_or(0),
+    }
+}
+
+#[tauri::command]
+pub async fn start_audio_recording(
+    app: AppHandle,
+    state: tauri::State<'_, RecordingState>,
+    directory_path: String,
+) -> Result<RecordingStatus, String> {
+    ensure_writable(&app, &directory_path)?;
+    let directory_path = normalize_path_string(&directory_path);
+    if !Path::new(&directory_path).is_dir() {
+        return Err(format!("Not a directory: {}", directory_path));
+    }
+    if current_status(&state).recording {
+        return Err("A recording is already in progress".to_string());
+    }
+
+    let samples = Arc::new(Mutex::new(Vec::new()));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (started_tx, started_rx) = mpsc::channel();
+
+    let thread_samples = samples.clone();
+    let thread_stop = stop.clone();
+    let handle = std::thread::spawn(move || record(thread_samples, thread_stop, started_tx));
+
+    let started = started_rx
+        .recv_timeout(Duration::from_secs(5))
+        .map_err(|_| "Timed out opening the microphone".to_string())
+        .and_then(|started| started);
+    let sample_rate = match started {
+        Ok(sample_rate) => sample_rate,
+        Err(e) => {
+            // The thread stops on its own once the microphone opens or fails to
+            stop_recording_thread(stop, handle).await;
+            return Err(e);
+        }
+    };
+
+    let recording = ActiveRecording {
+        directory_path,
+        started_at: Instant::now(),
+        sample_rate,
+        samples,
+        stop,
+        handle,
+    };
+    let unstored = match state.recording.lock() {
+        Ok(mut guard) => {
+            *guard = Some(recording);
+            None
+        }
+        Err(_) => Some(recording),
+    };
+    if let Some(recording) = unstored {
+        stop_recording_thread(recording.stop, recording.handle).await;
+        return Err("Recording state is unavailable".to_string());
+    }
+
+    Ok(current_status(&state))
+}
+
+/// Tell the recording thread to stop and wait for it, off the async runtime since opening
+/// the microphone can take a while to give up
+async fn stop_recording_thread(stop: Arc<AtomicBool>, handle: JoinHandle<()>) {
+    stop.store(true, Ordering::Relaxed);
+    let _ = tauri::async_runtime::spawn_blocking(move || handle.join()).await;
+}
+
+/// Stop recording, save the audio to the assets folder and append a link to today's
+/// note, along with a transcript when a whisper model is given. A failed transcription
+/// doesn't lose the memo: the link is added and the error returned alongside it
+#[tauri::command]
+pub async fn stop_audio_recording(
+    app: AppHandle,
+    state: tauri::State<'_, RecordingState>,
+    model_path: Option<String>,
+    language: Option<String>,
+) -> Result<AudioMemo, String> {
+    let recording = state
+        .recording
+        .lock()
+        .ok()
+        .and_then(|mut guard| guard.take())
+        .ok_or("No recording in progress")?;
+
+    stop_recording_thread(recording.stop, recording.handle).await;
+    let samples = recording
+        .samples
+        .lock()
+        .map(|mut s| std::mem::take(&mut *s))
+        .unwrap_or_default();
+    let sample_rate = recording.sample_rate;
+    let directory_path = recording.directory_path;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let captured_at = Local::now();
+        let duration_ms = samples.len() as u64 * 1000 / sample_rate.max(1) as u64;
+
+        emit_progress(&app, "saving", None);
+        let wav = encode_wav(&samples, sample_rate)?;
+        let asset_path = save_asset(Path::new(&directory_path), "memo", "wav", &wav, captured_at)
+            .map_err(|e| format!("Failed to save recording: {}", e))?;
+
+        let (transcript, transcription_error) = match model_path.as_deref() {
+            Some(model_path) => {
+                emit_progress(&app, "transcribing", Some(0));
+                match transcribe(&app, &samples, sample_rate, model_path, language.as_deref()) {
+                    Ok(transcript) => (Some(transcript), None),
+                    Err(e) => (None, Some(e)),
+                }
+            }
+            None => (None, None),
+        };
+
+        let mut body = format!("[Voice memo]({})", asset_path.replace(' ', "%20"));
+        if let Some(transcript) = transcript.as_deref().filter(|t| !t.is_empty()) {
+            body.push_str(&format!("\n\n> {}", transcript));
+        }
+        let note_path = append_capture(
+            Path::new(&directory_path),
+            captured_at,
+            "Voice memo",
+            None,
+            &body,
+        )
+        .map_err(|e| format!("Failed to append to note: {}", e))?;
+
+        emit_progress(&app, "done", Some(100));
+
+        Ok(AudioMemo {
+            note_path: note_path.to_string_lossy().to_string(),
+            asset_path,
+            duration_ms,
+            transcript,
+            transcription_error,
+        })
+    })
+    .await
+    .map_err(|e| format!("Audio memo task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_recording_status(
+    state: tauri::State<'_, RecordingState>,
+) -> Result<RecordingStatus, String> {
+    Ok(current_status(&state))
+}
@@ -0,0 +1,280 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use stream_core::lock::{hash_passphrase, verify_passphrase_hash};
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+
+use crate::secrets::{read_secret, write_secret};
+use crate::SETTINGS_STORE;
+
+/// Keychain account holding the hash of the app passphrase
+const PASSPHRASE_SECRET: &str = "app-passphrase";
+const AUTO_LOCK_KEY: &str = "auth_auto_lock_minutes";
+const DEFAULT_AUTO_LOCK: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthStatus {
+    /// False until an app passphrase is set; sensitive commands run ungated until then
+    pub enabled: bool,
+    pub unlocked: bool,
+    pub expires_in_ms: Option<u64>,
+    pub auto_lock_minutes: u64,
+    pub biometric_available: bool,
+}
+
+struct AuthSession {
+    unlocked_until: Option<Instant>,
+    auto_lock: Duration,
+}
+
+/// Managed state for the sensitive-command gate. Unlocking lasts for the auto-lock
+/// timeout, which restarts every time a gated command runs
+pub struct AuthState {
+    session: Mutex<AuthSession>,
+}
+
+impl AuthState {
+    pub fn load<R: Runtime>(app: &AppHandle<R>) -> Self {
+        let auto_lock = app
+            .store(SETTINGS_STORE)
+            .ok()
+            .and_then(|store| store.get(AUTO_LOCK_KEY))
+            .and_then(|value| value.as_u64())
+            .map(|minutes| Duration::from_secs(minutes * 60))
+            .unwrap_or(DEFAULT_AUTO_LOCK);
+
+        AuthState {
+            session: Mutex::new(AuthSession {
+                unlocked_until: None,
+                auto_lock,
+            }),
+        }
+    }
+
+    fn unlock(&self) {
+        if let Ok(mut session) = self.session.lock() {
+            session.unlocked_until = Some(Instant::now() + session.auto_lock);
+        }
+    }
+
+    fn lock(&self) {
+        if let Ok(mut session) = self.session.lock() {
+            session.unlocked_until = None;
+        }
+    }
+
+    fn status(&self, enabled: bool) -> AuthStatus {
+        let now = Instant::now();
+        let (unlocked_until, auto_lock) = self
+            .session
+            .lock()
+            .map(|s| (s.unlocked_until, s.auto_lock))
+            .unwrap_or((None, DEFAULT_AUTO_LOCK));
+        let remaining = unlocked_until.and_then(|until| until.checked_duration_since(now));
+
+        AuthStatus {
+            enabled,
+            unlocked: !enabled || remaining.is_some(),
+            expires_in_ms: remaining.map(|r| r.as_millis() as u64),
+            auto_lock_minutes: auto_lock.as_secs() / 60,
+            biometric_available: biometric_available(),
+        }
+    }
+}
+
+fn passphrase_hash() -> Result<Option<String>, String> {
+    read_secret(PASSPHRASE_SECRET)
+}
+
+/// Gate for sensitive commands: errors unless the app was unlocked within the auto-lock
+/// timeout, and extends the unlock when it passes
+pub(crate) fn require_auth(app: &AppHandle) -> Result<(), String> {
+    if passphrase_hash()?.is_none() {
+        return Ok(());
+    }
+
+    let state = app.state::<AuthState>();
+    let mut session = state
+        .session
+        .lock()
+        .map_err(|_| "Auth state is unavailable".to_string())?;
+    match session.unlocked_until {
+        Some(until) if until > Instant::now() => {
+            session.unlocked_until = Some(Instant::now() + session.auto_lock);
+            Ok(())
+        }
+        _ => {
+            session.unlocked_until = None;
+            Err("Authentication required".to_string())
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "LocalAuthentication", kind = "framework")]
+extern "C" {}
+
+/// LAPolicyDeviceOwnerAuthenticationWithBiometrics
+#[cfg(target_os = "macos")]
+const LA_POLICY_BIOMETRICS: isize = 1;
+
+#[cfg(target_os = "macos")]
+fn biometric_available() -> bool {
+    use objc::runtime::{Object, BOOL, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    unsafe {
+        let context: *mut Object = msg_send![class!(LAContext), new];
+        if context.is_null() {
+            return false;
+        }
+        let mut error: *mut Object = std::ptr::null_mut();
+        let available: BOOL =
+            msg_send![context, canEvaluatePolicy: LA_POLICY_BIOMETRICS error: &mut error];
+        let _: () = msg_send![context, release];
+        available == YES
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn biometric_available() -> bool {
+    false
+}
+
+/// Show the Touch ID prompt and wait for the user's answer
+#[cfg(target_os = "macos")]
+fn authenticate_biometric(reason: &str) -> Result<(), String> {
+    use block::ConcreteBlock;
+    use objc::runtime::{Object, BOOL, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::sync::mpsc;
+
+    if !biometric_available() {
+        return Err("Touch ID is not available".to_string());
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    unsafe {
+        let context: *mut Object = msg_send![class!(LAContext), new];
+        let text: *mut Object = msg_send![class!(NSString), alloc];
+        // NSUTF8StringEncoding
+        let text: *mut Object = msg_send![text, initWithBytes: reason.as_ptr()
+            length: reason.len()
+            encoding: 4usize];
+
+        let reply = ConcreteBlock::new(move |success: BOOL, _error: *mut Object| {
+            let _ = sender.send(success == YES);
+        })
+        .copy();
+        let _: () = msg_send![context, evaluatePolicy: LA_POLICY_BIOMETRICS
+            localizedReason: text
+            reply: &*reply];
+
+        let approved = receiver
+            .recv_timeout(Duration::from_secs(120))
+            .unwrap_or(false);
+        let _: () = msg_send![text, release];
+        let _: () = msg_send![context, release];
+
+        if approved {
+            Ok(())
+        } else {
+            Err("Touch ID authentication failed".to_string())
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn authenticate_biometric(_reason: &str) -> Result<(), String> {
+    Err("Biometric authentication is only supported on macOS".to_string())
+}
+
+/// Set or change the app passphrase. Changing it requires the current one
+#[tauri::command]
+pub async fn set_app_passphrase(
+    current_passphrase: Option<String>,
+    new_passphrase: String,
+) -> Result<(), String> {
+    if let Some(hash) = passphrase_hash()? {
+        let current = current_passphrase.unwrap_or_default();
+        if !verify_passphrase_hash(&current, &hash) {
+            return Err("Current passphrase is incorrect".to_string());
+        }
+    }
+
+    let hash = tauri::async_runtime::spawn_blocking(move || {
+        hash_passphrase(&new_passphrase).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Hashing task failed: {}", e))??;
+    write_secret(PASSPHRASE_SECRET, &hash)
+}
+
+/// Unlock sensitive commands with Touch ID (`method: "biometric"`) or the app passphrase
+#[tauri::command]
+pub async fn authenticate(
+    app: AppHandle,
+    method: String,
+    passphrase: Option<String>,
+) -> Result<AuthStatus, String> {
+    let hash = passphrase_hash()?.ok_or_else(|| "No app passphrase is set".to_string())?;
+
+    tauri::async_runtime::spawn_blocking(move || match method.as_str() {
+        "biometric" => authenticate_biometric("unlock your notes"),
+        "passphrase" => {
+            if verify_passphrase_hash(&passphrase.unwrap_or_default(), &hash) {
+                Ok(())
+            } else {
+                Err("Incorrect passphrase".to_string())
+            }
+        }
+        other => Err(format!("Unknown authentication method: {}", other)),
+    })
+    .await
+    .map_err(|e| format!("Authentication task failed: {}", e))??;
+
+    let state = app.state::<AuthState>();
+    state.unlock();
+    Ok(state.status(true))
+}
+
+#[tauri::command]
+pub async fn lock_app(app: AppHandle) -> Result<AuthStatus, String> {
+    let state = app.state::<AuthState>();
+    state.lock();
+    Ok(state.status(passphrase_hash()?.is_some()))
+}
+
+#[tauri::command]
+pub async fn get_auth_status(app: AppHandle) -> Result<AuthStatus, String> {
+    Ok(app
+        .state::<AuthState>()
+        .status(passphrase_hash()?.is_some()))
+}
+
+#[tauri::command]
+pub async fn set_auto_lock_timeout(app: AppHandle, minutes: u64) -> Result<AuthStatus, String> {
+    if minutes == 0 {
+        return Err("Auto-lock timeout must be at least a minute".to_string());
+    }
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(AUTO_LOCK_KEY, json!(minutes));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    let state = app.state::<AuthState>();
+    if let Ok(mut session) = state.session.lock() {
+        session.auto_lock = Duration::from_secs(minutes * 60);
+        // Shorten an unlock that would now outlive the new timeout
+        let latest = Instant::now() + session.auto_lock;
+        session.unlocked_until = session.unlocked_until.map(|until| until.min(latest));
+    }
+    Ok(state.status(passphrase_hash()?.is_some()))
+}
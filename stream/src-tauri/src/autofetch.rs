@@ -0,0 +1,293 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, Timelike};
+use serde::{Deserialize, Serialize};
+use stream_core::clock::now_ms;
+use stream_core::git;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::background;
+use crate::vaults::resolve_vault_config;
+
+fn default_interval_minutes() -> u64 {
+    15
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Opt-in background fetching of the diary's repositories
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FetchSchedule {
+    pub enabled: bool,
+    /// Repositories to fetch; empty means the active vault's repositories
+    #[serde(default)]
+    pub repo_paths: Vec<String>,
+    #[serde(default = "default_interval_minutes")]
+    pub interval_minutes: u64,
+    #[serde(default = "default_true")]
+    pub skip_on_metered: bool,
+    /// Local hours (0-23) during which nothing is fetched. The window may wrap past midnight
+    #[serde(default)]
+    pub quiet_hours_start: Option<u32>,
+    #[serde(default)]
+    pub quiet_hours_end: Option<u32>,
+}
+
+impl Default for FetchSchedule {
+    fn default() -> Self {
+        FetchSchedule {
+            enabled: false,
+            repo_paths: Vec::new(),
+            interval_minutes: default_interval_minutes(),
+            skip_on_metered: true,
+            quiet_hours_start: None,
+            quiet_hours_end: None,
+        }
+    }
+}
+
+impl FetchSchedule {
+    fn in_quiet_hours(&self, hour: u32) -> bool {
+        match (self.quiet_hours_start, self.quiet_hours_end) {
+            (Some(start), Some(end)) if start <= end => hour >= start && hour < end,
+            (Some(start), Some(end)) => hour >= start || hour < end,
+            _ => false,
+        }
+    }
+}
+
+/// Outcome of the last background fetch of a repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepoFetchStatus {
+    pub repo_path: String,
+    pub fetched_at: u64,
+    pub success: bool,
+    pub message: String,
+    pub new_commits_today: usize,
+}
+
+/// Managed state for the fetch scheduler; the schedule is persisted to the app data dir
+pub struct FetchSchedulerState {
+    path: PathBuf,
+    schedule: Mutex<FetchSchedule>,
+    last_run: Mutex<Option<Instant>>,
+    statuses: Mutex<HashMap<String, RepoFetchStatus>>,
+}
+
+impl FetchSchedulerState {
+    pub fn load(path: PathBuf) -> Self {
+        let schedule = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        FetchSchedulerState {
+            path,
+            schedule: Mutex::new(schedule),
+            last_run: Mutex::new(None),
+            statuses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn schedule(&self) -> FetchSchedule {
+        self.schedule.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    /// Claim the next run if the interval has elapsed
+    fn claim_run(&self, interval: Duration) -> bool {
+        let mut last_run = match self.last_run.lock() {
+            Ok(last_run) => last_run,
+            Err(_) => return false,
+        };
+        if last_run.is_some_and(|last| last.elapsed() < interval) {
+            return false;
+        }
+        *last_run = Some(Instant::now());
+        true
+    }
+}
+
+/// Ids of the commits made today (local time) on any branch of the repository
+fn todays_commit_ids(repo_path: &str) -> HashSet<String> {
+    git::local_day_bounds(Local::now().date_naive())
+        .and_then(|(start, end)| git::get_repo_commits(repo_path, start, end).ok())
+        .map(|walk| walk.commits.into_iter().map(|c| c.id).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(target_os = "macos")]
+#[link(name = "Network", kind = "framework")]
+extern "C" {
+    fn nw_path_monitor_create() -> *mut std::ffi::c_void;
+    fn nw_path_monitor_set_queue(monitor: *mut std::ffi::c_void, queue: *mut std::ffi::c_void);
+    fn nw_path_monitor_set_update_handler(
+        monitor: *mut std::ffi::c_void,
+        handler: &block::Block<(*mut std::ffi::c_void,), ()>,
+    );
+    fn nw_path_monitor_start(monitor: *mut std::ffi::c_void);
+    fn nw_path_monitor_cancel(monitor: *mut std::ffi::c_void);
+    fn nw_path_is_expensive(path: *mut std::ffi::c_void) -> bool;
+    fn nw_path_is_constrained(path: *mut std::ffi::c_void) -> bool;
+    fn nw_release(object: *mut std::ffi::c_void);
+    fn dispatch_get_global_queue(identifier: isize, flags: usize) -> *mut std::ffi::c_void;
+}
+
+/// Whether the current network path is expensive (cellular, personal hotspot) or in
+/// Low Data Mode
+#[cfg(target_os = "macos")]
+pub(crate) fn is_metered_connection() -> bool {
+    use block::ConcreteBlock;
+    use std::sync::mpsc;
+
+    let (sender, receiver) = mpsc::channel();
+    unsafe {
+        let monitor = nw_path_monitor_create();
+        if monitor.is_null() {
+            return false;
+        }
+        let handler = ConcreteBlock::new(move |path: *mut std::ffi::c_void| {
+            let _ = sender.send(nw_path_is_expensive(path) || nw_path_is_constrained(path));
+        })
+        .copy();
+        nw_path_monitor_set_update_handler(monitor, &handler);
+        nw_path_monitor_set_queue(monitor, dispatch_get_global_queue(0, 0));
+        nw_path_monitor_start(monitor);
+
+        let metered = receiver
+            .recv_timeout(Duration::from_secs(2))
+            .unwrap_or(false);
+        nw_path_monitor_cancel(monitor);
+        nw_release(monitor);
+        metered
+    }
+}
+
+/// NetworkManager's view of the connection, when it's available
+#[cfg(target_os = "linux")]
+pub(crate) fn is_metered_connection() -> bool {
+    std::process::Command::new("nmcli")
+        .args(["-t", "-f", "GENERAL.METERED", "dev", "show"])
+        .output()
+        .map(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .any(|line| line.ends_with(":yes") || line.ends_with(":yes (guessed)"))
+        })
+        .unwrap_or(false)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+pub(crate) fn is_metered_connection() -> bool {
+    false
+}
+
+/// Fetch the scheduled repositories when the interval has elapsed, outside quiet hours and
+/// metered connections. Emits `repo-commits-fetched` when new commits land in today's range.
+/// Called from the background refresh thread
+pub fn run_scheduled_fetch(app: &AppHandle) {
+    let state = app.state::<FetchSchedulerState>();
+    let schedule = state.schedule();
+    if !schedule.enabled || schedule.in_quiet_hours(Local::now().hour()) {
+        return;
+    }
+
+    let interval = Duration::from_secs(schedule.interval_minutes.max(1) * 60)
+        * background::interval_multiplier(app);
+    if !state.claim_run(interval) {
+        return;
+    }
+    if schedule.skip_on_metered && is_metered_connection() {
+        return;
+    }
+
+    let repo_paths = if schedule.repo_paths.is_empty() {
+        resolve_vault_config(app, None, None)
+            .map(|config| config.repo_paths)
+            .unwrap_or_default()
+    } else {
+        schedule.repo_paths
+    };
+
+    let mut updated = Vec::new();
+    for repo_path in repo_paths {
+        let before = todays_commit_ids(&repo_path);
+        let (success, message) = match git::fetch_repo(&repo_path) {
+            Ok(message) => (true, message),
+            Err(e) => (false, format!("Failed to fetch: {}", e)),
+        };
+        let new_commits_today = if success {
+            todays_commit_ids(&repo_path).difference(&before).count()
+        } else {
+            0
+        };
+
+        let status = RepoFetchStatus {
+            repo_path: repo_path.clone(),
+            fetched_at: now_ms(),
+            success,
+            message,
+            new_commits_today,
+        };
+        if new_commits_today > 0 {
+            updated.push(status.clone());
+        }
+        if let Ok(mut statuses) = state.statuses.lock() {
+            statuses.insert(repo_path, status);
+        }
+    }
+
+    if !updated.is_empty() {
+        if let Err(e) = app.emit("repo-commits-fetched", &updated) {
+            eprintln!("Failed to emit repo-commits-fetched event: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn set_fetch_schedule(
+    state: tauri::State<'_, FetchSchedulerState>,
+    schedule: FetchSchedule,
+) -> Result<(), String> {
+    let valid_hour = |hour: Option<u32>| hour.is_none_or(|h| h < 24);
+    if !valid_hour(schedule.quiet_hours_start) || !valid_hour(schedule.quiet_hours_end) {
+        return Err("Quiet hours must be between 0 and 23".to_string());
+    }
+
+    if let Some(parent) = state.path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to save schedule: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&schedule).map_err(|e| e.to_string())?;
+    fs::write(&state.path, json).map_err(|e| format!("Failed to save schedule: {}", e))?;
+
+    *state
+        .schedule
+        .lock()
+        .map_err(|_| "Fetch schedule is unavailable".to_string())? = schedule;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_fetch_schedule(
+    state: tauri::State<'_, FetchSchedulerState>,
+) -> Result<FetchSchedule, String> {
+    Ok(state.schedule())
+}
+
+/// Result of the last background fetch for each repository, newest first
+#[tauri::command]
+pub async fn get_fetch_statuses(
+    state: tauri::State<'_, FetchSchedulerState>,
+) -> Result<Vec<RepoFetchStatus>, String> {
+    let mut statuses: Vec<RepoFetchStatus> = state
+        .statuses
+        .lock()
+        .map(|s| s.values().cloned().collect())
+        .unwrap_or_default();
+    statuses.sort_by(|a, b| b.fetched_at.cmp(&a.fetched_at));
+    Ok(statuses)
+}
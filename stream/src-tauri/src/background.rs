@@ -0,0 +1,280 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::autofetch::is_metered_connection;
+
+/// Power and network state is re-read at most this often; the metered check can take seconds
+const STATUS_TTL: Duration = Duration::from_secs(60);
+
+/// What background work does under a given condition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackgroundAction {
+    Run,
+    /// Run `stretch_factor` times less often
+    Stretch,
+    Pause,
+}
+
+fn default_stretch_factor() -> u32 {
+    4
+}
+
+/// How background fetches and refresh jobs react to battery, Low Power Mode and metered
+/// connections. The most restrictive matching condition wins
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackgroundPolicy {
+    pub on_battery: BackgroundAction,
+    pub on_low_power: BackgroundAction,
+    pub on_metered: BackgroundAction,
+    #[serde(default = "default_stretch_factor")]
+    pub stretch_factor: u32,
+}
+
+impl Default for BackgroundPolicy {
+    fn default() -> Self {
+        BackgroundPolicy {
+            on_battery: BackgroundAction::Stretch,
+            on_low_power: BackgroundAction::Pause,
+            on_metered: BackgroundAction::Pause,
+            stretch_factor: default_stretch_factor(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PowerState {
+    pub on_battery: bool,
+    pub low_power: bool,
+    pub metered: bool,
+}
+
+/// Payload of the `background-status` event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackgroundStatus {
+    #[serde(flatten)]
+    pub power: PowerState,
+    pub action: BackgroundAction,
+    pub stretch_factor: u32,
+}
+
+impl BackgroundPolicy {
+    fn action(&self, power: PowerState) -> BackgroundAction {
+        [
+            (power.on_battery, self.on_battery),
+            (power.low_power, self.on_low_power),
+            (power.metered, self.on_metered),
+        ]
+        .into_iter()
+        .filter(|(active, _)| *active)
+        .map(|(_, action)| action)
+        .max()
+        .unwrap_or(BackgroundAction::Run)
+    }
+
+    fn status(&self, power: PowerState) -> BackgroundStatus {
+        BackgroundStatus {
+            power,
+            action: self.action(power),
+            stretch_factor: self.stretch_factor.max(1),
+        }
+    }
+}
+
+/// Managed state for the background policy; the policy is persisted to the app data dir
+pub struct BackgroundState {
+    path: PathBuf,
+    policy: Mutex<BackgroundPolicy>,
+    power: Mutex<Option<(Instant, PowerState)>>,
+    last_status: Mutex<Option<BackgroundStatus>>,
+    ticks: Mutex<u64>,
+}
+
+impl BackgroundState {
+    pub fn load(path: PathBuf) -> Self {
+        let policy = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        BackgroundState {
+            path,
+            policy: Mutex::new(policy),
+            power: Mutex::new(None),
+            last_status: Mutex::new(None),
+            ticks: Mutex::new(0),
+        }
+    }
+
+    fn policy(&self) -> BackgroundPolicy {
+        self.policy.lock().map(|p| p.clone()).unwrap_or_default()
+    }
+
+    fn power(&self) -> PowerState {
+        let mut cached = match self.power.lock() {
+            Ok(cached) => cached,
+            Err(_) => return PowerState::default(),
+        };
+        match *cached {
+            Some((read_at, power)) if read_at.elapsed() < STATUS_TTL => power,
+            _ => {
+                let power = read_power_state();
+                *cached = Some((Instant::now(), power));
+                power
+            }
+        }
+    }
+
+    fn status(&self) -> BackgroundStatus {
+        self.policy().status(self.power())
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn read_power_state() -> PowerState {
+    use objc::runtime::{Object, BOOL, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    let on_battery = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .map(|output| String::from_utf8_lossy(&output.stdout).contains("'Battery Power'"))
+        .unwrap_or(false);
+    let low_power = unsafe {
+        let info: *mut Object = msg_send![class!(NSProcessInfo), processInfo];
+        let enabled: BOOL = msg_send![info, isLowPowerModeEnabled];
+        enabled == YES
+    };
+    PowerState {
+        on_battery,
+        low_power,
+        metered: is_metered_connection(),
+    }
+}
+
+/// On battery when a battery is discharging; low power when the platform profile or
+/// power-profiles-daemon is set to save power
+#[cfg(target_os = "linux")]
+fn read_power_state() -> PowerState {
+    let read = |path: PathBuf| {
+        fs::read_to_string(path)
+            .map(|s| s.trim().to_string())
+            .unwrap_or_default()
+    };
+    let on_battery = fs::read_dir("/sys/class/power_supply")
+        .map(|entries| {
+            entries.flatten().any(|entry| {
+                read(entry.path().join("type")) == "Battery"
+                    && read(entry.path().join("status")) == "Discharging"
+            })
+        })
+        .unwrap_or(false);
+    let low_power = read(PathBuf::from("/sys/firmware/acpi/platform_profile")) == "low-power"
+        || std::process::Command::new("powerprofilesctl")
+            .arg("get")
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim() == "power-saver")
+            .unwrap_or(false);
+    PowerState {
+        on_battery,
+        low_power,
+        metered: is_metered_connection(),
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux")))]
+fn read_power_state() -> PowerState {
+    PowerState {
+        metered: is_metered_connection(),
+        ..PowerState::default()
+    }
+}
+
+/// Start a background refresh tick: emits `background-status` when the power state or policy
+/// outcome changed, and returns whether periodic jobs should run this tick. Called from the
+/// background refresh thread
+pub fn begin_tick(app: &AppHandle) -> bool {
+    let state = app.state::<BackgroundState>();
+    let status = state.status();
+
+    if let Ok(mut last_status) = state.last_status.lock() {
+        if last_status.as_ref() != Some(&status) {
+            if let Err(e) = app.emit("background-status", &status) {
+                eprintln!("Failed to emit background-status event: {}", e);
+            }
+            *last_status = Some(status.clone());
+        }
+    }
+
+    let tick = match state.ticks.lock() {
+        Ok(mut ticks) => {
+            *ticks += 1;
+            *ticks
+        }
+        Err(_) => return status.action == BackgroundAction::Run,
+    };
+    match status.action {
+        BackgroundAction::Run => true,
+        BackgroundAction::Stretch => tick % status.stretch_factor as u64 == 0,
+        BackgroundAction::Pause => false,
+    }
+}
+
+/// How much longer than usual intervals between background jobs should be, e.g. fetches
+pub fn interval_multiplier(app: &AppHandle) -> u32 {
+    let status = app.state::<BackgroundState>().status();
+    match status.action {
+        BackgroundAction::Stretch => status.stretch_factor,
+        _ => 1,
+    }
+}
+
+#[tauri::command]
+pub async fn set_background_policy(
+    app: AppHandle,
+    state: tauri::State<'_, BackgroundState>,
+    policy: BackgroundPolicy,
+) -> Result<(), String> {
+    if policy.stretch_factor == 0 {
+        return Err("Stretch factor must be at least 1".to_string());
+    }
+
+    if let Some(parent) = state.path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to save policy: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&policy).map_err(|e| e.to_string())?;
+    fs::write(&state.path, json).map_err(|e| format!("Failed to save policy: {}", e))?;
+
+    *state
+        .policy
+        .lock()
+        .map_err(|_| "Background policy is unavailable".to_string())? = policy;
+
+    let status = state.status();
+    if let Err(e) = app.emit("background-status", &status) {
+        eprintln!("Failed to emit background-status event: {}", e);
+    }
+    if let Ok(mut last_status) = state.last_status.lock() {
+        *last_status = Some(status);
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_background_policy(
+    state: tauri::State<'_, BackgroundState>,
+) -> Result<BackgroundPolicy, String> {
+    Ok(state.policy())
+}
+
+/// Current battery, Low Power Mode and metered state, and what background work is doing
+#[tauri::command]
+pub async fn get_background_status(
+    state: tauri::State<'_, BackgroundState>,
+) -> Result<BackgroundStatus, String> {
+    Ok(state.status())
+}
@@ -0,0 +1,296 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use arboard::Clipboard;
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use stream_core::capture::{append_capture, encode_png, image_link, save_asset};
+use stream_core::paths::normalize_path_string;
+use tauri::AppHandle;
+
+use crate::vaults::ensure_writable;
+
+const WATCH_INTERVAL: Duration = Duration::from_millis(1000);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardCapture {
+    pub note_path: String,
+    pub kind: String,
+    pub asset_path: Option<String>,
+    pub source_app: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClipboardWatcherStatus {
+    pub running: bool,
+    pub captured: usize,
+}
+
+struct RunningWatcher {
+    stop: Arc<AtomicBool>,
+    captured: Arc<Mutex<usize>>,
+    handle: JoinHandle<()>,
+}
+
+/// Managed state for the opt-in clipboard watcher. It's off until started
+#[derive(Default)]
+pub struct ClipboardWatcherState {
+    watcher: Mutex<Option<RunningWatcher>>,
+}
+
+/// Name of the application in front, used to label captures and filter the watcher
+#[cfg(target_os = "macos")]
+pub(crate) fn frontmost_app_name() -> Option<String> {
+    use objc::runtime::{Class, Object};
+    use objc::{msg_send, sel, sel_impl};
+    use std::ffi::CStr;
+    use std::os::raw::c_char;
+
+    unsafe {
+        let workspace_class = Class::get("NSWorkspace")?;
+        let workspace: *mut Object = msg_send![workspace_class, sharedWorkspace];
+        if workspace.is_null() {
+            return None;
+        }
+        let app: *mut Object = msg_send![workspace, frontmostApplication];
+        if app.is_null() {
+            return None;
+        }
+        let name: *mut Object = msg_send![app, localizedName];
+        if name.is_null() {
+            return None;
+        }
+        let utf8: *const c_char = msg_send![name, UTF8String];
+        if utf8.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(utf8).to_string_lossy().to_string())
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub(crate) fn frontmost_app_name() -> Option<String> {
+    None
+}
+
+fn append_text(
+    directory_path: &Path,
+    text: &str,
+    source_app: Option<String>,
+) -> Result<ClipboardCapture, String> {
+    let note_path = append_capture(
+        directory_path,
+        Local::now(),
+        "Clipboard",
+        source_app.as_deref(),
+        text,
+    )
+    .map_err(|e| format!("Failed to append to note: {}", e))?;
+
+    Ok(ClipboardCapture {
+        note_path: note_path.to_string_lossy().to_string(),
+        kind: "text".to_string(),
+        asset_path: None,
+        source_app,
+    })
+}
+
+/// Append whatever is on the clipboard to today's note. Images are saved as PNG assets
+fn capture_current(
+    clipboard: &mut Clipboard,
+    directory_path: &Path,
+) -> Result<ClipboardCapture, String> {
+    let source_app = frontmost_app_name();
+
+    if let Ok(text) = clipboard.get_text() {
+        if !text.trim().is_empty() {
+            return append_text(directory_path, &text, source_app);
+        }
+    }
+
+    let image = clipboard
+        .get_image()
+        .map_err(|_| "Clipboard has no text or image".to_string())?;
+    let png = encode_png(image.width as u32, image.height as u32, &image.bytes)
+        .map_err(|e| format!("Failed to encode image: {}", e))?;
+
+    let captured_at = Local::now();
+    let asset_path = save_asset(directory_path, "clipboard", "png", &png, captured_at)
+        .map_err(|e| format!("Failed to save image: {}", e))?;
+    let note_path = append_capture(
+        directory_path,
+        captured_at,
+        "Clipboard",
+        source_app.as_deref(),
+        &image_link("clipboard", &asset_path),
+    )
+    .map_err(|e| format!("Failed to append to note: {}", e))?;
+
+    Ok(ClipboardCapture {
+        note_path: note_path.to_string_lossy().to_string(),
+        kind: "image".to_string(),
+        asset_path: Some(asset_path),
+        source_app,
+    })
+}
+
+/// Case-insensitive allow/deny check. An allow list only admits captures whose source is known
+fn source_allowed(source: Option<&str>, allow_apps: &[String], deny_apps: &[String]) -> bool {
+    let matches = |list: &[String], name: &str| list.iter().any(|a| a.eq_ignore_ascii_case(name));
+
+    match source {
+        Some(name) => {
+            !matches(deny_apps, name) && (allow_apps.is_empty() || matches(allow_apps, name))
+        }
+        None => allow_apps.is_empty(),
+    }
+}
+
+/// Poll the clipboard and append new text as it's copied. Images are left to
+/// `capture_clipboard` since comparing them every second is too costly
+fn watch(
+    directory_path: String,
+    allow_apps: Vec<String>,
+    deny_apps: Vec<String>,
+    stop: Arc<AtomicBool>,
+    captured: Arc<Mutex<usize>>,
+) {
+    let mut clipboard = match Clipboard::new() {
+        Ok(clipboard) => clipboard,
+        Err(e) => {
+            eprintln!("Clipboard watcher: failed to open clipboard: {}", e);
+            return;
+        }
+    };
+
+    // Don't capture whatever was already on the clipboard when watching started
+    let mut last_text = clipboard.get_text().ok();
+
+    while !stop.load(Ordering::Relaxed) {
+        std::thread::sleep(WATCH_INTERVAL);
+
+        let text = match clipboard.get_text() {
+            Ok(text) => text,
+            Err(_) => continue,
+        };
+        if last_text.as_deref() == Some(text.as_str()) {
+            continue;
+        }
+        last_text = Some(text.clone());
+
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        let source_app = frontmost_app_name();
+        if !source_allowed(source_app.as_deref(), &allow_apps, &deny_apps) {
+            continue;
+        }
+
+        match append_text(Path::new(&directory_path), &text, source_app) {
+            Ok(_) => {
+                if let Ok(mut count) = captured.lock() {
+                    *count += 1;
+                }
+            }
+            Err(e) => eprintln!("Clipboard watcher: {}", e),
+        }
+    }
+}
+
+fn stop_watcher(state: &ClipboardWatcherState) {
+    let running = state.watcher.lock().ok().and_then(|mut guard| guard.take());
+    if let Some(running) = running {
+        running.stop.store(true, Ordering::Relaxed);
+        let _ = running.handle.join();
+    }
+}
+
+fn current_status(state: &ClipboardWatcherState) -> ClipboardWatcherStatus {
+    let captured = state.watcher.lock().ok().and_then(|guard| {
+        guard
+            .as_ref()
+            .map(|w| w.captured.lock().map(|c| *c).unwrap_or(0))
+    });
+
+    ClipboardWatcherStatus {
+        running: captured.is_some(),
+        captured: captured.unwrap_or(0),
+    }
+}
+
+#[tauri::command]
+pub async fn capture_clipboard(
+    app: AppHandle,
+    directory_path: String,
+) -> Result<ClipboardCapture, String> {
+    ensure_writable(&app, &directory_path)?;
+    let directory_path = normalize_path_string(&directory_path);
+    if !Path::new(&directory_path).is_dir() {
+        return Err(format!("Not a directory: {}", directory_path));
+    }
+
+    let mut clipboard = Clipboard::new().map_err(|e| format!("Failed to open clipboard: {}", e))?;
+    capture_current(&mut clipboard, Path::new(&directory_path))
+}
+
+#[tauri::command]
+pub async fn start_clipboard_watcher(
+    app: AppHandle,
+    state: tauri::State<'_, ClipboardWatcherState>,
+    directory_path: String,
+    allow_apps: Option<Vec<String>>,
+    deny_apps: Option<Vec<String>>,
+) -> Result<ClipboardWatcherStatus, String> {
+    ensure_writable(&app, &directory_path)?;
+    let directory_path = normalize_path_string(&directory_path);
+    if !Path::new(&directory_path).is_dir() {
+        return Err(format!("Not a directory: {}", directory_path));
+    }
+
+    stop_watcher(&state);
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let captured = Arc::new(Mutex::new(0));
+    let thread_stop = stop.clone();
+    let thread_captured = captured.clone();
+    let allow_apps = allow_apps.unwrap_or_default();
+    let deny_apps = deny_apps.unwrap_or_default();
+    let handle = std::thread::spawn(move || {
+        watch(
+            directory_path,
+            allow_apps,
+            deny_apps,
+            thread_stop,
+            thread_captured,
+        )
+    });
+
+    if let Ok(mut guard) = state.watcher.lock() {
+        *guard = Some(RunningWatcher {
+            stop,
+            captured,
+            handle,
+        });
+    }
+
+    Ok(current_status(&state))
+}
+
+#[tauri::command]
+pub async fn stop_clipboard_watcher(
+    state: tauri::State<'_, ClipboardWatcherState>,
+) -> Result<ClipboardWatcherStatus, String> {
+    stop_watcher(&state);
+    Ok(current_status(&state))
+}
+
+#[tauri::command]
+pub async fn get_clipboard_watcher_status(
+    state: tauri::State<'_, ClipboardWatcherState>,
+) -> Result<ClipboardWatcherStatus, String> {
+    Ok(current_status(&state))
+}
@@ -0,0 +1,145 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use stream_core::digest::{self, DigestSummary};
+use stream_core::paths::normalize_path_string;
+use stream_core::webhooks::WebhookEvent;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::vaults::{ensure_writable, resolve_vault_config};
+use crate::webhooks;
+
+/// Opt-in schedule: once a week has ended, its digest is generated on the next refresh check
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DigestSchedule {
+    pub enabled: bool,
+    pub directory_path: String,
+    #[serde(default)]
+    pub repo_paths: Vec<String>,
+}
+
+/// Managed state holding the digest schedule, persisted to the app data dir
+pub struct DigestState {
+    path: PathBuf,
+    schedule: Mutex<DigestSchedule>,
+}
+
+impl DigestState {
+    pub fn load(path: PathBuf) -> Self {
+        let schedule = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        DigestState {
+            path,
+            schedule: Mutex::new(schedule),
+        }
+    }
+
+    fn schedule(&self) -> DigestSchedule {
+        self.schedule.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+}
+
+/// Generate last week's digest if the schedule is enabled and it hasn't been written yet.
+/// Called from the background refresh thread
+pub fn run_scheduled_digest(app: &AppHandle) {
+    let schedule = app.state::<DigestState>().schedule();
+    if !schedule.enabled || schedule.directory_path.is_empty() {
+        return;
+    }
+
+    let directory = Path::new(&schedule.directory_path);
+    let week = digest::last_complete_week(Local::now().date_naive());
+    if !directory.is_dir()
+        || digest::digest_path(directory, week).exists()
+        || ensure_writable(app, &schedule.directory_path).is_err()
+    {
+        return;
+    }
+
+    // An empty repo list means "the vault's repositories"
+    let repo_paths = if schedule.repo_paths.is_empty() {
+        resolve_vault_config(app, None, Some(schedule.directory_path.clone()))
+            .map(|config| config.repo_paths)
+            .unwrap_or_default()
+    } else {
+        schedule.repo_paths.clone()
+    };
+
+    match digest::generate_weekly_digest(directory, week, &repo_paths) {
+        Ok(summary) => {
+            if let Err(e) = app.emit("digest-generated", &summary) {
+                eprintln!("Failed to emit digest-generated event: {}", e);
+            }
+            notify_summary(app, &summary);
+        }
+        Err(e) => eprintln!("Failed to generate scheduled digest: {}", e),
+    }
+}
+
+fn notify_summary(app: &AppHandle, summary: &DigestSummary) {
+    match serde_json::to_value(summary) {
+        Ok(data) => webhooks::notify(app, WebhookEvent::SummaryGenerated, data),
+        Err(e) => eprintln!("Failed to serialize digest for webhooks: {}", e),
+    }
+}
+
+#[tauri::command]
+pub async fn generate_weekly_digest(
+    app: AppHandle,
+    directory_path: String,
+    week: Option<String>,
+    repo_paths: Vec<String>,
+) -> Result<DigestSummary, String> {
+    ensure_writable(&app, &directory_path)?;
+    let directory_path = normalize_path_string(&directory_path);
+    let week = match week {
+        Some(week) => digest::parse_iso_week(&week)
+            .ok_or_else(|| format!("Invalid week '{}', expected e.g. 2024-W05", week))?,
+        None => digest::last_complete_week(Local::now().date_naive()),
+    };
+
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        digest::generate_weekly_digest(Path::new(&directory_path), week, &repo_paths)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Digest task failed: {}", e))?
+    .map_err(|e| format!("Failed to generate digest: {}", e))?;
+
+    notify_summary(&app, &summary);
+    Ok(summary)
+}
+
+#[tauri::command]
+pub async fn set_digest_schedule(
+    state: tauri::State<'_, DigestState>,
+    schedule: DigestSchedule,
+) -> Result<(), String> {
+    if schedule.enabled && !Path::new(&schedule.directory_path).is_dir() {
+        return Err(format!("Not a directory: {}", schedule.directory_path));
+    }
+
+    if let Some(parent) = state.path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to save schedule: {}", e))?;
+    }
+    let json = serde_json::to_string_pretty(&schedule).map_err(|e| e.to_string())?;
+    fs::write(&state.path, json).map_err(|e| format!("Failed to save schedule: {}", e))?;
+
+    *state
+        .schedule
+        .lock()
+        .map_err(|_| "Digest schedule is unavailable".to_string())? = schedule;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_digest_schedule(
+    state: tauri::State<'_, DigestState>,
+) -> Result<DigestSchedule, String> {
+    Ok(state.schedule())
+}
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use chrono::NaiveDate;
+use stream_core::clock::now_ms;
+use stream_core::focus::{CompletedFocusSession, FocusStats, FocusStatus, FocusStore};
+use stream_core::paths::normalize_path_string;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::vaults::ensure_writable;
+
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Managed state owning the focus timer, persisted to the app data dir on every change
+pub struct FocusState {
+    path: PathBuf,
+    store: Mutex<FocusStore>,
+}
+
+impl FocusState {
+    pub fn load(path: PathBuf) -> Self {
+        let store = FocusStore::load(&path);
+        FocusState {
+            path,
+            store: Mutex::new(store),
+        }
+    }
+
+    fn with_store<T>(
+        &self,
+        f: impl FnOnce(&mut FocusStore) -> Result<T, Box<dyn std::error::Error>>,
+    ) -> Result<T, String> {
+        let mut store = self
+            .store
+            .lock()
+            .map_err(|_| "Focus state is unavailable".to_string())?;
+        let result = f(&mut store).map_err(|e| e.to_string())?;
+        store
+            .save(&self.path)
+            .map_err(|e| format!("Failed to save focus state: {}", e))?;
+        Ok(result)
+    }
+
+    pub(crate) fn completed_sessions(&self) -> Vec<CompletedFocusSession> {
+        self.store
+            .lock()
+            .map(|s| s.completed.clone())
+            .unwrap_or_default()
+    }
+
+    fn status(&self) -> FocusStatus {
+        let store = self.store.lock().map(|s| s.clone()).unwrap_or_default();
+        store.status(now_ms())
+    }
+}
+
+/// Emit a tick each second while a session is active and complete timed sessions when
+/// they run out. Runs on its own thread so the timer doesn't depend on the window
+pub fn spawn_ticker(app: AppHandle) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(TICK_INTERVAL);
+        let state = app.state::<FocusState>();
+
+        let finished = state
+            .store
+            .lock()
+            .ok()
+            .and_then(|s| s.active.as_ref().map(|a| a.is_finished(now_ms())))
+            .unwrap_or(false);
+        if finished {
+            match state.with_store(|store| store.stop(now_ms())) {
+                Ok(completed) => {
+                    if let Err(e) = app.emit("focus-session-completed", &completed) {
+                        eprintln!("Failed to emit focus-session-completed event: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("Failed to complete focus session: {}", e),
+            }
+        }
+
+        let status = state.status();
+        if status.active {
+            if let Err(e) = app.emit("focus-tick", &status) {
+                eprintln!("Failed to emit focus-tick event: {}", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn start_focus_session(
+    app: AppHandle,
+    state: tauri::State<'_, FocusState>,
+    directory_path: String,
+    label: String,
+    duration_minutes: Option<u64>,
+) -> Result<FocusStatus, String> {
+    // Finished sessions are logged to the vault's daily note
+    ensure_writable(&app, &directory_path)?;
+    let directory_path = normalize_path_string(&directory_path);
+    if !Path::new(&directory_path).is_dir() {
+        return Err(format!("Not a directory: {}", directory_path));
+    }
+
+    let planned = duration_minutes.filter(|m| *m > 0).map(|m| m * 60_000);
+    state.with_store(|store| store.start(&label, &directory_path, planned, now_ms()))?;
+    Ok(state.status())
+}
+
+#[tauri::command]
+pub async fn pause_focus_session(
+    state: tauri::State<'_, FocusState>,
+) -> Result<FocusStatus, String> {
+    state.with_store(|store| store.pause(now_ms()))?;
+    Ok(state.status())
+}
+
+#[tauri::command]
+pub async fn resume_focus_session(
+    state: tauri::State<'_, FocusState>,
+) -> Result<FocusStatus, String> {
+    state.with_store(|store| store.resume(now_ms()))?;
+    Ok(state.status())
+}
+
+#[tauri::command]
+pub async fn stop_focus_session(
+    state: tauri::State<'_, FocusState>,
+) -> Result<CompletedFocusSession, String> {
+    state.with_store(|store| store.stop(now_ms()))
+}
+
+#[tauri::command]
+pub async fn get_focus_session(state: tauri::State<'_, FocusState>) -> Result<FocusStatus, String> {
+    Ok(state.status())
+}
+
+#[tauri::command]
+pub async fn get_focus_stats(
+    state: tauri::State<'_, FocusState>,
+    start_date: String,
+    end_date: String,
+) -> Result<FocusStats, String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date '{}': {}", date, e))
+    };
+    let (start, end) = (parse(&start_date)?, parse(&end_date)?);
+
+    let store = state
+        .store
+        .lock()
+        .map_err(|_| "Focus state is unavailable".to_string())?;
+    Ok(store.stats(start, end))
+}
@@ -0,0 +1,465 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use stream_core::file_lock::NoteLockedError;
+use stream_core::git::{get_repo_commits, GitCommit};
+use stream_core::markdown::{append_to_daily_note, daily_note_path, entry_day_bounds};
+use stream_core::paths::normalize_path_string;
+use stream_core::search::search_markdown_files;
+use stream_core::tasks::tasks;
+use tauri::AppHandle;
+
+use crate::vaults::ensure_writable;
+
+const DEFAULT_PORT: u16 = 7725;
+
+/// Maximum accepted request body (1MB) - notes pushed over the API are small
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+/// Maximum accepted request line and headers together (16KB)
+const MAX_HEADER_BYTES: u64 = 16 * 1024;
+/// Connections handled at once, each on its own thread; more are turned away with a 503
+const MAX_CONNECTIONS: usize = 16;
+const IO_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Clone)]
+struct HttpApiConfig {
+    /// For checking the vault's read-only flag on each write, so it applies as soon as set
+    app: AppHandle,
+    directory_path: String,
+    token: String,
+    repo_paths: Vec<String>,
+}
+
+struct RunningServer {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+/// Managed state holding the running server, if any. The server is off until started
+#[derive(Default)]
+pub struct HttpApiState {
+    server: Mutex<Option<RunningServer>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HttpApiStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DailySummary {
+    pub date: String,
+    pub note_path: String,
+    pub note_exists: bool,
+    pub word_count: usize,
+    pub open_tasks: usize,
+    pub completed_tasks: usize,
+    pub commits: Vec<GitCommit>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AppendRequest {
+    text: String,
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+struct HttpResponse {
+    status: u16,
+    body: String,
+}
+
+impl HttpResponse {
+    fn json<T: Serialize>(status: u16, value: &T) -> Self {
+        HttpResponse {
+            status,
+            body: serde_json::to_string(value).unwrap_or_else(|_| "{}".to_string()),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        HttpResponse::json(status, &json!({ "error": message }))
+    }
+}
+
+fn status_reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        413 => "Payload Too Large",
+        423 => "Locked",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Internal Server Error",
+    }
+}
+
+/// Read one request or header line. A line cut short by the header limit is refused with a 431
+fn read_header_line(
+    reader: &mut BufReader<std::io::Take<&TcpStream>>,
+    line: &mut String,
+    malformed: &str,
+) -> Result<usize, HttpResponse> {
+    let read = reader
+        .read_line(line)
+        .map_err(|_| HttpResponse::error(400, malformed))?;
+    if reader.get_ref().limit() == 0 && !line.ends_with('\n') {
+        return Err(HttpResponse::error(431, "Request headers too large"));
+    }
+    Ok(read)
+}
+
+fn read_request(stream: &TcpStream) -> Result<HttpRequest, HttpResponse> {
+    let mut reader = BufReader::new(stream.take(MAX_HEADER_BYTES));
+
+    let mut request_line = String::new();
+    read_header_line(&mut reader, &mut request_line, "Malformed request")?;
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_uppercase();
+    let target = parts.next().unwrap_or("/");
+
+    let (path, query_string) = target.split_once('?').unwrap_or((target, ""));
+    let query: HashMap<String, String> = url::form_urlencoded::parse(query_string.as_bytes())
+        .into_owned()
+        .collect();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        let read = read_header_line(&mut reader, &mut line, "Malformed headers")?;
+        let line = line.trim_end();
+        if read == 0 || line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let content_length = headers
+        .get("content-length")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(0);
+
+    if content_length > MAX_BODY_BYTES {
+        return Err(HttpResponse::error(413, "Request body too large"));
+    }
+
+    // The body has its own limit, checked above
+    reader.get_mut().set_limit(content_length as u64);
+    let mut body = vec![0u8; content_length];
+    reader
+        .read_exact(&mut body)
+        .map_err(|_| HttpResponse::error(400, "Incomplete request body"))?;
+
+    Ok(HttpRequest {
+        method,
+        path: path.to_string(),
+        query,
+        headers,
+        body,
+    })
+}
+
+fn write_response(mut stream: &TcpStream, response: HttpResponse) {
+    let payload = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        status_reason(response.status),
+        response.body.len(),
+        response.body
+    );
+    let _ = stream.write_all(payload.as_bytes());
+    let _ = stream.flush();
+}
+
+/// Compare tokens without short-circuiting on the first differing byte
+fn tokens_match(expected: &str, provided: &str) -> bool {
+    if expected.len() != provided.len() {
+        return false;
+    }
+    expected
+        .bytes()
+        .zip(provided.bytes())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+fn is_authorized(request: &HttpRequest, token: &str) -> bool {
+    let provided = request
+        .headers
+        .get("authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| request.headers.get("x-stream-token").map(|v| v.as_str()));
+
+    match provided {
+        Some(provided) => tokens_match(token, provided.trim()),
+        None => false,
+    }
+}
+
+fn parse_date_param(request: &HttpRequest) -> Result<NaiveDate, HttpResponse> {
+    match request.query.get("date") {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| HttpResponse::error(400, "Invalid date, expected YYYY-MM-DD")),
+        None => Ok(Local::now().date_naive()),
+    }
+}
+
+fn handle_append(config: &HttpApiConfig, request: &HttpRequest) -> HttpResponse {
+    if let Err(e) = ensure_writable(&config.app, &config.directory_path) {
+        return HttpResponse::error(403, &e);
+    }
+    let text = match serde_json::from_slice::<AppendRequest>(&request.body) {
+        Ok(append) => append.text,
+        Err(_) => match String::from_utf8(request.body.clone()) {
+            Ok(text) => text,
+            Err(_) => return HttpResponse::error(400, "Body must be UTF-8 text or JSON"),
+        },
+    };
+
+    if text.trim().is_empty() {
+        return HttpResponse::error(400, "Nothing to append");
+    }
+
+    let today = Local::now().date_naive();
+    match append_to_daily_note(Path::new(&config.directory_path), today, &text) {
+        Ok(path) => HttpResponse::json(200, &json!({ "file_path": path.to_string_lossy() })),
+        Err(e) => match e.downcast_ref::<NoteLockedError>() {
+            Some(locked) => HttpResponse::json(
+                423,
+                &json!({ "error": locked.to_string(), "retry_after_ms": locked.retry_after_ms }),
+            ),
+            None => HttpResponse::error(500, &format!("Failed to append to note: {}", e)),
+        },
+    }
+}
+
+fn handle_search(config: &HttpApiConfig, request: &HttpRequest) -> HttpResponse {
+    let query = match request.query.get("q") {
+        Some(q) if !q.trim().is_empty() => q,
+        _ => return HttpResponse::error(400, "Missing 'q' parameter"),
+    };
+    let limit = request
+        .query
+        .get("limit")
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(20);
+
+    match search_markdown_files(&config.directory_path, query, limit, true, false) {
+        Ok(results) => HttpResponse::json(200, &results),
+        Err(e) => HttpResponse::error(500, &e.to_string()),
+    }
+}
+
+fn build_daily_summary(config: &HttpApiConfig, date: NaiveDate) -> DailySummary {
+    let note_path = daily_note_path(Path::new(&config.directory_path), date);
+    let content = std::fs::read_to_string(&note_path).ok();
+
+    let mut open_tasks = 0;
+    let mut completed_tasks = 0;
+    if let Some(content) = &content {
+        for (_, task) in tasks(content) {
+            if task.done {
+                completed_tasks += 1;
+            } else {
+                open_tasks += 1;
+            }
+        }
+    }
+
+    let mut commits = Vec::new();
+    if let Some((start_seconds, end_seconds)) =
+        entry_day_bounds(Path::new(&config.directory_path), date)
+    {
+        for repo_path in &config.repo_paths {
+            match get_repo_commits(repo_path, start_seconds, end_seconds) {
+                Ok(walk) => commits.extend(walk.commits),
+                Err(e) => eprintln!("HTTP API: failed to read commits for {}: {}", repo_path, e),
+            }
+        }
+    }
+    commits.sort_by_key(|c| std::cmp::Reverse(c.timestamp));
+
+    DailySummary {
+        date: date.format("%Y-%m-%d").to_string(),
+        note_path: note_path.to_string_lossy().to_string(),
+        note_exists: content.is_some(),
+        word_count: content
+            .as_deref()
+            .map(|c| c.split_whitespace().count())
+            .unwrap_or(0),
+        open_tasks,
+        completed_tasks,
+        commits,
+    }
+}
+
+fn route(config: &HttpApiConfig, request: &HttpRequest) -> HttpResponse {
+    if !is_authorized(request, &config.token) {
+        return HttpResponse::error(401, "Missing or invalid token");
+    }
+
+    match (request.method.as_str(), request.path.as_str()) {
+        ("GET", "/health") => HttpResponse::json(200, &json!({ "ok": true })),
+        ("POST", "/today/append") => handle_append(config, request),
+        ("GET", "/search") => handle_search(config, request),
+        ("GET", "/summary") => match parse_date_param(request) {
+            Ok(date) => HttpResponse::json(200, &build_daily_summary(config, date)),
+            Err(response) => response,
+        },
+        (_, "/today/append") | (_, "/search") | (_, "/summary") => {
+            HttpResponse::error(405, "Method not allowed")
+        }
+        _ => HttpResponse::error(404, "Not found"),
+    }
+}
+
+fn handle_connection(stream: TcpStream, config: &HttpApiConfig) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(IO_TIMEOUT));
+    let _ = stream.set_write_timeout(Some(IO_TIMEOUT));
+    let response = match read_request(&stream) {
+        Ok(request) => route(config, &request),
+        Err(response) => response,
+    };
+    write_response(&stream, response);
+}
+
+/// Accept connections until `stop` is set, handling each on its own thread so a slow
+/// client can't hold up the others
+fn serve(listener: TcpListener, config: HttpApiConfig, stop: Arc<AtomicBool>) {
+    let config = Arc::new(config);
+    let active = Arc::new(AtomicUsize::new(0));
+    while !stop.load(Ordering::Relaxed) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                if active.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+                    active.fetch_sub(1, Ordering::SeqCst);
+                    let _ = stream.set_nonblocking(false);
+                    let _ = stream.set_write_timeout(Some(IO_TIMEOUT));
+                    write_response(&stream, HttpResponse::error(503, "Too many connections"));
+                    continue;
+                }
+                let config = config.clone();
+                let connection_active = active.clone();
+                let spawned = std::thread::Builder::new()
+                    .name("http-api-connection".to_string())
+                    .spawn(move || {
+                        handle_connection(stream, &config);
+                        connection_active.fetch_sub(1, Ordering::SeqCst);
+                    });
+                if let Err(e) = spawned {
+                    eprintln!("HTTP API: failed to start connection thread: {}", e);
+                    active.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Err(e) => {
+                eprintln!("HTTP API: failed to accept connection: {}", e);
+                std::thread::sleep(Duration::from_millis(100));
+            }
+        }
+    }
+}
+
+fn stop_server(state: &HttpApiState) {
+    let running = state.server.lock().ok().and_then(|mut guard| guard.take());
+    if let Some(running) = running {
+        running.stop.store(true, Ordering::Relaxed);
+        let _ = running.handle.join();
+    }
+}
+
+fn current_status(state: &HttpApiState) -> HttpApiStatus {
+    let port = state
+        .server
+        .lock()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(|s| s.port));
+
+    HttpApiStatus {
+        running: port.is_some(),
+        port,
+    }
+}
+
+#[tauri::command]
+pub async fn start_http_api(
+    app: AppHandle,
+    state: tauri::State<'_, HttpApiState>,
+    directory_path: String,
+    token: String,
+    port: Option<u16>,
+    repo_paths: Option<Vec<String>>,
+) -> Result<HttpApiStatus, String> {
+    let directory_path = normalize_path_string(&directory_path);
+    if token.trim().len() < 16 {
+        return Err("API token must be at least 16 characters".to_string());
+    }
+    if !Path::new(&directory_path).is_dir() {
+        return Err(format!("Not a directory: {}", directory_path));
+    }
+
+    stop_server(&state);
+
+    let port = port.unwrap_or(DEFAULT_PORT);
+    let listener = TcpListener::bind(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to bind 127.0.0.1:{}: {}", port, e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to configure listener: {}", e))?;
+
+    let config = HttpApiConfig {
+        app,
+        directory_path,
+        token: token.trim().to_string(),
+        repo_paths: repo_paths.unwrap_or_default(),
+    };
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread_stop = stop.clone();
+    let handle = std::thread::spawn(move || serve(listener, config, thread_stop));
+
+    if let Ok(mut guard) = state.server.lock() {
+        *guard = Some(RunningServer { port, stop, handle });
+    }
+
+    Ok(current_status(&state))
+}
+
+#[tauri::command]
+pub async fn stop_http_api(state: tauri::State<'_, HttpApiState>) -> Result<HttpApiStatus, String> {
+    stop_server(&state);
+    Ok(current_status(&state))
+}
+
+#[tauri::command]
+pub async fn get_http_api_status(
+    state: tauri::State<'_, HttpApiState>,
+) -> Result<HttpApiStatus, String> {
+    Ok(current_status(&state))
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use chrono::NaiveDate;
+use stream_core::archive;
+use stream_core::import::{self, DateRule};
+use stream_core::paths::normalize_path;
+use stream_core::undo::UndoJournal;
+use tauri::{AppHandle, State};
+
+use crate::auth::require_auth;
+use crate::vaults::{ensure_writable, resolve_vault_config};
+
+pub use stream_core::archive::{ArchiveSummary, DayBundleSummary, ImportSummary};
+pub use stream_core::import::TextImportSummary;
+
+#[tauri::command]
+pub(crate) async fn export_archive(
+    app: AppHandle,
+    directory_path: String,
+    output_path: String,
+    repo_paths: Option<Vec<String>>,
+) -> Result<ArchiveSummary, String> {
+    require_auth(&app)?;
+    archive::export_archive(
+        &normalize_path(&directory_path),
+        &normalize_path(&output_path),
+        repo_paths.as_deref(),
+    )
+    .map_err(|e| format!("Failed to export archive: {}", e))
+}
+
+/// Fails without importing anything when an undo copy can't be saved, unless
+/// `without_undo` is set
+#[tauri::command]
+pub(crate) async fn import_archive(
+    app: AppHandle,
+    undo: State<'_, UndoJournal>,
+    archive_path: String,
+    directory_path: String,
+    overwrite: Option<bool>,
+    without_undo: Option<bool>,
+) -> Result<ImportSummary, String> {
+    ensure_writable(&app, &directory_path)?;
+    archive::import_archive(
+        &normalize_path(&archive_path),
+        &normalize_path(&directory_path),
+        overwrite.unwrap_or(false),
+        (!without_undo.unwrap_or(false)).then_some(&*undo),
+    )
+    .map_err(|e| format!("Failed to import archive: {}", e))
+}
+
+/// Turn a folder exported from another app (Bear, Standard Notes, ...) into daily notes,
+/// dating files by name or by a frontmatter field. Like `import_archive`, an undo copy is
+/// required unless `without_undo` is set
+#[tauri::command]
+pub(crate) async fn import_text_files(
+    app: AppHandle,
+    undo: State<'_, UndoJournal>,
+    source_path: String,
+    directory_path: String,
+    rule: DateRule,
+    without_undo: Option<bool>,
+) -> Result<TextImportSummary, String> {
+    ensure_writable(&app, &directory_path)?;
+    import::import_text_files(
+        &normalize_path(&source_path),
+        &normalize_path(&directory_path),
+        &rule,
+        (!without_undo.unwrap_or(false)).then_some(&*undo),
+    )
+    .map_err(|e| format!("Failed to import notes: {}", e))
+}
+
+/// Bundle a day's note, attachments, metadata and commits for handing off. `output_path`
+/// ending in `.zip` produces a zip, anything else a folder
+#[tauri::command]
+pub(crate) async fn export_day_bundle(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    date: String,
+    output_path: String,
+    repo_paths: Option<Vec<String>>,
+) -> Result<DayBundleSummary, String> {
+    require_auth(&app)?;
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let repo_paths = repo_paths.unwrap_or(config.repo_paths);
+    let output_path = normalize_path(&output_path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        archive::export_day_bundle(Path::new(&config.path), date, &repo_paths, &output_path)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+    .map_err(|e| format!("Failed to export day bundle: {}", e))
+}
@@ -0,0 +1,30 @@
+use std::path::Path;
+
+use stream_core::attachments::{self, OrphanedAttachmentsReport};
+use tauri::AppHandle;
+
+use crate::vaults::{ensure_writable, resolve_directory};
+
+/// Files in the vault's assets folder that no note links to, with size totals. With
+/// `trash`, they're moved to the vault's `.trash` folder
+#[tauri::command]
+pub(crate) async fn find_orphaned_attachments(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    trash: Option<bool>,
+) -> Result<OrphanedAttachmentsReport, String> {
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+    let trash = trash.unwrap_or(false);
+    if trash {
+        ensure_writable(&app, &directory_path)?;
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        attachments::find_orphaned_attachments(Path::new(&directory_path), trash)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Attachment scan task failed: {}", e))?
+    .map_err(|e| format!("Failed to find orphaned attachments: {}", e))
+}
@@ -0,0 +1,29 @@
+use stream_core::bookmark;
+use stream_core::paths::normalize_path;
+use tauri::AppHandle;
+
+use crate::vaults::ensure_writable;
+
+pub use stream_core::bookmark::ArchivedPage;
+
+#[tauri::command]
+pub(crate) async fn archive_url(
+    app: AppHandle,
+    directory_path: String,
+    url: String,
+    note_path: String,
+) -> Result<ArchivedPage, String> {
+    ensure_writable(&app, &directory_path)?;
+    ensure_writable(&app, &note_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        bookmark::archive_url(
+            &normalize_path(&directory_path),
+            &url,
+            &normalize_path(&note_path),
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Archive task failed: {}", e))?
+    .map_err(|e| format!("Failed to archive page: {}", e))
+}
@@ -1,553 +1,624 @@
-use std::collections::{HashMap, HashSet};
-
-use chrono::{DateTime, Utc};
-use git2::{self, DiffOptions, Repository, Time};
-use rayon::prelude::*;
-use serde::{Deserialize, Serialize};
-
-/// Maximum number of commits to return per repository to prevent memory issues
-const MAX_COMMITS_PER_REPO: usize = 200;
-
-/// Maximum number of files changed to return per commit
-const MAX_FILES_PER_COMMIT: usize = 50;
-
-/// Limit the number of branch tips used for non-tip commit matching (performance guard)
-const MAX_BRANCH_TIPS_FOR_MATCH: usize = 50;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct GitCommit {
-    pub id: String,
-    pub message: String,
-    pub author_name: String,
-    pub author_email: String,
-    pub timestamp: u64,
-    pub date: String,
-    pub repo_path: String,
-    pub files_changed: Vec<String>,
-    pub branches: Vec<String>,
-    pub url: Option<String>,
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde_json::json;
+use stream_core::commit_export::{self, CommitExportFormat, CommitExportSummary};
+use stream_core::commit_log::{self, CommitLogFormat, CommitLogSummary};
+use stream_core::conventional::{self, CommitTypeStats};
+use stream_core::git::{AuthorFilter, CommitBackend, RepoSettings};
+use stream_core::highlights::{self, DailyHighlights};
+use stream_core::hooks::{self, DiaryHookStatus};
+use stream_core::languages::{self, LanguageStats};
+use stream_core::paths::{normalize_path, normalize_path_string, same_path};
+use stream_core::stats::DateRange;
+use stream_core::{git, markdown};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+
+use crate::vaults::{ensure_writable, resolve_directory, resolve_vault_config};
+use crate::SETTINGS_STORE;
+
+pub use stream_core::git::{
+    AuthorLeaderboard, BackendBenchmark, FetchResult, GitCommit, NotesSyncResult, RepoCommits,
+    RepoHealth, RepoMaintenanceResult,
+};
+
+const GIT_BACKEND_KEY: &str = "git_backend";
+const FIRST_PARENT_KEY: &str = "git_first_parent";
+/// Display settings keyed by normalized repository path
+const REPO_SETTINGS_KEY: &str = "repo_settings";
+const AUTHOR_FILTER_KEY: &str = "author_filter";
+/// Used by the diary hook when no CLI path is given; expected on the user's PATH
+const DEFAULT_CLI_PATH: &str = "stream";
+
+fn load_repo_settings<R: Runtime>(app: &AppHandle<R>) -> HashMap<String, RepoSettings> {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(REPO_SETTINGS_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct RepoCommits {
-    pub repo_path: String,
-    pub commits: Vec<GitCommit>,
-    pub error: Option<String>,
+fn load_author_filter<R: Runtime>(app: &AppHandle<R>) -> AuthorFilter {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(AUTHOR_FILTER_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FetchResult {
-    pub repo_path: String,
-    pub success: bool,
-    pub message: String,
-}
-
-#[tauri::command]
-pub(crate) async fn fetch_repos(repo_paths: Vec<String>) -> Result<Vec<FetchResult>, String> {
-    let mut results = Vec::new();
-
-    for repo_path in repo_paths {
-        let result = match fetch_repo(&repo_path).await {
-            Ok(message) => FetchResult {
-                repo_path: repo_path.clone(),
-                success: true,
-                message,
-            },
-            Err(e) => FetchResult {
-                repo_path: repo_path.clone(),
-                success: false,
-                message: format!("Failed to fetch: {}", e),
-            },
-        };
-        results.push(result);
+/// Trimmed, lowercased and deduplicated, dropping blanks
+fn clean_emails(emails: Vec<String>) -> Vec<String> {
+    let mut cleaned: Vec<String> = Vec::new();
+    for email in emails {
+        let email = email.trim().to_lowercase();
+        if !email.is_empty() && !cleaned.contains(&email) {
+            cleaned.push(email);
+        }
     }
+    cleaned
+}
 
-    Ok(results)
+fn settings_for<'a>(
+    all: &'a HashMap<String, RepoSettings>,
+    repo_path: &str,
+) -> Option<&'a RepoSettings> {
+    all.get(repo_path).or_else(|| {
+        all.iter()
+            .find(|(path, _)| same_path(path, repo_path))
+            .map(|(_, settings)| settings)
+    })
 }
 
-#[tauri::command]
-pub(crate) async fn get_git_commits_for_repos(
+/// Keep only repositories in one of `groups` (case-insensitive). No filter keeps them all
+fn filter_repos_by_group<R: Runtime>(
+    app: &AppHandle<R>,
     repo_paths: Vec<String>,
-    start_timestamp: u64,
-    end_timestamp: u64,
-) -> Result<Vec<RepoCommits>, String> {
-    let start_seconds = (start_timestamp / 1000) as i64;
-    let end_seconds = (end_timestamp / 1000) as i64;
-
-    // Process all repos in parallel using rayon
-    let results: Vec<RepoCommits> = repo_paths
-        .par_iter()
-        .map(|repo_path| {
-            match get_repo_commits(repo_path, start_seconds, end_seconds) {
-                Ok(commits) => RepoCommits {
-                    repo_path: repo_path.clone(),
-                    commits,
-                    error: None,
-                },
-                Err(e) => RepoCommits {
-                    repo_path: repo_path.clone(),
-                    commits: Vec::new(),
-                    error: Some(format!("Error reading repository: {}", e)),
-                },
-            }
+    groups: Option<&[String]>,
+) -> Vec<String> {
+    let groups = match groups {
+        Some(groups) if !groups.is_empty() => groups,
+        _ => return repo_paths,
+    };
+    let all = load_repo_settings(app);
+    repo_paths
+        .into_iter()
+        .filter(|path| {
+            settings_for(&all, path)
+                .and_then(|settings| settings.group.as_deref())
+                .is_some_and(|group| groups.iter().any(|g| g.eq_ignore_ascii_case(group)))
         })
-        .collect();
-
-    Ok(results)
+        .collect()
 }
 
-fn time_to_timestamp_ms(time: Time) -> u64 {
-    (time.seconds() as u64) * 1000
-}
-
-fn time_to_iso_date(time: Time) -> String {
-    let timestamp = time.seconds();
-    let dt = DateTime::from_timestamp(timestamp, 0).unwrap_or_else(|| Utc::now());
-    dt.format("%Y-%m-%d").to_string()
-}
-
-/// Build a map of commit OID -> (branches, is_on_remote) for all branch tips
-/// This is much more efficient than walking history for each commit
-fn build_branch_tip_map(
-    repo: &Repository,
-) -> Result<HashMap<git2::Oid, (Vec<String>, bool)>, Box<dyn std::error::Error>> {
-    let mut tip_map: HashMap<git2::Oid, (Vec<String>, bool)> = HashMap::new();
-
-    // Process local branches - just get the tip commits
-    let local_branches = repo.branches(Some(git2::BranchType::Local))?;
-    for branch in local_branches {
-        let (branch, _) = branch?;
-        if let Some(name) = branch.name()? {
-            let reference = branch.get();
-            if let Some(target) = reference.target() {
-                let entry = tip_map.entry(target).or_insert_with(|| (Vec::new(), false));
-                entry.0.push(name.to_string());
-            }
+/// Attach each repository's display settings to its results, tag commits with the
+/// sub-projects they touch and, with the author filter on, keep only the user's own commits
+/// by the repository's emails or the global ones
+fn apply_repo_settings<R: Runtime>(app: &AppHandle<R>, repos: &mut [RepoCommits]) {
+    let all = load_repo_settings(app);
+    let author_filter = load_author_filter(app);
+    for repo in repos {
+        if let Some(settings) = settings_for(&all, &repo.repo_path) {
+            repo.settings = settings.clone();
         }
-    }
-
-    // Process remote branches - just get the tip commits
-    let remote_branches = repo.branches(Some(git2::BranchType::Remote))?;
-    for branch in remote_branches {
-        let (branch, _) = branch?;
-        if let Some(name) = branch.name()? {
-            let reference = branch.get();
-            if let Some(target) = reference.target() {
-                let entry = tip_map.entry(target).or_insert_with(|| (Vec::new(), false));
-                entry.1 = true; // Mark as on remote
-                let normalized = normalize_branch_name(name);
-                if !entry.0.contains(&normalized) {
-                    entry.0.push(normalized);
-                }
-            }
-        }
-    }
-
-    Ok(tip_map)
-}
-
-#[derive(Clone)]
-struct BranchTip {
-    name: String,
-    oid: git2::Oid,
-    is_remote: bool,
-    time_seconds: i64,
-}
-
-/// Build a list of all branch tips (local + remote) with normalized names.
-fn build_branch_tip_list(
-    repo: &Repository,
-) -> Result<Vec<BranchTip>, Box<dyn std::error::Error>> {
-    let mut tips = Vec::new();
-
-    let local_branches = repo.branches(Some(git2::BranchType::Local))?;
-    for branch in local_branches {
-        let (branch, _) = branch?;
-        if let Some(name) = branch.name()? {
-            let reference = branch.get();
-            if let Some(target) = reference.target() {
-                let time_seconds = repo
-                    .find_commit(target)
-                    .map(|commit| commit.time().seconds())
-                    .unwrap_or(0);
-                tips.push(BranchTip {
-                    name: normalize_branch_name(name),
-                    oid: target,
-                    is_remote: false,
-                    time_seconds,
-                });
-            }
-        }
-    }
-
-    let remote_branches = repo.branches(Some(git2::BranchType::Remote))?;
-    for branch in remote_branches {
-        let (branch, _) = branch?;
-        if let Some(name) = branch.name()? {
-            let reference = branch.get();
-            if let Some(target) = reference.target() {
-                let time_seconds = repo
-                    .find_commit(target)
-                    .map(|commit| commit.time().seconds())
-                    .unwrap_or(0);
-                tips.push(BranchTip {
-                    name: normalize_branch_name(name),
-                    oid: target,
-                    is_remote: true,
-                    time_seconds,
-                });
-            }
+        git::attribute_projects(&mut repo.commits, &repo.settings.projects);
+        if author_filter.enabled {
+            let emails = if repo.settings.author_emails.is_empty() {
+                &author_filter.emails
+            } else {
+                &repo.settings.author_emails
+            };
+            git::retain_authored_by(&mut repo.commits, emails);
         }
     }
+}
 
-    Ok(tips)
-}
-
-/// Get the primary branch for a commit using a simplified approach
-/// Instead of walking all branch histories, we check if commit is reachable from main branches
-fn get_branch_for_commit_fast(
-    repo: &Repository,
-    commit_oid: git2::Oid,
-    branch_tip_map: &HashMap<git2::Oid, (Vec<String>, bool)>,
-    branch_tips: &[BranchTip],
-) -> (Vec<String>, bool) {
-    // First check if this commit is a branch tip (fast path)
-    if let Some((branches, is_remote)) = branch_tip_map.get(&commit_oid) {
-        let mut result = branches.clone();
-        // Prioritize main branches
-        result.sort_by(|a, b| {
-            let a_main = is_main_branch(a);
-            let b_main = is_main_branch(b);
-            b_main.cmp(&a_main)
-        });
-        result.truncate(2);
-        return (result, *is_remote);
-    }
-
-    // For non-tip commits, find all branches whose tip contains this commit.
-    let mut branches = Vec::new();
-    let mut is_on_remote = false;
-
-    for tip in branch_tips {
-        let is_ancestor = tip.oid == commit_oid
-            || repo
-                .graph_descendant_of(tip.oid, commit_oid)
-                .unwrap_or(false);
-
-        if is_ancestor {
-            if !branches.contains(&tip.name) {
-                branches.push(tip.name.clone());
-            }
-            if tip.is_remote {
-                is_on_remote = true;
-            }
-        }
-    }
+/// Label a repository with a display name, group and color, set which author emails are
+/// the user's there and which path prefixes are sub-projects. Blank fields are cleared, and a repository with nothing set is forgotten
+#[tauri::command]
+pub(crate) async fn set_repo_settings(
+    app: AppHandle,
+    repo_path: String,
+    settings: RepoSettings,
+) -> Result<(), String> {
+    let repo_path = normalize_path_string(&repo_path);
+    let clean = |value: Option<String>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let settings = RepoSettings {
+        display_name: clean(settings.display_name),
+        group: clean(settings.group),
+        color: clean(settings.color),
+        author_emails: clean_emails(settings.author_emails),
+        projects: settings
+            .projects
+            .iter()
+            .map(|prefix| prefix.trim().trim_matches('/').to_string())
+            .filter(|prefix| !prefix.is_empty())
+            .collect(),
+    };
 
-    if branches.is_empty() {
-        return (vec!["unknown".to_string()], false);
+    let mut all = load_repo_settings(&app);
+    all.retain(|path, _| !same_path(path, &repo_path));
+    if settings != RepoSettings::default() {
+        all.insert(repo_path, settings);
     }
 
-    branches.sort_by(|a, b| {
-        let a_main = is_main_branch(a);
-        let b_main = is_main_branch(b);
-        b_main.cmp(&a_main).then_with(|| a.cmp(b))
-    });
-    branches.truncate(3);
-
-    (branches, is_on_remote)
-}
-
-fn normalize_branch_name(branch_name: &str) -> String {
-    branch_name
-        .replace("origin/", "")
-        .replace("refs/heads/", "")
-}
-
-fn is_main_branch(branch_name: &str) -> bool {
-    let main_branch_names = [
-        "main",
-        "master",
-        "origin/main",
-        "origin/master",
-        "develop",
-        "origin/develop",
-    ];
-    main_branch_names.contains(&branch_name)
-}
-
-async fn fetch_repo(repo_path: &str) -> Result<String, Box<dyn std::error::Error>> {
-    let repo = Repository::open(repo_path)?;
-
-    let remotes = repo.remotes()?;
-    let mut fetch_results = Vec::new();
-
-    for remote_name in remotes.iter() {
-        if let Some(remote_name) = remote_name {
-            match repo.find_remote(remote_name) {
-                Ok(mut remote) => {
-                    let mut fetch_options = git2::FetchOptions::new();
-
-                    let mut callbacks = git2::RemoteCallbacks::new();
-                    callbacks.credentials(|_url, username_from_url, _allowed_types| {
-                        if let Some(username) = username_from_url {
-                            git2::Cred::ssh_key_from_agent(username)
-                        } else {
-                            git2::Cred::default()
-                        }
-                    });
-
-                    fetch_options.remote_callbacks(callbacks);
-
-                    match remote.fetch(&[] as &[&str], Some(&mut fetch_options), None) {
-                        Ok(()) => {
-                            let stats = remote.stats();
-                            fetch_results.push(format!(
-                                "{}: {} objects received",
-                                remote_name,
-                                stats.received_objects()
-                            ));
-                        }
-                        Err(e) => {
-                            fetch_results.push(format!("{}: {}", remote_name, e));
-                        }
-                    }
-                }
-                Err(e) => {
-                    fetch_results.push(format!("{}: Failed to find remote - {}", remote_name, e));
-                }
-            }
-        }
-    }
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(REPO_SETTINGS_KEY, json!(all));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
 
-    if fetch_results.is_empty() {
-        Ok("No remotes found".to_string())
-    } else {
-        Ok(fetch_results.join("; "))
-    }
+#[tauri::command]
+pub(crate) async fn get_repo_settings(
+    app: AppHandle,
+) -> Result<HashMap<String, RepoSettings>, String> {
+    Ok(load_repo_settings(&app))
 }
 
-fn get_remote_url(repo: &Repository) -> Option<String> {
-    if let Ok(remote) = repo.find_remote("origin") {
-        if let Some(url) = remote.url() {
-            return Some(url.to_string());
-        }
-    }
+/// Show only the user's own commits. `emails` are used for repositories that don't set
+/// their own `author_emails`
+#[tauri::command]
+pub(crate) async fn set_author_filter(
+    app: AppHandle,
+    enabled: bool,
+    emails: Vec<String>,
+) -> Result<(), String> {
+    let filter = AuthorFilter {
+        enabled,
+        emails: clean_emails(emails),
+    };
 
-    if let Ok(remotes) = repo.remotes() {
-        for remote_name in remotes.iter() {
-            if let Some(remote_name) = remote_name {
-                if let Ok(remote) = repo.find_remote(remote_name) {
-                    if let Some(url) = remote.url() {
-                        return Some(url.to_string());
-                    }
-                }
-            }
-        }
-    }
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(AUTHOR_FILTER_KEY, json!(filter));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
 
-    None
+#[tauri::command]
+pub(crate) async fn get_author_filter(app: AppHandle) -> Result<AuthorFilter, String> {
+    Ok(load_author_filter(&app))
 }
 
-fn build_commit_url(remote_url: &str, commit_id: &str) -> Option<String> {
-    let url = if remote_url.starts_with("git@") {
-        let parts: Vec<&str> = remote_url.split(':').collect();
-        if parts.len() != 2 {
-            return None;
+/// Apply the saved commit history backend. Called once at startup
+pub(crate) fn load_git_backend(app: &AppHandle) {
+    let backend = app
+        .store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(GIT_BACKEND_KEY))
+        .and_then(|value| value.as_str().and_then(CommitBackend::from_string));
+    if let Some(backend) = backend {
+        if let Err(e) = git::set_commit_backend(backend) {
+            eprintln!("Failed to apply saved git backend: {}", e);
         }
-        let host = parts[0].replace("git@", "");
-        let path = parts[1].trim_end_matches(".git");
-        format!("https://{}/{}", host, path)
-    } else if remote_url.starts_with("https://") || remote_url.starts_with("http://") {
-        remote_url.trim_end_matches(".git").to_string()
-    } else {
-        return None;
-    };
-
-    if url.contains("github.com") {
-        Some(format!("{}/commit/{}", url, commit_id))
-    } else if url.contains("gitlab.com") || url.contains("gitlab.") {
-        Some(format!("{}/-/commit/{}", url, commit_id))
-    } else if url.contains("bitbucket.org") {
-        Some(format!("{}/commits/{}", url, commit_id))
-    } else {
-        Some(format!("{}/commit/{}", url, commit_id))
     }
 }
 
-/// Get files changed for a commit using optimized diff options (no content, just file names)
-fn get_files_changed_fast(
-    repo: &Repository,
-    commit: &git2::Commit,
-) -> Vec<String> {
-    let mut files_changed = Vec::new();
-
-    let parent = match commit.parent(0) {
-        Ok(p) => p,
-        Err(_) => return files_changed, // Initial commit or error
-    };
-
-    let tree = match commit.tree() {
-        Ok(t) => t,
-        Err(_) => return files_changed,
-    };
-
-    let parent_tree = match parent.tree() {
-        Ok(t) => t,
-        Err(_) => return files_changed,
-    };
+/// Apply the saved first-parent setting. Called once at startup
+pub(crate) fn load_first_parent(app: &AppHandle) {
+    let first_parent = app
+        .store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(FIRST_PARENT_KEY))
+        .and_then(|value| value.as_bool())
+        .unwrap_or(false);
+    git::set_first_parent(first_parent);
+}
 
-    // Configure diff to skip content computation entirely
-    let mut diff_opts = DiffOptions::new();
-    diff_opts.skip_binary_check(true); // Don't check if files are binary
-    diff_opts.ignore_submodules(true); // Skip submodule processing
-    diff_opts.context_lines(0); // No context lines needed
+/// Follow only first parents when listing commits, collapsing merged branches into their
+/// merge commits
+#[tauri::command]
+pub(crate) async fn set_first_parent(app: AppHandle, first_parent: bool) -> Result<(), String> {
+    git::set_first_parent(first_parent);
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(FIRST_PARENT_KEY, json!(first_parent));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
 
-    let diff = match repo.diff_tree_to_tree(Some(&parent_tree), Some(&tree), Some(&mut diff_opts)) {
-        Ok(d) => d,
-        Err(_) => return files_changed,
-    };
+#[tauri::command]
+pub(crate) async fn get_first_parent() -> Result<bool, String> {
+    Ok(git::first_parent())
+}
 
-    // Use deltas() iterator - much faster than foreach, no callbacks
-    for delta in diff.deltas().take(MAX_FILES_PER_COMMIT) {
-        if let Some(path) = delta.new_file().path() {
-            if let Some(path_str) = path.to_str() {
-                files_changed.push(path_str.to_string());
-            }
-        }
-    }
+/// Library commit history is read with: "git2" (libgit2) or "gix" (gitoxide, only in builds
+/// with the `gix` feature)
+#[tauri::command]
+pub(crate) async fn set_git_backend(app: AppHandle, backend: String) -> Result<(), String> {
+    let backend = CommitBackend::from_string(&backend)
+        .ok_or_else(|| format!("Unknown git backend: {}", backend))?;
+    git::set_commit_backend(backend).map_err(|e| e.to_string())?;
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(GIT_BACKEND_KEY, json!(backend.as_str()));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
 
-    files_changed
+#[tauri::command]
+pub(crate) async fn get_git_backend() -> Result<CommitBackend, String> {
+    Ok(git::commit_backend())
 }
 
-fn get_repo_commits(
-    repo_path: &str,
-    start_seconds: i64,
-    end_seconds: i64,
-) -> Result<Vec<GitCommit>, Box<dyn std::error::Error>> {
-    let repo = Repository::open(repo_path)?;
-    let mut revwalk = repo.revwalk()?;
-
-    revwalk.push_glob("refs/heads/*")?;
-    revwalk.push_glob("refs/remotes/*")?;
-    revwalk.set_sorting(git2::Sort::TIME)?;
-
-    let remote_url = get_remote_url(&repo);
-    
-    // Build branch tip map once upfront (much faster than per-commit checks)
-    let branch_tip_map = build_branch_tip_map(&repo).unwrap_or_default();
-    let branch_tips_raw = build_branch_tip_list(&repo).unwrap_or_default();
-
-    // Consolidate by branch name and keep the newest tip per branch
-    let mut tips_by_name: HashMap<String, BranchTip> = HashMap::new();
-    for tip in branch_tips_raw {
-        tips_by_name
-            .entry(tip.name.clone())
-            .and_modify(|existing| {
-                if tip.time_seconds > existing.time_seconds {
-                    existing.oid = tip.oid;
-                    existing.time_seconds = tip.time_seconds;
-                }
-                if tip.is_remote {
-                    existing.is_remote = true;
-                }
-            })
-            .or_insert(tip);
-    }
+/// Time a one-day commit query against each available backend
+#[tauri::command]
+pub(crate) async fn benchmark_git_backends(
+    repo_path: String,
+    date: String,
+) -> Result<Vec<BackendBenchmark>, String> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let (start_seconds, end_seconds) =
+        git::local_day_bounds(date).ok_or_else(|| format!("Invalid local date: {}", date))?;
+    let repo_path = normalize_path_string(&repo_path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        git::benchmark_commit_backends(&repo_path, start_seconds, end_seconds)
+    })
+    .await
+    .map_err(|e| format!("Benchmark task failed: {}", e))
+}
 
-    let mut branch_tips: Vec<BranchTip> = tips_by_name.into_values().collect();
-    branch_tips.sort_by(|a, b| b.time_seconds.cmp(&a.time_seconds));
+#[tauri::command]
+pub(crate) async fn fetch_repos(repo_paths: Vec<String>) -> Result<Vec<FetchResult>, String> {
+    tauri::async_runtime::spawn_blocking(move || git::fetch_repos(&repo_paths))
+        .await
+        .map_err(|e| format!("Fetch task failed: {}", e))
+}
 
-    // Always include main-like branches even if they are old
-    let mut main_like: Vec<BranchTip> = branch_tips
+/// Quick per-repository status for a "needs attention" view, without a commit query
+#[tauri::command]
+pub(crate) async fn get_repo_health(repo_paths: Vec<String>) -> Result<Vec<RepoHealth>, String> {
+    let repo_paths: Vec<String> = repo_paths
         .iter()
-        .filter(|tip| is_main_branch(&tip.name))
-        .cloned()
+        .map(|path| normalize_path_string(path))
         .collect();
+    tauri::async_runtime::spawn_blocking(move || git::get_repo_health(&repo_paths))
+        .await
+        .map_err(|e| format!("Git task failed: {}", e))
+}
 
-    let mut limited: Vec<BranchTip> = branch_tips
-        .into_iter()
-        .take(MAX_BRANCH_TIPS_FOR_MATCH)
-        .collect();
+/// Write a commit-graph for a large repository so history walks and branch lookups run
+/// faster. Needs the git CLI
+#[tauri::command]
+pub(crate) async fn run_repo_maintenance(
+    repo_path: String,
+) -> Result<RepoMaintenanceResult, String> {
+    let repo_path = normalize_path_string(&repo_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        git::run_repo_maintenance(&repo_path).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Maintenance task failed: {}", e))?
+    .map_err(|e| format!("Failed to run repository maintenance: {}", e))
+}
 
-    for tip in main_like.drain(..) {
-        if !limited.iter().any(|existing| existing.name == tip.name) {
-            limited.push(tip);
+/// Commits across `repo_paths` in a time range, limited to repositories in `groups` if given.
+/// Conventional commit types in `exclude_types`, e.g. `chore`, are left out
+#[tauri::command]
+pub(crate) async fn get_git_commits_for_repos(
+    app: AppHandle,
+    repo_paths: Vec<String>,
+    start_timestamp: u64,
+    end_timestamp: u64,
+    groups: Option<Vec<String>>,
+    exclude_types: Option<Vec<String>>,
+) -> Result<Vec<RepoCommits>, String> {
+    let repo_paths = filter_repos_by_group(&app, repo_paths, groups.as_deref());
+    let mut repos = tauri::async_runtime::spawn_blocking(move || {
+        git::get_git_commits_for_repos(&repo_paths, start_timestamp, end_timestamp)
+    })
+    .await
+    .map_err(|e| format!("Git task failed: {}", e))?;
+
+    apply_repo_settings(&app, &mut repos);
+    if let Some(kinds) = exclude_types {
+        for repo in &mut repos {
+            conventional::exclude_commit_types(&mut repo.commits, &kinds);
         }
     }
+    Ok(repos)
+}
 
-    let mut commits = Vec::new();
-    let mut seen_commits = HashSet::new();
+/// Commits that landed on `branch` in a time range, e.g. a release branch. With
+/// `first_parent`, commits merged in from other branches are left out
+#[tauri::command]
+pub(crate) async fn get_commits_for_branch(
+    app: AppHandle,
+    repo_path: String,
+    branch: String,
+    start_timestamp: u64,
+    end_timestamp: u64,
+    first_parent: Option<bool>,
+) -> Result<RepoCommits, String> {
+    let mut repo = tauri::async_runtime::spawn_blocking(move || {
+        git::get_commits_for_branch(
+            &repo_path,
+            &branch,
+            start_timestamp,
+            end_timestamp,
+            first_parent.unwrap_or(false),
+        )
+    })
+    .await
+    .map_err(|e| format!("Git task failed: {}", e))?;
+
+    apply_repo_settings(&app, std::slice::from_mut(&mut repo));
+    Ok(repo)
+}
 
-    for oid in revwalk {
-        // Stop early if we've reached the limit
-        if commits.len() >= MAX_COMMITS_PER_REPO {
-            break;
-        }
+/// Commits and lines changed per author in `repo_path` over a time range, across everyone
+/// who committed rather than only the user
+#[tauri::command]
+pub(crate) async fn get_author_stats(
+    repo_path: String,
+    start_timestamp: u64,
+    end_timestamp: u64,
+) -> Result<AuthorLeaderboard, String> {
+    let repo_path = normalize_path_string(&repo_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        git::get_author_stats(&repo_path, start_timestamp, end_timestamp).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Git task failed: {}", e))?
+    .map_err(|e| format!("Failed to compute author stats: {}", e))
+}
 
-        let oid = match oid {
-            Ok(oid) => oid,
-            Err(_) => continue,
-        };
+/// Commit counts by conventional type over a time range, e.g. to say a week was mostly fixes
+#[tauri::command]
+pub(crate) async fn get_commit_type_stats(
+    app: AppHandle,
+    repo_paths: Vec<String>,
+    start_timestamp: u64,
+    end_timestamp: u64,
+    groups: Option<Vec<String>>,
+) -> Result<CommitTypeStats, String> {
+    let repos = get_git_commits_for_repos(
+        app,
+        repo_paths,
+        start_timestamp,
+        end_timestamp,
+        groups,
+        None,
+    )
+    .await?;
+    Ok(conventional::commit_type_stats(
+        repos.iter().flat_map(|repo| &repo.commits),
+    ))
+}
 
-        if seen_commits.contains(&oid) {
-            continue;
-        }
-        seen_commits.insert(oid);
+/// Changed files by language over a time range, overall and per day
+#[tauri::command]
+pub(crate) async fn get_language_stats(
+    app: AppHandle,
+    repo_paths: Vec<String>,
+    start_timestamp: u64,
+    end_timestamp: u64,
+    groups: Option<Vec<String>>,
+) -> Result<LanguageStats, String> {
+    let repos = get_git_commits_for_repos(
+        app,
+        repo_paths,
+        start_timestamp,
+        end_timestamp,
+        groups,
+        None,
+    )
+    .await?;
+    Ok(languages::language_stats(
+        repos.iter().flat_map(|repo| &repo.commits),
+    ))
+}
 
-        let commit = match repo.find_commit(oid) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
+/// Commits for the day a daily note covers, bucketed in the timezone the note was written in.
+/// Without `repo_paths`, the vault's repositories are used, limited to `groups` if given
+#[tauri::command]
+pub(crate) async fn get_commits_for_entry(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    date: String,
+    repo_paths: Option<Vec<String>>,
+    groups: Option<Vec<String>>,
+) -> Result<Vec<RepoCommits>, String> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let repo_paths = filter_repos_by_group(
+        &app,
+        repo_paths.unwrap_or(config.repo_paths),
+        groups.as_deref(),
+    );
+    let (start_seconds, end_seconds) = markdown::entry_day_bounds(Path::new(&config.path), date)
+        .ok_or_else(|| format!("Invalid local date: {}", date))?;
+
+    let mut repos = tauri::async_runtime::spawn_blocking(move || {
+        git::get_git_commits_for_repos(
+            &repo_paths,
+            start_seconds as u64 * 1000,
+            end_seconds as u64 * 1000,
+        )
+    })
+    .await
+    .map_err(|e| format!("Git task failed: {}", e))?;
+
+    apply_repo_settings(&app, &mut repos);
+    Ok(repos)
+}
 
-        let commit_time = commit.time();
-        let commit_timestamp = commit_time.seconds();
+/// The day's highest-impact commits and most edited notes, `limit` of each (default 5), so
+/// the daily view can lead with them. Repositories are chosen as in `get_commits_for_entry`
+#[tauri::command]
+pub(crate) async fn get_daily_highlights(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    date: String,
+    limit: Option<usize>,
+    repo_paths: Option<Vec<String>>,
+    groups: Option<Vec<String>>,
+) -> Result<DailyHighlights, String> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let repo_paths = filter_repos_by_group(
+        &app,
+        repo_paths.unwrap_or(config.repo_paths),
+        groups.as_deref(),
+    );
+    let limit = limit.unwrap_or(5);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        highlights::get_daily_highlights(Path::new(&config.path), &repo_paths, date, limit)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Highlights task failed: {}", e))?
+    .map_err(|e| format!("Failed to get highlights: {}", e))
+}
 
-        // Skip commits outside the date range
-        // Since we're sorted by time, we can break early if we're past the range
-        if commit_timestamp < start_seconds {
-            break;
-        }
-        if commit_timestamp > end_seconds {
-            continue;
-        }
+/// Write the day's commits into its daily note as a marked section that re-runs replace.
+/// `format` is "list" (default) or "table"
+#[tauri::command]
+pub(crate) async fn insert_commits_into_note(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    date: String,
+    repo_paths: Option<Vec<String>>,
+    format: Option<String>,
+) -> Result<CommitLogSummary, String> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let format = match format {
+        Some(format) => CommitLogFormat::from_string(&format)
+            .ok_or_else(|| format!("Unknown commit log format: {}", format))?,
+        None => CommitLogFormat::default(),
+    };
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    ensure_writable(&app, &config.path)?;
+    let repo_paths = repo_paths.unwrap_or(config.repo_paths);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        commit_log::insert_commits_into_note(Path::new(&config.path), date, &repo_paths, format)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Commit log task failed: {}", e))?
+    .map_err(|e| format!("Failed to insert commits: {}", e))
+}
 
-        let author = commit.author();
-        let message = commit.message().unwrap_or("").to_string();
-
-        // Get files changed using optimized method (no diff content)
-        let files_changed = get_files_changed_fast(&repo, &commit);
-
-        // Use the fast branch detection
-        let (branches, is_on_remote) =
-            get_branch_for_commit_fast(&repo, oid, &branch_tip_map, &limited);
-
-        let commit_id = format!("{}", oid);
-        let url = if is_on_remote {
-            remote_url
-                .as_ref()
-                .and_then(|remote| build_commit_url(remote, &commit_id))
-        } else {
-            None
-        };
-
-        let git_commit = GitCommit {
-            id: commit_id,
-            message: message.lines().next().unwrap_or("").to_string(),
-            author_name: author.name().unwrap_or("Unknown").to_string(),
-            author_email: author.email().unwrap_or("").to_string(),
-            timestamp: time_to_timestamp_ms(commit_time),
-            date: time_to_iso_date(commit_time),
-            repo_path: repo_path.to_string(),
-            files_changed,
-            branches,
-            url,
-        };
-
-        commits.push(git_commit);
-    }
+/// Write the commits in `range` with their line counts and ticket ids to `output_path`, as
+/// "csv" or "json", for spreadsheets and dashboards. Without `repo_paths`, the vault's
+/// repositories are used
+#[tauri::command]
+pub(crate) async fn export_commits(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    repo_paths: Option<Vec<String>>,
+    range: DateRange,
+    format: String,
+    output_path: String,
+) -> Result<CommitExportSummary, String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date '{}': {}", date, e))
+    };
+    let (start, end) = (parse(&range.start)?, parse(&range.end)?);
+    let (start_seconds, _) =
+        git::local_day_bounds(start).ok_or_else(|| format!("Invalid local date: {}", start))?;
+    let (_, end_seconds) =
+        git::local_day_bounds(end).ok_or_else(|| format!("Invalid local date: {}", end))?;
+    let format = CommitExportFormat::from_string(&format)
+        .ok_or_else(|| format!("Unknown export format: {}", format))?;
+    let repo_paths = match repo_paths {
+        Some(repo_paths) => repo_paths,
+        None => resolve_vault_config(&app, vault_id, directory_path)?.repo_paths,
+    };
+    let output_path = normalize_path(&output_path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        commit_export::export_commits(
+            &repo_paths,
+            start_seconds as u64 * 1000,
+            end_seconds as u64 * 1000 + 999,
+            &output_path,
+            format,
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+    .map_err(|e| format!("Failed to export commits: {}", e))
+}
 
-    commits.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+#[tauri::command]
+pub(crate) async fn sync_notes(
+    app: AppHandle,
+    directory_path: String,
+) -> Result<NotesSyncResult, String> {
+    ensure_writable(&app, &directory_path)?;
+    let directory_path = normalize_path_string(&directory_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        git::sync_notes(&directory_path).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Sync task failed: {}", e))?
+    .map_err(|e| format!("Failed to sync notes: {}", e))
+}
+
+/// Install a post-commit hook in `repo_path` that appends each new commit to today's note
+/// through the CLI. Reinstalling upgrades an older hook in place
+#[tauri::command]
+pub(crate) async fn install_diary_hook(
+    app: AppHandle,
+    repo_path: String,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    cli_path: Option<String>,
+) -> Result<DiaryHookStatus, String> {
+    let notes_dir = resolve_directory(&app, vault_id, directory_path)?;
+    // The hook writes to the vault on every commit
+    ensure_writable(&app, &notes_dir)?;
+    let notes_dir = normalize_path(&notes_dir);
+    let repo_path = normalize_path_string(&repo_path);
+    let cli_path = cli_path
+        .map(|path| path.trim().to_string())
+        .filter(|path| !path.is_empty())
+        .unwrap_or_else(|| DEFAULT_CLI_PATH.to_string());
+
+    hooks::install_diary_hook(&repo_path, &cli_path, &notes_dir)
+        .map_err(|e| format!("Failed to install diary hook: {}", e))
+}
 
-    Ok(commits)
+/// Remove the diary hook, leaving any other post-commit hook in place. Returns whether one
+/// was installed
+#[tauri::command]
+pub(crate) async fn uninstall_diary_hook(repo_path: String) -> Result<bool, String> {
+    hooks::uninstall_diary_hook(&normalize_path_string(&repo_path))
+        .map_err(|e| format!("Failed to uninstall diary hook: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn get_diary_hook_status(repo_path: String) -> Result<DiaryHookStatus, String> {
+    hooks::diary_hook_status(&normalize_path_string(&repo_path))
+        .map_err(|e| format!("Failed to read diary hook: {}", e))
 }
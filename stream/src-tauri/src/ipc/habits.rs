@@ -0,0 +1,32 @@
+use chrono::NaiveDate;
+use stream_core::habits;
+use stream_core::paths::normalize_path;
+
+pub use stream_core::habits::HabitStats;
+
+#[tauri::command]
+pub(crate) async fn get_habit_stats(
+    directory_path: String,
+    habit_patterns: Vec<String>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<HabitStats>, String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date '{}': {}", date, e))
+    };
+    let (start, end) = (parse(&start_date)?, parse(&end_date)?);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        habits::get_habit_stats(
+            &normalize_path(&directory_path),
+            &habit_patterns,
+            start,
+            end,
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Habit stats task failed: {}", e))?
+    .map_err(|e| format!("Failed to compute habit stats: {}", e))
+}
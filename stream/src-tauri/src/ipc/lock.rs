@@ -0,0 +1,57 @@
+use stream_core::lock;
+use stream_core::paths::normalize_path;
+use tauri::AppHandle;
+
+use crate::auth::require_auth;
+use crate::vaults::ensure_writable;
+
+#[tauri::command]
+pub(crate) async fn lock_note(
+    app: AppHandle,
+    file_path: String,
+    passphrase: String,
+) -> Result<String, String> {
+    ensure_writable(&app, &file_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        lock::lock_note(&normalize_path(&file_path), &passphrase)
+            .map(|path| path.to_string_lossy().to_string())
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Lock task failed: {}", e))?
+    .map_err(|e| format!("Failed to lock note: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn unlock_note(
+    app: AppHandle,
+    file_path: String,
+    passphrase: String,
+) -> Result<String, String> {
+    ensure_writable(&app, &file_path)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        lock::unlock_note(&normalize_path(&file_path), &passphrase)
+            .map(|path| path.to_string_lossy().to_string())
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Unlock task failed: {}", e))?
+    .map_err(|e| format!("Failed to unlock note: {}", e))
+}
+
+/// Decrypt a locked note for viewing while leaving it locked on disk
+#[tauri::command]
+pub(crate) async fn read_locked_note(
+    app: AppHandle,
+    file_path: String,
+    passphrase: String,
+) -> Result<String, String> {
+    require_auth(&app)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        lock::read_locked_note(&normalize_path(&file_path), &passphrase).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Unlock task failed: {}", e))?
+    .map_err(|e| format!("Failed to read locked note: {}", e))
+}
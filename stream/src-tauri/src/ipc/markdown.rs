@@ -1,242 +1,463 @@
 use std::collections::HashMap;
-use std::fs;
-use std::path::Path;
-use std::sync::LazyLock;
-
-use chrono::NaiveDate;
-use regex::Regex;
-use serde::{Deserialize, Serialize};
-use xattr;
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct MarkdownFileMetadata {
-    pub file_path: String,
-    pub file_name: String,
-    pub created_at: u64,
-    pub modified_at: u64,
-    pub size: u64,
-    pub country: Option<String>,
-    pub city: Option<String>,
-    pub date_from_filename: u64,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct StructuredMarkdownFileMetadata {
-    pub file_path: String,
-    pub file_name: String,
-    pub created_at: u64,
-    pub modified_at: u64,
-    pub size: u64,
-    pub country: Option<String>,
-    pub city: Option<String>,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-pub struct StructuredMarkdownFile {
-    pub file_path: String,
-    pub file_name: String,
-    pub created_at: u64,
-    pub modified_at: u64,
-    pub size: u64,
-    pub country: Option<String>,
-    pub city: Option<String>,
-    pub description: Option<String>,
-    pub content: String,
-    pub refresh_interval: Option<String>,
-    pub last_refreshed_at: Option<u64>,
-}
-
-static DATE_FILENAME_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"^(\d{4})-(\d{2})-(\d{2})\.md$").expect("Failed to compile date filename regex")
-});
-
-const XATTR_COUNTRY_KEY: &str = "user.location.country";
-const XATTR_CITY_KEY: &str = "user.location.city";
-const XATTR_DESCRIPTION_KEY: &str = "user.file.description";
-const XATTR_REFRESH_INTERVAL_KEY: &str = "user.refresh.interval";
-const XATTR_LAST_REFRESHED_KEY: &str = "user.refresh.last_refreshed";
-
-#[derive(Debug, Clone, PartialEq)]
-enum RefreshInterval {
-    Minutely,
-    Hourly,
-    Daily,
-    Weekly,
-    None,
-}
-
-impl RefreshInterval {
-    fn from_string(s: &str) -> Option<Self> {
-        match s.to_lowercase().as_str() {
-            "minutely" => Some(RefreshInterval::Minutely),
-            "hourly" => Some(RefreshInterval::Hourly),
-            "daily" => Some(RefreshInterval::Daily),
-            "weekly" => Some(RefreshInterval::Weekly),
-            "none" => Some(RefreshInterval::None),
-            _ => None,
-        }
-    }
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::{Local, NaiveDate};
+use serde_json::json;
+use stream_core::doctor::{self, MetadataDoctorReport};
+use stream_core::frontmatter::{self, Frontmatter};
+use stream_core::lint::{self, LintReport, LintRules};
+use stream_core::markdown::MetadataCache;
+use stream_core::paths::normalize_path;
+use stream_core::recents::RecentNotes;
+use stream_core::rollover::{self, RolloverMode, RolloverSummary};
+use stream_core::sources::CommandSourceStore;
+use stream_core::structure::{self, MarkdownTable, NoteStructure};
+use stream_core::template::{self, TemplateContext};
+use stream_core::undo::UndoJournal;
+use stream_core::webhooks::WebhookEvent;
+use stream_core::{cloud, markdown, note_ids, sync, walk};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::today::saved_calendar_feed;
+use crate::vaults::{ensure_writable, resolve_directory, resolve_vault_config, VaultConfig};
+use crate::webhooks;
+use crate::SETTINGS_STORE;
+
+pub use stream_core::markdown::{
+    FileChunk, FileContent, MarkdownFileMetadata, MoodPoint, NoteForDate, OnThisDayEntry,
+    RefreshScheduleEntry, ResurfacedNote, StructuredMarkdownFile, StructuredMarkdownFileMetadata,
+};
+pub use stream_core::recents::RecentNote;
+
+const SCAN_THREADS_KEY: &str = "scan_threads";
+
+/// Apply the saved thread count for directory scans. Called once at startup
+pub(crate) fn load_scan_threads(app: &AppHandle) {
+    let threads = app
+        .store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(SCAN_THREADS_KEY))
+        .and_then(|value| value.as_u64())
+        .unwrap_or(0);
+    walk::set_walk_threads(threads as usize);
+}
 
-    fn to_string(&self) -> String {
-        match self {
-            RefreshInterval::Minutely => "minutely".to_string(),
-            RefreshInterval::Hourly => "hourly".to_string(),
-            RefreshInterval::Daily => "daily".to_string(),
-            RefreshInterval::Weekly => "weekly".to_string(),
-            RefreshInterval::None => "none".to_string(),
-        }
-    }
+/// Threads used to scan the notes directory; 0 picks a count from the available cores.
+/// Fewer threads can help on slow network drives that throttle parallel requests
+#[tauri::command]
+pub(crate) async fn set_scan_threads(app: AppHandle, threads: usize) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(SCAN_THREADS_KEY, json!(threads));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    walk::set_walk_threads(threads);
+    Ok(())
+}
 
-    fn duration_ms(&self) -> Option<u64> {
-        match self {
-            RefreshInterval::Minutely => Some(60 * 1000),
-            RefreshInterval::Hourly => Some(60 * 60 * 1000),
-            RefreshInterval::Daily => Some(24 * 60 * 60 * 1000),
-            RefreshInterval::Weekly => Some(7 * 24 * 60 * 60 * 1000),
-            RefreshInterval::None => None,
-        }
+#[tauri::command]
+pub(crate) async fn get_scan_threads() -> Result<usize, String> {
+    Ok(walk::walk_threads())
+}
+
+/// Keep the file's current metadata so the overwrite can be undone. A journal failure
+/// shouldn't block the edit itself
+fn record_metadata_undo(undo: &UndoJournal, description: &str, path: &Path) {
+    if let Err(e) = undo.record_metadata(description, path) {
+        eprintln!("Failed to record undo for {}: {}", path.display(), e);
     }
 }
 
-fn read_location_xattrs(file_path: &Path) -> (Option<String>, Option<String>) {
-    let country = xattr::get(file_path, XATTR_COUNTRY_KEY)
-        .ok()
-        .flatten()
-        .and_then(|bytes| String::from_utf8(bytes).ok());
+#[tauri::command]
+pub(crate) async fn set_file_location_metadata(
+    app: AppHandle,
+    undo: tauri::State<'_, UndoJournal>,
+    file_path: String,
+    country: String,
+    city: String,
+) -> Result<(), String> {
+    ensure_writable(&app, &file_path)?;
+    let path = normalize_path(&file_path);
+    record_metadata_undo(&undo, "Set location", &path);
 
-    let city = xattr::get(file_path, XATTR_CITY_KEY)
-        .ok()
-        .flatten()
-        .and_then(|bytes| String::from_utf8(bytes).ok());
+    markdown::write_location_xattrs(&path, &country, &city)
+        .map_err(|e| format!("Failed to set location metadata: {}", e))?;
 
-    (country, city)
+    Ok(())
 }
 
-fn read_description_xattr(file_path: &Path) -> Option<String> {
-    xattr::get(file_path, XATTR_DESCRIPTION_KEY)
-        .ok()
-        .flatten()
-        .and_then(|bytes| String::from_utf8(bytes).ok())
-}
-
-fn write_description_xattr(
-    file_path: &Path,
-    description: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    if description.is_empty() {
-        let _ = xattr::remove(file_path, XATTR_DESCRIPTION_KEY);
-        Ok(())
-    } else {
-        xattr::set(file_path, XATTR_DESCRIPTION_KEY, description.as_bytes())?;
-        Ok(())
-    }
-}
+#[tauri::command]
+pub(crate) async fn set_file_description(
+    app: AppHandle,
+    undo: tauri::State<'_, UndoJournal>,
+    file_path: String,
+    description: String,
+) -> Result<(), String> {
+    ensure_writable(&app, &file_path)?;
+    let path = normalize_path(&file_path);
+    record_metadata_undo(&undo, "Set description", &path);
+
+    markdown::write_description_xattr(&path, &description)
+        .map_err(|e| format!("Failed to set file description: {}", e))?;
 
-fn write_location_xattrs(
-    file_path: &Path,
-    country: &str,
-    city: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
-    xattr::set(file_path, XATTR_COUNTRY_KEY, country.as_bytes())?;
-    xattr::set(file_path, XATTR_CITY_KEY, city.as_bytes())?;
     Ok(())
 }
 
-fn read_refresh_interval(file_path: &Path) -> Option<RefreshInterval> {
-    xattr::get(file_path, XATTR_REFRESH_INTERVAL_KEY)
-        .ok()
-        .flatten()
-        .and_then(|bytes| String::from_utf8(bytes).ok())
-        .and_then(|s| RefreshInterval::from_string(&s))
-}
-
-fn write_refresh_interval(
-    file_path: &Path,
-    interval: &RefreshInterval,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let interval_str = interval.to_string();
-    xattr::set(
-        file_path,
-        XATTR_REFRESH_INTERVAL_KEY,
-        interval_str.as_bytes(),
-    )?;
+#[tauri::command]
+pub(crate) async fn set_file_mood(
+    app: AppHandle,
+    undo: tauri::State<'_, UndoJournal>,
+    file_path: String,
+    mood: String,
+) -> Result<(), String> {
+    ensure_writable(&app, &file_path)?;
+    let path = normalize_path(&file_path);
+    record_metadata_undo(&undo, "Set mood", &path);
+
+    markdown::write_mood_xattr(&path, &mood).map_err(|e| format!("Failed to set mood: {}", e))?;
+
     Ok(())
 }
 
-fn read_last_refreshed(file_path: &Path) -> Option<u64> {
-    xattr::get(file_path, XATTR_LAST_REFRESHED_KEY)
-        .ok()
-        .flatten()
-        .and_then(|bytes| String::from_utf8(bytes).ok())
-        .and_then(|s| s.parse::<u64>().ok())
+/// Callouts and footnotes in a note, for the renderer and the outline
+#[tauri::command]
+pub(crate) async fn get_note_structure(file_path: String) -> Result<NoteStructure, String> {
+    structure::read_note_structure(&normalize_path(&file_path))
+        .map_err(|e| format!("Failed to parse note: {}", e))
 }
 
-fn write_last_refreshed(
-    file_path: &Path,
-    timestamp_ms: u64,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let timestamp_str = timestamp_ms.to_string();
-    xattr::set(
-        file_path,
-        XATTR_LAST_REFRESHED_KEY,
-        timestamp_str.as_bytes(),
-    )?;
-    Ok(())
+/// Tables in a note as headers and rows, for sortable views and charting logs kept in tables
+#[tauri::command]
+pub(crate) async fn extract_tables(file_path: String) -> Result<Vec<MarkdownTable>, String> {
+    structure::extract_tables(&normalize_path(&file_path))
+        .map_err(|e| format!("Failed to read tables: {}", e))
 }
 
-fn parse_date_from_filename(file_name: &str) -> Option<u64> {
-    let caps = DATE_FILENAME_REGEX.captures(file_name)?;
+#[tauri::command]
+pub(crate) async fn get_file_mood(file_path: String) -> Result<Option<String>, String> {
+    Ok(markdown::read_mood_xattr(&normalize_path(&file_path)))
+}
 
-    let year: i32 = caps.get(1)?.as_str().parse().ok()?;
-    let month: u32 = caps.get(2)?.as_str().parse().ok()?;
-    let day: u32 = caps.get(3)?.as_str().parse().ok()?;
+/// The vault's daily note for `date`, created from its template when missing
+fn ensure_daily_note(
+    app: &AppHandle,
+    config: VaultConfig,
+    date: NaiveDate,
+    location: Option<String>,
+) -> Result<String, String> {
+    let existed = markdown::find_daily_note_or_locked(Path::new(&config.path), date).is_some();
+    if !existed {
+        ensure_writable(app, &config.path)?;
+    }
+    let context = TemplateContext {
+        repo_paths: config.repo_paths,
+        location,
+        calendar_feed: saved_calendar_feed(app),
+    };
+    let path = markdown::create_daily_note(
+        Path::new(&config.path),
+        date,
+        config.daily_template.as_deref(),
+        &context,
+    )
+    .map(|path| path.to_string_lossy().to_string())
+    .map_err(|e| format!("Failed to create daily note: {}", e))?;
+
+    if !existed {
+        if let Err(e) = note_ids::ensure_note_id(Path::new(&config.path), Path::new(&path)) {
+            eprintln!("Failed to assign an id to {}: {}", path, e);
+        }
+        webhooks::notify(
+            app,
+            WebhookEvent::NoteCreated,
+            json!({ "file_path": path, "date": date.to_string() }),
+        );
+    }
+    Ok(path)
+}
 
-    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+/// Create a daily note from the vault's template if it doesn't exist yet. Template
+/// placeholders are resolved against the vault's repositories and `location`
+#[tauri::command]
+pub(crate) async fn create_daily_note(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    date: String,
+    location: Option<String>,
+) -> Result<String, String> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+
+    tauri::async_runtime::spawn_blocking(move || ensure_daily_note(&app, config, date, location))
+        .await
+        .map_err(|e| format!("Note creation task failed: {}", e))?
+}
 
-    let datetime = date.and_hms_opt(0, 0, 0)?.and_utc();
-    let timestamp_ms = datetime.timestamp_millis() as u64;
+/// Create the daily note for `date` from `templates/<template_name>.md`, resolving its
+/// placeholders like `create_daily_note`. Fails if the day already has a note
+#[tauri::command]
+pub(crate) async fn create_daily_note_from_template(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    date: String,
+    location: Option<String>,
+    template_name: String,
+) -> Result<String, String> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let mut config = resolve_vault_config(&app, vault_id, directory_path)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Some(existing) = markdown::find_daily_note_or_locked(Path::new(&config.path), date) {
+            return Err(format!(
+                "A note for {} already exists: {}",
+                date,
+                existing.display()
+            ));
+        }
+        let template = template::read_named_template(Path::new(&config.path), &template_name)
+            .map_err(|e| format!("Failed to read template: {}", e))?;
+        config.daily_template = Some(template);
+        ensure_daily_note(&app, config, date, location)
+    })
+    .await
+    .map_err(|e| format!("Note creation task failed: {}", e))?
+}
+
+/// The local date the scheduler last saw, so it notices midnight passing, including while
+/// the machine was asleep
+pub struct NewDayState {
+    today: Mutex<NaiveDate>,
+}
 
-    Some(timestamp_ms)
+impl Default for NewDayState {
+    fn default() -> Self {
+        NewDayState {
+            today: Mutex::new(Local::now().date_naive()),
+        }
+    }
 }
 
+/// Called from the background scheduler. Once the local date has changed, creates the new
+/// day's note in the active vault (unless it's read-only or opted out) and emits `new-day`.
+/// Comparing calendar dates rather than timing 24 hours keeps DST changes and sleeping
+/// through midnight from skipping or repeating a day
+pub(crate) fn create_note_for_new_day(app: &AppHandle) {
+    let today = Local::now().date_naive();
+    let state = app.state::<NewDayState>();
+    match state.today.lock() {
+        Ok(mut last) if *last != today => *last = today,
+        _ => return,
+    }
+
+    let file_path = match resolve_vault_config(app, None, None) {
+        Ok(config) if config.auto_create_daily_note && !config.read_only => {
+            ensure_daily_note(app, config, today, None)
+                .map_err(|e| eprintln!("Failed to create the new day's note: {}", e))
+                .ok()
+        }
+        _ => None,
+    };
+
+    let payload = json!({ "date": today.to_string(), "file_path": file_path });
+    if let Err(e) = app.emit("new-day", payload) {
+        eprintln!("Failed to emit new-day event: {}", e);
+    }
+}
+
+/// Carry unfinished tasks from one day's note into another's under a "Carried over"
+/// heading. `mode` is "move" (default) or "copy"; repeat runs add nothing new
 #[tauri::command]
-pub(crate) async fn set_file_location_metadata(
+pub(crate) async fn rollover_tasks(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    from_date: String,
+    to_date: String,
+    mode: Option<String>,
+) -> Result<RolloverSummary, String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date '{}': {}", date, e))
+    };
+    let from_date = parse(&from_date)?;
+    let to_date = parse(&to_date)?;
+    let mode = match mode {
+        Some(mode) => RolloverMode::from_string(&mode)
+            .ok_or_else(|| format!("Unknown rollover mode: {}", mode))?,
+        None => RolloverMode::default(),
+    };
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+    ensure_writable(&app, &directory_path)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let undo = app.state::<UndoJournal>();
+        rollover::rollover_tasks(
+            Path::new(&directory_path),
+            from_date,
+            to_date,
+            mode,
+            Some(&undo),
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Rollover task failed: {}", e))?
+    .map_err(|e| format!("Failed to carry over tasks: {}", e))
+}
+
+/// Record the timezone an entry was written in, defaulting to the system timezone
+#[tauri::command]
+pub(crate) async fn set_file_timezone(
+    app: AppHandle,
+    undo: tauri::State<'_, UndoJournal>,
     file_path: String,
-    country: String,
-    city: String,
+    timezone: Option<String>,
 ) -> Result<(), String> {
-    let path = Path::new(&file_path);
+    let timezone = timezone
+        .or_else(markdown::current_timezone)
+        .ok_or_else(|| "Could not determine the system timezone".to_string())?;
 
-    write_location_xattrs(path, &country, &city)
-        .map_err(|e| format!("Failed to set location metadata: {}", e))?;
+    ensure_writable(&app, &file_path)?;
+    let path = normalize_path(&file_path);
+    record_metadata_undo(&undo, "Set timezone", &path);
 
-    Ok(())
+    markdown::write_timezone_xattr(&path, &timezone)
+        .map_err(|e| format!("Failed to set timezone: {}", e))
 }
 
 #[tauri::command]
-pub(crate) async fn set_file_description(
+pub(crate) async fn get_mood_trend(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    start_date: String,
+    end_date: String,
+) -> Result<Vec<MoodPoint>, String> {
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date '{}': {}", date, e))
+    };
+    let (start, end) = (parse(&start_date)?, parse(&end_date)?);
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+
+    markdown::get_mood_trend(Path::new(&directory_path), start, end)
+        .map_err(|e| format!("Failed to read mood trend: {}", e))
+}
+
+/// Entries from the same calendar date in previous years, with up to `excerpt_chars` of
+/// each note's opening text when requested
+#[tauri::command]
+pub(crate) async fn get_on_this_day(
+    app: AppHandle,
+    cache: tauri::State<'_, MetadataCache>,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    month: u32,
+    day: u32,
+    excerpt_chars: Option<usize>,
+) -> Result<Vec<OnThisDayEntry>, String> {
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+
+    markdown::get_on_this_day(
+        Path::new(&directory_path),
+        month,
+        day,
+        excerpt_chars,
+        &cache,
+    )
+    .map_err(|e| format!("Failed to read on this day: {}", e))
+}
+
+/// Old entries to resurface, weighted toward ones never reopened unless `prefer_unviewed`
+/// is turned off
+#[tauri::command]
+pub(crate) async fn get_random_notes(
+    app: AppHandle,
+    cache: tauri::State<'_, MetadataCache>,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    count: Option<usize>,
+    min_age_days: Option<u32>,
+    prefer_unviewed: Option<bool>,
+) -> Result<Vec<ResurfacedNote>, String> {
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+
+    markdown::get_random_notes(
+        Path::new(&directory_path),
+        count.unwrap_or(3),
+        min_age_days.unwrap_or(30),
+        prefer_unviewed.unwrap_or(true),
+        &cache,
+    )
+    .map_err(|e| format!("Failed to pick notes: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn mark_note_viewed(app: AppHandle, file_path: String) -> Result<(), String> {
+    ensure_writable(&app, &file_path)?;
+    markdown::mark_note_viewed(&normalize_path(&file_path))
+        .map_err(|e| format!("Failed to mark note as viewed: {}", e))
+}
+
+/// Record that a note was opened, for the recents list and search ranking
+#[tauri::command]
+pub(crate) async fn record_note_view(
+    app: AppHandle,
+    recents: tauri::State<'_, RecentNotes>,
     file_path: String,
-    description: String,
 ) -> Result<(), String> {
-    let path = Path::new(&file_path);
+    let path = normalize_path(&file_path);
+    if !path.is_file() {
+        return Err(format!("Not a file: {}", path.display()));
+    }
+    // Resurfacing reads the last view from the note itself, which read-only vaults keep as is
+    if ensure_writable(&app, &file_path).is_ok() {
+        if let Err(e) = markdown::mark_note_viewed(&path) {
+            eprintln!("Failed to mark note as viewed: {}", e);
+        }
+    }
+    recents
+        .record_view(&path)
+        .map_err(|e| format!("Failed to record note view: {}", e))
+}
 
-    write_description_xattr(path, &description)
-        .map_err(|e| format!("Failed to set file description: {}", e))?;
+#[tauri::command]
+pub(crate) async fn get_recent_notes(
+    recents: tauri::State<'_, RecentNotes>,
+    limit: Option<usize>,
+) -> Result<Vec<RecentNote>, String> {
+    Ok(recents.recent(limit.unwrap_or(20)))
+}
 
-    Ok(())
+fn notify_refreshed(app: &AppHandle, path: &Path) {
+    webhooks::notify(
+        app,
+        WebhookEvent::RefreshCompleted,
+        json!({ "file_path": path.to_string_lossy() }),
+    );
 }
 
 #[tauri::command]
 pub(crate) async fn set_file_refresh_interval(
+    app: AppHandle,
+    undo: tauri::State<'_, UndoJournal>,
     file_path: String,
     interval: String,
 ) -> Result<(), String> {
-    let path = Path::new(&file_path);
+    ensure_writable(&app, &file_path)?;
+    let path = normalize_path(&file_path);
+    record_metadata_undo(&undo, "Set refresh interval", &path);
 
-    let refresh_interval = RefreshInterval::from_string(&interval)
-        .ok_or_else(|| format!("Invalid refresh interval: {}", interval))?;
-
-    write_refresh_interval(path, &refresh_interval)
+    markdown::set_refresh_interval(&path, &interval)
         .map_err(|e| format!("Failed to set refresh interval: {}", e))?;
 
     Ok(())
@@ -244,394 +465,292 @@ pub(crate) async fn set_file_refresh_interval(
 
 #[tauri::command]
 pub(crate) async fn update_last_refreshed(
+    app: AppHandle,
     file_path: String,
     timestamp_ms: u64,
 ) -> Result<(), String> {
-    let path = Path::new(&file_path);
+    ensure_writable(&app, &file_path)?;
+    let path = normalize_path(&file_path);
 
-    write_last_refreshed(path, timestamp_ms)
+    markdown::write_last_refreshed(&path, timestamp_ms)
         .map_err(|e| format!("Failed to update last refreshed timestamp: {}", e))?;
 
+    notify_refreshed(&app, &path);
     Ok(())
 }
 
 #[tauri::command]
-pub(crate) async fn mark_file_as_refreshed(file_path: String) -> Result<(), String> {
-    let path = Path::new(&file_path);
-
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64;
+pub(crate) async fn mark_file_as_refreshed(
+    app: AppHandle,
+    file_path: String,
+) -> Result<(), String> {
+    ensure_writable(&app, &file_path)?;
+    let path = normalize_path(&file_path);
 
-    write_last_refreshed(path, now)
+    markdown::mark_file_as_refreshed(&path)
         .map_err(|e| format!("Failed to update last refreshed: {}", e))?;
 
+    notify_refreshed(&app, &path);
     Ok(())
 }
 
 #[tauri::command]
 pub(crate) async fn get_files_needing_refresh(
-    directory_path: String,
-) -> Result<Vec<String>, String> {
-    let structured_dir_path = Path::new(&directory_path).join("structured");
-
-    if !structured_dir_path.exists() {
-        return Ok(Vec::new());
-    }
-
-    let mut files_needing_refresh = Vec::new();
-
-    let entries = match fs::read_dir(&structured_dir_path) {
-        Ok(entries) => entries,
-        Err(_) => return Ok(Vec::new()),
-    };
+    app: AppHandle,
+    commands: tauri::State<'_, CommandSourceStore>,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+) -> Result<Vec<RefreshScheduleEntry>, String> {
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+    // Command sources live in app data rather than on the file, so the core can't see them
+    let mut files = markdown::get_files_needing_refresh(Path::new(&directory_path));
+    files.retain(|entry| commands.get(Path::new(&entry.file_path)).is_none());
+    Ok(files)
+}
 
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_millis() as u64;
-
-    for entry in entries {
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(_) => continue,
-        };
-
-        let path = entry.path();
-
-        if path.is_file() {
-            if let Some(extension) = path.extension() {
-                if extension.to_string_lossy().to_lowercase() == "md" {
-                    if let Some(interval) = read_refresh_interval(&path) {
-                        if interval != RefreshInterval::None {
-                            let last_refreshed = read_last_refreshed(&path).unwrap_or(0);
-
-                            if let Some(duration_ms) = interval.duration_ms() {
-                                let time_since_refresh = now.saturating_sub(last_refreshed);
-
-                                if time_since_refresh >= duration_ms {
-                                    files_needing_refresh.push(path.to_string_lossy().to_string());
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+/// Every structured file and daily note with a refresh interval, including ones not yet due
+#[tauri::command]
+pub(crate) async fn get_refresh_schedule(
+    app: AppHandle,
+    commands: tauri::State<'_, CommandSourceStore>,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+) -> Result<Vec<RefreshScheduleEntry>, String> {
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+    let mut schedule = markdown::get_refresh_schedule(Path::new(&directory_path));
+    for entry in &mut schedule {
+        entry.has_source |= commands.get(Path::new(&entry.file_path)).is_some();
     }
+    Ok(schedule)
+}
 
-    Ok(files_needing_refresh)
+#[tauri::command]
+pub(crate) async fn materialize_file(file_path: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        cloud::materialize_file(&normalize_path(&file_path)).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Download task failed: {}", e))?
+    .map_err(|e| format!("Failed to download file: {}", e))
 }
 
 #[tauri::command]
 pub(crate) async fn read_markdown_files_content(
     file_paths: Vec<String>,
-) -> Result<HashMap<String, String>, String> {
-    let mut results = HashMap::new();
-
-    for file_path in file_paths {
-        match std::fs::read_to_string(&file_path) {
-            Ok(content) => {
-                results.insert(file_path, content);
-            }
-            Err(e) => {
-                eprintln!("Error reading file {}: {}", file_path, e);
-            }
-        }
-    }
-
-    Ok(results)
+) -> Result<HashMap<String, FileContent>, String> {
+    tauri::async_runtime::spawn_blocking(move || markdown::read_markdown_files_content(file_paths))
+        .await
+        .map_err(|e| format!("Read task failed: {}", e))
 }
 
 #[tauri::command]
 pub(crate) async fn read_markdown_files_metadata(
-    directory_path: String,
+    app: AppHandle,
+    cache: tauri::State<'_, MetadataCache>,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
     max_file_size: Option<u64>,
+    include_xattrs: Option<bool>,
 ) -> Result<Vec<MarkdownFileMetadata>, String> {
-    let max_size = max_file_size.unwrap_or(10 * 1024 * 1024);
-    let mut files = Vec::new();
-
-    fn visit_dir(
-        dir: &Path,
-        files: &mut Vec<MarkdownFileMetadata>,
-        max_size: u64,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        if !dir.is_dir() {
-            return Ok(());
-        }
-
-        let entries = fs::read_dir(dir)?;
-
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.is_dir() {
-                visit_dir(&path, files, max_size)?;
-            } else if path.is_file() {
-                if let Some(extension) = path.extension() {
-                    if extension.to_string_lossy().to_lowercase() == "md" {
-                        let file_name = path
-                            .file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("unknown")
-                            .to_string();
-
-                        if let Some(date_timestamp) = parse_date_from_filename(&file_name) {
-                            if let Ok(metadata) = entry.metadata() {
-                                let size = metadata.len();
-
-                                if size <= max_size {
-                                    let file_path = path.to_string_lossy().to_string();
-
-                                    let created_at = metadata
-                                        .created()
-                                        .or_else(|_| metadata.modified())
-                                        .unwrap_or_else(|_| std::time::SystemTime::now())
-                                        .duration_since(std::time::UNIX_EPOCH)
-                                        .unwrap_or_default()
-                                        .as_millis()
-                                        as u64;
-
-                                    let modified_at = metadata
-                                        .modified()
-                                        .unwrap_or_else(|_| std::time::SystemTime::now())
-                                        .duration_since(std::time::UNIX_EPOCH)
-                                        .unwrap_or_default()
-                                        .as_millis()
-                                        as u64;
-
-                                    let (country, city) = read_location_xattrs(&path);
-
-                                    files.push(MarkdownFileMetadata {
-                                        file_path,
-                                        file_name,
-                                        created_at,
-                                        modified_at,
-                                        size,
-                                        country,
-                                        city,
-                                        date_from_filename: date_timestamp,
-                                    });
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+    markdown::read_markdown_files_metadata_cached(
+        Path::new(&directory_path),
+        max_file_size,
+        include_xattrs.unwrap_or(true),
+        &cache,
+    )
+    .map_err(|e| format!("Error reading directory: {}", e))
+}
 
-    let dir_path = Path::new(&directory_path);
-    if let Err(e) = visit_dir(dir_path, &mut files, max_size) {
-        return Err(format!("Error reading directory: {}", e));
+/// The note for `date` (YYYY-MM-DD) with its content, or None. With `include_commits`, the
+/// day's commits across the vault's repositories are included
+#[tauri::command]
+pub(crate) async fn get_note_for_date(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    date: String,
+    include_commits: Option<bool>,
+) -> Result<Option<NoteForDate>, String> {
+    let parsed = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let resolved = resolve_directory(&app, vault_id.clone(), directory_path.clone())?;
+
+    let note = tauri::async_runtime::spawn_blocking(move || {
+        markdown::get_note_for_date(Path::new(&resolved), parsed).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Read task failed: {}", e))?
+    .map_err(|e| format!("Failed to read note: {}", e))?;
+
+    let mut note = match note {
+        Some(note) => note,
+        None => return Ok(None),
+    };
+    if include_commits.unwrap_or(false) {
+        note.commits = Some(
+            crate::ipc::git::get_commits_for_entry(app, vault_id, directory_path, date, None, None)
+                .await?,
+        );
     }
-
-    files.sort_by(|a, b| b.date_from_filename.cmp(&a.date_from_filename));
-
-    Ok(files)
+    Ok(Some(note))
 }
 
 #[tauri::command]
 pub(crate) async fn read_structured_markdown_files_metadata(
-    directory_path: String,
+    app: AppHandle,
+    cache: tauri::State<'_, MetadataCache>,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
     max_file_size: Option<u64>,
+    include_xattrs: Option<bool>,
 ) -> Result<Vec<StructuredMarkdownFileMetadata>, String> {
-    let max_size = max_file_size.unwrap_or(10 * 1024 * 1024);
-    let mut files = Vec::new();
-
-    let structured_dir_path = Path::new(&directory_path).join("structured");
-
-    if !structured_dir_path.exists() {
-        return Ok(files);
-    }
-
-    if !structured_dir_path.is_dir() {
-        return Err(format!(
-            "Path is not a directory: {}",
-            structured_dir_path.display()
-        ));
-    }
-
-    let entries = match fs::read_dir(&structured_dir_path) {
-        Ok(entries) => entries,
-        Err(e) => return Err(format!("Error reading structured directory: {}", e)),
-    };
-
-    for entry in entries {
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(e) => {
-                eprintln!("Error reading directory entry: {}", e);
-                continue;
-            }
-        };
-
-        let path = entry.path();
-
-        if path.is_file() {
-            if let Some(extension) = path.extension() {
-                if extension.to_string_lossy().to_lowercase() == "md" {
-                    let file_name = path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("unknown")
-                        .to_string();
-
-                    if let Ok(metadata) = entry.metadata() {
-                        let size = metadata.len();
-
-                        if size <= max_size {
-                            let file_path = path.to_string_lossy().to_string();
-
-                            let created_at = metadata
-                                .created()
-                                .or_else(|_| metadata.modified())
-                                .unwrap_or_else(|_| std::time::SystemTime::now())
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_millis() as u64;
-
-                            let modified_at = metadata
-                                .modified()
-                                .unwrap_or_else(|_| std::time::SystemTime::now())
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_millis() as u64;
-
-                            let (country, city) = read_location_xattrs(&path);
-
-                            files.push(StructuredMarkdownFileMetadata {
-                                file_path,
-                                file_name,
-                                created_at,
-                                modified_at,
-                                size,
-                                country,
-                                city,
-                            });
-                        }
-                    }
-                }
-            }
-        }
-    }
-
-    files.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+    markdown::read_structured_markdown_files_metadata_cached(
+        Path::new(&directory_path),
+        max_file_size,
+        include_xattrs.unwrap_or(true),
+        &cache,
+    )
+    .map_err(|e| e.to_string())
+}
 
-    Ok(files)
+/// Manually arrange the structured list; files not listed lose their position
+#[tauri::command]
+pub(crate) async fn set_structured_file_order(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    paths_in_order: Vec<String>,
+) -> Result<(), String> {
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+    ensure_writable(&app, &directory_path)?;
+    let paths: Vec<PathBuf> = paths_in_order
+        .iter()
+        .map(|path| normalize_path(path))
+        .collect();
+    markdown::set_structured_file_order(Path::new(&directory_path), &paths)
+        .map_err(|e| format!("Failed to set structured file order: {}", e))
 }
 
 #[tauri::command]
 pub(crate) async fn read_structured_markdown_files(
-    directory_path: String,
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
     max_file_size: Option<u64>,
 ) -> Result<Vec<StructuredMarkdownFile>, String> {
-    let max_size = max_file_size.unwrap_or(10 * 1024 * 1024);
-    let mut files = Vec::new();
-
-    let structured_dir_path = Path::new(&directory_path).join("structured");
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+    markdown::read_structured_markdown_files(Path::new(&directory_path), max_file_size)
+        .map_err(|e| e.to_string())
+}
 
-    if !structured_dir_path.exists() {
-        return Ok(files);
-    }
+/// Save a note's content atomically, keeping its extended attributes, and return its
+/// updated metadata
+#[tauri::command]
+pub(crate) async fn write_markdown_file(
+    app: AppHandle,
+    file_path: String,
+    content: String,
+) -> Result<MarkdownFileMetadata, String> {
+    ensure_writable(&app, &file_path)?;
+    let path = normalize_path(&file_path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        markdown::write_markdown_file(&path, &content).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("File write task failed: {}", e))?
+    .map_err(|e| format!("Failed to write {}: {}", file_path, e))
+}
 
-    if !structured_dir_path.is_dir() {
-        return Err(format!(
-            "Path is not a directory: {}",
-            structured_dir_path.display()
-        ));
-    }
+/// Set one key in a note's YAML frontmatter, or remove it when `value` is null, leaving
+/// the body untouched. Returns the frontmatter as saved
+#[tauri::command]
+pub(crate) async fn set_frontmatter_field(
+    app: AppHandle,
+    file_path: String,
+    key: String,
+    value: Option<serde_json::Value>,
+) -> Result<Frontmatter, String> {
+    ensure_writable(&app, &file_path)?;
+    let path = normalize_path(&file_path);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        frontmatter::set_frontmatter_field(&path, &key, value).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Frontmatter task failed: {}", e))?
+    .map_err(|e| format!("Failed to update frontmatter of {}: {}", file_path, e))
+}
 
-    let entries = match fs::read_dir(&structured_dir_path) {
-        Ok(entries) => entries,
-        Err(e) => return Err(format!("Error reading structured directory: {}", e)),
-    };
+/// Load more of a structured file returned with `is_truncated`: up to `len` bytes (at most
+/// 4MB) from byte `offset`
+#[tauri::command]
+pub(crate) async fn read_structured_file_chunk(
+    file_path: String,
+    offset: u64,
+    len: u64,
+) -> Result<FileChunk, String> {
+    let path = normalize_path(&file_path);
+    tauri::async_runtime::spawn_blocking(move || {
+        markdown::read_file_chunk(&path, offset, len).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("File read task failed: {}", e))?
+    .map_err(|e| format!("Failed to read {}: {}", file_path, e))
+}
 
-    for entry in entries {
-        let entry = match entry {
-            Ok(entry) => entry,
-            Err(e) => {
-                eprintln!("Error reading directory entry: {}", e);
-                continue;
-            }
-        };
-
-        let path = entry.path();
-
-        if path.is_file() {
-            if let Some(extension) = path.extension() {
-                if extension.to_string_lossy().to_lowercase() == "md" {
-                    let file_name = path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("unknown")
-                        .to_string();
-
-                    if let Ok(metadata) = entry.metadata() {
-                        let size = metadata.len();
-
-                        if size <= max_size {
-                            let file_path = path.to_string_lossy().to_string();
-
-                            let content = match fs::read_to_string(&path) {
-                                Ok(content) => content,
-                                Err(e) => {
-                                    eprintln!(
-                                        "Error reading file content for {}: {}",
-                                        file_path, e
-                                    );
-                                    continue;
-                                }
-                            };
-
-                            let created_at = metadata
-                                .created()
-                                .or_else(|_| metadata.modified())
-                                .unwrap_or_else(|_| std::time::SystemTime::now())
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_millis() as u64;
-
-                            let modified_at = metadata
-                                .modified()
-                                .unwrap_or_else(|_| std::time::SystemTime::now())
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap_or_default()
-                                .as_millis() as u64;
-
-                            let (country, city) = read_location_xattrs(&path);
-
-                            let description = read_description_xattr(&path);
-
-                            let refresh_interval =
-                                read_refresh_interval(&path).map(|i| i.to_string());
-                            let last_refreshed_at = read_last_refreshed(&path);
-
-                            files.push(StructuredMarkdownFile {
-                                file_path,
-                                file_name,
-                                created_at,
-                                modified_at,
-                                size,
-                                country,
-                                city,
-                                description,
-                                content,
-                                refresh_interval,
-                                last_refreshed_at,
-                            });
-                        }
-                    }
-                }
-            }
-        }
+/// Find metadata the app can't use: malformed or misplaced xattrs, unreadable notes and
+/// stale sync base copies. With `apply_fixes`, everything fixable is removed after the
+/// attributes are snapshotted for `rollback_import`
+#[tauri::command]
+pub(crate) async fn run_metadata_doctor(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    apply_fixes: Option<bool>,
+) -> Result<MetadataDoctorReport, String> {
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+    let apply_fixes = apply_fixes.unwrap_or(false);
+    if apply_fixes {
+        ensure_writable(&app, &directory_path)?;
     }
+    let directory_path = normalize_path(&directory_path);
+    let sync_state_path = app.path().app_data_dir().ok().map(|dir| {
+        dir.join("sync")
+            .join(sync::state_file_name(&directory_path))
+    });
+
+    tauri::async_runtime::spawn_blocking(move || {
+        doctor::run_metadata_doctor(
+            &directory_path,
+            sync_state_path.as_deref(),
+            apply_fixes,
+            Some(&app.state::<UndoJournal>()),
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Metadata doctor task failed: {}", e))?
+    .map_err(|e| format!("Failed to check metadata: {}", e))
+}
 
-    files.sort_by(|a, b| b.modified_at.cmp(&a.modified_at));
-
-    Ok(files)
+/// Check daily notes against the lint rules: `rules` when given, else the vault's
+#[tauri::command]
+pub(crate) async fn lint_notes(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    rules: Option<LintRules>,
+) -> Result<LintReport, String> {
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let rules = rules.unwrap_or(config.lint_rules);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        lint::lint_notes(Path::new(&config.path), &rules).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Lint task failed: {}", e))?
+    .map_err(|e| format!("Failed to lint notes: {}", e))
 }
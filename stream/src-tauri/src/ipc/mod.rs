@@ -1,5 +1,22 @@
+pub mod archive;
+pub mod attachments;
+pub mod bookmark;
 pub mod git;
+pub mod habits;
+pub mod lock;
 pub mod markdown;
+pub mod network;
+pub mod note_ids;
+pub mod perf;
+pub mod publish;
+pub mod references;
+pub mod site;
+pub mod sources;
+pub mod spellcheck;
+pub mod stats;
+pub mod sync;
+pub mod undo;
+pub mod unfurl;
 
 pub use git::{FetchResult, GitCommit, RepoCommits};
 pub use markdown::{MarkdownFileMetadata, StructuredMarkdownFile, StructuredMarkdownFileMetadata};
@@ -0,0 +1,46 @@
+use serde_json::json;
+use stream_core::net::{self, NetworkSettings};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::SETTINGS_STORE;
+
+const NETWORK_SETTINGS_KEY: &str = "network";
+
+fn saved_network_settings(app: &AppHandle) -> NetworkSettings {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(NETWORK_SETTINGS_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Apply the saved proxy and CA bundle settings. Called once at startup
+pub(crate) fn load_network_settings(app: &AppHandle) {
+    if let Err(e) = net::configure(&saved_network_settings(app)) {
+        eprintln!("Failed to apply saved network settings: {}", e);
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_network_settings(app: AppHandle) -> Result<NetworkSettings, String> {
+    Ok(saved_network_settings(&app))
+}
+
+/// Proxy and CA bundle used by every network feature. Rejected without saving when the
+/// proxy URL is invalid or the CA bundle can't be read
+#[tauri::command]
+pub(crate) async fn set_network_settings(
+    app: AppHandle,
+    settings: NetworkSettings,
+) -> Result<(), String> {
+    net::configure(&settings).map_err(|e| format!("Invalid network settings: {}", e))?;
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(NETWORK_SETTINGS_KEY, json!(settings));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
@@ -0,0 +1,88 @@
+use std::path::Path;
+
+use serde::Serialize;
+use stream_core::note_ids;
+use stream_core::paths::normalize_path;
+use tauri::{AppHandle, Emitter};
+
+use crate::vaults::{ensure_writable, resolve_vault_config};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OpenNoteEvent {
+    pub note_id: String,
+    pub file_path: String,
+}
+
+/// A `stream://note/<id>` link to a note that keeps working after it's renamed or moved.
+/// Assigns the note an id if it doesn't have one yet
+#[tauri::command]
+pub(crate) async fn get_note_link(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    file_path: String,
+) -> Result<String, String> {
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let path = normalize_path(&file_path);
+    if let Some(id) = note_ids::read_note_id(&path) {
+        return Ok(note_ids::note_link(&id));
+    }
+    ensure_writable(&app, &file_path)?;
+
+    let id = note_ids::ensure_note_id(Path::new(&config.path), &path)
+        .map_err(|e| format!("Failed to assign note id: {}", e))?;
+    Ok(note_ids::note_link(&id))
+}
+
+/// The current path of the note a `stream://note/<id>` link (or bare id) points to
+#[tauri::command]
+pub(crate) async fn resolve_note_link(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    link: String,
+) -> Result<Option<String>, String> {
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let id = note_ids::parse_note_link(&link)
+        .or_else(|| note_ids::parse_note_link(&note_ids::note_link(&link)))
+        .ok_or_else(|| format!("Not a note link: {}", link))?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        note_ids::resolve_note_id(Path::new(&config.path), &id, !config.read_only)
+            .map(|path| path.map(|path| path.to_string_lossy().to_string()))
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Note lookup task failed: {}", e))?
+    .map_err(|e| format!("Failed to resolve note link: {}", e))
+}
+
+/// Resolve a deep link opened from outside the app against the active vault and emit
+/// `open-note` for the frontend to navigate to
+pub(crate) fn open_deep_link(app: &AppHandle, url: &str) {
+    let id = match note_ids::parse_note_link(url) {
+        Some(id) => id,
+        None => return,
+    };
+    let config = match resolve_vault_config(app, None, None) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("Failed to open {}: {}", url, e);
+            return;
+        }
+    };
+
+    match note_ids::resolve_note_id(Path::new(&config.path), &id, !config.read_only) {
+        Ok(Some(path)) => {
+            let event = OpenNoteEvent {
+                note_id: id,
+                file_path: path.to_string_lossy().to_string(),
+            };
+            if let Err(e) = app.emit("open-note", &event) {
+                eprintln!("Failed to emit open-note event: {}", e);
+            }
+        }
+        Ok(None) => eprintln!("No note with id {}", id),
+        Err(e) => eprintln!("Failed to open {}: {}", url, e),
+    }
+}
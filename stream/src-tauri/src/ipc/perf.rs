@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use serde::Deserialize;
+use stream_core::perf::{self, PerfCategory, PerformanceReport};
+
+/// A command round trip as timed by the webview
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct CommandTiming {
+    command: String,
+    duration_ms: f64,
+}
+
+/// Command latencies, time spent scanning, reading xattrs, walking git and searching, and
+/// cache hit rates, since startup or the last reset
+#[tauri::command]
+pub(crate) async fn get_performance_report() -> Result<PerformanceReport, String> {
+    Ok(perf::performance_report())
+}
+
+#[tauri::command]
+pub(crate) async fn record_command_timings(timings: Vec<CommandTiming>) -> Result<(), String> {
+    for timing in timings {
+        if !timing.duration_ms.is_finite() || timing.duration_ms < 0.0 {
+            continue;
+        }
+        perf::record(
+            PerfCategory::Command,
+            &timing.command,
+            Duration::from_secs_f64(timing.duration_ms / 1000.0),
+        );
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn reset_performance_report() -> Result<(), String> {
+    perf::reset();
+    Ok(())
+}
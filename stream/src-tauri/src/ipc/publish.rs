@@ -0,0 +1,46 @@
+use stream_core::paths::normalize_path;
+use stream_core::publish::{self, PublishTarget};
+use tauri::AppHandle;
+
+pub use stream_core::publish::PublishedNote;
+
+use crate::secrets::read_secret;
+use crate::vaults::ensure_writable;
+
+/// The target's token from the keychain, where `store_secret` saves it
+fn publish_token(target: PublishTarget) -> Result<String, String> {
+    let name = match target {
+        PublishTarget::Gist => "github",
+        PublishTarget::GitlabSnippet => "gitlab",
+    };
+    read_secret(name)?.ok_or_else(|| format!("No {} token configured", name))
+}
+
+#[tauri::command]
+pub(crate) async fn publish_note(
+    app: AppHandle,
+    file_path: String,
+    target: String,
+    visibility: Option<String>,
+    base_url: Option<String>,
+) -> Result<PublishedNote, String> {
+    // Where the note was published is recorded on the note itself
+    ensure_writable(&app, &file_path)?;
+    let target = PublishTarget::from_string(&target)
+        .ok_or_else(|| format!("Unknown publish target: {}", target))?;
+    let token = publish_token(target)?;
+    let visibility = visibility.unwrap_or_else(|| "private".to_string());
+
+    tauri::async_runtime::spawn_blocking(move || {
+        publish::publish_note(
+            &normalize_path(&file_path),
+            target,
+            &visibility,
+            &token,
+            base_url.as_deref(),
+        )
+        .map_err(|e| format!("Failed to publish note: {}", e))
+    })
+    .await
+    .map_err(|e| format!("Publish task failed: {}", e))?
+}
@@ -0,0 +1,128 @@
+use std::path::Path;
+
+use serde_json::json;
+use stream_core::git;
+use stream_core::references::{self, CommitNoteLinks, NoteReference, Reference, TicketActivity};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::vaults::resolve_vault_config;
+use crate::SETTINGS_STORE;
+
+const TICKET_PATTERNS_KEY: &str = "ticket_patterns";
+
+/// Notes mentioning a commit hash (short or full) or a ticket id like `PROJ-123` or `#42`
+#[tauri::command]
+pub(crate) async fn find_references(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    reference: Reference,
+) -> Result<Vec<NoteReference>, String> {
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        references::find_references(Path::new(&config.path), &reference).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Reference search task failed: {}", e))?
+    .map_err(|e| format!("Failed to find references: {}", e))
+}
+
+/// Commits in the range whose hash or ticket id is mentioned in a note, with those notes.
+/// Without `repo_paths`, the vault's repositories are used
+#[tauri::command]
+pub(crate) async fn get_commit_note_links(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    repo_paths: Option<Vec<String>>,
+    start_timestamp: u64,
+    end_timestamp: u64,
+) -> Result<Vec<CommitNoteLinks>, String> {
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let repo_paths = repo_paths.unwrap_or(config.repo_paths);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let commits: Vec<_> =
+            git::get_git_commits_for_repos(&repo_paths, start_timestamp, end_timestamp)
+                .into_iter()
+                .flat_map(|repo| repo.commits)
+                .collect();
+        references::link_commits_to_notes(Path::new(&config.path), &commits)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Reference search task failed: {}", e))?
+    .map_err(|e| format!("Failed to link commits to notes: {}", e))
+}
+
+/// Apply the saved ticket patterns. Called once at startup
+pub(crate) fn load_ticket_patterns(app: &AppHandle) {
+    let patterns: Vec<String> = app
+        .store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(TICKET_PATTERNS_KEY))
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    if let Err(e) = references::set_ticket_patterns(&patterns) {
+        eprintln!("Failed to apply saved ticket patterns: {}", e);
+    }
+}
+
+/// Regexes ticket ids are extracted with, e.g. `[A-Z]+-\d+` or `#\d+`. An empty list
+/// restores the built-in patterns
+#[tauri::command]
+pub(crate) async fn set_ticket_patterns(
+    app: AppHandle,
+    patterns: Vec<String>,
+) -> Result<(), String> {
+    let patterns: Vec<String> = patterns
+        .iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect();
+    references::set_ticket_patterns(&patterns).map_err(|e| e.to_string())?;
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    store.set(TICKET_PATTERNS_KEY, json!(patterns));
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn get_ticket_patterns() -> Result<Vec<String>, String> {
+    Ok(references::ticket_patterns())
+}
+
+/// Every commit in the range and every note line mentioning `ticket`.
+/// Without `repo_paths`, the vault's repositories are used
+#[tauri::command]
+pub(crate) async fn get_activity_by_ticket(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    ticket: String,
+    repo_paths: Option<Vec<String>>,
+    start_timestamp: u64,
+    end_timestamp: u64,
+) -> Result<TicketActivity, String> {
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let repo_paths = repo_paths.unwrap_or(config.repo_paths);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let commits: Vec<_> =
+            git::get_git_commits_for_repos(&repo_paths, start_timestamp, end_timestamp)
+                .into_iter()
+                .flat_map(|repo| repo.commits)
+                .collect();
+        references::ticket_activity(Path::new(&config.path), &ticket, commits)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Reference search task failed: {}", e))?
+    .map_err(|e| format!("Failed to find ticket activity: {}", e))
+}
@@ -0,0 +1,27 @@
+use stream_core::paths::{normalize_path, normalize_path_string};
+use stream_core::site::{self, SiteFlavor};
+
+pub use stream_core::site::SiteExportSummary;
+
+#[tauri::command]
+pub(crate) async fn export_static_site(
+    file_paths: Vec<String>,
+    output_path: String,
+    flavor: String,
+    section: Option<String>,
+) -> Result<SiteExportSummary, String> {
+    let flavor = SiteFlavor::from_string(&flavor)
+        .ok_or_else(|| format!("Unsupported site generator: {}", flavor))?;
+    let file_paths: Vec<String> = file_paths
+        .iter()
+        .map(|path| normalize_path_string(path))
+        .collect();
+
+    site::export_static_site(
+        &file_paths,
+        &normalize_path(&output_path),
+        flavor,
+        section.as_deref().unwrap_or("posts"),
+    )
+    .map_err(|e| format!("Failed to export site: {}", e))
+}
@@ -0,0 +1,127 @@
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use stream_core::paths::normalize_path;
+use stream_core::sources::{self, CommandSourceStore, SourceRegistry};
+use stream_core::webhooks::WebhookEvent;
+use tauri::{AppHandle, Emitter, Manager};
+
+pub use stream_core::sources::SourceRefresh;
+
+use crate::vaults::{ensure_writable, resolve_vault_config};
+use crate::webhooks;
+
+fn notify_refreshed(app: &AppHandle, refresh: &SourceRefresh) {
+    webhooks::notify(
+        app,
+        WebhookEvent::RefreshCompleted,
+        json!({ "file_path": refresh.file_path, "source": refresh.source }),
+    );
+}
+
+/// Refresh the active vault's source-backed structured files that are due. Called from the
+/// background refresh thread; emits `structured-files-refreshed` when anything ran. Commands
+/// not yet approved on this machine are left alone
+pub(crate) fn refresh_due_sources(app: &AppHandle) {
+    let config = match resolve_vault_config(app, None, None) {
+        Ok(config) if config.auto_refresh && !config.read_only => config,
+        _ => return,
+    };
+
+    let refreshed = sources::refresh_due_sources(
+        Path::new(&config.path),
+        &SourceRegistry::default(),
+        &app.state::<CommandSourceStore>(),
+    );
+    for refresh in &refreshed {
+        if let Some(error) = &refresh.error {
+            eprintln!("Failed to refresh {}: {}", refresh.file_path, error);
+        }
+    }
+    if !refreshed.is_empty() {
+        if let Err(e) = app.emit("structured-files-refreshed", &refreshed) {
+            eprintln!("Failed to emit structured-files-refreshed event: {}", e);
+        }
+    }
+    for refresh in refreshed.iter().filter(|refresh| refresh.error.is_none()) {
+        notify_refreshed(app, refresh);
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn list_content_sources() -> Result<Vec<String>, String> {
+    Ok(SourceRegistry::default().kinds())
+}
+
+/// Attach a content source (e.g. `{"kind": "rss", "url": "..."}`) to a structured file,
+/// or detach it with `null`. A command source won't run until `approve_file_source`
+#[tauri::command]
+pub(crate) async fn set_file_source(
+    app: AppHandle,
+    commands: tauri::State<'_, CommandSourceStore>,
+    file_path: String,
+    source: Option<Value>,
+) -> Result<(), String> {
+    ensure_writable(&app, &file_path)?;
+    sources::write_source_config(
+        &normalize_path(&file_path),
+        source.as_ref(),
+        &SourceRegistry::default(),
+        &commands,
+    )
+    .map_err(|e| format!("Failed to set content source: {}", e))
+}
+
+#[derive(Debug, Serialize)]
+pub struct FileSource {
+    pub config: Value,
+    /// False for a command that hasn't been approved on this machine yet
+    pub approved: bool,
+}
+
+#[tauri::command]
+pub(crate) async fn get_file_source(
+    commands: tauri::State<'_, CommandSourceStore>,
+    file_path: String,
+) -> Result<Option<FileSource>, String> {
+    let path = normalize_path(&file_path);
+    let approved = commands.get(&path).is_none() || commands.is_approved(&path);
+    Ok(sources::source_config(&path, &commands).map(|config| FileSource { config, approved }))
+}
+
+/// Let a file's command source run on this machine, by hand and on its refresh interval
+#[tauri::command]
+pub(crate) async fn approve_file_source(
+    commands: tauri::State<'_, CommandSourceStore>,
+    file_path: String,
+) -> Result<(), String> {
+    commands
+        .approve(&normalize_path(&file_path))
+        .map_err(|e| format!("Failed to approve command source: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn refresh_file_source(
+    app: AppHandle,
+    file_path: String,
+) -> Result<SourceRefresh, String> {
+    ensure_writable(&app, &file_path)?;
+    let app_handle = app.clone();
+    let refresh = tauri::async_runtime::spawn_blocking(move || {
+        sources::refresh_from_source(
+            &normalize_path(&file_path),
+            &SourceRegistry::default(),
+            &app_handle.state::<CommandSourceStore>(),
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Refresh task failed: {}", e))?
+    .map_err(|e| format!("Failed to refresh from source: {}", e))?;
+
+    if refresh.error.is_none() {
+        notify_refreshed(&app, &refresh);
+    }
+    Ok(refresh)
+}
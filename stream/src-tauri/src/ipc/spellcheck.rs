@@ -0,0 +1,43 @@
+use stream_core::paths::normalize_path;
+use stream_core::spellcheck::{Misspelling, Spellchecker};
+use tauri::{AppHandle, Manager, State};
+
+/// Misspelled words in a note, or in `content` when given (e.g. unsaved editor text), using
+/// the hunspell dictionary for `language` such as `en_US`
+#[tauri::command]
+pub(crate) async fn spellcheck(
+    app: AppHandle,
+    file_path: Option<String>,
+    content: Option<String>,
+    language: String,
+) -> Result<Vec<Misspelling>, String> {
+    if file_path.is_none() && content.is_none() {
+        return Err("Pass a file path or content to check".to_string());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let spellchecker = app.state::<Spellchecker>();
+        match (content, file_path) {
+            (Some(content), _) => spellchecker.check_content(&content, &language),
+            (None, Some(file_path)) => {
+                spellchecker.check_file(&normalize_path(&file_path), &language)
+            }
+            (None, None) => unreachable!(),
+        }
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Spellcheck task failed: {}", e))?
+    .map_err(|e| format!("Failed to spellcheck: {}", e))
+}
+
+/// Add a word to the personal dictionary so it's never flagged again
+#[tauri::command]
+pub(crate) async fn add_word(
+    spellchecker: State<'_, Spellchecker>,
+    word: String,
+) -> Result<(), String> {
+    spellchecker
+        .add_word(&word)
+        .map_err(|e| format!("Failed to add word: {}", e))
+}
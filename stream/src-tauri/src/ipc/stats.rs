@@ -0,0 +1,117 @@
+use std::path::Path;
+
+use chrono::NaiveDate;
+use stream_core::git;
+use stream_core::paths::normalize_path;
+use stream_core::stats::{self, DateRange, PeriodSummary, RangeComparison};
+use tauri::AppHandle;
+
+use crate::ipc::git::get_git_commits_for_repos;
+use crate::vaults::{resolve_directory, resolve_vault_config};
+
+pub use stream_core::stats::{NoteStats, RangeStats, VaultStats};
+
+fn parse_date(date: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))
+}
+
+#[tauri::command]
+pub(crate) async fn get_note_stats(file_path: String) -> Result<NoteStats, String> {
+    stats::get_note_stats(&normalize_path(&file_path))
+        .map_err(|e| format!("Failed to compute note stats: {}", e))
+}
+
+/// Totals for the whole vault, for the settings and about screens
+#[tauri::command]
+pub(crate) async fn get_vault_stats(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+) -> Result<VaultStats, String> {
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        stats::get_vault_stats(&normalize_path(&directory_path)).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Vault stats task failed: {}", e))?
+    .map_err(|e| format!("Failed to compute vault stats: {}", e))
+}
+
+/// `get_note_stats` for every daily note in a date range, with totals for the range
+#[tauri::command]
+pub(crate) async fn get_note_stats_for_range(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    start_date: String,
+    end_date: String,
+) -> Result<RangeStats, String> {
+    let (start, end) = (parse_date(&start_date)?, parse_date(&end_date)?);
+    let directory_path = resolve_directory(&app, vault_id, directory_path)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        stats::get_range_stats(&normalize_path(&directory_path), start, end)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Note stats task failed: {}", e))?
+    .map_err(|e| format!("Failed to compute note stats: {}", e))
+}
+
+async fn summarize_range(
+    app: &AppHandle,
+    directory_path: &str,
+    repo_paths: &[String],
+    range: &DateRange,
+) -> Result<PeriodSummary, String> {
+    let (start, end) = (parse_date(&range.start)?, parse_date(&range.end)?);
+    let (start_seconds, _) =
+        git::local_day_bounds(start).ok_or_else(|| format!("Invalid local date: {}", start))?;
+    let (_, end_seconds) =
+        git::local_day_bounds(end).ok_or_else(|| format!("Invalid local date: {}", end))?;
+    let repos = get_git_commits_for_repos(
+        app.clone(),
+        repo_paths.to_vec(),
+        start_seconds as u64 * 1000,
+        end_seconds as u64 * 1000,
+        None,
+        None,
+    )
+    .await?;
+
+    let directory_path = directory_path.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        stats::summarize_period(Path::new(&directory_path), start, end, &repos)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Note stats task failed: {}", e))?
+    .map_err(|e| {
+        format!(
+            "Failed to summarize {} to {}: {}",
+            range.start, range.end, e
+        )
+    })
+}
+
+/// Commits, words written, active repositories and top tags for two date ranges side by
+/// side, e.g. this week against last week. Without `repo_paths`, the vault's repositories
+/// are used
+#[tauri::command]
+pub(crate) async fn compare_ranges(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    range_a: DateRange,
+    range_b: DateRange,
+    repo_paths: Option<Vec<String>>,
+) -> Result<RangeComparison, String> {
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let repo_paths = repo_paths.unwrap_or(config.repo_paths);
+
+    let a = summarize_range(&app, &config.path, &repo_paths, &range_a).await?;
+    let b = summarize_range(&app, &config.path, &repo_paths, &range_b).await?;
+    Ok(stats::compare_periods(a, b))
+}
@@ -0,0 +1,271 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use stream_core::lock::{self, LockedNotesRotation};
+use stream_core::paths::normalize_path_string;
+use stream_core::sync::{self, EncryptedBackend, SyncBackend};
+use stream_core::undo::UndoJournal;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::auth::require_auth;
+use crate::vaults::{ensure_writable, resolve_vault_config};
+
+pub use stream_core::sync::{SyncBackendConfig, SyncProgress, SyncReport};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotationReport {
+    pub notes: LockedNotesRotation,
+    /// Replaces the old recovery code when the remote was rotated; shown to the user once
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncStatus {
+    pub running: bool,
+    pub progress: Option<SyncProgress>,
+    pub last_report: Option<SyncReport>,
+    pub last_error: Option<String>,
+    pub last_synced_at: Option<u64>,
+}
+
+/// Managed state tracking the current or most recent remote sync
+#[derive(Default)]
+pub struct SyncState {
+    status: Mutex<SyncStatus>,
+}
+
+impl SyncState {
+    fn update(&self, f: impl FnOnce(&mut SyncStatus)) {
+        if let Ok(mut status) = self.status.lock() {
+            f(&mut status);
+        }
+    }
+
+    /// Mark a sync as running unless one already is, in one step so two callers can't both
+    /// start. Returns false when one was running
+    fn try_start(&self) -> bool {
+        match self.status.lock() {
+            Ok(mut status) if !status.running => {
+                status.running = true;
+                status.progress = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn snapshot(&self) -> SyncStatus {
+        self.status
+            .lock()
+            .map(|status| status.clone())
+            .unwrap_or_default()
+    }
+}
+
+/// Build the configured backend, unlocking it when the remote is end-to-end encrypted
+fn open_backend(
+    config: &SyncBackendConfig,
+    passphrase: Option<&str>,
+) -> Result<Box<dyn SyncBackend>, String> {
+    let backend = config.build();
+    let encrypted = sync::is_encrypted(backend.as_ref()).map_err(|e| e.to_string())?;
+
+    match (encrypted, passphrase) {
+        (true, Some(passphrase)) => EncryptedBackend::unlock(backend, passphrase)
+            .map(|b| Box::new(b) as Box<dyn SyncBackend>)
+            .map_err(|e| e.to_string()),
+        (true, None) => Err("Remote is encrypted; a passphrase is required".to_string()),
+        (false, Some(_)) => {
+            Err("Remote is not set up for encryption; enable encryption first".to_string())
+        }
+        (false, None) => Ok(backend),
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn sync_with_remote(
+    app: AppHandle,
+    directory_path: String,
+    config: SyncBackendConfig,
+    passphrase: Option<String>,
+) -> Result<SyncReport, String> {
+    ensure_writable(&app, &directory_path)?;
+    let directory_path = normalize_path_string(&directory_path);
+    let vault = resolve_vault_config(&app, None, Some(directory_path.clone()))?;
+    if vault.sync_encryption && passphrase.is_none() {
+        return Err("This vault requires encrypted sync; a passphrase is required".to_string());
+    }
+
+    let state_path = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?
+        .join("sync")
+        .join(sync::state_file_name(Path::new(&directory_path)));
+
+    let state = app.state::<SyncState>();
+    if !state.try_start() {
+        return Err("A sync is already in progress".to_string());
+    }
+    state.update(|status| status.last_error = None);
+
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let backend = open_backend(&config, passphrase.as_deref())?;
+        sync::sync_directory(
+            Path::new(&directory_path),
+            backend.as_ref(),
+            &state_path,
+            Some(&handle.state::<UndoJournal>()),
+            &|progress| {
+                handle.state::<SyncState>().update(|status| {
+                    status.progress = Some(progress.clone());
+                });
+                if let Err(e) = handle.emit("sync-progress", &progress) {
+                    eprintln!("Failed to emit sync-progress event: {}", e);
+                }
+            },
+        )
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Sync task failed: {}", e))
+    .and_then(|result| result);
+
+    let state = app.state::<SyncState>();
+    state.update(|status| {
+        status.running = false;
+        match &result {
+            Ok(report) => {
+                status.last_synced_at = Some(report.finished_at);
+                status.last_report = Some(report.clone());
+            }
+            Err(e) => status.last_error = Some(e.clone()),
+        }
+    });
+
+    // Only conflicts the merge couldn't resolve reach the user
+    if let Ok(report) = &result {
+        for conflict in &report.conflicts {
+            if let Err(e) = app.emit("sync-conflict", conflict) {
+                eprintln!("Failed to emit sync-conflict event: {}", e);
+            }
+        }
+    }
+
+    result.map_err(|e| format!("Failed to sync notes: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn get_sync_status(app: AppHandle) -> Result<SyncStatus, String> {
+    Ok(app.state::<SyncState>().snapshot())
+}
+
+#[tauri::command]
+pub(crate) async fn setup_sync_encryption(
+    config: SyncBackendConfig,
+    passphrase: String,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        sync::setup_encryption(config.build().as_ref(), &passphrase).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Encryption setup task failed: {}", e))?
+    .map_err(|e| format!("Failed to set up encryption: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn verify_sync_passphrase(
+    config: SyncBackendConfig,
+    passphrase: String,
+) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        sync::verify_passphrase(config.build().as_ref(), &passphrase).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Passphrase check task failed: {}", e))?
+    .map_err(|e| format!("Failed to verify passphrase: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn reset_sync_passphrase(
+    config: SyncBackendConfig,
+    recovery_code: String,
+    new_passphrase: String,
+) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        sync::reset_passphrase(config.build().as_ref(), &recovery_code, &new_passphrase)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Passphrase reset task failed: {}", e))?
+    .map_err(|e| format!("Failed to reset passphrase: {}", e))
+}
+
+/// Move every locked note in the vault and, when `config` is given, the encrypted remote
+/// from `old_passphrase` to `new_passphrase`. Emits `key-rotation-progress`. Both steps can
+/// be rerun after an interruption to finish the rotation. The locked notes aren't touched
+/// when an undo copy of them can't be saved, unless `without_undo` is set
+#[tauri::command]
+pub(crate) async fn rotate_encryption_key(
+    app: AppHandle,
+    directory_path: String,
+    old_passphrase: String,
+    new_passphrase: String,
+    config: Option<SyncBackendConfig>,
+    without_undo: Option<bool>,
+) -> Result<KeyRotationReport, String> {
+    require_auth(&app)?;
+    ensure_writable(&app, &directory_path)?;
+    let directory_path = normalize_path_string(&directory_path);
+    let state = app.state::<SyncState>();
+    // Keeps syncs from running against a half-rotated remote
+    if !state.try_start() {
+        return Err("A sync is in progress; try again when it finishes".to_string());
+    }
+
+    let handle = app.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let progress = |progress: SyncProgress| {
+            if let Err(e) = handle.emit("key-rotation-progress", &progress) {
+                eprintln!("Failed to emit key-rotation-progress event: {}", e);
+            }
+        };
+
+        let recovery_code = match &config {
+            Some(config) => Some(
+                sync::rotate_encryption_key(
+                    config.build().as_ref(),
+                    &old_passphrase,
+                    &new_passphrase,
+                    &progress,
+                )
+                .map_err(|e| e.to_string())?,
+            ),
+            None => None,
+        };
+        let journal = handle.state::<UndoJournal>();
+        let notes = lock::rotate_locked_notes(
+            Path::new(&directory_path),
+            &old_passphrase,
+            &new_passphrase,
+            (!without_undo.unwrap_or(false)).then_some(&*journal),
+            &progress,
+        )
+        .map_err(|e| e.to_string())?;
+
+        Ok::<_, String>(KeyRotationReport {
+            notes,
+            recovery_code,
+        })
+    })
+    .await
+    .map_err(|e| format!("Key rotation task failed: {}", e))
+    .and_then(|result| result);
+
+    app.state::<SyncState>()
+        .update(|status| status.running = false);
+
+    result.map_err(|e| format!("Failed to rotate encryption key: {}", e))
+}
@@ -0,0 +1,49 @@
+use stream_core::undo::{UndoEntry, UndoJournal, UndoSummary};
+use tauri::{AppHandle, State};
+
+use crate::vaults::ensure_writable;
+
+/// Roll back the most recent delete, merge, metadata overwrite or import. Returns None
+/// when there is nothing left to undo
+#[tauri::command]
+pub(crate) async fn undo_last_operation(
+    app: AppHandle,
+    undo: State<'_, UndoJournal>,
+) -> Result<Option<UndoSummary>, String> {
+    if let Some(entry) = undo.entries().last() {
+        for file in &entry.files {
+            ensure_writable(&app, &file.file_path)?;
+        }
+    }
+    undo.undo_last()
+        .map_err(|e| format!("Failed to undo: {}", e))
+}
+
+/// Roll back an import or bulk migration to the snapshot id its result reported, even if
+/// other operations were recorded since
+#[tauri::command]
+pub(crate) async fn rollback_import(
+    app: AppHandle,
+    undo: State<'_, UndoJournal>,
+    snapshot_id: String,
+) -> Result<UndoSummary, String> {
+    let missing = || format!("Snapshot {} is no longer in the undo journal", snapshot_id);
+    let entries = undo.entries();
+    let entry = entries
+        .iter()
+        .find(|entry| entry.id == snapshot_id)
+        .ok_or_else(missing)?;
+    for file in &entry.files {
+        ensure_writable(&app, &file.file_path)?;
+    }
+    undo.undo(&snapshot_id)
+        .map_err(|e| format!("Failed to roll back: {}", e))?
+        .ok_or_else(missing)
+}
+
+#[tauri::command]
+pub(crate) async fn list_undo_operations(
+    undo: State<'_, UndoJournal>,
+) -> Result<Vec<UndoEntry>, String> {
+    Ok(undo.entries())
+}
@@ -0,0 +1,87 @@
+use std::fs;
+use std::path::PathBuf;
+
+use stream_core::file_lock::lock_note_file;
+use stream_core::paths::normalize_path;
+use stream_core::unfurl::{self, UnfurlCache};
+use tauri::{AppHandle, Manager};
+
+use crate::vaults::ensure_writable;
+
+pub use stream_core::unfurl::UrlPreview;
+
+fn cache_path(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_cache_dir()
+        .map(|dir| dir.join("unfurl-cache.json"))
+        .map_err(|e| format!("Failed to resolve cache directory: {}", e))
+}
+
+/// Load the cache, run `f` on a blocking thread and save whatever it added
+async fn with_cache<T: Send + 'static>(
+    app: &AppHandle,
+    f: impl FnOnce(&mut UnfurlCache) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    let path = cache_path(app)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut cache = UnfurlCache::load(&path);
+        let result = f(&mut cache);
+        if let Err(e) = cache.save(&path) {
+            eprintln!("Failed to save link preview cache: {}", e);
+        }
+        result
+    })
+    .await
+    .map_err(|e| format!("Unfurl task failed: {}", e))?
+}
+
+#[tauri::command]
+pub(crate) async fn unfurl_url(app: AppHandle, url: String) -> Result<UrlPreview, String> {
+    with_cache(&app, move |cache| {
+        unfurl::unfurl_url(&url, cache).map_err(|e| format!("Failed to unfurl {}: {}", url, e))
+    })
+    .await
+}
+
+/// Previews for several URLs; failed ones come back as `null`
+#[tauri::command]
+pub(crate) async fn unfurl_urls(
+    app: AppHandle,
+    urls: Vec<String>,
+) -> Result<Vec<Option<UrlPreview>>, String> {
+    with_cache(&app, move |cache| {
+        Ok(unfurl::unfurl_urls(&urls, cache)
+            .into_iter()
+            .map(Result::ok)
+            .collect())
+    })
+    .await
+}
+
+/// Optional on-save pass: turn bare URLs in a note into titled markdown links.
+/// Returns how many links were rewritten
+#[tauri::command]
+pub(crate) async fn unfurl_links_in_file(
+    app: AppHandle,
+    file_path: String,
+) -> Result<usize, String> {
+    ensure_writable(&app, &file_path)?;
+    with_cache(&app, move |cache| {
+        let path = normalize_path(&file_path);
+        let read = || fs::read_to_string(&path).map_err(|e| format!("Failed to read note: {}", e));
+
+        // Fetch previews before taking the lock so slow sites don't hold up other writers,
+        // then redo the rewrite from the cache against the note as it is now
+        let (_, replaced) = unfurl::link_bare_urls(&read()?, cache);
+        if replaced == 0 {
+            return Ok(0);
+        }
+        let _lock = lock_note_file(&path).map_err(|e| e.to_string())?;
+        let (updated, replaced) = unfurl::link_bare_urls(&read()?, cache);
+        if replaced > 0 {
+            fs::write(&path, updated).map_err(|e| format!("Failed to write note: {}", e))?;
+        }
+        Ok(replaced)
+    })
+    .await
+}
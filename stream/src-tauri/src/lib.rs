@@ -1,7 +1,25 @@
+mod audio;
+mod auth;
+mod autofetch;
+mod background;
+mod clipboard;
+mod digest;
+mod focus;
+mod http_api;
 mod ipc;
+mod mcp;
+mod screenshot;
 mod search;
+mod secrets;
+mod timeline;
+mod today;
+mod vaults;
+mod watcher;
+mod webhooks;
+mod worklog;
 
 use tauri::{Emitter, Manager, WindowEvent};
+use tauri_plugin_deep_link::DeepLinkExt;
 
 #[cfg(target_os = "macos")]
 use objc::runtime::Object;
@@ -12,14 +30,58 @@ pub use ipc::{
     FetchResult, GitCommit, MarkdownFileMetadata, RepoCommits, StructuredMarkdownFile,
     StructuredMarkdownFileMetadata,
 };
+pub use mcp::{run_stdio_server as run_mcp_server, McpOptions};
 
-use crate::ipc::git::{fetch_repos, get_git_commits_for_repos};
+/// Store file holding the app's settings, shared with the frontend
+pub(crate) const SETTINGS_STORE: &str = "settings.json";
+
+use crate::ipc::archive::{export_archive, export_day_bundle, import_archive, import_text_files};
+use crate::ipc::attachments::find_orphaned_attachments;
+use crate::ipc::bookmark::archive_url;
+use crate::ipc::git::{
+    benchmark_git_backends, export_commits, fetch_repos, get_author_filter, get_author_stats,
+    get_commit_type_stats, get_commits_for_branch, get_commits_for_entry, get_daily_highlights,
+    get_diary_hook_status, get_first_parent, get_git_backend, get_git_commits_for_repos,
+    get_language_stats, get_repo_health, get_repo_settings, insert_commits_into_note,
+    install_diary_hook, run_repo_maintenance, set_author_filter, set_first_parent, set_git_backend,
+    set_repo_settings, sync_notes, uninstall_diary_hook,
+};
+use crate::ipc::habits::get_habit_stats;
+use crate::ipc::lock::{lock_note, read_locked_note, unlock_note};
 use crate::ipc::markdown::{
-    get_files_needing_refresh, mark_file_as_refreshed, read_markdown_files_content,
-    read_markdown_files_metadata, read_structured_markdown_files,
-    read_structured_markdown_files_metadata, set_file_description, set_file_location_metadata,
-    set_file_refresh_interval, update_last_refreshed,
+    create_daily_note, create_daily_note_from_template, extract_tables, get_file_mood,
+    get_files_needing_refresh, get_mood_trend, get_note_for_date, get_note_structure,
+    get_on_this_day, get_random_notes, get_recent_notes, get_refresh_schedule, get_scan_threads,
+    lint_notes, mark_file_as_refreshed, mark_note_viewed, materialize_file,
+    read_markdown_files_content, read_markdown_files_metadata, read_structured_file_chunk,
+    read_structured_markdown_files, read_structured_markdown_files_metadata, record_note_view,
+    rollover_tasks, run_metadata_doctor, set_file_description, set_file_location_metadata,
+    set_file_mood, set_file_refresh_interval, set_file_timezone, set_frontmatter_field,
+    set_scan_threads, set_structured_file_order, update_last_refreshed, write_markdown_file,
+};
+use crate::ipc::network::{get_network_settings, set_network_settings};
+use crate::ipc::note_ids::{get_note_link, resolve_note_link};
+use crate::ipc::perf::{get_performance_report, record_command_timings, reset_performance_report};
+use crate::ipc::publish::publish_note;
+use crate::ipc::references::{
+    find_references, get_activity_by_ticket, get_commit_note_links, get_ticket_patterns,
+    set_ticket_patterns,
+};
+use crate::ipc::site::export_static_site;
+use crate::ipc::sources::{
+    approve_file_source, get_file_source, list_content_sources, refresh_file_source,
+    set_file_source,
 };
+use crate::ipc::spellcheck::{add_word, spellcheck};
+use crate::ipc::stats::{
+    compare_ranges, get_note_stats, get_note_stats_for_range, get_vault_stats,
+};
+use crate::ipc::sync::{
+    get_sync_status, reset_sync_passphrase, rotate_encryption_key, setup_sync_encryption,
+    sync_with_remote, verify_sync_passphrase, SyncState,
+};
+use crate::ipc::undo::{list_undo_operations, rollback_import, undo_last_operation};
+use crate::ipc::unfurl::{unfurl_links_in_file, unfurl_url, unfurl_urls};
 
 #[cfg(target_os = "macos")]
 fn setup_macos_window(window: &tauri::Window) -> Result<(), Box<dyn std::error::Error>> {
@@ -57,21 +119,184 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .manage(http_api::HttpApiState::default())
+        .manage(clipboard::ClipboardWatcherState::default())
+        .manage(watcher::DirectoryWatcherState::default())
+        .manage(audio::RecordingState::default())
+        .manage(SyncState::default())
+        .manage(timeline::TimelineCache::default())
+        .manage(today::TodaySummaryState::default())
+        .manage(stream_core::markdown::MetadataCache::default())
+        .manage(ipc::markdown::NewDayState::default())
         .invoke_handler(tauri::generate_handler![
             read_markdown_files_metadata,
+            set_scan_threads,
+            get_scan_threads,
+            get_note_for_date,
             read_structured_markdown_files_metadata,
+            set_structured_file_order,
             read_structured_markdown_files,
+            read_structured_file_chunk,
+            write_markdown_file,
+            set_frontmatter_field,
             read_markdown_files_content,
             get_git_commits_for_repos,
+            get_commit_type_stats,
+            get_commits_for_branch,
+            get_author_stats,
+            install_diary_hook,
+            uninstall_diary_hook,
+            get_diary_hook_status,
+            get_language_stats,
+            set_repo_settings,
+            get_repo_settings,
+            set_author_filter,
+            get_author_filter,
+            get_commits_for_entry,
+            get_daily_highlights,
+            insert_commits_into_note,
+            export_commits,
+            find_references,
+            get_commit_note_links,
+            get_activity_by_ticket,
+            set_ticket_patterns,
+            get_ticket_patterns,
+            get_network_settings,
+            set_network_settings,
+            get_note_link,
+            resolve_note_link,
             fetch_repos,
+            run_repo_maintenance,
+            get_repo_health,
+            set_git_backend,
+            get_git_backend,
+            set_first_parent,
+            get_first_parent,
+            benchmark_git_backends,
+            sync_notes,
             set_file_location_metadata,
             set_file_description,
+            set_file_mood,
+            get_file_mood,
+            get_note_structure,
+            run_metadata_doctor,
+            find_orphaned_attachments,
+            lint_notes,
+            spellcheck,
+            add_word,
+            extract_tables,
+            get_mood_trend,
+            set_file_timezone,
             set_file_refresh_interval,
             update_last_refreshed,
             mark_file_as_refreshed,
             get_files_needing_refresh,
+            get_refresh_schedule,
+            get_on_this_day,
+            get_random_notes,
+            mark_note_viewed,
+            record_note_view,
+            get_recent_notes,
+            create_daily_note,
+            create_daily_note_from_template,
+            rollover_tasks,
+            materialize_file,
+            lock_note,
+            unlock_note,
+            read_locked_note,
+            export_archive,
+            import_archive,
+            import_text_files,
+            export_day_bundle,
+            undo_last_operation,
+            list_undo_operations,
+            rollback_import,
+            archive_url,
+            export_static_site,
+            list_content_sources,
+            set_file_source,
+            approve_file_source,
+            get_file_source,
+            refresh_file_source,
+            unfurl_url,
+            unfurl_urls,
+            unfurl_links_in_file,
+            publish_note,
+            get_habit_stats,
+            get_note_stats,
+            get_note_stats_for_range,
+            get_vault_stats,
+            get_performance_report,
+            record_command_timings,
+            reset_performance_report,
+            compare_ranges,
+            sync_with_remote,
+            get_sync_status,
+            setup_sync_encryption,
+            verify_sync_passphrase,
+            reset_sync_passphrase,
+            rotate_encryption_key,
             search::search_markdown_files,
-            search::rebuild_search_index
+            search::search_callouts,
+            search::rebuild_search_index,
+            search::get_related_notes,
+            search::get_file_preview,
+            http_api::start_http_api,
+            http_api::stop_http_api,
+            http_api::get_http_api_status,
+            clipboard::capture_clipboard,
+            clipboard::start_clipboard_watcher,
+            clipboard::stop_clipboard_watcher,
+            clipboard::get_clipboard_watcher_status,
+            watcher::start_directory_watcher,
+            watcher::stop_directory_watcher,
+            watcher::get_directory_watcher_status,
+            screenshot::capture_screenshot,
+            audio::start_audio_recording,
+            audio::stop_audio_recording,
+            audio::get_recording_status,
+            focus::start_focus_session,
+            focus::pause_focus_session,
+            focus::resume_focus_session,
+            focus::stop_focus_session,
+            focus::get_focus_session,
+            focus::get_focus_stats,
+            digest::generate_weekly_digest,
+            digest::set_digest_schedule,
+            digest::get_digest_schedule,
+            webhooks::get_webhooks,
+            webhooks::save_webhook,
+            webhooks::remove_webhook,
+            webhooks::test_webhook,
+            webhooks::emit_webhook_event,
+            timeline::get_timeline,
+            timeline::get_day_as_text,
+            today::get_today_summary,
+            today::get_calendar_feed,
+            today::set_calendar_feed,
+            worklog::export_worklog,
+            autofetch::set_fetch_schedule,
+            autofetch::get_fetch_schedule,
+            autofetch::get_fetch_statuses,
+            background::set_background_policy,
+            background::get_background_policy,
+            background::get_background_status,
+            secrets::store_secret,
+            secrets::get_secret,
+            secrets::delete_secret,
+            secrets::has_secret,
+            auth::set_app_passphrase,
+            auth::authenticate,
+            auth::lock_app,
+            auth::get_auth_status,
+            auth::set_auto_lock_timeout,
+            vaults::list_vaults,
+            vaults::add_vault,
+            vaults::remove_vault,
+            vaults::set_active_vault,
+            vaults::set_vault_overrides,
+            vaults::get_vault_config
         ])
         .on_window_event(|window, event| match event {
             WindowEvent::Resized(_) | WindowEvent::Moved(_) => {
@@ -93,13 +318,108 @@ pub fn run() {
                 }
             }
 
+            // The focus timer lives in the backend so it survives window reloads
+            let focus_path = app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("focus.json"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("stream-focus.json"));
+            app.manage(focus::FocusState::load(focus_path));
+            focus::spawn_ticker(app.handle().clone());
+
+            let digest_path = app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("digest.json"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("stream-digest.json"));
+            app.manage(digest::DigestState::load(digest_path));
+            let webhooks_path = app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("webhooks.json"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("stream-webhooks.json"));
+            app.manage(webhooks::WebhookState::load(webhooks_path));
+            let fetch_schedule_path = app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("fetch-schedule.json"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("stream-fetch-schedule.json"));
+            app.manage(autofetch::FetchSchedulerState::load(fetch_schedule_path));
+            let background_policy_path = app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("background-policy.json"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("stream-background-policy.json"));
+            app.manage(background::BackgroundState::load(background_policy_path));
+            app.manage(auth::AuthState::load(app.handle()));
+            let undo_dir = app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("undo"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("stream-undo"));
+            app.manage(stream_core::undo::UndoJournal::load(&undo_dir));
+            let spelling_dir = app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("spelling"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("stream-spelling"));
+            app.manage(stream_core::spellcheck::Spellchecker::load(&spelling_dir));
+            let recents_path = app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("recent-notes.json"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("stream-recent-notes.json"));
+            app.manage(stream_core::recents::RecentNotes::load(&recents_path));
+            let command_sources_path = app
+                .path()
+                .app_data_dir()
+                .map(|dir| dir.join("command-sources.json"))
+                .unwrap_or_else(|_| std::env::temp_dir().join("stream-command-sources.json"));
+            app.manage(stream_core::sources::CommandSourceStore::load(
+                &command_sources_path,
+            ));
+            ipc::markdown::load_scan_threads(app.handle());
+            ipc::git::load_git_backend(app.handle());
+            ipc::git::load_first_parent(app.handle());
+            ipc::references::load_ticket_patterns(app.handle());
+            ipc::network::load_network_settings(app.handle());
+
+            // `stream://note/<id>` links opened from other apps
+            let deep_link_handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                for url in event.urls() {
+                    ipc::note_ids::open_deep_link(&deep_link_handle, url.as_str());
+                }
+            });
+            if let Ok(Some(urls)) = app.deep_link().get_current() {
+                for url in urls {
+                    ipc::note_ids::open_deep_link(app.handle(), url.as_str());
+                }
+            }
+            if let Err(e) = secrets::migrate_store_secrets(app.handle()) {
+                eprintln!("Failed to move stored tokens to the keychain: {}", e);
+            }
+
             // Start background thread to check for files needing refresh
             let app_handle = app.handle().clone();
             std::thread::spawn(move || loop {
                 std::thread::sleep(std::time::Duration::from_secs(10));
-                if let Err(e) = app_handle.emit("check-for-refresh", ()) {
-                    eprintln!("Failed to emit check-for-refresh event: {}", e);
+                ipc::markdown::create_note_for_new_day(&app_handle);
+                digest::run_scheduled_digest(&app_handle);
+                // Refreshes and fetches pause or slow down on battery and metered connections
+                if !background::begin_tick(&app_handle) {
+                    continue;
+                }
+                let auto_refresh = vaults::resolve_vault_config(&app_handle, None, None)
+                    .map(|config| config.auto_refresh && !config.read_only)
+                    .unwrap_or(true);
+                if auto_refresh {
+                    if let Err(e) = app_handle.emit("check-for-refresh", ()) {
+                        eprintln!("Failed to emit check-for-refresh event: {}", e);
+                    }
                 }
+                ipc::sources::refresh_due_sources(&app_handle);
+                autofetch::run_scheduled_fetch(&app_handle);
             });
 
             Ok(())
@@ -2,5 +2,41 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // `stream-app --mcp --notes <dir>` serves the diary over MCP instead of opening the window
+    if let Some(options) = stream_lib::McpOptions::from_args(&args) {
+        #[cfg(windows)]
+        attach_parent_console();
+        let result = options.and_then(|o| stream_lib::run_mcp_server(o).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            eprintln!("MCP server error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     stream_lib::run()
 }
+
+/// Release builds use the GUI subsystem, so a `--mcp` run from a terminal starts without
+/// stdio. Attach to the parent's console when no handles were passed in; an MCP client that
+/// spawns the app with pipes already has them
+#[cfg(windows)]
+fn attach_parent_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetStdHandle(std_handle: u32) -> *mut std::ffi::c_void;
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+    const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    unsafe {
+        let missing = |handle: *mut std::ffi::c_void| handle.is_null() || handle as isize == -1;
+        if missing(GetStdHandle(STD_INPUT_HANDLE)) || missing(GetStdHandle(STD_OUTPUT_HANDLE)) {
+            AttachConsole(ATTACH_PARENT_PROCESS);
+        }
+    }
+}
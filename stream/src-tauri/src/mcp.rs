@@ -0,0 +1,275 @@
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use stream_core::git::get_repo_commits;
+use stream_core::markdown::{entry_day_bounds, find_daily_note};
+use stream_core::paths::normalize_path_string;
+use stream_core::search::search_markdown_files;
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+
+/// Options for the stdio MCP server, parsed from the command line
+#[derive(Debug, Default)]
+pub struct McpOptions {
+    pub notes_dir: String,
+    pub repo_paths: Vec<String>,
+}
+
+impl McpOptions {
+    /// Parse `--mcp --notes <dir> [--repo <path>]...`. Returns None when `--mcp` is absent
+    pub fn from_args(args: &[String]) -> Option<Result<Self, String>> {
+        if !args.iter().any(|arg| arg == "--mcp") {
+            return None;
+        }
+
+        let mut options = McpOptions::default();
+        let mut iter = args.iter().skip(1);
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--mcp" => {}
+                "--notes" => match iter.next() {
+                    Some(value) => options.notes_dir = normalize_path_string(value),
+                    None => return Some(Err("--notes requires a directory".to_string())),
+                },
+                "--repo" => match iter.next() {
+                    Some(value) => options.repo_paths.push(value.clone()),
+                    None => return Some(Err("--repo requires a path".to_string())),
+                },
+                other => return Some(Err(format!("Unknown argument: {}", other))),
+            }
+        }
+
+        if options.notes_dir.is_empty() {
+            return Some(Err("--notes <dir> is required in MCP mode".to_string()));
+        }
+
+        Some(Ok(options))
+    }
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "search_notes",
+            "description": "Full-text search across daily notes. Returns matching lines with file and line number.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string", "description": "Search terms" },
+                    "limit": { "type": "integer", "description": "Maximum results (default 20)" }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "get_note",
+            "description": "Read the daily note for a date (YYYY-MM-DD).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "date": { "type": "string", "description": "Date in YYYY-MM-DD format" }
+                },
+                "required": ["date"]
+            }
+        },
+        {
+            "name": "get_commits_for_day",
+            "description": "List git commits made on a date (YYYY-MM-DD) across the configured repositories.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "date": { "type": "string", "description": "Date in YYYY-MM-DD format" },
+                    "repo_paths": {
+                        "type": "array",
+                        "items": { "type": "string" },
+                        "description": "Repositories to read (defaults to those passed with --repo)"
+                    }
+                },
+                "required": ["date"]
+            }
+        }
+    ])
+}
+
+fn parse_date_arg(arguments: &Value) -> Result<NaiveDate, String> {
+    let date = arguments
+        .get("date")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing 'date' argument".to_string())?;
+
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))
+}
+
+fn tool_search_notes(options: &McpOptions, arguments: &Value) -> Result<String, String> {
+    let query = arguments
+        .get("query")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "Missing 'query' argument".to_string())?;
+    let limit = arguments
+        .get("limit")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(20) as usize;
+
+    let results = search_markdown_files(&options.notes_dir, query, limit, true, false)
+        .map_err(|e| e.to_string())?;
+
+    if results.matches.is_empty() {
+        return Ok(format!("No notes matched '{}'", query));
+    }
+
+    let lines: Vec<String> = results
+        .matches
+        .iter()
+        .map(|m| {
+            let file_name = Path::new(&m.file_path)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| m.file_path.clone());
+            format!(
+                "{}:{}: {}",
+                file_name,
+                m.line_number,
+                m.context_snippet.trim()
+            )
+        })
+        .collect();
+
+    Ok(lines.join("\n"))
+}
+
+fn tool_get_note(options: &McpOptions, arguments: &Value) -> Result<String, String> {
+    let date = parse_date_arg(arguments)?;
+
+    let note_path = find_daily_note(Path::new(&options.notes_dir), date)
+        .ok_or_else(|| format!("No note found for {}", date))?;
+
+    std::fs::read_to_string(note_path).map_err(|e| format!("Failed to read note: {}", e))
+}
+
+fn tool_get_commits_for_day(options: &McpOptions, arguments: &Value) -> Result<String, String> {
+    let date = parse_date_arg(arguments)?;
+
+    let repo_paths: Vec<String> = match arguments.get("repo_paths").and_then(|v| v.as_array()) {
+        Some(paths) => paths
+            .iter()
+            .filter_map(|p| p.as_str().map(|s| s.to_string()))
+            .collect(),
+        None => options.repo_paths.clone(),
+    };
+
+    if repo_paths.is_empty() {
+        return Err("No repositories configured; pass --repo or 'repo_paths'".to_string());
+    }
+
+    let (start_seconds, end_seconds) = entry_day_bounds(Path::new(&options.notes_dir), date)
+        .ok_or_else(|| format!("Invalid local date: {}", date))?;
+
+    let mut lines = Vec::new();
+    for repo_path in &repo_paths {
+        match get_repo_commits(repo_path, start_seconds, end_seconds) {
+            Ok(walk) => {
+                if walk.truncated {
+                    lines.push(format!(
+                        "{}: history too large to read in full; some commits may be missing",
+                        repo_path
+                    ));
+                }
+                for commit in walk.commits {
+                    lines.push(format!(
+                        "{} {} [{}] {} <{}>",
+                        &commit.id[..7.min(commit.id.len())],
+                        repo_path,
+                        commit.branches.join(", "),
+                        commit.message,
+                        commit.author_name
+                    ));
+                }
+            }
+            Err(e) => lines.push(format!("{}: error reading repository: {}", repo_path, e)),
+        }
+    }
+
+    if lines.is_empty() {
+        Ok(format!("No commits on {}", date))
+    } else {
+        Ok(lines.join("\n"))
+    }
+}
+
+fn call_tool(options: &McpOptions, params: &Value) -> Value {
+    let name = params.get("name").and_then(|v| v.as_str()).unwrap_or("");
+    let empty = json!({});
+    let arguments = params.get("arguments").unwrap_or(&empty);
+
+    let result = match name {
+        "search_notes" => tool_search_notes(options, arguments),
+        "get_note" => tool_get_note(options, arguments),
+        "get_commits_for_day" => tool_get_commits_for_day(options, arguments),
+        _ => Err(format!("Unknown tool: {}", name)),
+    };
+
+    match result {
+        Ok(text) => json!({ "content": [{ "type": "text", "text": text }], "isError": false }),
+        Err(text) => json!({ "content": [{ "type": "text", "text": text }], "isError": true }),
+    }
+}
+
+/// Handle a single JSON-RPC message. Notifications (no id) produce no response
+fn handle_message(options: &McpOptions, message: &Value) -> Option<Value> {
+    let id = message.get("id")?.clone();
+    let method = message.get("method").and_then(|v| v.as_str()).unwrap_or("");
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": PROTOCOL_VERSION,
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "stream", "version": env!("CARGO_PKG_VERSION") }
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tool_definitions() }),
+        "tools/call" => call_tool(options, &params),
+        _ => {
+            return Some(json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": -32601, "message": format!("Method not found: {}", method) }
+            }))
+        }
+    };
+
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+/// Run a read-only MCP server over stdio (newline-delimited JSON-RPC) until stdin closes
+pub fn run_stdio_server(options: McpOptions) -> Result<(), Box<dyn std::error::Error>> {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_message(&options, &message),
+            Err(e) => Some(json!({
+                "jsonrpc": "2.0",
+                "id": Value::Null,
+                "error": { "code": -32700, "message": format!("Parse error: {}", e) }
+            })),
+        };
+
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)?;
+            stdout.flush()?;
+        }
+    }
+
+    Ok(())
+}
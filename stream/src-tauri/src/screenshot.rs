@@ -0,0 +1,156 @@
+use std::path::Path;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use stream_core::capture::{encode_png, image_link, save_asset};
+use stream_core::paths::normalize_path_string;
+use tauri::AppHandle;
+use xcap::image::RgbaImage;
+use xcap::{Monitor, Window};
+
+use crate::vaults::ensure_writable;
+
+/// What to capture. Region coordinates are global screen points
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum ScreenshotTarget {
+    Screen {
+        monitor: Option<usize>,
+    },
+    Window {
+        title: Option<String>,
+    },
+    Region {
+        x: i32,
+        y: i32,
+        width: u32,
+        height: u32,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Screenshot {
+    pub file_path: String,
+    pub asset_path: String,
+    pub markdown: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+fn capture_screen(monitor: Option<usize>) -> Result<RgbaImage, String> {
+    let monitors = Monitor::all().map_err(|e| format!("Failed to list monitors: {}", e))?;
+    let monitor = match monitor {
+        Some(index) => monitors
+            .into_iter()
+            .nth(index)
+            .ok_or_else(|| format!("No monitor at index {}", index))?,
+        None => monitors
+            .iter()
+            .position(|m| m.is_primary().unwrap_or(false))
+            .map(|index| monitors[index].clone())
+            .or_else(|| monitors.first().cloned())
+            .ok_or("No monitors found")?,
+    };
+    monitor
+        .capture_image()
+        .map_err(|e| format!("Failed to capture screen: {}", e))
+}
+
+/// The focused window of another app, or the first window whose title contains `title`
+fn capture_window(title: Option<&str>) -> Result<RgbaImage, String> {
+    let own_pid = std::process::id();
+    let windows: Vec<Window> = Window::all()
+        .map_err(|e| format!("Failed to list windows: {}", e))?
+        .into_iter()
+        .filter(|w| !w.is_minimized().unwrap_or(true) && w.pid().ok() != Some(own_pid))
+        .collect();
+
+    let window = match title {
+        Some(title) => {
+            let needle = title.to_lowercase();
+            windows.iter().find(|w| {
+                w.title()
+                    .map(|t| t.to_lowercase().contains(&needle))
+                    .unwrap_or(false)
+            })
+        }
+        None => windows
+            .iter()
+            .find(|w| w.is_focused().unwrap_or(false))
+            .or_else(|| windows.first()),
+    }
+    .ok_or("No matching window found")?;
+
+    window
+        .capture_image()
+        .map_err(|e| format!("Failed to capture window: {}", e))
+}
+
+fn capture_region(x: i32, y: i32, width: u32, height: u32) -> Result<RgbaImage, String> {
+    if width == 0 || height == 0 {
+        return Err("Region must have a non-zero size".to_string());
+    }
+    let monitor =
+        Monitor::from_point(x, y).map_err(|e| format!("No monitor contains the region: {}", e))?;
+    let origin_x = monitor.x().unwrap_or(0);
+    let origin_y = monitor.y().unwrap_or(0);
+    monitor
+        .capture_region(
+            (x - origin_x).max(0) as u32,
+            (y - origin_y).max(0) as u32,
+            width,
+            height,
+        )
+        .map_err(|e| format!("Failed to capture region: {}", e))
+}
+
+#[tauri::command]
+pub async fn capture_screenshot(
+    app: AppHandle,
+    directory_path: String,
+    target: ScreenshotTarget,
+) -> Result<Screenshot, String> {
+    ensure_writable(&app, &directory_path)?;
+    let directory_path = normalize_path_string(&directory_path);
+    if !Path::new(&directory_path).is_dir() {
+        return Err(format!("Not a directory: {}", directory_path));
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let image = match &target {
+            ScreenshotTarget::Screen { monitor } => capture_screen(*monitor)?,
+            ScreenshotTarget::Window { title } => capture_window(title.as_deref())?,
+            ScreenshotTarget::Region {
+                x,
+                y,
+                width,
+                height,
+            } => capture_region(*x, *y, *width, *height)?,
+        };
+
+        let (width, height) = (image.width(), image.height());
+        let png = encode_png(width, height, image.as_raw())
+            .map_err(|e| format!("Failed to encode screenshot: {}", e))?;
+        let asset_path = save_asset(
+            Path::new(&directory_path),
+            "screenshot",
+            "png",
+            &png,
+            Local::now(),
+        )
+        .map_err(|e| format!("Failed to save screenshot: {}", e))?;
+
+        Ok(Screenshot {
+            file_path: Path::new(&directory_path)
+                .join(&asset_path)
+                .to_string_lossy()
+                .to_string(),
+            markdown: image_link("screenshot", &asset_path),
+            asset_path,
+            width,
+            height,
+        })
+    })
+    .await
+    .map_err(|e| format!("Screenshot task failed: {}", e))?
+}
@@ -0,0 +1,115 @@
+use keyring::Entry;
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use crate::auth::require_auth;
+use crate::SETTINGS_STORE;
+
+/// Keychain service name; each secret is stored under its own account
+const KEYCHAIN_SERVICE: &str = "stream";
+
+/// Secrets the frontend reads in normal use, like the Gemini key behind the AI features
+const APP_SECRETS: &[&str] = &["gemini"];
+
+/// Integration tokens. The backend reads them itself; handing one to the frontend needs
+/// the app unlocked
+const INTEGRATION_SECRETS: &[&str] = &["github", "gitlab", "jira", "weather"];
+
+/// Tokens that older versions kept in the settings store, with the secret each moves to
+const LEGACY_STORE_SECRETS: &[(&str, &str)] = &[("gemini_api_key", "gemini")];
+
+fn entry(name: &str) -> Result<Entry, String> {
+    Entry::new(KEYCHAIN_SERVICE, name).map_err(|e| format!("Keychain unavailable: {}", e))
+}
+
+pub(crate) fn read_secret(name: &str) -> Result<Option<String>, String> {
+    match entry(name)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read {} token: {}", name, e)),
+    }
+}
+
+pub(crate) fn write_secret(name: &str, secret: &str) -> Result<(), String> {
+    entry(name)?
+        .set_password(secret)
+        .map_err(|e| format!("Failed to store {} secret: {}", name, e))
+}
+
+pub(crate) fn remove_secret(name: &str) -> Result<(), String> {
+    match entry(name)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete {} secret: {}", name, e)),
+    }
+}
+
+/// Move tokens saved in settings.json by older versions into the keychain
+pub(crate) fn migrate_store_secrets(app: &AppHandle) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+
+    let mut migrated = false;
+    for (key, name) in LEGACY_STORE_SECRETS {
+        let value = match store.get(*key) {
+            Some(value) => value,
+            None => continue,
+        };
+        if let Some(secret) = value.as_str().filter(|s| !s.trim().is_empty()) {
+            if read_secret(name)?.is_none() {
+                write_secret(name, secret.trim())?;
+            }
+        }
+        store.delete(*key);
+        migrated = true;
+    }
+
+    if migrated {
+        store
+            .save()
+            .map_err(|e| format!("Failed to save settings: {}", e))?;
+    }
+    Ok(())
+}
+
+/// The secret commands only reach the app and integration secrets, never the app
+/// passphrase hash or webhook signing secrets kept in the same keychain service
+fn check_command_secret(name: &str) -> Result<(), String> {
+    if APP_SECRETS.contains(&name) || INTEGRATION_SECRETS.contains(&name) {
+        Ok(())
+    } else {
+        Err(format!("Unknown secret: {}", name))
+    }
+}
+
+#[tauri::command]
+pub async fn store_secret(name: String, value: String) -> Result<(), String> {
+    check_command_secret(&name)?;
+    if value.trim().is_empty() {
+        return Err("Secret is empty".to_string());
+    }
+    write_secret(&name, value.trim())
+}
+
+/// Return a stored secret in plain text. Integration tokens are gated behind app
+/// authentication
+#[tauri::command]
+pub async fn get_secret(app: AppHandle, name: String) -> Result<Option<String>, String> {
+    check_command_secret(&name)?;
+    if INTEGRATION_SECRETS.contains(&name.as_str()) {
+        require_auth(&app)?;
+    }
+    read_secret(&name)
+}
+
+#[tauri::command]
+pub async fn delete_secret(name: String) -> Result<(), String> {
+    check_command_secret(&name)?;
+    remove_secret(&name)
+}
+
+#[tauri::command]
+pub async fn has_secret(name: String) -> Result<bool, String> {
+    check_command_secret(&name)?;
+    Ok(read_secret(&name)?.is_some())
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use chrono::{Local, NaiveDate};
+use stream_core::timeline::{
+    self, TimelineEvent, TimelineEventKind, TimelineInputs, TimelineSource,
+};
+use stream_core::{perf, plain_text};
+use tauri::{AppHandle, Manager};
+
+use crate::focus::FocusState;
+use crate::vaults::{resolve_vault_config, VaultConfig};
+
+/// Today's timeline changes as you work; past days rarely do
+const TODAY_CACHE_TTL: Duration = Duration::from_secs(30);
+const PAST_CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Managed cache of assembled timelines, keyed by notes directory, date and sources
+#[derive(Default)]
+pub struct TimelineCache {
+    entries: Mutex<HashMap<String, (Instant, Vec<TimelineEvent>)>>,
+}
+
+impl TimelineCache {
+    fn get(&self, key: &str, ttl: Duration) -> Option<Vec<TimelineEvent>> {
+        let entries = self.entries.lock().ok()?;
+        let events = entries
+            .get(key)
+            .filter(|(built_at, _)| built_at.elapsed() < ttl)
+            .map(|(_, events)| events.clone());
+        let hit = events.is_some() as u64;
+        perf::record_cache("timeline", hit, 1 - hit);
+        events
+    }
+
+    fn insert(&self, key: String, events: Vec<TimelineEvent>) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|_, (built_at, _)| built_at.elapsed() < PAST_CACHE_TTL);
+            entries.insert(key, (Instant::now(), events));
+        }
+    }
+}
+
+fn cache_key(directory_path: &str, date: NaiveDate, sources: &[TimelineSource]) -> String {
+    format!("{}|{}|{:?}", directory_path, date, sources)
+}
+
+/// A day's commits, taken from any cached timeline that has them and is younger than
+/// `max_age`; otherwise read from the vault's repositories and cached
+pub(crate) fn commits_for_day(
+    cache: &TimelineCache,
+    config: &VaultConfig,
+    date: NaiveDate,
+    max_age: Duration,
+) -> Result<Vec<TimelineEvent>, String> {
+    let commits_only = [TimelineSource::Commits];
+    for sources in [&commits_only[..], &TimelineSource::ALL[..]] {
+        if let Some(events) = cache.get(&cache_key(&config.path, date, sources), max_age) {
+            return Ok(events
+                .into_iter()
+                .filter(|event| event.kind == TimelineEventKind::Commit)
+                .collect());
+        }
+    }
+
+    let inputs = TimelineInputs {
+        repo_paths: &config.repo_paths,
+        focus_sessions: &[],
+    };
+    let events = timeline::build_timeline(Path::new(&config.path), date, &commits_only, &inputs)
+        .map_err(|e| format!("Failed to read commits: {}", e))?;
+    cache.insert(cache_key(&config.path, date, &commits_only), events.clone());
+    Ok(events)
+}
+
+/// Commits, note edits, focus sessions and captures for a day as one chronological list.
+/// `sources` defaults to all of them; `refresh` bypasses the cache
+#[tauri::command]
+pub async fn get_timeline(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    date: String,
+    sources: Option<Vec<String>>,
+    refresh: Option<bool>,
+) -> Result<Vec<TimelineEvent>, String> {
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let mut sources = match sources {
+        Some(sources) => sources
+            .iter()
+            .map(|s| {
+                TimelineSource::from_string(s)
+                    .ok_or_else(|| format!("Unknown timeline source: {}", s))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => TimelineSource::ALL.to_vec(),
+    };
+    sources.sort();
+    sources.dedup();
+
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let key = cache_key(&config.path, date, &sources);
+    let ttl = if date >= Local::now().date_naive() {
+        TODAY_CACHE_TTL
+    } else {
+        PAST_CACHE_TTL
+    };
+
+    let cache = app.state::<TimelineCache>();
+    if !refresh.unwrap_or(false) {
+        if let Some(events) = cache.get(&key, ttl) {
+            return Ok(events);
+        }
+    }
+
+    let focus_sessions = app.state::<FocusState>().completed_sessions();
+    let events = tauri::async_runtime::spawn_blocking(move || {
+        let inputs = TimelineInputs {
+            repo_paths: &config.repo_paths,
+            focus_sessions: &focus_sessions,
+        };
+        timeline::build_timeline(Path::new(&config.path), date, &sources, &inputs)
+            .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Timeline task failed: {}", e))?
+    .map_err(|e| format!("Failed to build timeline: {}", e))?;
+
+    cache.insert(key, events.clone());
+    Ok(events)
+}
+
+/// The day's note and timeline as plain text without markdown syntax, for screen readers
+/// and pasting elsewhere. The timeline comes from the same cache as `get_timeline`
+#[tauri::command]
+pub async fn get_day_as_text(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    date: String,
+) -> Result<String, String> {
+    let events = get_timeline(
+        app.clone(),
+        vault_id.clone(),
+        directory_path.clone(),
+        date.clone(),
+        None,
+        None,
+    )
+    .await?;
+    let date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date '{}': {}", date, e))?;
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        plain_text::day_as_text(Path::new(&config.path), date, &events).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Export task failed: {}", e))?
+    .map_err(|e| format!("Failed to render day as text: {}", e))
+}
@@ -0,0 +1,242 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime};
+
+use chrono::{Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use stream_core::calendar::{self, CalendarEvent};
+use stream_core::clock::now_ms;
+use stream_core::markdown;
+use stream_core::stats::{self, NoteProgress};
+use stream_core::timeline::TimelineEvent;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+use crate::timeline::{commits_for_day, TimelineCache};
+use crate::vaults::resolve_vault_config;
+use crate::SETTINGS_STORE;
+
+const CALENDAR_FEED_KEY: &str = "calendar_feed_url";
+
+/// Commits are taken from a cached timeline up to this old
+const COMMITS_MAX_AGE: Duration = Duration::from_secs(2 * 60);
+const CALENDAR_TTL: Duration = Duration::from_secs(15 * 60);
+/// How long a missing note is assumed to stay missing, apart from a note at the top level,
+/// which is checked every time. Finding notes in subfolders takes a directory walk
+const NOTE_LOOKUP_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// What the mini window shows about today
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TodaySummary {
+    pub date: String,
+    pub file_path: Option<String>,
+    #[serde(flatten)]
+    pub progress: NoteProgress,
+    pub commit_count: usize,
+    pub last_commit: Option<TimelineEvent>,
+    /// From the calendar feed, when one is set
+    pub next_event: Option<CalendarEvent>,
+}
+
+struct CachedNote {
+    directory_path: String,
+    date: NaiveDate,
+    path: Option<PathBuf>,
+    looked_up_at: Instant,
+    /// Modification time and size the progress was read at
+    stamp: Option<(SystemTime, u64)>,
+    progress: NoteProgress,
+}
+
+struct CachedFeed {
+    url: String,
+    fetched_at: Instant,
+    ics: String,
+}
+
+/// Managed state caching today's note and the calendar feed between summary polls
+#[derive(Default)]
+pub struct TodaySummaryState {
+    note: Mutex<Option<CachedNote>>,
+    feed: Mutex<Option<CachedFeed>>,
+}
+
+/// Today's note and its progress, re-read only when the note's stamp changes
+fn note_progress(
+    state: &TodaySummaryState,
+    directory_path: &str,
+    date: NaiveDate,
+) -> (Option<PathBuf>, NoteProgress) {
+    let Ok(mut cached) = state.note.lock() else {
+        return (None, NoteProgress::default());
+    };
+    let previous = cached
+        .take()
+        .filter(|note| note.directory_path == directory_path && note.date == date);
+
+    let root_note = markdown::daily_note_path(Path::new(directory_path), date);
+    let (path, looked_up_at) = match &previous {
+        Some(note) if note.path.as_ref().is_some_and(|path| path.is_file()) => {
+            (note.path.clone(), note.looked_up_at)
+        }
+        _ if root_note.is_file() => (Some(root_note), Instant::now()),
+        Some(note) if note.path.is_none() && note.looked_up_at.elapsed() < NOTE_LOOKUP_TTL => {
+            (None, note.looked_up_at)
+        }
+        _ => (
+            markdown::find_daily_note(Path::new(directory_path), date),
+            Instant::now(),
+        ),
+    };
+
+    let stamp = path
+        .as_ref()
+        .and_then(|path| fs::metadata(path).ok())
+        .and_then(|metadata| Some((metadata.modified().ok()?, metadata.len())));
+    let progress = match previous {
+        Some(note) if note.path == path && note.stamp == stamp => note.progress,
+        _ => path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|content| stats::note_progress(&content))
+            .unwrap_or_default(),
+    };
+
+    *cached = Some(CachedNote {
+        directory_path: directory_path.to_string(),
+        date,
+        path: path.clone(),
+        looked_up_at,
+        stamp,
+        progress: progress.clone(),
+    });
+    (path, progress)
+}
+
+pub(crate) fn saved_calendar_feed(app: &AppHandle) -> Option<String> {
+    app.store(SETTINGS_STORE)
+        .ok()
+        .and_then(|store| store.get(CALENDAR_FEED_KEY))
+        .and_then(|value| value.as_str().map(str::to_string))
+        .filter(|url| !url.trim().is_empty())
+}
+
+/// The next event in the calendar feed, downloading it at most every `CALENDAR_TTL`. A
+/// failed download keeps the previous copy until the next attempt
+fn next_calendar_event(app: &AppHandle, state: &TodaySummaryState) -> Option<CalendarEvent> {
+    let url = saved_calendar_feed(app)?;
+    let mut cached = state.feed.lock().ok()?;
+    let fresh = cached
+        .as_ref()
+        .is_some_and(|feed| feed.url == url && feed.fetched_at.elapsed() < CALENDAR_TTL);
+
+    if !fresh {
+        let ics = match calendar::fetch_ics(&url) {
+            Ok(ics) => ics,
+            Err(e) => {
+                eprintln!("Failed to fetch calendar feed: {}", e);
+                cached
+                    .take()
+                    .filter(|feed| feed.url == url)
+                    .map(|feed| feed.ics)
+                    .unwrap_or_default()
+            }
+        };
+        *cached = Some(CachedFeed {
+            url,
+            fetched_at: Instant::now(),
+            ics,
+        });
+    }
+
+    cached
+        .as_ref()
+        .and_then(|feed| calendar::next_event(&feed.ics, now_ms()))
+}
+
+/// Today's word count, tasks, commits so far and next calendar event in one call, for the
+/// mini and tray windows to poll every minute. Everything comes from caches: the note is
+/// re-read only when it changes, commits are reused from recent timelines and the calendar
+/// is downloaded every 15 minutes
+#[tauri::command]
+pub async fn get_today_summary(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+) -> Result<TodaySummary, String> {
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let today = Local::now().date_naive();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let state = app.state::<TodaySummaryState>();
+        let (file_path, progress) = note_progress(&state, &config.path, today);
+
+        let commits = commits_for_day(
+            &app.state::<TimelineCache>(),
+            &config,
+            today,
+            COMMITS_MAX_AGE,
+        )
+        .unwrap_or_else(|e| {
+            eprintln!("Failed to read today's commits: {}", e);
+            Vec::new()
+        });
+
+        Ok(TodaySummary {
+            date: today.to_string(),
+            file_path: file_path.map(|path| path.to_string_lossy().to_string()),
+            progress,
+            commit_count: commits.len(),
+            last_commit: commits.into_iter().max_by_key(|event| event.timestamp),
+            next_event: next_calendar_event(&app, &state),
+        })
+    })
+    .await
+    .map_err(|e| format!("Summary task failed: {}", e))?
+}
+
+#[tauri::command]
+pub async fn get_calendar_feed(app: AppHandle) -> Result<Option<String>, String> {
+    Ok(saved_calendar_feed(&app))
+}
+
+/// iCalendar feed (`.ics` or `webcal://` link) the summary's next event comes from; None
+/// clears it
+#[tauri::command]
+pub async fn set_calendar_feed(
+    app: AppHandle,
+    state: tauri::State<'_, TodaySummaryState>,
+    url: Option<String>,
+) -> Result<(), String> {
+    let url = url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    if let Some(url) = &url {
+        if !["https://", "http://", "webcal://"]
+            .iter()
+            .any(|scheme| url.starts_with(scheme))
+        {
+            return Err("Calendar feed must be an http(s) or webcal link".to_string());
+        }
+    }
+
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+    match &url {
+        Some(url) => store.set(CALENDAR_FEED_KEY, json!(url)),
+        None => {
+            store.delete(CALENDAR_FEED_KEY);
+        }
+    }
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    if let Ok(mut feed) = state.feed.lock() {
+        *feed = None;
+    }
+    Ok(())
+}
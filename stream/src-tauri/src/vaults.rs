@@ -0,0 +1,355 @@
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use stream_core::lint::LintRules;
+use stream_core::paths::{normalize_path, normalize_path_string, same_path};
+use tauri::{AppHandle, Runtime};
+use tauri_plugin_store::StoreExt;
+
+use crate::SETTINGS_STORE;
+
+const VAULTS_KEY: &str = "vaults";
+const ACTIVE_VAULT_KEY: &str = "active_vault_id";
+/// Folder picked before vaults existed; becomes the first vault
+const LEGACY_FOLDER_KEY: &str = "stream-last-selected-folder";
+/// Per-directory repository lists kept by the frontend
+const REPO_MAPPINGS_STORE: &str = "repo-mappings.json";
+
+/// A named notes directory. Work and personal diaries live in separate vaults
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Vault {
+    pub id: String,
+    pub name: String,
+    pub path: String,
+    #[serde(default)]
+    pub overrides: VaultOverrides,
+}
+
+/// Per-vault settings. Anything left unset falls back to the app-wide behavior
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VaultOverrides {
+    pub repo_paths: Option<Vec<String>>,
+    pub daily_template: Option<String>,
+    pub auto_refresh: Option<bool>,
+    pub sync_encryption: Option<bool>,
+    pub lint_rules: Option<LintRules>,
+    /// Refuse every change to the vault's files, e.g. for a teammate's exported archive
+    pub read_only: Option<bool>,
+    /// Create each day's note at local midnight
+    pub auto_create_daily_note: Option<bool>,
+}
+
+/// Effective settings for a notes directory after applying its vault's overrides
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VaultConfig {
+    pub vault_id: Option<String>,
+    pub path: String,
+    pub repo_paths: Vec<String>,
+    pub daily_template: Option<String>,
+    pub auto_refresh: bool,
+    pub sync_encryption: bool,
+    pub lint_rules: LintRules,
+    pub read_only: bool,
+    pub auto_create_daily_note: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VaultList {
+    pub vaults: Vec<Vault>,
+    pub active_vault_id: Option<String>,
+}
+
+fn slugify(name: &str) -> String {
+    let slug: String = name
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    let slug = slug
+        .split('-')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-");
+    if slug.is_empty() {
+        "vault".to_string()
+    } else {
+        slug
+    }
+}
+
+fn vault_name_from_path(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Notes".to_string())
+}
+
+pub(crate) fn load_vaults<R: Runtime>(app: &AppHandle<R>) -> Result<VaultList, String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+
+    let mut vaults: Vec<Vault> = store
+        .get(VAULTS_KEY)
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default();
+    let mut active_vault_id = store
+        .get(ACTIVE_VAULT_KEY)
+        .and_then(|value| value.as_str().map(|s| s.to_string()));
+
+    if vaults.is_empty() {
+        if let Some(folder) = store
+            .get(LEGACY_FOLDER_KEY)
+            .and_then(|value| value.as_str().map(|s| s.to_string()))
+        {
+            let name = vault_name_from_path(&folder);
+            vaults.push(Vault {
+                id: slugify(&name),
+                name,
+                path: folder,
+                overrides: VaultOverrides::default(),
+            });
+        }
+    }
+
+    if !active_vault_id
+        .as_ref()
+        .is_some_and(|id| vaults.iter().any(|v| &v.id == id))
+    {
+        active_vault_id = vaults.first().map(|v| v.id.clone());
+    }
+
+    Ok(VaultList {
+        vaults,
+        active_vault_id,
+    })
+}
+
+fn save_vaults<R: Runtime>(app: &AppHandle<R>, list: &VaultList) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings: {}", e))?;
+
+    store.set(VAULTS_KEY, json!(list.vaults));
+    match &list.active_vault_id {
+        Some(id) => store.set(ACTIVE_VAULT_KEY, json!(id)),
+        None => {
+            store.delete(ACTIVE_VAULT_KEY);
+        }
+    }
+    store
+        .save()
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+/// Notes directory for a command: an explicit vault, else an explicit path, else the
+/// active vault. Lets existing callers keep passing `directory_path`
+pub(crate) fn resolve_directory<R: Runtime>(
+    app: &AppHandle<R>,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+) -> Result<String, String> {
+    if vault_id.is_none() {
+        if let Some(path) = directory_path {
+            return Ok(normalize_path_string(&path));
+        }
+    }
+
+    let list = load_vaults(app)?;
+    let id = vault_id
+        .or(list.active_vault_id)
+        .ok_or_else(|| "No vault selected".to_string())?;
+    list.vaults
+        .into_iter()
+        .find(|v| v.id == id)
+        .map(|v| normalize_path_string(&v.path))
+        .ok_or_else(|| format!("Unknown vault: {}", id))
+}
+
+/// Repositories connected to `path` in the frontend's repo mappings
+fn mapped_repos<R: Runtime>(app: &AppHandle<R>, path: &str) -> Vec<String> {
+    app.store(REPO_MAPPINGS_STORE)
+        .ok()
+        .and_then(|store| store.get("mappings"))
+        .and_then(|mappings| {
+            mappings.get(path).cloned().or_else(|| {
+                mappings
+                    .as_object()?
+                    .iter()
+                    .find(|(key, _)| same_path(key, path))
+                    .map(|(_, repos)| repos.clone())
+            })
+        })
+        .and_then(|repos| serde_json::from_value(repos).ok())
+        .unwrap_or_default()
+}
+
+/// Settings for a vault (by id, else by matching path, else the active vault) with its
+/// overrides applied. A directory that isn't a vault gets the app-wide defaults
+pub(crate) fn resolve_vault_config<R: Runtime>(
+    app: &AppHandle<R>,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+) -> Result<VaultConfig, String> {
+    let list = load_vaults(app)?;
+    let vault = match (&vault_id, &directory_path) {
+        (Some(id), _) => Some(
+            list.vaults
+                .iter()
+                .find(|v| &v.id == id)
+                .ok_or_else(|| format!("Unknown vault: {}", id))?,
+        ),
+        (None, Some(path)) => list.vaults.iter().find(|v| same_path(&v.path, path)),
+        (None, None) => list
+            .active_vault_id
+            .as_ref()
+            .and_then(|id| list.vaults.iter().find(|v| &v.id == id)),
+    };
+
+    let path = match (vault, directory_path) {
+        (Some(vault), _) => vault.path.clone(),
+        (None, Some(path)) => path,
+        (None, None) => return Err("No vault selected".to_string()),
+    };
+    let path = normalize_path_string(&path);
+    let overrides = vault.map(|v| v.overrides.clone()).unwrap_or_default();
+
+    Ok(VaultConfig {
+        vault_id: vault.map(|v| v.id.clone()),
+        repo_paths: overrides
+            .repo_paths
+            .unwrap_or_else(|| mapped_repos(app, &path)),
+        path,
+        daily_template: overrides.daily_template.filter(|t| !t.trim().is_empty()),
+        auto_refresh: overrides.auto_refresh.unwrap_or(true),
+        sync_encryption: overrides.sync_encryption.unwrap_or(false),
+        lint_rules: overrides.lint_rules.unwrap_or_default(),
+        read_only: overrides.read_only.unwrap_or(false),
+        auto_create_daily_note: overrides.auto_create_daily_note.unwrap_or(true),
+    })
+}
+
+/// Prefix of the error returned for changes to a read-only vault, for the frontend to match on
+pub(crate) const READ_ONLY_ERROR: &str = "read_only_vault";
+
+/// Fail with `READ_ONLY_ERROR` when `path` is inside (or is) a vault marked read-only
+pub(crate) fn ensure_writable<R: Runtime>(app: &AppHandle<R>, path: &str) -> Result<(), String> {
+    let path = normalize_path(path);
+    let list = load_vaults(app)?;
+    let read_only = list.vaults.iter().find(|vault| {
+        vault.overrides.read_only == Some(true) && path.starts_with(normalize_path(&vault.path))
+    });
+    match read_only {
+        Some(vault) => Err(format!(
+            "{}: {} is read-only and can't be changed",
+            READ_ONLY_ERROR, vault.name
+        )),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub async fn list_vaults(app: AppHandle) -> Result<VaultList, String> {
+    load_vaults(&app)
+}
+
+#[tauri::command]
+pub async fn add_vault(app: AppHandle, name: String, path: String) -> Result<Vault, String> {
+    let path = normalize_path_string(&path);
+    if !Path::new(&path).is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+
+    let mut list = load_vaults(&app)?;
+    if list.vaults.iter().any(|v| same_path(&v.path, &path)) {
+        return Err(format!("{} is already a vault", path));
+    }
+
+    let name = if name.trim().is_empty() {
+        vault_name_from_path(&path)
+    } else {
+        name.trim().to_string()
+    };
+    let base = slugify(&name);
+    let mut id = base.clone();
+    let mut counter = 2;
+    while list.vaults.iter().any(|v| v.id == id) {
+        id = format!("{}-{}", base, counter);
+        counter += 1;
+    }
+
+    let vault = Vault {
+        id,
+        name,
+        path,
+        overrides: VaultOverrides::default(),
+    };
+    list.vaults.push(vault.clone());
+    if list.active_vault_id.is_none() {
+        list.active_vault_id = Some(vault.id.clone());
+    }
+    save_vaults(&app, &list)?;
+
+    Ok(vault)
+}
+
+/// Forget a vault. The notes directory itself is left untouched
+#[tauri::command]
+pub async fn remove_vault(app: AppHandle, vault_id: String) -> Result<(), String> {
+    let mut list = load_vaults(&app)?;
+    let before = list.vaults.len();
+    list.vaults.retain(|v| v.id != vault_id);
+    if list.vaults.len() == before {
+        return Err(format!("Unknown vault: {}", vault_id));
+    }
+
+    if list.active_vault_id.as_deref() == Some(vault_id.as_str()) {
+        list.active_vault_id = list.vaults.first().map(|v| v.id.clone());
+    }
+    save_vaults(&app, &list)
+}
+
+#[tauri::command]
+pub async fn set_active_vault(app: AppHandle, vault_id: String) -> Result<Vault, String> {
+    let mut list = load_vaults(&app)?;
+    let vault = list
+        .vaults
+        .iter()
+        .find(|v| v.id == vault_id)
+        .cloned()
+        .ok_or_else(|| format!("Unknown vault: {}", vault_id))?;
+
+    list.active_vault_id = Some(vault.id.clone());
+    save_vaults(&app, &list)?;
+
+    Ok(vault)
+}
+
+#[tauri::command]
+pub async fn set_vault_overrides(
+    app: AppHandle,
+    vault_id: String,
+    overrides: VaultOverrides,
+) -> Result<VaultConfig, String> {
+    let mut list = load_vaults(&app)?;
+    let vault = list
+        .vaults
+        .iter_mut()
+        .find(|v| v.id == vault_id)
+        .ok_or_else(|| format!("Unknown vault: {}", vault_id))?;
+    vault.overrides = overrides;
+    save_vaults(&app, &list)?;
+
+    resolve_vault_config(&app, Some(vault_id), None)
+}
+
+#[tauri::command]
+pub async fn get_vault_config(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+) -> Result<VaultConfig, String> {
+    resolve_vault_config(&app, vault_id, directory_path)
+}
@@ -0,0 +1,212 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use stream_core::markdown::{self, MarkdownFileMetadata};
+use stream_core::paths::normalize_path_string;
+use stream_core::walk::{is_excluded_path, walk_files};
+use tauri::{AppHandle, Emitter};
+
+/// A note's changes are reported once none have arrived for this long, so bursts such as an
+/// atomic save's write and rename become a single event
+const SETTLE_DELAY: Duration = Duration::from_millis(300);
+
+/// Payload of the `file-created`, `file-modified`, `file-deleted` and `xattr-changed` events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileChangeEvent {
+    pub file_path: String,
+    /// None for deleted files
+    pub metadata: Option<MarkdownFileMetadata>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DirectoryWatcherStatus {
+    pub running: bool,
+    pub directory_path: Option<String>,
+}
+
+struct RunningWatcher {
+    directory_path: String,
+    watcher: RecommendedWatcher,
+    handle: JoinHandle<()>,
+}
+
+/// Managed state for the notes directory watcher. It's off until started
+#[derive(Default)]
+pub struct DirectoryWatcherState {
+    watcher: Mutex<Option<RunningWatcher>>,
+}
+
+/// Changes to one note seen since it was last reported
+struct PendingChange {
+    /// Anything beyond metadata changed: content, or the file appeared, moved or went away
+    content: bool,
+    last_seen: Instant,
+}
+
+fn emit_change(app: &AppHandle, event: &str, path: &Path) {
+    let payload = FileChangeEvent {
+        file_path: path.to_string_lossy().to_string(),
+        metadata: markdown::read_note_metadata(path),
+    };
+    if let Err(e) = app.emit(event, &payload) {
+        eprintln!("Failed to emit {} event: {}", event, e);
+    }
+}
+
+/// Report a note whose changes have settled. Whether it was created or deleted goes by
+/// whether it exists now and existed before, since renames and atomic saves reach each
+/// platform's backend as different event sequences
+fn report(app: &AppHandle, known: &mut HashSet<PathBuf>, path: PathBuf, change: PendingChange) {
+    let exists = path.is_file();
+    let event = match (known.contains(&path), exists) {
+        (false, true) => "file-created",
+        (true, false) => "file-deleted",
+        (true, true) if change.content => "file-modified",
+        (true, true) => "xattr-changed",
+        // Created and removed before it settled
+        (false, false) => return,
+    };
+    if exists {
+        known.insert(path.clone());
+    } else {
+        known.remove(&path);
+    }
+    emit_change(app, event, &path);
+}
+
+/// Collect notify events for notes under `root` and report each note once it settles.
+/// Returns when the watcher is dropped
+fn watch(
+    app: AppHandle,
+    root: PathBuf,
+    events: std::sync::mpsc::Receiver<notify::Result<notify::Event>>,
+) {
+    let mut known: HashSet<PathBuf> = walk_files(&root, |path, _| {
+        markdown::is_note_path(path).then(|| path.to_path_buf())
+    })
+    .into_iter()
+    .collect();
+    let mut pending: HashMap<PathBuf, PendingChange> = HashMap::new();
+
+    loop {
+        match events.recv_timeout(SETTLE_DELAY) {
+            Ok(Ok(event)) => {
+                // The backend dropped events; only a full scan can catch up
+                if event.need_rescan() {
+                    if let Err(e) = app.emit("watcher-rescan", root.to_string_lossy()) {
+                        eprintln!("Failed to emit watcher-rescan event: {}", e);
+                    }
+                }
+                let content = !matches!(event.kind, EventKind::Modify(ModifyKind::Metadata(_)));
+                for path in event.paths {
+                    if !markdown::is_note_path(&path) || is_excluded_path(&root, &path) {
+                        continue;
+                    }
+                    let change = pending.entry(path).or_insert(PendingChange {
+                        content: false,
+                        last_seen: Instant::now(),
+                    });
+                    change.content |= content;
+                    change.last_seen = Instant::now();
+                }
+            }
+            Ok(Err(e)) => eprintln!("Directory watcher: {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, change)| change.last_seen.elapsed() >= SETTLE_DELAY)
+            .map(|(path, _)| path.clone())
+            .collect();
+        for path in settled {
+            if let Some(change) = pending.remove(&path) {
+                report(&app, &mut known, path, change);
+            }
+        }
+    }
+}
+
+fn stop_watcher(state: &DirectoryWatcherState) {
+    let running = state.watcher.lock().ok().and_then(|mut guard| guard.take());
+    if let Some(running) = running {
+        // Dropping the watcher closes the channel, which ends the thread
+        drop(running.watcher);
+        let _ = running.handle.join();
+    }
+}
+
+fn current_status(state: &DirectoryWatcherState) -> DirectoryWatcherStatus {
+    let directory_path = state
+        .watcher
+        .lock()
+        .ok()
+        .and_then(|guard| guard.as_ref().map(|w| w.directory_path.clone()));
+
+    DirectoryWatcherStatus {
+        running: directory_path.is_some(),
+        directory_path,
+    }
+}
+
+/// Watch the notes directory and emit `file-created`, `file-modified`, `file-deleted` and
+/// `xattr-changed` for daily notes as they change, so the note list can be kept current
+/// without rescanning. Replaces any directory already being watched
+#[tauri::command]
+pub async fn start_directory_watcher(
+    app: AppHandle,
+    state: tauri::State<'_, DirectoryWatcherState>,
+    directory_path: String,
+) -> Result<DirectoryWatcherStatus, String> {
+    let directory_path = normalize_path_string(&directory_path);
+    if !Path::new(&directory_path).is_dir() {
+        return Err(format!("Not a directory: {}", directory_path));
+    }
+
+    stop_watcher(&state);
+
+    let (sender, receiver) = channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = sender.send(event);
+    })
+    .map_err(|e| format!("Failed to start watcher: {}", e))?;
+    watcher
+        .watch(Path::new(&directory_path), RecursiveMode::Recursive)
+        .map_err(|e| format!("Failed to watch {}: {}", directory_path, e))?;
+
+    let root = PathBuf::from(&directory_path);
+    let handle = std::thread::spawn(move || watch(app, root, receiver));
+
+    if let Ok(mut guard) = state.watcher.lock() {
+        *guard = Some(RunningWatcher {
+            directory_path,
+            watcher,
+            handle,
+        });
+    }
+
+    Ok(current_status(&state))
+}
+
+#[tauri::command]
+pub async fn stop_directory_watcher(
+    state: tauri::State<'_, DirectoryWatcherState>,
+) -> Result<DirectoryWatcherStatus, String> {
+    stop_watcher(&state);
+    Ok(current_status(&state))
+}
+
+#[tauri::command]
+pub async fn get_directory_watcher_status(
+    state: tauri::State<'_, DirectoryWatcherState>,
+) -> Result<DirectoryWatcherStatus, String> {
+    Ok(current_status(&state))
+}
@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde_json::Value;
+use stream_core::webhooks::{self, Webhook, WebhookDelivery, WebhookEvent};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::secrets::{read_secret, remove_secret, write_secret};
+
+/// Managed state holding the configured webhooks, persisted to the app data dir. Signing
+/// secrets live in the keychain
+pub struct WebhookState {
+    path: PathBuf,
+    webhooks: Mutex<Vec<Webhook>>,
+}
+
+impl WebhookState {
+    pub fn load(path: PathBuf) -> Self {
+        let webhooks = fs::read_to_string(&path)
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        WebhookState {
+            path,
+            webhooks: Mutex::new(webhooks),
+        }
+    }
+
+    fn webhooks(&self) -> Vec<Webhook> {
+        self.webhooks.lock().map(|w| w.clone()).unwrap_or_default()
+    }
+
+    fn save(&self, webhooks: Vec<Webhook>) -> Result<(), String> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to save webhooks: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(&webhooks).map_err(|e| e.to_string())?;
+        fs::write(&self.path, json).map_err(|e| format!("Failed to save webhooks: {}", e))?;
+
+        *self
+            .webhooks
+            .lock()
+            .map_err(|_| "Webhooks are unavailable".to_string())? = webhooks;
+        Ok(())
+    }
+}
+
+fn secret_name(webhook_id: &str) -> String {
+    format!("webhook-{}", webhook_id)
+}
+
+fn deliver(webhook: &Webhook, event: WebhookEvent, data: &Value) -> WebhookDelivery {
+    let secret = read_secret(&secret_name(&webhook.id)).unwrap_or_else(|e| {
+        eprintln!("Sending webhook {} unsigned: {}", webhook.id, e);
+        None
+    });
+    webhooks::deliver_webhook(webhook, secret.as_deref(), event, data)
+}
+
+/// POST `event` to every enabled webhook subscribed to it, in the background. Failed
+/// deliveries are retried, then reported with a `webhook-delivery-failed` event
+pub(crate) fn notify(app: &AppHandle, event: WebhookEvent, data: Value) {
+    let subscribed: Vec<Webhook> = app
+        .state::<WebhookState>()
+        .webhooks()
+        .into_iter()
+        .filter(|webhook| webhook.wants(event))
+        .collect();
+    if subscribed.is_empty() {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        for webhook in &subscribed {
+            let delivery = deliver(webhook, event, &data);
+            if let Some(error) = &delivery.error {
+                eprintln!("Webhook {} failed: {}", webhook.id, error);
+                if let Err(e) = app.emit("webhook-delivery-failed", &delivery) {
+                    eprintln!("Failed to emit webhook-delivery-failed event: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn get_webhooks(state: tauri::State<'_, WebhookState>) -> Result<Vec<Webhook>, String> {
+    Ok(state.webhooks())
+}
+
+/// Add a webhook or replace the one with the same id. A `secret` turns on signing; an
+/// empty one turns it off, and leaving it out keeps the current secret
+#[tauri::command]
+pub async fn save_webhook(
+    state: tauri::State<'_, WebhookState>,
+    webhook: Webhook,
+    secret: Option<String>,
+) -> Result<(), String> {
+    webhooks::validate_webhook(&webhook).map_err(|e| e.to_string())?;
+
+    match secret.as_deref().map(str::trim) {
+        Some("") => remove_secret(&secret_name(&webhook.id))?,
+        Some(secret) => write_secret(&secret_name(&webhook.id), secret)?,
+        None => {}
+    }
+
+    let mut webhooks = state.webhooks();
+    match webhooks.iter_mut().find(|w| w.id == webhook.id) {
+        Some(existing) => *existing = webhook,
+        None => webhooks.push(webhook),
+    }
+    state.save(webhooks)
+}
+
+#[tauri::command]
+pub async fn remove_webhook(
+    state: tauri::State<'_, WebhookState>,
+    id: String,
+) -> Result<(), String> {
+    let mut webhooks = state.webhooks();
+    webhooks.retain(|w| w.id != id);
+    state.save(webhooks)?;
+    remove_secret(&secret_name(&id))
+}
+
+/// Send a sample `event` to one webhook right away and report how it went
+#[tauri::command]
+pub async fn test_webhook(
+    state: tauri::State<'_, WebhookState>,
+    id: String,
+    event: Option<String>,
+) -> Result<WebhookDelivery, String> {
+    let webhook = state
+        .webhooks()
+        .into_iter()
+        .find(|w| w.id == id)
+        .ok_or_else(|| format!("No webhook with id {}", id))?;
+    let event = match event {
+        Some(event) => WebhookEvent::from_string(&event)
+            .ok_or_else(|| format!("Unknown webhook event: {}", event))?,
+        None => webhook
+            .events
+            .first()
+            .copied()
+            .unwrap_or(WebhookEvent::NoteCreated),
+    };
+
+    tauri::async_runtime::spawn_blocking(move || {
+        deliver(&webhook, event, &serde_json::json!({ "test": true }))
+    })
+    .await
+    .map_err(|e| format!("Webhook task failed: {}", e))
+}
+
+/// Fire an event that happened in the webview, such as an AI summary being written
+#[tauri::command]
+pub async fn emit_webhook_event(app: AppHandle, event: String, data: Value) -> Result<(), String> {
+    let event = WebhookEvent::from_string(&event)
+        .ok_or_else(|| format!("Unknown webhook event: {}", event))?;
+    notify(&app, event, data);
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use chrono::NaiveDate;
+use serde::Deserialize;
+use stream_core::paths::normalize_path_string;
+use stream_core::worklog::{self, WorklogFormat, WorklogSummary};
+use tauri::{AppHandle, Manager};
+
+use crate::focus::FocusState;
+use crate::vaults::resolve_vault_config;
+
+/// Inclusive range of days, as YYYY-MM-DD
+#[derive(Debug, Deserialize)]
+pub struct WorklogRange {
+    pub start_date: String,
+    pub end_date: String,
+}
+
+/// Write a timesheet of estimated time per project per day, from commit clustering and
+/// focus sessions, as "csv" or "json". Without `repo_paths`, the vault's repositories are used
+#[tauri::command]
+pub async fn export_worklog(
+    app: AppHandle,
+    vault_id: Option<String>,
+    directory_path: Option<String>,
+    repo_paths: Option<Vec<String>>,
+    range: WorklogRange,
+    format: String,
+    output_path: String,
+) -> Result<WorklogSummary, String> {
+    let output_path = normalize_path_string(&output_path);
+    let parse = |date: &str| {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| format!("Invalid date '{}': {}", date, e))
+    };
+    let (start, end) = (parse(&range.start_date)?, parse(&range.end_date)?);
+    let format = WorklogFormat::from_string(&format)
+        .ok_or_else(|| format!("Unknown worklog format: {}", format))?;
+    let config = resolve_vault_config(&app, vault_id, directory_path)?;
+    let repo_paths = repo_paths.unwrap_or(config.repo_paths);
+    let focus_sessions = app.state::<FocusState>().completed_sessions();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        worklog::build_worklog(
+            Some(Path::new(&config.path)),
+            &repo_paths,
+            &focus_sessions,
+            start,
+            end,
+        )
+        .and_then(|entries| worklog::export_worklog(&entries, Path::new(&output_path), format))
+        .map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| format!("Worklog task failed: {}", e))?
+    .map_err(|e| format!("Failed to export worklog: {}", e))
+}
@@ -1,6 +1,8 @@
 "use client";
 
 import { useAutoRefresh } from "@/hooks/use-auto-refresh";
+import { useFileWatcher } from "@/hooks/use-file-watcher";
+import { useNewDay } from "@/hooks/use-new-day";
 import { useUserStore } from "@/stores/user-store";
 
 /**
@@ -13,6 +15,10 @@ export function AutoRefreshProvider() {
 
   // Enable auto-refresh when a folder path is set
   useAutoRefresh(folderPath || "", !!folderPath);
+  // Pick up the note the scheduler creates at midnight
+  useNewDay(folderPath || "");
+  // Keep the note list current as files change on disk
+  useFileWatcher(folderPath || "");
 
   return null;
 }
@@ -0,0 +1,106 @@
+import { useQueryClient } from "@tanstack/react-query";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect } from "react";
+import { markdownKeys } from "@/hooks/use-markdown-queries";
+import { invoke } from "@/ipc/invoke";
+import {
+  fromRustMetadata,
+  type MarkdownFileMetadata,
+  type RustMarkdownFileMetadata,
+} from "@/ipc/markdown-reader";
+
+interface FileChangeEvent {
+  file_path: string;
+  metadata: RustMarkdownFileMetadata | null; // null for deleted files
+}
+
+const FILE_EVENTS = [
+  "file-created",
+  "file-modified",
+  "file-deleted",
+  "xattr-changed",
+] as const;
+
+/**
+ * Hook that has Rust watch the notes folder and patches the cached note list as files
+ * are created, edited, deleted or have their metadata changed, instead of rescanning.
+ *
+ * @param folderPath - The base folder path to watch
+ */
+export function useFileWatcher(folderPath: string) {
+  const queryClient = useQueryClient();
+
+  useEffect(() => {
+    if (!folderPath) return;
+
+    const unlisteners: (() => void)[] = [];
+    let cancelled = false;
+
+    const applyChange = ({ payload }: { payload: FileChangeEvent }) => {
+      queryClient.setQueryData<MarkdownFileMetadata[]>(
+        markdownKeys.metadata(folderPath),
+        (old) => {
+          if (!old) return old;
+          const rest = old.filter(
+            (file) => file.filePath !== payload.file_path,
+          );
+          if (!payload.metadata) return rest;
+
+          // Keep a location from meta.json, which the scan applies on top
+          const previous = old.find(
+            (file) => file.filePath === payload.file_path,
+          );
+          const updated = fromRustMetadata(payload.metadata);
+          if (previous && !updated.city) {
+            updated.country = previous.country;
+            updated.city = previous.city;
+          }
+          return [...rest, updated].sort(
+            (a, b) =>
+              b.dateFromFilename.getTime() - a.dateFromFilename.getTime(),
+          );
+        },
+      );
+    };
+
+    const setup = async () => {
+      try {
+        await invoke("start_directory_watcher", { directoryPath: folderPath });
+      } catch (error) {
+        console.error("Failed to watch notes folder:", error);
+        return;
+      }
+
+      for (const event of FILE_EVENTS) {
+        const unlisten = await listen<FileChangeEvent>(event, applyChange);
+        unlisteners.push(unlisten);
+      }
+      // Events were dropped, so only a full scan can catch up
+      unlisteners.push(
+        await listen("watcher-rescan", () => {
+          queryClient.invalidateQueries({
+            queryKey: markdownKeys.metadata(folderPath),
+          });
+        }),
+      );
+
+      if (cancelled) {
+        for (const unlisten of unlisteners) {
+          unlisten();
+        }
+      }
+    };
+
+    setup();
+
+    return () => {
+      cancelled = true;
+      for (const unlisten of unlisteners) {
+        unlisten();
+      }
+      invoke("stop_directory_watcher").catch((error) =>
+        console.error("Failed to stop watching notes folder:", error),
+      );
+    };
+  }, [folderPath, queryClient]);
+}
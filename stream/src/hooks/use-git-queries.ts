@@ -4,7 +4,6 @@ import {
   useQuery,
   useQueryClient,
 } from "@tanstack/react-query";
-import { invoke } from "@tauri-apps/api/core";
 import { load } from "@tauri-apps/plugin-store";
 import { useMemo } from "react";
 import { toast } from "sonner";
@@ -14,6 +13,7 @@ import {
   getGitCommitsForRepos,
   groupCommitsByDate,
 } from "@/ipc/git-reader";
+import { invoke } from "@/ipc/invoke";
 import type { MarkdownFileMetadata } from "@/ipc/markdown-reader";
 import { getDateFromFilename, getDateKey } from "@/utils/date-utils";
 
@@ -365,8 +365,10 @@ export function useStructuredMarkdownFiles(folderPath: string) {
         maxFileSize: 5 * 1024 * 1024, // 5MB limit
       });
 
-      // Also populate individual content cache for each file
+      // Also populate individual content cache for each file. Previews of large
+      // files are left out so the editor loads (and saves) the whole file
       for (const file of files) {
+        if (file.isTruncated) continue;
         queryClient.setQueryData(
           markdownKeys.content(file.filePath),
           file.content,
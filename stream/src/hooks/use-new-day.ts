@@ -0,0 +1,41 @@
+import { useQueryClient } from "@tanstack/react-query";
+import { listen } from "@tauri-apps/api/event";
+import { useEffect } from "react";
+import { markdownKeys } from "@/hooks/use-markdown-queries";
+
+interface NewDayEvent {
+  date: string;
+  file_path: string | null;
+}
+
+/**
+ * Hook that listens for the "new-day" event the Rust scheduler emits after local
+ * midnight (or on waking past it) and reloads the note list so today's note shows up.
+ *
+ * @param folderPath - The base folder path whose notes to reload
+ */
+export function useNewDay(folderPath: string) {
+  const queryClient = useQueryClient();
+
+  useEffect(() => {
+    if (!folderPath) return;
+
+    let unlisten: (() => void) | undefined;
+
+    const setupListener = async () => {
+      unlisten = await listen<NewDayEvent>("new-day", () => {
+        queryClient.invalidateQueries({
+          queryKey: markdownKeys.metadata(folderPath),
+        });
+      });
+    };
+
+    setupListener();
+
+    return () => {
+      if (unlisten) {
+        unlisten();
+      }
+    };
+  }, [folderPath, queryClient]);
+}
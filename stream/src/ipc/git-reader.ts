@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/ipc/invoke";
 import { useUserStore } from "@/stores/user-store";
 
 /**
@@ -15,12 +15,40 @@ export interface GitCommit {
   files_changed: string[];
   branches: string[]; // Branches that contain this commit
   url?: string; // URL to commit on remote (if available)
+  projects: string[]; // Monorepo sub-projects the commit touches
+  conventional?: ConventionalCommit; // Parsed `type(scope): subject`, if the message follows it
+  tickets: string[]; // Ticket ids like "PROJ-123" or "#42" mentioned in the message
+  pull_request?: PullRequestLink; // PR/MR the commit was merged through, from merge commit messages
+  repo_paths: string[]; // Every clone/worktree it was found in when deduplicated; empty otherwise
+  duplicate_of?: string; // Id of the commit kept for the same change, e.g. the squash merge
+}
+
+export interface PullRequestLink {
+  number: number;
+  url?: string;
+}
+
+export interface ConventionalCommit {
+  kind: string; // e.g. "feat", "fix"
+  scope?: string;
+  subject: string;
+  breaking: boolean;
+}
+
+export interface RepoSettings {
+  display_name?: string; // Label shown instead of the path
+  group?: string; // e.g. "work" or "personal"
+  color?: string;
+  author_emails: string[]; // Emails that are "me" here; empty uses the global list
+  projects: string[]; // Sub-project path prefixes, e.g. "apps/web"
 }
 
 export interface RepoCommits {
   repo_path: string;
   commits: GitCommit[];
   error?: string;
+  truncated: boolean; // Walk hit a commit or time limit before covering the range
+  settings: RepoSettings;
 }
 
 /**
@@ -81,6 +109,11 @@ export function groupCommitsByDate(repoCommits: RepoCommits[]): CommitsByDate {
     }
 
     for (const commit of repoCommit.commits) {
+      // Squashed or rebased work is shown once, under the commit it landed as
+      if (commit.duplicate_of) {
+        continue;
+      }
+
       const dateKey = commit.date; // Already in YYYY-MM-DD format
 
       if (!commitsByDate[dateKey]) {
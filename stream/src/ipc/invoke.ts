@@ -0,0 +1,49 @@
+import { type InvokeArgs, invoke as tauriInvoke } from "@tauri-apps/api/core";
+
+interface CommandTiming {
+  command: string;
+  durationMs: number;
+}
+
+const FLUSH_SIZE = 50;
+const FLUSH_DELAY_MS = 10_000;
+
+let pending: CommandTiming[] = [];
+let flushTimer: ReturnType<typeof setTimeout> | null = null;
+
+function flush() {
+  if (flushTimer) {
+    clearTimeout(flushTimer);
+    flushTimer = null;
+  }
+  if (pending.length === 0) {
+    return;
+  }
+  const timings = pending;
+  pending = [];
+  tauriInvoke("record_command_timings", { timings }).catch((error) => {
+    console.error("Error recording command timings:", error);
+  });
+}
+
+function recordTiming(command: string, durationMs: number) {
+  pending.push({ command, durationMs });
+  if (pending.length >= FLUSH_SIZE) {
+    flush();
+  } else if (!flushTimer) {
+    flushTimer = setTimeout(flush, FLUSH_DELAY_MS);
+  }
+}
+
+/**
+ * Tauri's invoke, timing each round trip for the performance report. Timings are sent
+ * to the backend in batches
+ */
+export async function invoke<T>(command: string, args?: InvokeArgs): Promise<T> {
+  const start = performance.now();
+  try {
+    return await tauriInvoke<T>(command, args);
+  } finally {
+    recordTiming(command, performance.now() - start);
+  }
+}
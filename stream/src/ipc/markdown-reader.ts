@@ -1,5 +1,5 @@
-import { invoke } from "@tauri-apps/api/core";
 import { remove, stat } from "@tauri-apps/plugin-fs";
+import { invoke } from "@/ipc/invoke";
 import {
   markFileRefreshedMeta,
   type RefreshInterval,
@@ -30,6 +30,14 @@ export interface MarkdownFileMetadata {
   city?: string;
   /** The date parsed from the filename (YYYY-MM-DD format) */
   dateFromFilename: Date;
+  /** The file description */
+  description?: string;
+  /** The refresh interval */
+  refreshInterval?: string;
+  /** The last refreshed timestamp */
+  lastRefreshedAt?: Date;
+  /** Stable id used by `stream://note/<id>` links, once assigned */
+  noteId?: string;
 }
 
 /**
@@ -51,6 +59,19 @@ export interface StructuredMarkdownFileMetadata {
   country?: string;
   /** The city associated with this file (from meta.json) */
   city?: string;
+  /** Position in the manually arranged list, if arranged */
+  orderIndex?: number;
+}
+
+/**
+ * A note's YAML frontmatter (matches Rust struct)
+ */
+export interface Frontmatter {
+  title?: string;
+  tags: string[];
+  aliases: string[];
+  /** Every other key, with its value */
+  custom: Record<string, unknown>;
 }
 
 /**
@@ -79,12 +100,28 @@ export interface StructuredMarkdownFile {
   refreshInterval?: string;
   /** The last refreshed timestamp */
   lastRefreshedAt?: Date;
+  /** Whether `content` is only a preview of a large file */
+  isTruncated: boolean;
+  /** Parsed from the start of the content, if it has a valid YAML block */
+  frontmatter?: Frontmatter;
+}
+
+/**
+ * Part of a file loaded on demand (matches Rust struct)
+ */
+export interface FileChunk {
+  content: string;
+  /** Byte offset the content starts at */
+  offset: number;
+  /** Byte offset of the next chunk; equals `size` at the end of the file */
+  next_offset: number;
+  size: number;
 }
 
 /**
  * Rust-side metadata structure (matches Rust struct)
  */
-interface RustMarkdownFileMetadata {
+export interface RustMarkdownFileMetadata {
   file_path: string;
   file_name: string;
   created_at: number; // Unix timestamp in milliseconds
@@ -93,6 +130,34 @@ interface RustMarkdownFileMetadata {
   country?: string;
   city?: string;
   date_from_filename: number; // Date from filename as Unix timestamp (midnight UTC)
+  description?: string;
+  refresh_interval?: string;
+  last_refreshed_at?: number;
+  note_id?: string;
+}
+
+/**
+ * Convert metadata from the Rust side, e.g. from a scan or a watcher event
+ */
+export function fromRustMetadata(
+  rustFile: RustMarkdownFileMetadata,
+): MarkdownFileMetadata {
+  return {
+    filePath: rustFile.file_path,
+    fileName: rustFile.file_name,
+    createdAt: new Date(rustFile.created_at),
+    modifiedAt: new Date(rustFile.modified_at),
+    size: rustFile.size,
+    country: rustFile.country,
+    city: rustFile.city,
+    dateFromFilename: new Date(rustFile.date_from_filename),
+    description: rustFile.description ?? undefined,
+    refreshInterval: rustFile.refresh_interval ?? undefined,
+    lastRefreshedAt: rustFile.last_refreshed_at
+      ? new Date(rustFile.last_refreshed_at)
+      : undefined,
+    noteId: rustFile.note_id ?? undefined,
+  };
 }
 
 /**
@@ -106,6 +171,7 @@ interface RustStructuredMarkdownFileMetadata {
   size: number;
   country?: string;
   city?: string;
+  order_index?: number;
 }
 
 /**
@@ -123,6 +189,8 @@ interface RustStructuredMarkdownFile {
   content: string;
   refresh_interval?: string;
   last_refreshed_at?: number;
+  is_truncated: boolean;
+  frontmatter?: Frontmatter | null;
 }
 
 /**
@@ -162,16 +230,7 @@ export async function readAllMarkdownFilesMetadata(
     );
 
     const filesFromRust: MarkdownFileMetadata[] = rustMetadata.map(
-      (rustFile) => ({
-        filePath: rustFile.file_path,
-        fileName: rustFile.file_name,
-        createdAt: new Date(rustFile.created_at),
-        modifiedAt: new Date(rustFile.modified_at),
-        size: rustFile.size,
-        country: rustFile.country,
-        city: rustFile.city,
-        dateFromFilename: new Date(rustFile.date_from_filename),
-      }),
+      fromRustMetadata,
     );
 
     const meta = await readMeta(directoryPath);
@@ -195,16 +254,21 @@ export async function readAllMarkdownFilesMetadata(
   }
 }
 
+/**
+ * Per-file result of `read_markdown_files_content`
+ */
+export type FileContentResult = { content: string } | { error: string };
+
 /**
  * Reads the content of multiple markdown files by their absolute paths.
  * Returns a Map keyed by file path to content. Files that fail to read
- * are omitted from the resulting Map.
+ * are logged and omitted from the resulting Map.
  * Uses a fast Rust-based implementation for optimal performance.
  */
 export async function readMarkdownFilesContentByPaths(
   filePaths: string[],
 ): Promise<Map<string, string>> {
-  const markdown_files: Record<string, string> = await invoke(
+  const markdown_files: Record<string, FileContentResult> = await invoke(
     "read_markdown_files_content",
     {
       filePaths,
@@ -212,8 +276,12 @@ export async function readMarkdownFilesContentByPaths(
   );
 
   const map = new Map<string, string>();
-  for (const [path, content] of Object.entries(markdown_files)) {
-    map.set(path, content);
+  for (const [path, result] of Object.entries(markdown_files)) {
+    if ("content" in result) {
+      map.set(path, result.content);
+    } else {
+      console.error(`Error reading file ${path}:`, result.error);
+    }
   }
 
   return map;
@@ -241,8 +309,7 @@ export async function writeMarkdownFileContent(
       fileExists = false;
     }
 
-    const { writeTextFile } = await import("@tauri-apps/plugin-fs");
-    await writeTextFile(filePath, content);
+    await invoke("write_markdown_file", { filePath, content });
 
     if (!fileExists) {
       const baseFolderPath =
@@ -454,7 +521,8 @@ export async function deleteMarkdownFile(filePath: string): Promise<void> {
 /**
  * Reads metadata for structured markdown files in the structured subdirectory.
  * Reads all .md files regardless of naming pattern from {directoryPath}/structured/.
- * Files are sorted by modification time (newest first).
+ * Manually arranged files come first in their set order, then the rest by modification
+ * time (newest first).
  * This function only reads file metadata, not content.
  * Uses a fast Rust-based implementation for optimal performance.
  *
@@ -488,6 +556,7 @@ export async function readStructuredMarkdownFilesMetadata(
         size: rustFile.size,
         country: rustFile.country,
         city: rustFile.city,
+        orderIndex: rustFile.order_index ?? undefined,
       }),
     );
 
@@ -556,6 +625,8 @@ export async function readStructuredMarkdownFiles(
       lastRefreshedAt: rustFile.last_refreshed_at
         ? new Date(rustFile.last_refreshed_at)
         : undefined,
+      isTruncated: rustFile.is_truncated,
+      frontmatter: rustFile.frontmatter ?? undefined,
     }));
 
     const meta = await readMeta(directoryPath);
@@ -595,6 +666,32 @@ export async function readStructuredMarkdownFiles(
   }
 }
 
+/**
+ * Loads more of a structured file returned with `isTruncated`. Offsets are in bytes;
+ * the preview ends at the UTF-8 length of its content.
+ *
+ * @param filePath - The absolute path to the file
+ * @param offset - Byte offset to start reading from
+ * @param len - Maximum number of bytes to read (capped at 4MB)
+ * @returns Promise<FileChunk> - The chunk and where the next one starts
+ */
+export async function readStructuredFileChunk(
+  filePath: string,
+  offset: number,
+  len: number = 1024 * 1024,
+): Promise<FileChunk> {
+  try {
+    return await invoke<FileChunk>("read_structured_file_chunk", {
+      filePath,
+      offset,
+      len,
+    });
+  } catch (error) {
+    console.error(`Error reading chunk of ${filePath}:`, error);
+    throw new Error(`Failed to read file chunk: ${error}`);
+  }
+}
+
 /**
  * Creates a structured markdown file with the given name in the structured subdirectory.
  * Creates the structured directory if it doesn't exist.
@@ -696,6 +793,31 @@ export async function setFileDescription(
   }
 }
 
+/**
+ * Sets one key in a file's YAML frontmatter, leaving the body untouched.
+ *
+ * @param filePath - The absolute path to the file
+ * @param key - The frontmatter key, e.g. "title" or "tags"
+ * @param value - The new value, or null to remove the key
+ * @returns Promise<Frontmatter> - The frontmatter as saved
+ */
+export async function setFrontmatterField(
+  filePath: string,
+  key: string,
+  value: unknown,
+): Promise<Frontmatter> {
+  try {
+    return await invoke<Frontmatter>("set_frontmatter_field", {
+      filePath,
+      key,
+      value: value ?? null,
+    });
+  } catch (error) {
+    console.error(`Error setting frontmatter for ${filePath}:`, error);
+    throw new Error(`Failed to set frontmatter field: ${error}`);
+  }
+}
+
 /**
  * Sets the refresh interval for a file in meta.json.
  *
@@ -804,6 +926,18 @@ export async function refreshFileWithAI(filePath: string): Promise<void> {
       throw new Error(`Could not find structured file: ${filePath}`);
     }
 
+    // Large files come back as a preview; merging into it would drop the rest
+    let structuredContent = structuredFile.content;
+    if (structuredFile.isTruncated) {
+      const fullContent = (
+        await readMarkdownFilesContentByPaths([filePath])
+      ).get(filePath);
+      if (fullContent === undefined) {
+        throw new Error(`Could not read structured file: ${filePath}`);
+      }
+      structuredContent = fullContent;
+    }
+
     const baseFolderPath = filePath.split("/structured/")[0];
 
     const dailyFilesMetadata =
@@ -831,7 +965,7 @@ export async function refreshFileWithAI(filePath: string): Promise<void> {
     const relevantDailyFiles = await classifyRelevantDailyFiles(
       structuredFile.fileName,
       structuredFile.description || "",
-      structuredFile.content,
+      structuredContent,
       dailyFilesWithContent,
     );
 
@@ -844,7 +978,7 @@ export async function refreshFileWithAI(filePath: string): Promise<void> {
     const updatedContent = await mergeRelevantContent(
       structuredFile.fileName,
       structuredFile.description || "",
-      structuredFile.content,
+      structuredContent,
       relevantDailyFiles,
     );
 
@@ -1,4 +1,4 @@
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "@/ipc/invoke";
 
 /**
  * Represents a single search match in a markdown file
@@ -14,6 +14,8 @@ export interface SearchMatch {
   contextSnippet: string;
   /** Relevance score (based on number of matches) */
   score: number;
+  /** Metadata field matched ("description", "city" or "country"), with lineNumber 0 */
+  field?: string;
 }
 
 /**
@@ -25,6 +27,7 @@ interface RustSearchMatch {
   match_ranges: Array<[number, number]>;
   context_snippet: string;
   score: number;
+  field?: string | null;
 }
 
 /**
@@ -53,7 +56,8 @@ interface RustSearchResults {
  * Always searches the current state of files (no indexing required).
  *
  * @param folderPath - Path to the folder containing markdown files
- * @param query - Search query string (last term uses prefix matching for type-ahead)
+ * @param query - Search query string (last term uses prefix matching for type-ahead).
+ *   `description:`, `city:` and `country:` scope terms to note metadata, e.g. `city:lisbon retro`
  * @param limit - Maximum number of results to return (default: 100)
  * @param sortByDate - Sort results by date in filename (newest first) (default: false)
  * @returns Promise<SearchResults> - Search results with matches and metadata
@@ -90,6 +94,7 @@ export async function searchMarkdownFiles(
       matchRanges: rustMatch.match_ranges,
       contextSnippet: rustMatch.context_snippet,
       score: rustMatch.score,
+      field: rustMatch.field ?? undefined,
     }));
 
     return {
@@ -1,27 +1,15 @@
-import { Store } from "@tauri-apps/plugin-store";
+import { invoke } from "@/ipc/invoke";
 
-// Store instance for settings
-let store: Store | null = null;
+const GEMINI_SECRET = "gemini";
 
 /**
- * Initialize the settings store
- */
-async function getStore(): Promise<Store> {
-  if (!store) {
-    store = await Store.load("settings.json");
-  }
-  return store;
-}
-
-const GEMINI_API_KEY = "gemini_api_key";
-
-/**
- * Get the stored Google Gemini API key
+ * Get the stored Google Gemini API key from the OS keychain
  */
 export async function getApiKey(): Promise<string | null> {
   try {
-    const s = await getStore();
-    const key = await s.get<string>(GEMINI_API_KEY);
+    const key = await invoke<string | null>("get_secret", {
+      name: GEMINI_SECRET,
+    });
     return key || null;
   } catch (error) {
     console.error("Error getting API key:", error);
@@ -30,13 +18,11 @@ export async function getApiKey(): Promise<string | null> {
 }
 
 /**
- * Save the Google Gemini API key
+ * Save the Google Gemini API key to the OS keychain
  */
 export async function setApiKey(apiKey: string): Promise<void> {
   try {
-    const s = await getStore();
-    await s.set(GEMINI_API_KEY, apiKey);
-    await s.save();
+    await invoke("store_secret", { name: GEMINI_SECRET, value: apiKey });
   } catch (error) {
     console.error("Error saving API key:", error);
     throw new Error("Failed to save API key");
@@ -48,9 +34,7 @@ export async function setApiKey(apiKey: string): Promise<void> {
  */
 export async function removeApiKey(): Promise<void> {
   try {
-    const s = await getStore();
-    await s.delete(GEMINI_API_KEY);
-    await s.save();
+    await invoke("delete_secret", { name: GEMINI_SECRET });
   } catch (error) {
     console.error("Error removing API key:", error);
     throw new Error("Failed to remove API key");